@@ -0,0 +1,115 @@
+//! Link check that the sensor layer can't panic.
+//!
+//! The panic handler calls a function which doesn't exist, so linking fails as soon as any
+//! panic path of the code used here survives optimization. Build with
+//! `cargo build --release --target thumbv7em-none-eabihf` in this directory.
+
+#![no_std]
+#![no_main]
+
+use core::{panic::PanicInfo, ptr};
+
+use embedded_hal::blocking::{
+    delay::DelayMs,
+    i2c::{Read, Write as I2cWrite},
+};
+use mics_vz_89te::{
+    display::{render, TextBuffer},
+    fixed::{FixedMeasurements, IntMeasurements},
+    format, protocol, MicsVz89Te,
+};
+
+extern "Rust" {
+    /// Never defined, see the crate documentation.
+    fn panic_path_in_sensor_layer() -> !;
+}
+
+#[panic_handler]
+fn panic(_: &PanicInfo) -> ! {
+    unsafe { panic_path_in_sensor_layer() }
+}
+
+const DATA: *mut u8 = 0x4000_5410 as *mut u8;
+
+/// Bus reading and writing a data register, so the optimizer can't know the frames.
+struct Bus;
+
+impl I2cWrite for Bus {
+    type Error = u8;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), u8> {
+        for byte in bytes {
+            unsafe { ptr::write_volatile(DATA, *byte ^ address) };
+        }
+        match unsafe { ptr::read_volatile(DATA) } {
+            0 => Ok(()),
+            e => Err(e),
+        }
+    }
+}
+
+impl Read for Bus {
+    type Error = u8;
+
+    fn read(&mut self, _address: u8, buffer: &mut [u8]) -> Result<(), u8> {
+        for byte in buffer.iter_mut() {
+            *byte = unsafe { ptr::read_volatile(DATA) };
+        }
+        Ok(())
+    }
+}
+
+struct Delay;
+
+impl DelayMs<u16> for Delay {
+    fn delay_ms(&mut self, ms: u16) {
+        unsafe { ptr::write_volatile(DATA, ms as u8) };
+    }
+}
+
+fn report(text: &str) {
+    for byte in text.bytes() {
+        unsafe { ptr::write_volatile(DATA, byte) };
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn sensor_task() -> ! {
+    let mut device = MicsVz89Te::new(Bus);
+    let mut delay = Delay;
+    let mut line = TextBuffer::<32>::new();
+    loop {
+        line.clear();
+        match device.read_measurements(&mut delay) {
+            Ok(m) => {
+                let _ = render("CO2 {co2}ppm VOC {voc.1}ppb", &m, &mut line);
+                let _ = m.write_fixed_to(&mut line, 2);
+            }
+            Err(e) => {
+                let _ = e.write_to(&mut line);
+            }
+        }
+        report(line.as_str());
+
+        line.clear();
+        if let Ok(revision) = device.read_revision(&mut delay) {
+            let _ = revision.write_to(&mut line);
+        }
+        if let Ok(r0) = device.read_calibration_r0(&mut delay) {
+            let _ = format::write_fixed(&mut line, f32::from(r0), 0);
+        }
+        let _ = device.write_calibration_ppm(f32::from(unsafe { ptr::read_volatile(DATA) }) * 8.0);
+        report(line.as_str());
+
+        let mut response = [0u8; protocol::RESPONSE_LEN];
+        for byte in response.iter_mut() {
+            *byte = unsafe { ptr::read_volatile(DATA) };
+        }
+        let int = IntMeasurements::from_response(&response);
+        let fixed = FixedMeasurements::from_response(&response);
+        line.clear();
+        let _ = format::write_fixed(&mut line, f32::from(int.co2_ppm), 0);
+        let _ = format::write_fixed(&mut line, f32::from(fixed.voc_ppb()), 0);
+        report(line.as_str());
+    }
+}
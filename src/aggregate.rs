@@ -0,0 +1,422 @@
+//! Aggregation of measurements into summary records.
+//!
+//! Use [Downsampler] to reduce a stream of samples (e.g. 1 Hz) into one [Summary] per window
+//! (e.g. one per minute) suitable for long-term storage.
+
+#[cfg(any(feature = "alloc", doc, test))]
+use crate::history::VecHistory;
+use crate::{history::History, Measurements};
+
+/// Mean, minimum and maximum of a set of measurements.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Summary {
+    pub mean: Measurements,
+    pub min: Measurements,
+    pub max: Measurements,
+    /// Number of measurements the summary was built from.
+    pub count: u32,
+}
+
+impl Summary {
+    /// Summarize the given measurements. Returns `None` if there are none.
+    pub fn from_measurements<'a>(
+        measurements: impl IntoIterator<Item = &'a Measurements>,
+    ) -> Option<Self> {
+        let mut acc = Accumulator::new();
+        measurements.into_iter().for_each(|m| acc.add(m));
+        acc.summary()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Accumulator {
+    sum_co2: f32,
+    sum_voc: f32,
+    min: Measurements,
+    max: Measurements,
+    count: u32,
+}
+
+impl Accumulator {
+    const fn new() -> Self {
+        Self {
+            sum_co2: 0.0,
+            sum_voc: 0.0,
+            min: Measurements {
+                co2: f32::INFINITY,
+                voc: f32::INFINITY,
+            },
+            max: Measurements {
+                co2: f32::NEG_INFINITY,
+                voc: f32::NEG_INFINITY,
+            },
+            count: 0,
+        }
+    }
+
+    fn add(&mut self, m: &Measurements) {
+        self.sum_co2 += m.co2;
+        self.sum_voc += m.voc;
+        self.min.co2 = self.min.co2.min(m.co2);
+        self.min.voc = self.min.voc.min(m.voc);
+        self.max.co2 = self.max.co2.max(m.co2);
+        self.max.voc = self.max.voc.max(m.voc);
+        self.count = self.count.saturating_add(1);
+    }
+
+    fn summary(&self) -> Option<Summary> {
+        if self.count == 0 {
+            return None;
+        }
+        let count = self.count as f32;
+        Some(Summary {
+            mean: Measurements {
+                co2: self.sum_co2 / count,
+                voc: self.sum_voc / count,
+            },
+            min: self.min,
+            max: self.max,
+            count: self.count,
+        })
+    }
+}
+
+/// Streaming reducer emitting one [Summary] per `window` pushed measurements.
+///
+/// # Example Usage
+/// ```ignore
+/// let mut downsampler = Downsampler::new(60); // 1 Hz samples into 1 minute records
+/// loop {
+///     let measurements = device.read_measurements(&mut delay).unwrap();
+///     if let Some(summary) = downsampler.push(measurements) {
+///         store(summary);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Downsampler {
+    window: u32,
+    acc: Accumulator,
+}
+
+impl Downsampler {
+    /// Create a reducer over windows of `window` measurements. A window of `0` is treated as `1`.
+    pub const fn new(window: u32) -> Self {
+        Self {
+            window: if window == 0 { 1 } else { window },
+            acc: Accumulator::new(),
+        }
+    }
+
+    /// Number of measurements per window.
+    pub fn window(&self) -> u32 {
+        self.window
+    }
+
+    /// Add a measurement. Returns the summary of the window if it is completed by this measurement.
+    pub fn push(&mut self, measurements: Measurements) -> Option<Summary> {
+        self.acc.add(&measurements);
+        if self.acc.count >= self.window {
+            self.flush()
+        } else {
+            None
+        }
+    }
+
+    /// Summarize the current, possibly incomplete, window and start a new one.
+    pub fn flush(&mut self) -> Option<Summary> {
+        let summary = self.acc.summary();
+        self.acc = Accumulator::new();
+        summary
+    }
+}
+
+/// Summary of a time window `[start_ms, end_ms)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowSummary {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub summary: Summary,
+    /// `true` if the window holds fewer samples than expected, e.g. because of read errors
+    /// or because sampling started within the window.
+    pub missing_data: bool,
+}
+
+/// Streaming reducer emitting one [WindowSummary] per time window.
+///
+/// Windows are aligned to multiples of the window length, e.g. with 15 minute windows and
+/// wall-clock timestamps (millis since the Unix epoch) at `:00`, `:15`, `:30` and `:45`.
+/// Monotonic timestamps align to multiples since the clock's start. Windows without any
+/// sample are skipped; gaps can be detected by the `start_ms` of the next summary.
+///
+/// # Example Usage
+/// ```ignore
+/// // 1 Hz samples into 15 minute averages
+/// let mut aggregator = WindowAggregator::new(15 * 60 * 1000, 1000);
+/// if let Some(window) = aggregator.push(clock.now_ms(), measurements) {
+///     report(window.start_ms, window.summary.mean);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct WindowAggregator {
+    window_ms: u64,
+    expected_samples: u32,
+    start_ms: Option<u64>,
+    acc: Accumulator,
+}
+
+impl WindowAggregator {
+    /// Create a reducer over windows of `window_ms` fed every `sample_interval_ms`. A window
+    /// of `0` is treated as `1`.
+    pub fn new(window_ms: u64, sample_interval_ms: u64) -> Self {
+        let window_ms = window_ms.max(1);
+        Self {
+            window_ms,
+            expected_samples: u32::try_from(window_ms / sample_interval_ms.max(1))
+                .unwrap_or(u32::MAX),
+            start_ms: None,
+            acc: Accumulator::new(),
+        }
+    }
+
+    /// Length of a window in millis.
+    pub fn window_ms(&self) -> u64 {
+        self.window_ms
+    }
+
+    /// Add a measurement taken at `timestamp_ms`. Returns the summary of the previous window
+    /// if the measurement belongs to a later window.
+    ///
+    /// Measurements older than the current window are ignored.
+    pub fn push(&mut self, timestamp_ms: u64, measurements: Measurements) -> Option<WindowSummary> {
+        let start_ms = timestamp_ms - timestamp_ms % self.window_ms;
+        let completed = match self.start_ms {
+            Some(current) if start_ms < current => return None,
+            Some(current) if start_ms > current => self.flush(),
+            _ => None,
+        };
+        self.start_ms = Some(start_ms);
+        self.acc.add(&measurements);
+        completed
+    }
+
+    /// Summarize the current, possibly incomplete, window and start a new one.
+    pub fn flush(&mut self) -> Option<WindowSummary> {
+        let start_ms = self.start_ms.take()?;
+        let summary = self.acc.summary()?;
+        self.acc = Accumulator::new();
+        Some(WindowSummary {
+            start_ms,
+            end_ms: start_ms.saturating_add(self.window_ms),
+            summary,
+            missing_data: summary.count < self.expected_samples,
+        })
+    }
+}
+
+/// Length of a day in millis.
+pub const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// Minimum and maximum of one day.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DailyExtremes {
+    /// Start of the day in millis.
+    pub day_start_ms: u64,
+    pub min: Measurements,
+    pub max: Measurements,
+}
+
+/// Tracks the minimum and maximum of CO2 and VOC per day.
+///
+/// Timestamps are local wall-clock millis (e.g. millis since the Unix epoch shifted by the
+/// UTC offset). A day starts `rollover_ms` after midnight.
+///
+/// # Example Usage
+/// ```ignore
+/// let mut daily = DailyTracker::new(6 * 60 * 60 * 1000); // days start at 06:00
+/// if let Some(yesterday) = daily.push(local_now_ms, measurements) {
+///     store(yesterday);
+/// }
+/// show(daily.today());
+/// ```
+#[derive(Debug, Clone)]
+pub struct DailyTracker {
+    rollover_ms: u64,
+    today: Option<DailyExtremes>,
+}
+
+impl DailyTracker {
+    /// Create a tracker with days starting `rollover_ms` after midnight. Values beyond a day
+    /// wrap around.
+    pub const fn new(rollover_ms: u64) -> Self {
+        Self {
+            rollover_ms: rollover_ms % DAY_MS,
+            today: None,
+        }
+    }
+
+    /// Extremes of the current day.
+    pub fn today(&self) -> Option<&DailyExtremes> {
+        self.today.as_ref()
+    }
+
+    /// Add a measurement taken at `timestamp_ms`. Returns the extremes of the previous day if
+    /// the measurement starts a new day. Measurements of earlier days are ignored.
+    pub fn push(&mut self, timestamp_ms: u64, measurements: Measurements) -> Option<DailyExtremes> {
+        // days before the first rollover start at 0
+        let day = (timestamp_ms.saturating_add(DAY_MS) - self.rollover_ms) / DAY_MS;
+        let day_start_ms = (day * DAY_MS + self.rollover_ms).saturating_sub(DAY_MS);
+        let new_day = DailyExtremes {
+            day_start_ms,
+            min: measurements,
+            max: measurements,
+        };
+        match &mut self.today {
+            Some(today) if today.day_start_ms == day_start_ms => {
+                today.min.co2 = today.min.co2.min(measurements.co2);
+                today.min.voc = today.min.voc.min(measurements.voc);
+                today.max.co2 = today.max.co2.max(measurements.co2);
+                today.max.voc = today.max.voc.max(measurements.voc);
+                None
+            }
+            Some(today) if today.day_start_ms > day_start_ms => None,
+            _ => self.today.replace(new_day),
+        }
+    }
+}
+
+impl<const N: usize> History<N> {
+    /// Summary over all stored measurements.
+    pub fn summary(&self) -> Option<Summary> {
+        Summary::from_measurements(self.iter())
+    }
+
+    /// Summaries of consecutive windows of `window` measurements, oldest first.
+    /// The last summary may cover fewer measurements.
+    pub fn downsample(&self, window: u32) -> impl Iterator<Item = Summary> + '_ {
+        downsample(self.iter(), window)
+    }
+}
+
+#[cfg(any(feature = "alloc", doc, test))]
+impl VecHistory {
+    /// Summary over all stored measurements.
+    pub fn summary(&self) -> Option<Summary> {
+        Summary::from_measurements(self.iter())
+    }
+
+    /// Summaries of consecutive windows of `window` measurements. See [History::downsample()].
+    pub fn downsample(&self, window: u32) -> impl Iterator<Item = Summary> + '_ {
+        downsample(self.iter(), window)
+    }
+}
+
+fn downsample<'a>(
+    mut measurements: impl Iterator<Item = &'a Measurements>,
+    window: u32,
+) -> impl Iterator<Item = Summary> {
+    let mut downsampler = Downsampler::new(window);
+    core::iter::from_fn(move || {
+        for m in measurements.by_ref() {
+            if let Some(summary) = downsampler.push(*m) {
+                return Some(summary);
+            }
+        }
+        downsampler.flush()
+    })
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{DailyTracker, Downsampler, WindowAggregator, DAY_MS};
+    use crate::{history::History, Measurements};
+    use core::assert_eq;
+
+    fn m(co2: f32, voc: f32) -> Measurements {
+        Measurements { co2, voc }
+    }
+
+    #[test]
+    fn test_downsampler() {
+        let mut downsampler = Downsampler::new(3);
+
+        assert!(downsampler.push(m(400.0, 10.0)).is_none());
+        assert!(downsampler.push(m(600.0, 30.0)).is_none());
+        let summary = downsampler.push(m(500.0, 20.0)).unwrap();
+
+        assert_eq!(summary.count, 3);
+        assert_eq!(summary.mean.co2 as u32, 500);
+        assert_eq!(summary.mean.voc as u32, 20);
+        assert_eq!(summary.min.co2 as u32, 400);
+        assert_eq!(summary.max.voc as u32, 30);
+
+        assert!(downsampler.flush().is_none());
+    }
+
+    #[test]
+    fn test_window_aggregator() {
+        let mut aggregator = WindowAggregator::new(60_000, 10_000);
+
+        // starts in the middle of the first window
+        assert!(aggregator.push(125_000, m(400.0, 0.0)).is_none());
+        assert!(aggregator.push(175_000, m(500.0, 0.0)).is_none());
+        let first = aggregator.push(180_000, m(600.0, 0.0)).unwrap();
+        assert_eq!((first.start_ms, first.end_ms), (120_000, 180_000));
+        assert_eq!(first.summary.mean.co2 as u32, 450);
+        assert!(first.missing_data);
+
+        for t in 1..6 {
+            assert!(aggregator
+                .push(180_000 + t * 10_000, m(600.0, 0.0))
+                .is_none());
+        }
+        // late sample of a completed window
+        assert!(aggregator.push(179_000, m(0.0, 0.0)).is_none());
+        // skips the empty window 240_000..300_000
+        let second = aggregator.push(300_000, m(400.0, 0.0)).unwrap();
+        assert_eq!(second.start_ms, 180_000);
+        assert_eq!(second.summary.count, 6);
+        assert!(!second.missing_data);
+
+        assert_eq!(aggregator.flush().map(|w| w.start_ms), Some(300_000));
+        assert!(aggregator.flush().is_none());
+    }
+
+    #[test]
+    fn test_daily_tracker() {
+        const HOUR: u64 = 60 * 60 * 1000;
+        let mut daily = DailyTracker::new(6 * HOUR);
+
+        assert!(daily.push(HOUR, m(500.0, 10.0)).is_none());
+        assert_eq!(daily.today().map(|d| d.day_start_ms), Some(0));
+        assert!(daily.push(DAY_MS + 5 * HOUR, m(500.0, 10.0)).is_some());
+        // new day starts at 06:00
+        let previous = daily.push(DAY_MS + 7 * HOUR, m(800.0, 40.0)).unwrap();
+        assert_eq!(previous.day_start_ms, 6 * HOUR);
+        assert!(daily.push(DAY_MS + 5 * HOUR, m(0.0, 0.0)).is_none());
+        assert!(daily.push(2 * DAY_MS + 3 * HOUR, m(600.0, 90.0)).is_none());
+
+        let today = daily.today().unwrap();
+        assert_eq!(today.day_start_ms, DAY_MS + 6 * HOUR);
+        assert_eq!((today.min.co2, today.min.voc), (600.0, 40.0));
+        assert_eq!((today.max.co2, today.max.voc), (800.0, 90.0));
+    }
+
+    #[test]
+    fn test_history_downsample() {
+        let mut history = History::<5>::new();
+        for i in 0..5 {
+            history.push(m(400.0 + 10.0 * i as f32, 0.0));
+        }
+
+        let mut summaries = history.downsample(2);
+        assert_eq!(summaries.next().map(|s| s.mean.co2 as u32), Some(405));
+        assert_eq!(summaries.next().map(|s| s.mean.co2 as u32), Some(425));
+        let last = summaries.next().unwrap();
+        assert_eq!((last.count, last.mean.co2 as u32), (1, 440));
+        assert!(summaries.next().is_none());
+
+        assert_eq!(history.summary().map(|s| s.max.co2 as u32), Some(440));
+    }
+}
@@ -0,0 +1,200 @@
+//! Mapping of CO2 and VOC levels to a US AQI-style score.
+//!
+//! Each channel is mapped to a `0..=500` score by linear interpolation between breakpoints,
+//! like the US EPA AQI. The overall score is the maximum of both channels. The default
+//! breakpoints are common indoor guidance values; they can be replaced by custom tables.
+
+use crate::Measurements;
+
+/// Band of an AQI score.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AqiBand {
+    /// `0..=50`
+    Good,
+    /// `51..=100`
+    Moderate,
+    /// `101..=150`
+    UnhealthyForSensitiveGroups,
+    /// `151..=200`
+    Unhealthy,
+    /// `201..=300`
+    VeryUnhealthy,
+    /// `301..=500`
+    Hazardous,
+}
+
+impl AqiBand {
+    /// Band of `score`.
+    pub fn from_score(score: u16) -> Self {
+        match score {
+            0..=50 => Self::Good,
+            51..=100 => Self::Moderate,
+            101..=150 => Self::UnhealthyForSensitiveGroups,
+            151..=200 => Self::Unhealthy,
+            201..=300 => Self::VeryUnhealthy,
+            _ => Self::Hazardous,
+        }
+    }
+
+    /// Display label of the band.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Good => "Good",
+            Self::Moderate => "Moderate",
+            Self::UnhealthyForSensitiveGroups => "Unhealthy for Sensitive Groups",
+            Self::Unhealthy => "Unhealthy",
+            Self::VeryUnhealthy => "Very Unhealthy",
+            Self::Hazardous => "Hazardous",
+        }
+    }
+}
+
+/// Concentration range mapped linearly onto a score range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Breakpoint {
+    pub concentration_low: f32,
+    pub concentration_high: f32,
+    pub score_low: u16,
+    pub score_high: u16,
+}
+
+/// Shorthand constructor for breakpoint tables.
+pub(crate) const fn bp(
+    concentration_low: f32,
+    concentration_high: f32,
+    score_low: u16,
+    score_high: u16,
+) -> Breakpoint {
+    Breakpoint {
+        concentration_low,
+        concentration_high,
+        score_low,
+        score_high,
+    }
+}
+
+/// Default breakpoints for CO2 in ppm.
+pub const DEFAULT_CO2_BREAKPOINTS: [Breakpoint; 6] = [
+    bp(0.0, 600.0, 0, 50),
+    bp(600.0, 1000.0, 51, 100),
+    bp(1000.0, 1500.0, 101, 150),
+    bp(1500.0, 2000.0, 151, 200),
+    bp(2000.0, 5000.0, 201, 300),
+    bp(5000.0, 40000.0, 301, 500),
+];
+
+/// Default breakpoints for VOC in ppb.
+pub const DEFAULT_VOC_BREAKPOINTS: [Breakpoint; 6] = [
+    bp(0.0, 220.0, 0, 50),
+    bp(220.0, 660.0, 51, 100),
+    bp(660.0, 1430.0, 101, 150),
+    bp(1430.0, 2200.0, 151, 200),
+    bp(2200.0, 3300.0, 201, 300),
+    bp(3300.0, 5500.0, 301, 500),
+];
+
+/// Score of both channels and the resulting overall score.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AqiScore {
+    pub co2: u16,
+    pub voc: u16,
+    /// Maximum of the channel scores.
+    pub score: u16,
+}
+
+impl AqiScore {
+    /// Band of the overall score.
+    pub fn band(&self) -> AqiBand {
+        AqiBand::from_score(self.score)
+    }
+}
+
+/// Breakpoint tables of both channels, sorted by concentration.
+#[derive(Debug, Clone, Copy)]
+pub struct AqiScale<'a> {
+    pub co2: &'a [Breakpoint],
+    pub voc: &'a [Breakpoint],
+}
+
+impl Default for AqiScale<'static> {
+    fn default() -> Self {
+        Self {
+            co2: &DEFAULT_CO2_BREAKPOINTS,
+            voc: &DEFAULT_VOC_BREAKPOINTS,
+        }
+    }
+}
+
+impl AqiScale<'_> {
+    /// Score of the measurements.
+    pub fn score(&self, measurements: &Measurements) -> AqiScore {
+        let co2 = interpolate(self.co2, measurements.co2);
+        let voc = interpolate(self.voc, measurements.voc);
+        AqiScore {
+            co2,
+            voc,
+            score: co2.max(voc),
+        }
+    }
+}
+
+/// Score of `value` in `breakpoints`. Values below the first breakpoint map to its lower score,
+/// values above the last breakpoint to its upper score.
+pub(crate) fn interpolate(breakpoints: &[Breakpoint], value: f32) -> u16 {
+    let (Some(first), Some(last)) = (breakpoints.first(), breakpoints.last()) else {
+        return 0;
+    };
+    if value.is_nan() || value <= first.concentration_low {
+        return first.score_low;
+    }
+    let bp = breakpoints
+        .iter()
+        .find(|bp| value <= bp.concentration_high)
+        .unwrap_or(last);
+    if value >= bp.concentration_high {
+        return bp.score_high;
+    }
+    let span = bp.concentration_high - bp.concentration_low;
+    let fraction = if span > 0.0 {
+        (value - bp.concentration_low).max(0.0) / span
+    } else {
+        1.0
+    };
+    let score =
+        f32::from(bp.score_low) + fraction * f32::from(bp.score_high.saturating_sub(bp.score_low));
+    crate::units::round_to_u16(score)
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{AqiBand, AqiScale};
+    use crate::Measurements;
+    use core::assert_eq;
+
+    #[test]
+    fn test_score() {
+        let scale = AqiScale::default();
+
+        let score = scale.score(&Measurements {
+            co2: 800.0,
+            voc: 100.0,
+        });
+        assert_eq!((score.co2, score.voc, score.score), (76, 23, 76));
+        assert_eq!(score.band(), AqiBand::Moderate);
+        assert_eq!(score.band().label(), "Moderate");
+
+        let score = scale.score(&Measurements {
+            co2: 400.0,
+            voc: 1430.0,
+        });
+        assert_eq!(score.score, 150);
+        assert_eq!(score.band(), AqiBand::UnhealthyForSensitiveGroups);
+
+        let score = scale.score(&Measurements {
+            co2: 1e6,
+            voc: -5.0,
+        });
+        assert_eq!((score.co2, score.voc), (500, 0));
+    }
+}
@@ -0,0 +1,455 @@
+//! Async frontends for embedded-hal-async buses and delays.
+//!
+//! [MicsVz89TeAsync] awaits the bus transfers and the response time of the sensor instead of
+//! blocking, e.g. on embassy. [Awaiting] only awaits the response time and keeps the bus
+//! transfers of a blocking [MicsVz89Te] blocking, e.g. for a driver shared behind an async
+//! mutex. Both are generated from the same source as the blocking driver, so they retry, check
+//! checksums (including the lenient mode), record calibrations and decode responses identically.
+//!
+//! # Cancellation
+//! The read futures are cancellation safe. If one is dropped mid-request, e.g. by losing an
+//! embassy `select!`, [MicsVz89TeAsync::pending()] keeps the abandoned command. The next request
+//! first drains the response of the abandoned one, so it never reads a stale frame, and then
+//! sends its own complete command frame. A request which fails doesn't leave a command behind.
+//!
+//! # Example Usage
+//! ```ignore
+//! let i2c = embassy_rp::i2c::I2c::new_async(p.I2C0, scl, sda, Irqs, config);
+//! let mut device = MicsVz89TeAsync::new(i2c);
+//! let measurements = device.read_measurements(&mut embassy_time::Delay).await?;
+//!
+//! // blocking bus, awaited response time
+//! let measurements = blocking.awaiting().read_measurements(&mut embassy_time::Delay).await?;
+//! ```
+
+use core::ops::{Deref, DerefMut};
+
+use embedded_hal_async::{delay::DelayNs, i2c::I2c};
+
+use crate::{
+    checksum::ChecksumStats,
+    config::{CalibrationRecord, Config},
+    error::PacketParseError,
+    frontend,
+    protocol::{Command, RESPONSE_LEN},
+    transport::Transport,
+    Clamped, MicsVz89Te,
+};
+
+/// Async driver for MICS-VZ-89TE sensor.
+pub struct MicsVz89TeAsync<I2C> {
+    i2c: I2C,
+    config: Config,
+    calibration: Option<CalibrationRecord>,
+    clamped: Clamped,
+    pending: Option<Command>,
+    retries_used: u8,
+    last_status: Option<u8>,
+    checksum: ChecksumStats,
+}
+
+impl<I2C, E> MicsVz89TeAsync<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Create new driver on the supplied i2c bus.
+    pub fn new(i2c: I2C) -> Self {
+        Self::with_config(i2c, Config::default())
+    }
+
+    /// Create new driver on the supplied i2c bus with a custom configuration.
+    pub fn with_config(i2c: I2C, config: Config) -> Self {
+        Self {
+            i2c,
+            config,
+            calibration: None,
+            clamped: Clamped::default(),
+            pending: None,
+            retries_used: 0,
+            last_status: None,
+            checksum: ChecksumStats::default(),
+        }
+    }
+
+    frontend::impl_requests!([async] [.await] impl DelayNs);
+
+    async fn send_frame(&mut self, command: Command) -> Result<(), PacketParseError<E>> {
+        self.i2c
+            .write(self.config.address, &command.frame())
+            .await
+            .map_err(PacketParseError::from)
+    }
+
+    async fn receive_frame(&mut self) -> Result<[u8; RESPONSE_LEN], PacketParseError<E>> {
+        let mut buffer = [0u8; RESPONSE_LEN];
+        self.i2c.read(self.config.address, &mut buffer).await?;
+        Ok(buffer)
+    }
+
+    async fn wait(&mut self, delay: &mut impl DelayNs) {
+        delay.delay_ms(u32::from(self.config.wait_time_ms)).await;
+    }
+
+    async fn settle_pending(&mut self) -> Result<(), PacketParseError<E>> {
+        if self.pending.take().is_some() {
+            // response of a request whose future was dropped, its content is irrelevant, and a
+            // failed read of it isn't retried
+            self.receive_frame().await?;
+        }
+        Ok(())
+    }
+}
+
+impl<I2C> MicsVz89TeAsync<I2C> {
+    /// Releases the underlying I2C bus and destroys the driver.
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+
+    /// Command which was sent, but whose response wasn't read yet, e.g. because the read future
+    /// was dropped. See [Cancellation](self#cancellation).
+    pub fn pending(&self) -> Option<Command> {
+        self.pending
+    }
+
+    /// Statistics of the response checksums.
+    pub fn checksum_stats(&self) -> &ChecksumStats {
+        &self.checksum
+    }
+
+    frontend::impl_state!();
+}
+
+/// Blocking [MicsVz89Te] awaiting the response time of the sensor, see
+/// [MicsVz89Te::awaiting()].
+///
+/// Everything but the reads and calibration writes is reached through [Deref] to the driver.
+pub struct Awaiting<'a, I2C> {
+    driver: &'a mut MicsVz89Te<I2C>,
+}
+
+impl<I2C> MicsVz89Te<I2C> {
+    /// Borrow the driver with reads awaiting the response time of the sensor on an
+    /// embedded-hal-async delay instead of blocking. The bus transfers stay blocking.
+    pub fn awaiting(&mut self) -> Awaiting<'_, I2C> {
+        Awaiting { driver: self }
+    }
+}
+
+impl<I2C, E> Awaiting<'_, I2C>
+where
+    I2C: Transport<Error = E>,
+{
+    frontend::impl_requests!([async] [.await] impl DelayNs);
+
+    async fn send_frame(&mut self, command: Command) -> Result<(), PacketParseError<E>> {
+        self.driver.send_frame(command)
+    }
+
+    async fn receive_frame(&mut self) -> Result<[u8; RESPONSE_LEN], PacketParseError<E>> {
+        self.driver.receive_frame()
+    }
+
+    async fn wait(&mut self, delay: &mut impl DelayNs) {
+        delay.delay_ms(u32::from(self.config.wait_time_ms)).await;
+    }
+
+    async fn settle_pending(&mut self) -> Result<(), PacketParseError<E>> {
+        if self.pending.take().is_some() {
+            // response of a request whose future was dropped, its content is irrelevant, and a
+            // failed read of it isn't retried
+            self.receive_frame().await?;
+        }
+        Ok(())
+    }
+}
+
+impl<I2C> Deref for Awaiting<'_, I2C> {
+    type Target = MicsVz89Te<I2C>;
+
+    fn deref(&self) -> &Self::Target {
+        self.driver
+    }
+}
+
+impl<I2C> DerefMut for Awaiting<'_, I2C> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.driver
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::MicsVz89TeAsync;
+    use crate::{
+        config::{CalibrationMethod, Config},
+        error::PacketParseError,
+        protocol::Command,
+        warnings::Warnings,
+        MicsVz89Te,
+    };
+    use assert_matches::assert_matches;
+    use core::{
+        assert_eq,
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+    use embedded_hal_async::{
+        delay::DelayNs,
+        i2c::{ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation, SevenBitAddress},
+    };
+    use embedded_hal_mock::{
+        delay::MockNoop as DelayMock,
+        i2c::{Mock as I2cMock, Transaction as I2cTransaction},
+    };
+    use std::{vec, vec::Vec};
+
+    /// Bus answering the reads with the queued responses and recording the writes.
+    #[derive(Default)]
+    struct Bus {
+        written: Vec<Vec<u8>>,
+        responses: Vec<[u8; 7]>,
+        reads: usize,
+        fail_writes: usize,
+    }
+
+    impl ErrorType for Bus {
+        type Error = ErrorKind;
+    }
+
+    impl I2c for Bus {
+        async fn transaction(
+            &mut self,
+            address: SevenBitAddress,
+            operations: &mut [Operation<'_>],
+        ) -> Result<(), ErrorKind> {
+            assert_eq!(address, 0x70);
+            for operation in operations {
+                match operation {
+                    Operation::Write(_) if self.fail_writes > 0 => {
+                        self.fail_writes -= 1;
+                        return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+                    }
+                    Operation::Write(bytes) => self.written.push(bytes.to_vec()),
+                    Operation::Read(buffer) => {
+                        self.reads += 1;
+                        buffer.copy_from_slice(&self.responses.remove(0))
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// Delay which completes immediately.
+    struct NoDelay;
+
+    impl DelayNs for NoDelay {
+        async fn delay_ns(&mut self, _ns: u32) {}
+    }
+
+    /// Delay which stays pending on its first poll, like a timer which hasn't expired yet.
+    struct SlowDelay {
+        polled: bool,
+    }
+
+    impl DelayNs for SlowDelay {
+        async fn delay_ns(&mut self, _ns: u32) {
+            core::future::poll_fn(|_| {
+                if core::mem::replace(&mut self.polled, true) {
+                    Poll::Ready(())
+                } else {
+                    Poll::Pending
+                }
+            })
+            .await
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    fn test_read_measurements() {
+        let bus = Bus {
+            responses: vec![
+                [0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x26],
+                [0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27],
+                [0x10, 0x03, 0x11, 0x48, 0, 0, 0x93],
+            ],
+            ..Bus::default()
+        };
+        let config = Config {
+            retries: 1,
+            ..Config::default()
+        };
+        let mut device = MicsVz89TeAsync::with_config(bus, config);
+
+        let m = block_on(device.read_measurements(&mut NoDelay)).unwrap();
+        assert_eq!(m.co2_ppm_u16(), 728);
+        assert_eq!(device.checksum_stats().failures, 1);
+        let revision = block_on(device.read_revision(&mut NoDelay)).unwrap();
+        assert_eq!(revision.year, 2016);
+        assert_eq!(device.pending(), None);
+
+        let bus = device.release();
+        assert_eq!(bus.written[0], [0x0C, 0, 0, 0, 0, 0xF3]);
+        assert_eq!(bus.written[2], [0x0D, 0, 0, 0, 0, 0xF2]);
+    }
+
+    #[test]
+    fn test_wrong_checksum() {
+        let bus = Bus {
+            responses: vec![[0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x26]],
+            ..Bus::default()
+        };
+        let mut device = MicsVz89TeAsync::new(bus);
+
+        assert_matches!(
+            block_on(device.read_measurements(&mut NoDelay)),
+            Err(PacketParseError::WrongChecksum)
+        );
+        assert_eq!(device.checksum_stats().failures, 1);
+    }
+
+    #[test]
+    fn test_same_decoding_as_blocking() {
+        let response = [0x27, 0xFA, 0, 0xBA, 0xBA, 0, 0x68];
+        let config = Config {
+            strict_range: true,
+            raw_offset: 15,
+            ..Config::default()
+        };
+        let mut device = MicsVz89TeAsync::with_config(
+            Bus {
+                responses: vec![response],
+                ..Bus::default()
+            },
+            config,
+        );
+        let expectations = [
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, response.to_vec()),
+        ];
+        let mut blocking = MicsVz89Te::with_config(I2cMock::new(&expectations), config);
+
+        assert_eq!(
+            block_on(device.read_measurements(&mut NoDelay)).unwrap(),
+            blocking.read_measurements(&mut DelayMock::new()).unwrap()
+        );
+        assert_eq!(device.last_clamped(), blocking.last_clamped());
+        blocking.release().done();
+    }
+
+    #[test]
+    fn test_dropped_read() {
+        let bus = Bus {
+            responses: vec![
+                // stale response of the dropped read
+                [0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x26],
+                [0x10, 0x03, 0x11, 0x48, 0, 0, 0x93],
+            ],
+            ..Bus::default()
+        };
+        let mut device = MicsVz89TeAsync::new(bus);
+
+        {
+            let mut delay = SlowDelay { polled: false };
+            let mut read = pin!(device.read_measurements(&mut delay));
+            let mut cx = Context::from_waker(Waker::noop());
+            assert!(read.as_mut().poll(&mut cx).is_pending());
+            // dropped like the losing branch of a select
+        }
+        assert_eq!(device.pending(), Some(Command::GetStatus));
+
+        let revision = block_on(device.read_revision(&mut NoDelay)).unwrap();
+        assert_eq!(revision.year, 2016);
+        assert_eq!(device.pending(), None);
+        assert_eq!(device.checksum_stats().failures, 0);
+        let bus = device.release();
+        assert_eq!(bus.reads, 2);
+        assert_eq!(bus.written[1], [0x0D, 0, 0, 0, 0, 0xF2]);
+    }
+
+    #[test]
+    fn test_lenient_checksum() {
+        let bus = Bus {
+            responses: vec![[0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x26]; 2],
+            ..Bus::default()
+        };
+        let config = Config {
+            retries: 1,
+            lenient_checksum_after: 2,
+            ..Config::default()
+        };
+        let mut device = MicsVz89TeAsync::with_config(bus, config);
+
+        let reading = block_on(device.read_measurements_with_warnings(&mut NoDelay)).unwrap();
+        assert_eq!(reading.measurements.co2_ppm_u16(), 728);
+        assert_eq!(reading.warnings, Warnings::RETRIED | Warnings::UNVERIFIED);
+        let state = device.state();
+        assert_eq!(state.retries_used, 1);
+        assert_eq!(state.checksum.unverified, 1);
+        assert_eq!(state.last_status, None);
+    }
+
+    #[test]
+    fn test_write_calibration() {
+        let mut device = MicsVz89TeAsync::new(Bus::default());
+        let now = || 42;
+
+        block_on(device.write_calibration(2500.0, CalibrationMethod::Manual, &now)).unwrap();
+        let record = device.last_calibration().unwrap();
+        assert_eq!(record.reference_ppm, 2000.0);
+        assert_eq!(record.timestamp_ms, Some(42));
+        assert_eq!(device.state().pending, None);
+        let bus = device.release();
+        assert_eq!(bus.written, [Command::set_calibration_ppm(2500.0).frame()]);
+    }
+
+    #[test]
+    fn test_awaiting() {
+        let expectations = [
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+        ];
+        let mut device = MicsVz89Te::new(I2cMock::new(&expectations));
+
+        let m = block_on(device.awaiting().read_measurements(&mut NoDelay)).unwrap();
+        assert_eq!(m.co2_ppm_u16(), 728);
+        assert_eq!(device.state().last_status, Some(0));
+        device.release().done();
+    }
+
+    #[test]
+    fn test_failed_write() {
+        let bus = Bus {
+            responses: vec![[0x10, 0x03, 0x11, 0x48, 0, 0, 0x93]],
+            fail_writes: 1,
+            ..Bus::default()
+        };
+        let mut device = MicsVz89TeAsync::new(bus);
+
+        assert_matches!(
+            block_on(device.read_measurements(&mut NoDelay)),
+            Err(PacketParseError::BusError(ErrorKind::NoAcknowledge(_)))
+        );
+        assert_eq!(device.pending(), None);
+
+        // nothing to drain, the read only takes the response to its own request
+        let revision = block_on(device.read_revision(&mut NoDelay)).unwrap();
+        assert_eq!(revision.year, 2016);
+        let bus = device.release();
+        assert_eq!(bus.reads, 1);
+        assert_eq!(bus.written, [[0x0D, 0, 0, 0, 0, 0xF2]]);
+    }
+}
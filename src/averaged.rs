@@ -0,0 +1,192 @@
+//! Averaged reads over several consecutive measurements.
+
+use crate::{
+    delay::Delay, error::PacketParseError, math, transport::Transport, Measurements, MicsVz89Te,
+};
+
+/// Result of [MicsVz89Te::read_measurements_averaged()].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AveragedMeasurements {
+    /// Mean of the samples which were not discarded as outliers.
+    pub mean: Measurements,
+    /// Standard deviation of the samples which were not discarded as outliers.
+    pub std_dev: Measurements,
+    /// Number of samples the result is built from.
+    pub samples: usize,
+}
+
+impl AveragedMeasurements {
+    /// Mean and standard deviation of `samples`, discarding samples where CO2 or VOC deviates
+    /// more than two standard deviations from the mean. Returns `None` if `samples` is empty.
+    pub fn from_samples(samples: &[Measurements]) -> Option<Self> {
+        let all = mean_std_dev(samples.iter())?;
+        let is_inlier = |m: &&Measurements| {
+            (m.co2 - all.mean.co2).abs() <= 2.0 * all.std_dev.co2
+                && (m.voc - all.mean.voc).abs() <= 2.0 * all.std_dev.voc
+        };
+        Some(mean_std_dev(samples.iter().filter(is_inlier)).unwrap_or(all))
+    }
+}
+
+fn mean_std_dev<'a>(
+    samples: impl Iterator<Item = &'a Measurements> + Clone,
+) -> Option<AveragedMeasurements> {
+    let (count, sum_co2, sum_voc) = samples
+        .clone()
+        .fold((0usize, 0.0f32, 0.0f32), |(n, co2, voc), m| {
+            (n + 1, co2 + m.co2, voc + m.voc)
+        });
+    if count == 0 {
+        return None;
+    }
+    let mean = Measurements {
+        co2: sum_co2 / count as f32,
+        voc: sum_voc / count as f32,
+    };
+    let (var_co2, var_voc) = samples.fold((0.0f32, 0.0f32), |(co2, voc), m| {
+        (
+            co2 + (m.co2 - mean.co2) * (m.co2 - mean.co2),
+            voc + (m.voc - mean.voc) * (m.voc - mean.voc),
+        )
+    });
+    Some(AveragedMeasurements {
+        mean,
+        std_dev: Measurements {
+            co2: math::sqrt(var_co2 / count as f32),
+            voc: math::sqrt(var_voc / count as f32),
+        },
+        samples: count,
+    })
+}
+
+/// Maximum number of samples of [MicsVz89Te::read_measurements_averaged()].
+pub const MAX_AVERAGED_SAMPLES: usize = 32;
+
+impl<I2C, E> MicsVz89Te<I2C>
+where
+    I2C: Transport<Error = E>,
+{
+    /// Read `n` consecutive measurements and return their mean and standard deviation with
+    /// outliers discarded. See [AveragedMeasurements::from_samples()].
+    ///
+    /// `n` is clamped to `1..=`[MAX_AVERAGED_SAMPLES], use
+    /// [MicsVz89Te::read_measurements_averaged_into()] with a larger buffer for more samples.
+    /// Consecutive reads start `interval_ms` apart, use
+    /// [SENSOR_UPDATE_INTERVAL_MS](crate::sampler::SENSOR_UPDATE_INTERVAL_MS) to get a new value
+    /// from the sensor for each sample. The first failing read aborts and returns its error.
+    ///
+    /// # Example Usage
+    /// ```ignore
+    /// let result = device.read_measurements_averaged(&mut delay, 10, SENSOR_UPDATE_INTERVAL_MS)?;
+    /// ```
+    pub fn read_measurements_averaged(
+        &mut self,
+        delay: &mut impl Delay,
+        n: usize,
+        interval_ms: u16,
+    ) -> Result<AveragedMeasurements, PacketParseError<E>> {
+        let mut samples = [Measurements { co2: 0.0, voc: 0.0 }; MAX_AVERAGED_SAMPLES];
+        let samples = &mut samples[..n.clamp(1, MAX_AVERAGED_SAMPLES)];
+        let result = self.read_measurements_averaged_into(delay, samples, interval_ms)?;
+        // at least one sample was read, so the fallback is never taken
+        let none = Measurements { co2: 0.0, voc: 0.0 };
+        Ok(result.unwrap_or(AveragedMeasurements {
+            mean: none,
+            std_dev: none,
+            samples: 0,
+        }))
+    }
+
+    /// Like [MicsVz89Te::read_measurements_averaged()], but reads as many measurements as fit
+    /// into `samples` and keeps them there. Returns `None` if `samples` is empty.
+    pub fn read_measurements_averaged_into(
+        &mut self,
+        delay: &mut impl Delay,
+        samples: &mut [Measurements],
+        interval_ms: u16,
+    ) -> Result<Option<AveragedMeasurements>, PacketParseError<E>> {
+        for (i, sample) in samples.iter_mut().enumerate() {
+            if i > 0 {
+                delay.delay_ms(interval_ms.saturating_sub(self.config().wait_time_ms));
+            }
+            *sample = self.read_measurements(delay)?;
+        }
+        Ok(AveragedMeasurements::from_samples(samples))
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::AveragedMeasurements;
+    use crate::{Measurements, MicsVz89Te};
+    use core::assert_eq;
+    use embedded_hal_mock::{
+        delay::MockNoop as DelayMock,
+        i2c::{Mock as I2cMock, Transaction as I2cTransaction},
+    };
+    use std::vec;
+
+    #[test]
+    fn test_from_samples_discards_outliers() {
+        let mut samples = [Measurements {
+            co2: 700.0,
+            voc: 100.0,
+        }; 10];
+        samples[1].co2 = 710.0;
+        samples[2].co2 = 690.0;
+        samples[7].co2 = 1500.0;
+
+        let result = AveragedMeasurements::from_samples(&samples).unwrap();
+
+        assert_eq!(result.samples, 9);
+        assert_eq!(result.mean.co2, 700.0);
+        assert!((result.std_dev.co2 - 4.714).abs() < 0.01);
+        assert_eq!(result.std_dev.voc, 0.0);
+        assert!(AveragedMeasurements::from_samples(&[]).is_none());
+    }
+
+    #[test]
+    fn test_read_measurements_averaged() {
+        let expectations = [
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3E, 0, 0xBA, 0xBA, 0, 0x25]),
+        ];
+        let i2c = I2cMock::new(&expectations);
+        let mut delay = DelayMock::new();
+
+        let mut device = MicsVz89Te::new(i2c);
+        let result = device
+            .read_measurements_averaged(&mut delay, 2, 1000)
+            .unwrap();
+
+        assert_eq!(result.samples, 2);
+        assert_eq!(result.mean.co2_ppm_u16(), 735);
+        device.release().done();
+    }
+
+    #[test]
+    fn test_read_measurements_averaged_into() {
+        let expectations = [
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+        ];
+        let mut device = MicsVz89Te::new(I2cMock::new(&expectations));
+        let mut delay = DelayMock::new();
+        let mut samples = [Measurements { co2: 0.0, voc: 0.0 }; 1];
+
+        let result = device
+            .read_measurements_averaged_into(&mut delay, &mut samples, 1000)
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.mean, samples[0]);
+        assert_eq!(samples[0].co2_ppm_u16(), 728);
+        assert!(device
+            .read_measurements_averaged_into(&mut delay, &mut [], 1000)
+            .unwrap()
+            .is_none());
+        device.release().done();
+    }
+}
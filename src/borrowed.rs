@@ -0,0 +1,92 @@
+//! Driver operating on a borrowed I2C bus.
+//!
+//! Useful if the bus is owned by a board struct and the driver should only be created for a
+//! short time, e.g. per transaction.
+//!
+//! # Example Usage
+//! ```ignore
+//! let measurements = MicsVz89Te::borrowed(&mut board.i2c).read_measurements(&mut delay)?;
+//! ```
+
+use embedded_hal::blocking::i2c::{Read, Write};
+
+use crate::{config::Config, MicsVz89Te};
+
+/// Mutable reference to an I2C bus which implements the I2C traits itself.
+pub struct BusRef<'a, I2C>(pub &'a mut I2C);
+
+impl<I2C> Read for BusRef<'_, I2C>
+where
+    I2C: Read,
+{
+    type Error = I2C::Error;
+
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.0.read(address, buffer)
+    }
+}
+
+impl<I2C> Write for BusRef<'_, I2C>
+where
+    I2C: Write,
+{
+    type Error = I2C::Error;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.0.write(address, bytes)
+    }
+}
+
+/// Driver borrowing the I2C bus for the lifetime `'a`.
+pub type MicsVz89TeRef<'a, I2C> = MicsVz89Te<BusRef<'a, I2C>>;
+
+impl<'a, I2C, E> MicsVz89Te<BusRef<'a, I2C>>
+where
+    I2C: Read<Error = E> + Write<Error = E>,
+{
+    /// Create a driver borrowing the supplied i2c bus.
+    pub fn borrowed(i2c: &'a mut I2C) -> Self {
+        Self::new(BusRef(i2c))
+    }
+
+    /// Create a driver borrowing the supplied i2c bus with a custom configuration.
+    pub fn borrowed_with_config(i2c: &'a mut I2C, config: Config) -> Self {
+        Self::with_config(BusRef(i2c), config)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::MicsVz89Te;
+    use core::assert_eq;
+    use embedded_hal_mock::{
+        delay::MockNoop as DelayMock,
+        i2c::{Mock as I2cMock, Transaction as I2cTransaction},
+    };
+    use std::vec;
+
+    #[test]
+    fn test_borrowed_driver() {
+        let expectations = [
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+            I2cTransaction::write(0x70, vec![0x0D, 0, 0, 0, 0, 0xF2]),
+            I2cTransaction::read(0x70, vec![0x10, 0x03, 0x11, 0x48, 00, 0, 0x93]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = DelayMock::new();
+
+        let measurements = MicsVz89Te::borrowed(&mut i2c)
+            .read_measurements(&mut delay)
+            .unwrap();
+        assert_eq!(measurements.co2_ppm_u16(), 728);
+
+        let revision = MicsVz89Te::borrowed(&mut i2c)
+            .read_revision(&mut delay)
+            .unwrap();
+        assert_eq!(revision.year, 2016);
+
+        i2c.done();
+    }
+}
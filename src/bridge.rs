@@ -0,0 +1,116 @@
+//! Constructors for USB-to-I2C bridges on a PC.
+//!
+//! For bench characterization and production programming stations the sensor is connected to a
+//! PC with a USB-to-I2C bridge. [MicsVz89Te::ft232h()] opens an FTDI FT232H through
+//! [ftdi-embedded-hal](https://docs.rs/ftdi-embedded-hal) and the FTD2XX library,
+//! [MicsVz89Te::ch341()] opens a CH341 through the Linux `i2c-ch341-usb` kernel driver. Both
+//! run the bus at [BRIDGE_FREQUENCY_HZ]. The blocking driver needs a delay, e.g.
+//! `linux_embedded_hal::Delay` wrapped in `Eh1Delay`.
+//!
+//! # Example Usage
+//! ```ignore
+//! let mut device = MicsVz89Te::ft232h("Single RS232-HS")?;
+//! let measurements = device.read_measurements(&mut Eh1Delay(linux_embedded_hal::Delay))?;
+//! ```
+
+use std::io;
+#[cfg(target_os = "linux")]
+use std::{fs, path::Path};
+
+use ftdi_embedded_hal::{libftd2xx::Ft232h, FtHal};
+
+use crate::{eh1::Eh1Bus, MicsVz89Te};
+
+/// I2C clock of the bridges in Hz, the maximum of the sensor.
+pub const BRIDGE_FREQUENCY_HZ: u32 = 100_000;
+
+/// Bus of a driver opened with [MicsVz89Te::ft232h()].
+pub type Ft232hBus = Eh1Bus<ftdi_embedded_hal::I2c<Ft232h>>;
+
+/// Bus of a driver opened with [MicsVz89Te::ch341()].
+#[cfg(target_os = "linux")]
+pub type Ch341Bus = Eh1Bus<linux_embedded_hal::I2cdev>;
+
+impl MicsVz89Te<Ft232hBus> {
+    /// Open the FT232H with the USB `description` (`"Single RS232-HS"` unless reprogrammed) as
+    /// I2C master.
+    pub fn ft232h(description: &str) -> io::Result<Self> {
+        let device = Ft232h::with_description(description).map_err(io::Error::other)?;
+        let hal = FtHal::init_freq(device, BRIDGE_FREQUENCY_HZ).map_err(io::Error::other)?;
+        let i2c = hal.i2c().map_err(io::Error::other)?;
+        Ok(Self::new(Eh1Bus(i2c)))
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[cfg_attr(docsrs, doc(cfg(target_os = "linux")))]
+impl MicsVz89Te<Ch341Bus> {
+    /// Open the `index`th CH341 bridge (`0` for the first), counted in the order of the I2C bus
+    /// numbers the kernel assigned to them.
+    ///
+    /// The I2C mode of the CH341 needs the `i2c-ch341-usb` kernel module. The bus speed is set
+    /// by the module, load it with `speed=1` for [BRIDGE_FREQUENCY_HZ].
+    pub fn ch341(index: usize) -> io::Result<Self> {
+        let buses = buses_named(Path::new("/sys/class/i2c-adapter"), "ch341")?;
+        let bus = buses.get(index).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "no CH341 I2C adapter with this index",
+            )
+        })?;
+        let i2c =
+            linux_embedded_hal::I2cdev::new(format!("/dev/i2c-{bus}")).map_err(io::Error::other)?;
+        Ok(Self::new(Eh1Bus(i2c)))
+    }
+}
+
+/// Numbers of the I2C buses in `sysfs` whose adapter name contains `name` (ignoring case),
+/// ascending.
+#[cfg(target_os = "linux")]
+fn buses_named(sysfs: &Path, name: &str) -> io::Result<Vec<u32>> {
+    let mut buses = Vec::new();
+    for entry in fs::read_dir(sysfs)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let Some(bus) = file_name
+            .to_str()
+            .and_then(|n| n.strip_prefix("i2c-"))
+            .and_then(|n| n.parse().ok())
+        else {
+            continue;
+        };
+        // adapters can vanish while iterating, e.g. an unplugged bridge
+        let adapter = fs::read_to_string(entry.path().join("name")).unwrap_or_default();
+        if adapter.to_ascii_lowercase().contains(name) {
+            buses.push(bus);
+        }
+    }
+    buses.sort_unstable();
+    Ok(buses)
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod test {
+
+    use super::buses_named;
+    use core::assert_eq;
+    use std::{env, fs, process};
+
+    #[test]
+    fn test_buses_named() {
+        let sysfs = env::temp_dir().join(format!("mics-vz-89te-sysfs-{}", process::id()));
+        let adapters = [
+            ("i2c-11", "i2c-ch341-usb at bus 001 device 007"),
+            ("i2c-0", "Synopsys DesignWare I2C adapter"),
+            ("i2c-3", "i2c-ch341-usb at bus 001 device 004"),
+        ];
+        for (bus, name) in adapters {
+            fs::create_dir_all(sysfs.join(bus)).unwrap();
+            fs::write(sysfs.join(bus).join("name"), name).unwrap();
+        }
+
+        let buses = buses_named(&sysfs, "ch341");
+        fs::remove_dir_all(&sysfs).unwrap();
+        assert_eq!(buses.unwrap(), vec![3, 11]);
+    }
+}
@@ -0,0 +1,125 @@
+//! Builder for [MicsVz89Te].
+
+use core::num::NonZeroU8;
+
+use crate::{
+    config::Config, delay::Delay, error::PacketParseError, transport::Transport, MicsVz89Te,
+};
+
+/// Builder to create a [MicsVz89Te] with a custom configuration.
+///
+/// # Example Usage
+/// ```ignore
+/// let driver = MicsVz89Te::builder(i2c)
+///     .address(0x71)
+///     .wait_ms(150)
+///     .retries(2)
+///     .build();
+/// ```
+pub struct MicsVz89TeBuilder<I2C> {
+    i2c: I2C,
+    config: Config,
+}
+
+impl<I2C, E> MicsVz89TeBuilder<I2C>
+where
+    I2C: Transport<Error = E>,
+{
+    /// Start building a driver on the supplied i2c bus with the default configuration.
+    pub fn new(i2c: I2C) -> Self {
+        Self {
+            i2c,
+            config: Config::default(),
+        }
+    }
+
+    /// Replace the whole configuration.
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// I2C address of the sensor.
+    pub fn address(mut self, address: u8) -> Self {
+        self.config.address = address;
+        self
+    }
+
+    /// Time (in millis) to wait between a request and reading the response.
+    pub fn wait_ms(mut self, wait_time_ms: u16) -> Self {
+        self.config.wait_time_ms = wait_time_ms;
+        self
+    }
+
+    /// Number of times a failed request is repeated before the error is returned.
+    pub fn retries(mut self, retries: u8) -> Self {
+        self.config.retries = retries;
+        self
+    }
+
+    /// Clamp measurements to the documented ranges, see [Config::strict_range].
+    pub fn strict_range(mut self, strict_range: bool) -> Self {
+        self.config.strict_range = strict_range;
+        self
+    }
+
+    /// Raw offset and span of the conversion, see [Config::raw_offset] and [Config::raw_span].
+    pub fn raw_scale(mut self, raw_offset: u8, raw_span: NonZeroU8) -> Self {
+        self.config.raw_offset = raw_offset;
+        self.config.raw_span = raw_span;
+        self
+    }
+
+    /// Accept responses with a wrong checksum after `after` failures in a row, see
+    /// [Config::lenient_checksum_after].
+    pub fn lenient_checksum(mut self, after: u8) -> Self {
+        self.config.lenient_checksum_after = after;
+        self
+    }
+
+    /// Create the driver.
+    pub fn build(self) -> MicsVz89Te<I2C> {
+        MicsVz89Te::with_config(self.i2c, self.config)
+    }
+
+    /// Create the driver and check that the sensor responds. See [MicsVz89Te::new_checked()].
+    pub fn build_checked(
+        self,
+        delay: &mut impl Delay,
+    ) -> Result<MicsVz89Te<I2C>, (PacketParseError<E>, I2C)> {
+        self.build().checked(delay)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::{config::Config, MicsVz89Te};
+    use core::{assert_eq, num::NonZeroU8};
+    use embedded_hal_mock::i2c::Mock as I2cMock;
+
+    #[test]
+    fn test_builder() {
+        let driver = MicsVz89Te::builder(I2cMock::new(&[]))
+            .address(0x71)
+            .wait_ms(150)
+            .retries(2)
+            .strict_range(true)
+            .raw_scale(15, NonZeroU8::new(225).unwrap())
+            .lenient_checksum(3)
+            .build();
+
+        assert_eq!(
+            driver.config(),
+            &Config {
+                address: 0x71,
+                wait_time_ms: 150,
+                retries: 2,
+                strict_range: true,
+                raw_offset: 15,
+                raw_span: NonZeroU8::new(225).unwrap(),
+                lenient_checksum_after: 3,
+            }
+        );
+    }
+}
@@ -0,0 +1,293 @@
+//! Recording of the first minutes after power-up and a stabilization report.
+//!
+//! [BurnInLogger] stores the raw status response frames, which hold the raw values and the
+//! sensor resistance, for a configured time after power-up. [BurnInLogger::report()] then
+//! evaluates how long the sensor took to settle and how far it drifted until then, e.g. to
+//! reject weak units during incoming inspection. As the frames are kept unprocessed, a
+//! recording can be evaluated again later, e.g. with a different raw scale.
+//!
+//! # Example Usage
+//! ```ignore
+//! let mut log = BurnInLogger::<600>::new(clock.now_ms(), 10 * 60 * 1000);
+//! while log.is_recording() {
+//!     log.read(&mut device, &mut delay, clock.now_ms())?;
+//!     delay.delay_ms(1000u16);
+//! }
+//! let report = log.report(&StabilityCriteria::default()).unwrap();
+//! if !report.passes(8 * 60 * 1000, 200) {
+//!     reject_unit();
+//! }
+//! ```
+
+use crate::{
+    delay::Delay,
+    error::PacketParseError,
+    protocol::{self, Command, RESPONSE_LEN},
+    transport::Transport,
+    warmup::resistance_settled,
+    Measurements, MicsVz89Te,
+};
+
+/// A reading recorded by [BurnInLogger].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BurnInRecord {
+    /// Time (in millis) since power-up.
+    pub elapsed_ms: u64,
+    /// Raw response frame to [Command::GetStatus].
+    pub response: [u8; RESPONSE_LEN],
+}
+
+impl BurnInRecord {
+    /// Measurements of the frame, converted with the default raw scale.
+    pub fn measurements(&self) -> Measurements {
+        protocol::decode_measurements(&self.response)
+    }
+
+    /// Sensor resistance of the frame in Ohms.
+    pub fn resistance_ohm(&self) -> u32 {
+        protocol::decode_resistance(&self.response)
+    }
+}
+
+/// When the sensor counts as stable.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StabilityCriteria {
+    /// Maximum relative resistance change between two records in permille.
+    pub max_resistance_step_permille: u16,
+    /// Maximum CO2 change between two records.
+    pub max_co2_step_ppm: f32,
+    /// Number of consecutive settled steps needed.
+    pub stable_steps: usize,
+}
+
+impl Default for StabilityCriteria {
+    fn default() -> Self {
+        Self {
+            max_resistance_step_permille: 10,
+            max_co2_step_ppm: 20.0,
+            stable_steps: 30,
+        }
+    }
+}
+
+/// Result of the burn-in evaluation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StabilizationReport {
+    /// Number of evaluated records.
+    pub records: usize,
+    /// Time (in millis since power-up) from which the sensor stayed stable. `None` if it never
+    /// settled during the recording.
+    pub time_to_stable_ms: Option<u64>,
+    /// Relative resistance change from the first record until stable (or the end of the
+    /// recording) in permille.
+    pub resistance_drift_permille: u32,
+    /// CO2 change from the first record until stable (or the end of the recording).
+    pub co2_drift_ppm: f32,
+    /// VOC change from the first record until stable (or the end of the recording).
+    pub voc_drift_ppb: f32,
+}
+
+impl StabilizationReport {
+    /// Returns `true` if the sensor settled within `max_time_to_stable_ms` and its resistance
+    /// drifted at most `max_drift_permille` until then.
+    pub fn passes(&self, max_time_to_stable_ms: u64, max_drift_permille: u32) -> bool {
+        self.time_to_stable_ms
+            .is_some_and(|t| t <= max_time_to_stable_ms)
+            && self.resistance_drift_permille <= max_drift_permille
+    }
+}
+
+/// Records up to `N` readings during the first `duration_ms` after power-up.
+#[derive(Debug, Clone)]
+pub struct BurnInLogger<const N: usize> {
+    start_ms: u64,
+    duration_ms: u64,
+    records: [Option<BurnInRecord>; N],
+    len: usize,
+}
+
+impl<const N: usize> BurnInLogger<N> {
+    /// Create a logger for a sensor powered up at `start_ms`, recording for `duration_ms`.
+    pub fn new(start_ms: u64, duration_ms: u64) -> Self {
+        Self {
+            start_ms,
+            duration_ms,
+            records: [None; N],
+            len: 0,
+        }
+    }
+
+    /// Returns `true` until the duration elapsed or the buffer is full.
+    pub fn is_recording(&self) -> bool {
+        self.len < N
+            && self
+                .records()
+                .last()
+                .is_none_or(|r| r.elapsed_ms < self.duration_ms)
+    }
+
+    /// Record a status response frame received at `now_ms`. Returns `false` if it wasn't
+    /// recorded, because the duration elapsed or the buffer is full.
+    pub fn push(&mut self, now_ms: u64, response: &[u8; RESPONSE_LEN]) -> bool {
+        let elapsed_ms = now_ms.saturating_sub(self.start_ms);
+        if elapsed_ms > self.duration_ms {
+            return false;
+        }
+        let Some(slot) = self.records.get_mut(self.len) else {
+            return false;
+        };
+        *slot = Some(BurnInRecord {
+            elapsed_ms,
+            response: *response,
+        });
+        self.len += 1;
+        true
+    }
+
+    /// Read the status of `driver` and record the response frame at `now_ms`. Returns whether
+    /// it was recorded, see [BurnInLogger::push()].
+    pub fn read<I2C, E>(
+        &mut self,
+        driver: &mut MicsVz89Te<I2C>,
+        delay: &mut impl Delay,
+        now_ms: u64,
+    ) -> Result<bool, PacketParseError<E>>
+    where
+        I2C: Transport<Error = E>,
+    {
+        let response = driver.request_data(Command::GetStatus, delay)?;
+        Ok(self.push(now_ms, &response))
+    }
+
+    /// The recorded readings, oldest first.
+    pub fn records(&self) -> impl Iterator<Item = &BurnInRecord> {
+        self.records.iter().take(self.len).flatten()
+    }
+
+    /// Evaluate the recording. Returns `None` if nothing was recorded.
+    pub fn report(&self, criteria: &StabilityCriteria) -> Option<StabilizationReport> {
+        let first = self.records().next()?;
+
+        let mut stable = 0;
+        let mut stable_since: Option<&BurnInRecord> = None;
+        let mut last = first;
+        for record in self.records().skip(1) {
+            let settled = resistance_settled(
+                last.resistance_ohm(),
+                record.resistance_ohm(),
+                criteria.max_resistance_step_permille,
+            ) && (record.measurements().co2 - last.measurements().co2).abs()
+                <= criteria.max_co2_step_ppm;
+            if settled {
+                stable += 1;
+                if stable == 1 {
+                    stable_since = Some(last);
+                }
+            } else {
+                stable = 0;
+                stable_since = None;
+            }
+            last = record;
+        }
+        let stable_since = stable_since.filter(|_| stable >= criteria.stable_steps.max(1));
+
+        let end = stable_since.unwrap_or(last);
+        let (first_ohm, end_ohm) = (first.resistance_ohm(), end.resistance_ohm());
+        let resistance_drift_permille = if first_ohm == 0 {
+            0
+        } else {
+            let drift = u64::from(first_ohm.abs_diff(end_ohm)) * 1000 / u64::from(first_ohm);
+            u32::try_from(drift).unwrap_or(u32::MAX)
+        };
+        let (first_m, end_m) = (first.measurements(), end.measurements());
+        Some(StabilizationReport {
+            records: self.len,
+            time_to_stable_ms: stable_since.map(|r| r.elapsed_ms),
+            resistance_drift_permille,
+            co2_drift_ppm: (end_m.co2 - first_m.co2).abs(),
+            voc_drift_ppb: (end_m.voc - first_m.voc).abs(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{BurnInLogger, StabilityCriteria};
+    use crate::{protocol::RESPONSE_LEN, MicsVz89Te};
+    use core::assert_eq;
+    use embedded_hal_mock::{
+        delay::MockNoop as DelayMock,
+        i2c::{Mock as I2cMock, Transaction as I2cTransaction},
+    };
+    use std::vec;
+
+    /// Status frame with raw CO2 `13 + step` and the resistance, without checksum.
+    fn frame(step: u8, resistance_ohm: u32) -> [u8; RESPONSE_LEN] {
+        let [_, r0, r1, r2] = (resistance_ohm / 10).to_be_bytes();
+        [13, 13 + step, r0, r1, r2, 0, 0]
+    }
+
+    #[test]
+    fn test_report() {
+        let mut log = BurnInLogger::<16>::new(1000, 10_000);
+        let resistances = [
+            100_000, 130_000, 160_000, 180_000, 181_000, 181_500, 181_000, 180_500,
+        ];
+        for (i, r) in resistances.into_iter().enumerate() {
+            assert!(log.push(1000 + 1000 * i as u64, &frame(i as u8, r)));
+        }
+        let criteria = StabilityCriteria {
+            stable_steps: 3,
+            ..Default::default()
+        };
+
+        let report = log.report(&criteria).unwrap();
+        assert_eq!(report.records, 8);
+        assert_eq!(report.time_to_stable_ms, Some(3000));
+        assert_eq!(report.resistance_drift_permille, 800);
+        // three raw steps of 1600 / 229 ppm
+        assert!((report.co2_drift_ppm - 20.96).abs() < 0.01);
+        assert!(report.passes(5000, 800));
+        assert!(!report.passes(5000, 500));
+
+        let strict = StabilityCriteria {
+            stable_steps: 10,
+            ..criteria
+        };
+        let report = log.report(&strict).unwrap();
+        assert_eq!(report.time_to_stable_ms, None);
+        assert!(!report.passes(u64::MAX, u32::MAX));
+    }
+
+    #[test]
+    fn test_recording_ends() {
+        let mut log = BurnInLogger::<2>::new(0, 5000);
+        assert!(log.report(&StabilityCriteria::default()).is_none());
+        assert!(log.is_recording());
+        assert!(log.push(0, &frame(0, 1000)));
+        assert!(!log.push(6000, &frame(0, 1000)));
+        assert!(log.push(5000, &frame(0, 1000)));
+        assert!(!log.is_recording());
+        assert!(!log.push(5000, &frame(0, 1000)));
+        assert_eq!(log.records().count(), 2);
+    }
+
+    #[test]
+    fn test_read_keeps_raw_frame() {
+        let response = [0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27];
+        let expectations = [
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, response.to_vec()),
+        ];
+        let mut device = MicsVz89Te::new(I2cMock::new(&expectations));
+        let mut log = BurnInLogger::<1>::new(0, 1000);
+
+        assert!(log.read(&mut device, &mut DelayMock::new(), 10).unwrap());
+        let record = log.records().next().unwrap();
+        assert_eq!(record.response, response);
+        assert_eq!(record.measurements().co2_ppm_u16(), 728);
+        assert_eq!(record.resistance_ohm(), 478_020);
+        device.release().done();
+    }
+}
@@ -0,0 +1,90 @@
+//! Verification of calibration writes.
+
+use crate::{
+    delay::Delay, error::PacketParseError, sampler::SENSOR_UPDATE_INTERVAL_MS,
+    transport::Transport, MicsVz89Te, CO2_MAX, CO2_MIN,
+};
+
+/// Result of reading back a measurement after a calibration write.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CalibrationCheck {
+    /// CO2 value (in ppm) written to the sensor.
+    pub reference_ppm: f32,
+    /// CO2 value (in ppm) measured after the calibration.
+    pub measured_ppm: f32,
+    /// Allowed difference (in ppm) between the reference and the measured value.
+    pub tolerance_ppm: f32,
+}
+
+impl CalibrationCheck {
+    /// Difference between the measured and the reference value in ppm.
+    pub fn deviation_ppm(&self) -> f32 {
+        self.measured_ppm - self.reference_ppm
+    }
+
+    /// Returns `true` if the calibration took effect within the tolerance.
+    pub fn passed(&self) -> bool {
+        self.deviation_ppm().abs() <= self.tolerance_ppm
+    }
+}
+
+impl<I2C, E> MicsVz89Te<I2C>
+where
+    I2C: Transport<Error = E>,
+{
+    /// Write the calibration CO2 value and verify it took effect by reading back a measurement
+    /// after the next sensor update.
+    ///
+    /// This function blocks a minimum time of [SENSOR_UPDATE_INTERVAL_MS] plus
+    /// [MicsVz89Te::WAIT_ON_RESPONSE_TIME].
+    pub fn write_calibration_ppm_verified(
+        &mut self,
+        ppm: f32,
+        tolerance_ppm: f32,
+        delay: &mut impl Delay,
+    ) -> Result<CalibrationCheck, PacketParseError<E>> {
+        self.write_calibration_ppm(ppm)?;
+        delay.delay_ms(SENSOR_UPDATE_INTERVAL_MS);
+        let measurements = self.read_measurements(delay)?;
+        Ok(CalibrationCheck {
+            reference_ppm: ppm.clamp(CO2_MIN, CO2_MAX),
+            measured_ppm: measurements.co2,
+            tolerance_ppm,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::MicsVz89Te;
+    use embedded_hal_mock::{
+        delay::MockNoop as DelayMock,
+        i2c::{Mock as I2cMock, Transaction as I2cTransaction},
+    };
+    use std::vec;
+
+    #[test]
+    fn test_write_calibration_ppm_verified() {
+        let expectations = [
+            I2cTransaction::write(0x70, vec![0x08, 0x3C, 0, 0, 0, 0xBB]),
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+        ];
+        let i2c = I2cMock::new(&expectations);
+        let mut delay = DelayMock::new();
+
+        let mut device = MicsVz89Te::new(i2c);
+        let check = device
+            .write_calibration_ppm_verified(730.0, 10.0, &mut delay)
+            .unwrap();
+
+        assert!(check.passed());
+        assert!((check.deviation_ppm() + 1.6).abs() < 0.1);
+        assert!(!crate::calibration::CalibrationCheck {
+            tolerance_ppm: 1.0,
+            ..check
+        }
+        .passed());
+    }
+}
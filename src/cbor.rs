@@ -0,0 +1,389 @@
+//! Compact CBOR ([RFC 8949](https://www.rfc-editor.org/rfc/rfc8949)) encoding of measurements
+//! and diagnostics, e.g. for CoAP or LwM2M payloads.
+//!
+//! Records are CBOR maps with small integer keys, which stay stable across versions: new fields
+//! get new keys and decoders ignore unknown keys, whatever their value. Floats are written as
+//! single precision, missing values as `null`.
+//!
+//! The records are encoded and decoded with [minicbor](https://docs.rs/minicbor), enabled by the
+//! `minicbor` feature. Without the `alloc` feature minicbor can't skip unknown values holding an
+//! indefinite-length array or map inside another array or map, those fail with
+//! [CborError::InvalidSchema].
+//!
+//! Every record carries the version of its schema in key `0`, currently [SCHEMA_VERSION]. The
+//! version only changes on incompatible changes, e.g. a changed unit. Records of version `1`
+//! predate the key and don't carry it, decoders treat a missing key `0` as version `1`.
+//!
+//! Measurements ([MEASUREMENTS_LEN] bytes):
+//!
+//! | key | content        |
+//! |-----|----------------|
+//! | 0   | schema version |
+//! | 1   | CO2 in ppm     |
+//! | 2   | VOC in ppb     |
+//!
+//! Tagged measurements additionally carry the [SensorIdentity] of the sensor:
+//!
+//! | key | content                                     |
+//! |-----|---------------------------------------------|
+//! | 3   | label (text string)                         |
+//! | 4   | revision date as `YYYYMMDD`, if known       |
+//!
+//! Diagnostics:
+//!
+//! | key | content                                                                      |
+//! |-----|------------------------------------------------------------------------------|
+//! | 0   | schema version                                                               |
+//! | 1   | config: `{1: address, 2: wait time in ms, 3: retries}`                        |
+//! | 2   | last calibration: `{1: reference ppm, 2: method, 3: timestamp ms or null}`    |
+//! | 3   | R0 drift: `{1: kOhm/day, 2: permille/day, 3: days to recal., 4: days to repl.}` |
+//!
+//! # Example Usage
+//! ```ignore
+//! let mut payload = [0u8; cbor::MEASUREMENTS_LEN];
+//! let len = cbor::encode_measurements(&device.read_measurements(&mut delay)?, &mut payload)?;
+//! coap.post("/sensors/air", &payload[..len]);
+//! ```
+
+use minicbor::{
+    decode,
+    encode::{self, write::Cursor, write::EndOfSlice},
+    Decoder, Encoder,
+};
+
+use crate::{
+    config::{CalibrationRecord, Config},
+    diagnostics::Diagnostics,
+    identity::SensorIdentity,
+    r0_drift::R0Drift,
+    Measurements,
+};
+
+/// Version of the schema written by the encoders.
+pub const SCHEMA_VERSION: u8 = 2;
+/// Size of encoded measurements in bytes.
+pub const MEASUREMENTS_LEN: usize = 15;
+
+/// Errors which can occur while encoding or decoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CborError {
+    /// The output buffer can't hold the encoded record.
+    BufferTooSmall,
+    /// The data ends in the middle of an item.
+    Truncated,
+    /// The data isn't a record of the expected schema.
+    InvalidSchema,
+    /// The record was written with a newer schema version than [SCHEMA_VERSION].
+    UnsupportedVersion(u64),
+}
+
+impl From<encode::Error<EndOfSlice>> for CborError {
+    fn from(_: encode::Error<EndOfSlice>) -> Self {
+        // writing into a slice only fails at its end
+        Self::BufferTooSmall
+    }
+}
+
+impl From<decode::Error> for CborError {
+    fn from(e: decode::Error) -> Self {
+        if e.is_end_of_input() {
+            Self::Truncated
+        } else {
+            Self::InvalidSchema
+        }
+    }
+}
+
+/// Start a top-level record in `out` with `len` fields besides the schema version.
+fn record(out: &mut [u8], len: u64) -> Result<Encoder<Cursor<&mut [u8]>>, CborError> {
+    let mut e = Encoder::new(Cursor::new(out));
+    e.map(len + 1)?.u8(0)?.u8(SCHEMA_VERSION)?;
+    Ok(e)
+}
+
+/// Encode measurements into `out` and return the number of written bytes.
+pub fn encode_measurements(
+    measurements: &Measurements,
+    out: &mut [u8],
+) -> Result<usize, CborError> {
+    let mut e = record(out, 2)?;
+    e.u8(1)?.f32(measurements.co2)?;
+    e.u8(2)?.f32(measurements.voc)?;
+    Ok(e.writer().position())
+}
+
+/// Encode measurements tagged with the `identity` of the sensor into `out` and return the
+/// number of written bytes. Without identity this is the same as [encode_measurements()].
+pub fn encode_tagged_measurements(
+    measurements: &Measurements,
+    identity: Option<&SensorIdentity>,
+    out: &mut [u8],
+) -> Result<usize, CborError> {
+    let Some(identity) = identity else {
+        return encode_measurements(measurements, out);
+    };
+    let mut e = record(out, if identity.revision.is_some() { 4 } else { 3 })?;
+    e.u8(1)?.f32(measurements.co2)?;
+    e.u8(2)?.f32(measurements.voc)?;
+    e.u8(3)?.str(identity.label)?;
+    if let Some(revision) = &identity.revision {
+        e.u8(4)?.u32(revision.to_yyyymmdd())?;
+    }
+    Ok(e.writer().position())
+}
+
+/// Encode a diagnostics report into `out` and return the number of written bytes.
+pub fn encode_diagnostics(diagnostics: &Diagnostics, out: &mut [u8]) -> Result<usize, CborError> {
+    let mut e = record(out, 3)?;
+    e.u8(1)?;
+    encode_config(&mut e, &diagnostics.config)?;
+    e.u8(2)?;
+    match &diagnostics.last_calibration {
+        Some(c) => encode_calibration(&mut e, c)?,
+        None => {
+            e.null()?;
+        }
+    }
+    e.u8(3)?;
+    match &diagnostics.r0_drift {
+        Some(d) => encode_r0_drift(&mut e, d)?,
+        None => {
+            e.null()?;
+        }
+    }
+    Ok(e.writer().position())
+}
+
+fn encode_config(e: &mut Encoder<Cursor<&mut [u8]>>, config: &Config) -> Result<(), CborError> {
+    e.map(3)?;
+    e.u8(1)?.u8(config.address)?;
+    e.u8(2)?.u16(config.wait_time_ms)?;
+    e.u8(3)?.u8(config.retries)?;
+    Ok(())
+}
+
+fn encode_calibration(
+    e: &mut Encoder<Cursor<&mut [u8]>>,
+    calibration: &CalibrationRecord,
+) -> Result<(), CborError> {
+    e.map(3)?;
+    e.u8(1)?.f32(calibration.reference_ppm)?;
+    e.u8(2)?.u8(calibration.method as u8)?;
+    e.u8(3)?.encode(calibration.timestamp_ms)?;
+    Ok(())
+}
+
+fn encode_r0_drift(e: &mut Encoder<Cursor<&mut [u8]>>, drift: &R0Drift) -> Result<(), CborError> {
+    e.map(4)?;
+    e.u8(1)?.f32(drift.kohm_per_day)?;
+    e.u8(2)?.f32(drift.permille_per_day)?;
+    e.u8(3)?.encode(drift.days_until_recalibration)?;
+    e.u8(4)?.encode(drift.days_until_replacement)?;
+    Ok(())
+}
+
+/// Decode measurements written by [encode_measurements()] or [encode_tagged_measurements()] of
+/// schema version `1` up to [SCHEMA_VERSION]. Unknown keys are skipped whatever their value.
+pub fn decode_measurements(data: &[u8]) -> Result<Measurements, CborError> {
+    let mut d = Decoder::new(data);
+    let len = d.map()?.ok_or(CborError::InvalidSchema)?;
+    let (mut co2, mut voc, mut version) = (None, None, 1);
+    for _ in 0..len {
+        match d.u64()? {
+            0 => version = d.u64()?,
+            1 => co2 = Some(d.f32()?),
+            2 => voc = Some(d.f32()?),
+            _ => d.skip()?,
+        }
+    }
+    if version > u64::from(SCHEMA_VERSION) {
+        return Err(CborError::UnsupportedVersion(version));
+    }
+    Ok(Measurements {
+        co2: co2.ok_or(CborError::InvalidSchema)?,
+        voc: voc.ok_or(CborError::InvalidSchema)?,
+    })
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{
+        decode_measurements, encode_diagnostics, encode_measurements, encode_tagged_measurements,
+        CborError, MEASUREMENTS_LEN, SCHEMA_VERSION,
+    };
+    use crate::{
+        config::{CalibrationMethod, CalibrationRecord, Config},
+        diagnostics::Diagnostics,
+        identity::SensorIdentity,
+        Measurements, RevisionDate,
+    };
+    use core::assert_eq;
+
+    #[test]
+    fn test_measurements() {
+        let m = Measurements {
+            co2: 728.0,
+            voc: 0.5,
+        };
+        let mut out = [0u8; MEASUREMENTS_LEN];
+        assert_eq!(encode_measurements(&m, &mut out), Ok(MEASUREMENTS_LEN));
+        // {0: 2, 1: 728.0, 2: 0.5}
+        assert_eq!(
+            out,
+            [0xA3, 0x00, 0x02, 0x01, 0xFA, 0x44, 0x36, 0, 0, 0x02, 0xFA, 0x3F, 0, 0, 0]
+        );
+        assert_eq!(decode_measurements(&out), Ok(m));
+
+        // unknown key 3 with an unsigned value is skipped
+        let extended = [
+            0xA3, 0x01, 0xFA, 0x44, 0x36, 0, 0, 0x03, 0x19, 0x01, 0x00, 0x02, 0xFA, 0x3F, 0, 0, 0,
+        ];
+        assert_eq!(decode_measurements(&extended), Ok(m));
+        assert_eq!(decode_measurements(&out[..5]), Err(CborError::Truncated));
+        assert_eq!(
+            encode_measurements(&m, &mut [0u8; MEASUREMENTS_LEN - 1]),
+            Err(CborError::BufferTooSmall)
+        );
+    }
+
+    #[test]
+    fn test_skip_unknown_values() {
+        let m = Measurements {
+            co2: 728.0,
+            voc: 0.5,
+        };
+        let unknown_values: [&[u8]; 9] = [
+            &[0xF6],                                     // null
+            &[0x38, 0x63],                               // -100
+            &[0xA1, 0x01, 0x82, 0x01, 0xF5],             // {1: [1, true]}
+            &[0xF9, 0x3C, 0x00],                         // f16 1.0
+            &[0xFB, 0x3F, 0xF0, 0, 0, 0, 0, 0, 0],       // f64 1.0
+            &[0x42, 0x01, 0x02],                         // h'0102'
+            &[0xC1, 0x1A, 0x57, 0x5A, 0xA8, 0x00],       // epoch time tag
+            &[0x9F, 0x01, 0x7F, 0x61, b'a', 0xFF, 0xFF], // [_ 1, (_ "a")]
+            &[0xBF, 0x01, 0x02, 0xFF],                   // {_ 1: 2}
+        ];
+        for value in unknown_values {
+            let mut data = std::vec![0xA3, 0x01, 0xFA, 0x44, 0x36, 0, 0, 0x07];
+            data.extend_from_slice(value);
+            data.extend_from_slice(&[0x02, 0xFA, 0x3F, 0, 0, 0]);
+            assert_eq!(decode_measurements(&data), Ok(m), "{value:02X?}");
+        }
+
+        // text longer than 255 bytes
+        let mut data = std::vec![0xA3, 0x01, 0xFA, 0x44, 0x36, 0, 0, 0x07, 0x79, 0x01, 0x2C];
+        data.extend_from_slice(&[b'x'; 300]);
+        data.extend_from_slice(&[0x02, 0xFA, 0x3F, 0, 0, 0]);
+        assert_eq!(decode_measurements(&data), Ok(m));
+
+        // deeply nested
+        let mut data = std::vec![0xA3, 0x01, 0xFA, 0x44, 0x36, 0, 0, 0x07];
+        data.extend_from_slice(&[0x81; 100]);
+        data.push(0x00);
+        data.extend_from_slice(&[0x02, 0xFA, 0x3F, 0, 0, 0]);
+        assert_eq!(decode_measurements(&data), Ok(m));
+        // [[_ ], 1], skipped with a stack of lengths
+        let mut data = std::vec![0xA3, 0x01, 0xFA, 0x44, 0x36, 0, 0, 0x07];
+        data.extend_from_slice(&[0x82, 0x9F, 0xFF, 0x01]);
+        data.extend_from_slice(&[0x02, 0xFA, 0x3F, 0, 0, 0]);
+        if cfg!(feature = "alloc") {
+            assert_eq!(decode_measurements(&data), Ok(m));
+        } else {
+            assert_eq!(decode_measurements(&data), Err(CborError::InvalidSchema));
+        }
+        // truncated unknown value
+        assert_eq!(
+            decode_measurements(&[0xA3, 0x07, 0x5A, 0xFF, 0xFF, 0xFF, 0xFF]),
+            Err(CborError::Truncated)
+        );
+    }
+
+    #[test]
+    fn test_schema_version() {
+        let m = Measurements {
+            co2: 728.0,
+            voc: 0.5,
+        };
+        // version 1 without key 0
+        let legacy = [
+            0xA2, 0x01, 0xFA, 0x44, 0x36, 0, 0, 0x02, 0xFA, 0x3F, 0, 0, 0,
+        ];
+        assert_eq!(decode_measurements(&legacy), Ok(m));
+
+        let mut out = [0u8; MEASUREMENTS_LEN];
+        encode_measurements(&m, &mut out).unwrap();
+        assert_eq!(out[1..3], [0x00, SCHEMA_VERSION]);
+        out[2] = 0x03;
+        assert_eq!(
+            decode_measurements(&out),
+            Err(CborError::UnsupportedVersion(3))
+        );
+        // version with a float value
+        let invalid = [0xA1, 0x00, 0xFA, 0x40, 0, 0, 0];
+        assert_eq!(decode_measurements(&invalid), Err(CborError::InvalidSchema));
+    }
+
+    #[test]
+    fn test_tagged_measurements() {
+        let m = Measurements {
+            co2: 728.0,
+            voc: 0.5,
+        };
+        let identity = SensorIdentity::new("lab").with_revision(RevisionDate {
+            year: 2016,
+            month: 3,
+            day: 17,
+        });
+        let mut out = [0u8; 32];
+        let len = encode_tagged_measurements(&m, Some(&identity), &mut out).unwrap();
+        assert_eq!(
+            out[..len],
+            [
+                0xA5, 0x00, 0x02, // version
+                0x01, 0xFA, 0x44, 0x36, 0, 0, 0x02, 0xFA, 0x3F, 0, 0, 0, // measurements
+                0x03, 0x63, b'l', b'a', b'b', // label
+                0x04, 0x1A, 0x01, 0x33, 0x9F, 0x3D, // 20160317
+            ]
+        );
+        assert_eq!(decode_measurements(&out[..len]), Ok(m));
+
+        // labels of 256 bytes and more have a 2-byte length
+        let label = core::str::from_utf8(&[b'x'; 300]).unwrap();
+        let mut long = [0u8; 319];
+        let len = encode_tagged_measurements(&m, Some(&SensorIdentity::new(label)), &mut long);
+        assert_eq!(len, Ok(long.len()));
+        assert_eq!(long[15..18], [0x03, 0x79, 0x01]);
+        assert_eq!(decode_measurements(&long), Ok(m));
+
+        assert_eq!(
+            encode_tagged_measurements(&m, None, &mut out),
+            Ok(MEASUREMENTS_LEN)
+        );
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let diagnostics = Diagnostics {
+            config: Config::default(),
+            last_calibration: Some(CalibrationRecord {
+                reference_ppm: 420.0,
+                method: CalibrationMethod::FreshAir,
+                timestamp_ms: Some(86_400_000),
+            }),
+            r0_drift: None,
+        };
+        let mut out = [0u8; 64];
+        let len = encode_diagnostics(&diagnostics, &mut out).unwrap();
+        assert_eq!(
+            out[..len],
+            [
+                0xA4, 0x00, 0x02, // map(4), version
+                0x01, 0xA3, 0x01, 0x18, 0x70, 0x02, 0x18, 0x64, 0x03, 0x00, // config
+                0x02, 0xA3, 0x01, 0xFA, 0x43, 0xD2, 0, 0, 0x02, 0x01, 0x03, 0x1A, 0x05, 0x26, 0x5C,
+                0x00, // calibration
+                0x03, 0xF6, // no drift estimate
+            ]
+        );
+    }
+}
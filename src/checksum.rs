@@ -0,0 +1,155 @@
+//! Checksum failure statistics and the lenient checksum mode.
+//!
+//! The driver counts every response with a wrong checksum in [ChecksumStats]. Modules with a
+//! firmware checksum quirk answer every request with a wrong checksum and would be unusable.
+//! For them, [Config::lenient_checksum_after](crate::config::Config::lenient_checksum_after)
+//! opts into accepting such responses after a number of failures in a row. Only
+//! [MicsVz89Te::read_measurements_with_warnings()] accepts them, flagged with
+//! [Warnings::UNVERIFIED](crate::warnings::Warnings::UNVERIFIED). Every other read (revision,
+//! R0, the plain and higher level measurement reads) stays strict, as it can't flag the value,
+//! but its failures count towards the threshold.
+//!
+//! # Example Usage
+//! ```ignore
+//! let mut device = MicsVz89Te::builder(i2c).lenient_checksum(3).retries(2).build();
+//! let reading = device.read_measurements_with_warnings(&mut delay)?;
+//! if reading.warnings.contains(Warnings::UNVERIFIED) {
+//!     log::warn!("unverified reading, {} checksum failures", device.checksum_stats().failures);
+//! }
+//! ```
+
+use crate::MicsVz89Te;
+
+/// Statistics of the response checksums.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChecksumStats {
+    /// Number of responses with a wrong checksum.
+    pub failures: u32,
+    /// Number of responses with a wrong checksum since the last valid one.
+    pub consecutive_failures: u16,
+    /// Number of responses with a wrong checksum accepted in the lenient mode.
+    pub unverified: u32,
+    /// The last accepted response had a wrong checksum.
+    pub last_unverified: bool,
+}
+
+impl ChecksumStats {
+    /// Record a response with a valid checksum.
+    pub(crate) fn record_valid(&mut self) {
+        self.consecutive_failures = 0;
+        self.last_unverified = false;
+    }
+
+    /// Record a response with a wrong checksum. Returns `true` if it is accepted because
+    /// `lenient_after` (`0` disables the lenient mode) failures happened in a row.
+    pub(crate) fn record_failure(&mut self, lenient_after: u8) -> bool {
+        self.failures = self.failures.saturating_add(1);
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        let accept = lenient_after > 0 && self.consecutive_failures >= u16::from(lenient_after);
+        if accept {
+            self.unverified = self.unverified.saturating_add(1);
+            self.last_unverified = true;
+        }
+        accept
+    }
+}
+
+impl<I2C> MicsVz89Te<I2C> {
+    /// Statistics of the response checksums, see [ChecksumStats].
+    pub fn checksum_stats(&self) -> &ChecksumStats {
+        &self.checksum
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::{config::Config, error::PacketParseError, warnings::Warnings, MicsVz89Te};
+    use assert_matches::assert_matches;
+    use core::assert_eq;
+    use embedded_hal_mock::{
+        delay::MockNoop as DelayMock,
+        i2c::{Mock as I2cMock, Transaction as I2cTransaction},
+    };
+    use std::vec;
+
+    #[test]
+    fn test_lenient_checksum() {
+        let status = || I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]);
+        let corrupted = || I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x26]);
+        let expectations = [
+            status(),
+            corrupted(),
+            status(),
+            corrupted(),
+            status(),
+            corrupted(),
+            I2cTransaction::write(0x70, vec![0x0D, 0, 0, 0, 0, 0xF2]),
+            I2cTransaction::read(0x70, vec![0x10, 0x03, 0x11, 0x48, 0, 0, 0x92]),
+            status(),
+            corrupted(),
+            status(),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+        ];
+        let config = Config {
+            lenient_checksum_after: 2,
+            ..Config::default()
+        };
+        let mut device = MicsVz89Te::with_config(I2cMock::new(&expectations), config);
+        let mut delay = DelayMock::new();
+
+        assert_matches!(
+            device.read_measurements(&mut delay),
+            Err(PacketParseError::WrongChecksum)
+        );
+
+        let reading = device.read_measurements_with_warnings(&mut delay).unwrap();
+        assert_eq!(reading.measurements.co2_ppm_u16(), 728);
+        assert_eq!(reading.warnings, Warnings::UNVERIFIED);
+
+        // reads which can't flag the value stay strict, but count the failures
+        assert_matches!(
+            device.read_measurements(&mut delay),
+            Err(PacketParseError::WrongChecksum)
+        );
+        assert_matches!(
+            device.read_revision(&mut delay),
+            Err(PacketParseError::WrongChecksum)
+        );
+        assert_eq!(device.checksum_stats().consecutive_failures, 4);
+
+        // still accepted while the failures go on
+        let reading = device.read_measurements_with_warnings(&mut delay).unwrap();
+        assert_eq!(reading.warnings, Warnings::UNVERIFIED);
+        assert_eq!(device.checksum_stats().unverified, 2);
+
+        let reading = device.read_measurements_with_warnings(&mut delay).unwrap();
+        assert!(reading.warnings.is_empty());
+        let stats = device.checksum_stats();
+        assert_eq!((stats.failures, stats.consecutive_failures), (5, 0));
+        assert!(!stats.last_unverified);
+        device.release().done();
+    }
+
+    #[test]
+    fn test_strict_by_default() {
+        let expectations = [
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x26]),
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x26]),
+        ];
+        let mut device = MicsVz89Te::new(I2cMock::new(&expectations));
+        let mut delay = DelayMock::new();
+
+        for _ in 0..2 {
+            assert_matches!(
+                device.read_measurements(&mut delay),
+                Err(PacketParseError::WrongChecksum)
+            );
+        }
+        assert_eq!(device.checksum_stats().consecutive_failures, 2);
+        assert_eq!(device.checksum_stats().unverified, 0);
+        device.release().done();
+    }
+}
@@ -0,0 +1,116 @@
+//! Monotonic time source used for timestamps.
+
+use crate::{
+    delay::Delay, error::PacketParseError, transport::Transport, Measurements, MicsVz89Te,
+};
+
+/// Monotonic clock returning the time in millis since an arbitrary start.
+///
+/// Implemented for closures, so e.g. `|| timer.millis()` can be used as clock.
+pub trait Clock {
+    /// Current time in millis.
+    fn now_ms(&self) -> u64;
+}
+
+impl<F> Clock for F
+where
+    F: Fn() -> u64,
+{
+    fn now_ms(&self) -> u64 {
+        self()
+    }
+}
+
+/// A value with the time (in millis) it was acquired at.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timestamped<T> {
+    pub timestamp_ms: u64,
+    pub value: T,
+}
+
+impl<T> Timestamped<T> {
+    /// Create a value acquired at `timestamp_ms`.
+    pub const fn new(timestamp_ms: u64, value: T) -> Self {
+        Self {
+            timestamp_ms,
+            value,
+        }
+    }
+
+    /// Time (in millis) since the value was acquired.
+    pub fn age(&self, clock: &impl Clock) -> u64 {
+        age(self.timestamp_ms, clock)
+    }
+
+    /// Returns `true` if the value is older than `max_age_ms`.
+    pub fn is_stale(&self, clock: &impl Clock, max_age_ms: u64) -> bool {
+        self.age(clock) > max_age_ms
+    }
+}
+
+/// Time (in millis) since `timestamp_ms`, `0` if it lies in the future.
+pub(crate) fn age(timestamp_ms: u64, clock: &impl Clock) -> u64 {
+    clock.now_ms().saturating_sub(timestamp_ms)
+}
+
+impl<I2C, E> MicsVz89Te<I2C>
+where
+    I2C: Transport<Error = E>,
+{
+    /// Read measurements like [MicsVz89Te::read_measurements()] and attach the time they were
+    /// requested at.
+    ///
+    /// This function blocks a minimum time of [MicsVz89Te::WAIT_ON_RESPONSE_TIME].
+    pub fn read_measurements_timestamped(
+        &mut self,
+        delay: &mut impl Delay,
+        clock: &impl Clock,
+    ) -> Result<Timestamped<Measurements>, PacketParseError<E>> {
+        let timestamp_ms = clock.now_ms();
+        self.read_measurements(delay)
+            .map(|m| Timestamped::new(timestamp_ms, m))
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::Timestamped;
+    use crate::MicsVz89Te;
+    use core::{assert_eq, cell::Cell};
+    use embedded_hal_mock::{
+        delay::MockNoop as DelayMock,
+        i2c::{Mock as I2cMock, Transaction as I2cTransaction},
+    };
+    use std::vec;
+
+    #[test]
+    fn test_age() {
+        let time = Cell::new(5000);
+        let clock = || time.get();
+        let value = Timestamped::new(4000, ());
+
+        assert_eq!(value.age(&clock), 1000);
+        assert!(!value.is_stale(&clock, 1000));
+        time.set(5001);
+        assert!(value.is_stale(&clock, 1000));
+        time.set(0);
+        assert_eq!(value.age(&clock), 0);
+    }
+
+    #[test]
+    fn test_read_measurements_timestamped() {
+        let expectations = [
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+        ];
+        let mut device = MicsVz89Te::new(I2cMock::new(&expectations));
+        let clock = || 1234;
+
+        let m = device
+            .read_measurements_timestamped(&mut DelayMock::new(), &clock)
+            .unwrap();
+        assert_eq!(m.timestamp_ms, 1234);
+        device.release().done();
+    }
+}
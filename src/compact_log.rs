@@ -0,0 +1,269 @@
+//! Compact append-only log format for measurements.
+//!
+//! A log starts with a header of [HEADER_LEN] bytes followed by records. Values are stored
+//! with a resolution of 1 ppm (CO2) and 1 ppb (VOC).
+//!
+//! Header (version 1):
+//!
+//! | byte | content                     |
+//! |------|-----------------------------|
+//! | 0..2 | magic `b"MV"`               |
+//! | 2    | format version              |
+//! | 3    | keyframe interval (records) |
+//!
+//! Records (version 1):
+//!
+//! - keyframe: `0x80`, CO2 as `u16` LE, VOC as `u16` LE (5 bytes)
+//! - delta: CO2 delta as `i8` (never `-128`), VOC delta as `i8` (2 bytes)
+//!
+//! A keyframe is written for the first record, every `keyframe interval` records and whenever
+//! a delta doesn't fit into an `i8`.
+
+use crate::Measurements;
+
+/// Version written by [Encoder].
+pub const FORMAT_VERSION: u8 = 1;
+/// Size of the log header in bytes.
+pub const HEADER_LEN: usize = 4;
+/// Maximum size of a single record in bytes.
+pub const MAX_RECORD_LEN: usize = 5;
+
+const MAGIC: [u8; 2] = *b"MV";
+const KEYFRAME_TAG: u8 = 0x80;
+
+/// Errors which can occur while encoding or decoding a log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogError {
+    /// The output buffer can't hold the record.
+    BufferTooSmall,
+    /// The data doesn't start with a valid header.
+    InvalidHeader,
+    /// The log was written in a version this decoder doesn't know.
+    UnsupportedVersion(u8),
+    /// The log ends in the middle of a record.
+    Truncated,
+    /// A delta record was found before the first keyframe.
+    MissingKeyframe,
+}
+
+/// Encodes measurements into log records.
+#[derive(Debug, Clone)]
+pub struct Encoder {
+    keyframe_interval: u8,
+    since_keyframe: u8,
+    last: Option<(u16, u16)>,
+}
+
+impl Encoder {
+    /// Create an encoder writing a keyframe at least every `keyframe_interval` records.
+    /// An interval of `0` is treated as `1` (keyframes only).
+    pub const fn new(keyframe_interval: u8) -> Self {
+        Self {
+            keyframe_interval: if keyframe_interval == 0 {
+                1
+            } else {
+                keyframe_interval
+            },
+            since_keyframe: 0,
+            last: None,
+        }
+    }
+
+    /// Header to write at the start of the log.
+    pub fn header(&self) -> [u8; HEADER_LEN] {
+        [MAGIC[0], MAGIC[1], FORMAT_VERSION, self.keyframe_interval]
+    }
+
+    /// Encode a record into `out` and return the number of written bytes.
+    pub fn encode(
+        &mut self,
+        measurements: &Measurements,
+        out: &mut [u8],
+    ) -> Result<usize, LogError> {
+        let value = (measurements.co2_ppm_u16(), measurements.voc_ppb_u16());
+
+        let delta = match self.last {
+            Some(last) if self.since_keyframe < self.keyframe_interval => {
+                delta(last.0, value.0).zip(delta(last.1, value.1))
+            }
+            _ => None,
+        };
+
+        let len = match delta {
+            Some((co2, voc)) => {
+                let record = out.get_mut(..2).ok_or(LogError::BufferTooSmall)?;
+                record.copy_from_slice(&[co2 as u8, voc as u8]);
+                self.since_keyframe += 1;
+                2
+            }
+            None => {
+                let record = out.get_mut(..5).ok_or(LogError::BufferTooSmall)?;
+                let [co2_l, co2_h] = value.0.to_le_bytes();
+                let [voc_l, voc_h] = value.1.to_le_bytes();
+                record.copy_from_slice(&[KEYFRAME_TAG, co2_l, co2_h, voc_l, voc_h]);
+                self.since_keyframe = 1;
+                5
+            }
+        };
+        self.last = Some(value);
+        Ok(len)
+    }
+}
+
+fn delta(from: u16, to: u16) -> Option<i8> {
+    let delta = i32::from(to) - i32::from(from);
+    match i8::try_from(delta) {
+        Ok(d) if d != i8::MIN => Some(d),
+        _ => None,
+    }
+}
+
+/// Decodes a log, yielding one measurement per record.
+#[derive(Debug, Clone)]
+pub struct Decoder<'a> {
+    version: u8,
+    data: &'a [u8],
+    last: Option<(u16, u16)>,
+}
+
+impl<'a> Decoder<'a> {
+    /// Create a decoder over a complete log including its header.
+    pub fn new(log: &'a [u8]) -> Result<Self, LogError> {
+        let [m0, m1, version, _, data @ ..] = log else {
+            return Err(LogError::InvalidHeader);
+        };
+        if [*m0, *m1] != MAGIC {
+            return Err(LogError::InvalidHeader);
+        }
+        match *version {
+            1 => Ok(Self {
+                version: 1,
+                data,
+                last: None,
+            }),
+            v => Err(LogError::UnsupportedVersion(v)),
+        }
+    }
+
+    /// Format version of the decoded log.
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn decode_v1(&mut self) -> Result<(u16, u16), LogError> {
+        match self.data {
+            [KEYFRAME_TAG, co2_l, co2_h, voc_l, voc_h, rest @ ..] => {
+                self.data = rest;
+                Ok((
+                    u16::from_le_bytes([*co2_l, *co2_h]),
+                    u16::from_le_bytes([*voc_l, *voc_h]),
+                ))
+            }
+            [KEYFRAME_TAG, ..] => Err(LogError::Truncated),
+            [co2, voc, rest @ ..] => {
+                let last = self.last.ok_or(LogError::MissingKeyframe)?;
+                self.data = rest;
+                Ok((
+                    last.0.wrapping_add_signed(i16::from(*co2 as i8)),
+                    last.1.wrapping_add_signed(i16::from(*voc as i8)),
+                ))
+            }
+            _ => Err(LogError::Truncated),
+        }
+    }
+}
+
+impl Iterator for Decoder<'_> {
+    type Item = Result<Measurements, LogError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+        match self.decode_v1() {
+            Ok(value) => {
+                self.last = Some(value);
+                Some(Ok(Measurements {
+                    co2: f32::from(value.0),
+                    voc: f32::from(value.1),
+                }))
+            }
+            Err(e) => {
+                self.data = &[];
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{Decoder, Encoder, LogError, HEADER_LEN};
+    use crate::Measurements;
+    use assert_matches::assert_matches;
+    use core::assert_eq;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_encode_decode() {
+        let samples = [
+            (728.0, 113.0),
+            (735.0, 109.0),
+            (742.0, 109.0),
+            (1800.0, 20.0),
+            (1795.0, 25.0),
+            (1790.0, 30.0),
+        ];
+        let mut encoder = Encoder::new(2);
+        let mut log = Vec::from(encoder.header());
+        for (co2, voc) in samples {
+            let mut record = [0u8; super::MAX_RECORD_LEN];
+            let len = encoder
+                .encode(&Measurements { co2, voc }, &mut record)
+                .unwrap();
+            log.extend_from_slice(&record[..len]);
+        }
+
+        // keyframe, delta, keyframe (interval), keyframe (delta too large), delta, keyframe (interval)
+        assert_eq!(log.len(), HEADER_LEN + 5 + 2 + 5 + 5 + 2 + 5);
+
+        let decoder = Decoder::new(&log).unwrap();
+        assert_eq!(decoder.version(), 1);
+        let decoded = decoder
+            .map(|m| m.map(|m| (m.co2, m.voc)))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(decoded, samples);
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert_matches!(Decoder::new(b"XX\x01\x04"), Err(LogError::InvalidHeader));
+        assert_matches!(
+            Decoder::new(b"MV\x07\x04"),
+            Err(LogError::UnsupportedVersion(7))
+        );
+
+        let mut decoder = Decoder::new(b"MV\x01\x04\x01\x02").unwrap();
+        assert_matches!(decoder.next(), Some(Err(LogError::MissingKeyframe)));
+        assert!(decoder.next().is_none());
+
+        let mut decoder = Decoder::new(b"MV\x01\x04\x80\xD8\x02").unwrap();
+        assert_matches!(decoder.next(), Some(Err(LogError::Truncated)));
+    }
+
+    #[test]
+    fn test_encode_buffer_too_small() {
+        let mut encoder = Encoder::new(4);
+        let mut record = [0u8; 4];
+        let res = encoder.encode(
+            &Measurements {
+                co2: 400.0,
+                voc: 0.0,
+            },
+            &mut record,
+        );
+        assert_matches!(res, Err(LogError::BufferTooSmall));
+    }
+}
@@ -0,0 +1,383 @@
+//! Driver configuration and state snapshots.
+//!
+//! A [StateSnapshot] captures everything needed to restore a driver, e.g. after a deep-sleep
+//! cycle. It can be stored as bytes with [StateSnapshot::to_bytes()] and restored with
+//! [StateSnapshot::from_bytes()] and [MicsVz89Te::apply()](crate::MicsVz89Te::apply).
+//! Snapshots written by previous versions of this crate can still be read.
+//!
+//! With the `serde` feature, [Config], [StateSnapshot] and the [CalibrationRecord] implement
+//! `Serialize` and `Deserialize`, e.g. to store them with postcard.
+//! [Measurements](crate::Measurements) implements them too. The serde derives carry no schema
+//! version: they are meant for state read back by the same firmware, e.g. across deep-sleep
+//! cycles. Data exchanged with other firmware or the cloud should use the versioned
+//! `cbor` or [wire](crate::wire) records.
+
+use core::num::NonZeroU8;
+
+use crate::{MICS_VZ_89TE_ADDR, MICS_VZ_89TE_WAIT_TIME_MS, RAW_MIN, RAW_SPAN};
+
+/// Configuration of the driver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Config {
+    /// I2C address of the sensor.
+    pub address: u8,
+    /// Time (in millis) to wait between a request and reading the response.
+    pub wait_time_ms: u16,
+    /// Number of times a failed request is repeated before the error is returned.
+    pub retries: u8,
+    /// Clamp measurements to [CO2_RANGE_PPM](crate::CO2_RANGE_PPM) and
+    /// [VOC_RANGE_PPB](crate::VOC_RANGE_PPB). Clamped channels are reported by
+    /// [MicsVz89Te::last_clamped()](crate::MicsVz89Te::last_clamped()).
+    pub strict_range: bool,
+    /// Raw value of the lower end of the ranges, [RAW_RANGE](crate::RAW_RANGE) by default.
+    /// Characterized batches with a different floor can override it.
+    pub raw_offset: u8,
+    /// Number of raw steps from the lower to the upper end of the ranges, `229` by default.
+    pub raw_span: NonZeroU8,
+    /// Accept responses with a wrong checksum as unverified once this many checksums in a row
+    /// were wrong, only in
+    /// [MicsVz89Te::read_measurements_with_warnings()](crate::MicsVz89Te::read_measurements_with_warnings()),
+    /// see [checksum](crate::checksum). `0` (default) rejects them all.
+    pub lenient_checksum_after: u8,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            address: MICS_VZ_89TE_ADDR,
+            wait_time_ms: MICS_VZ_89TE_WAIT_TIME_MS,
+            retries: 0,
+            strict_range: false,
+            raw_offset: RAW_MIN,
+            raw_span: RAW_SPAN,
+            lenient_checksum_after: 0,
+        }
+    }
+}
+
+/// How a calibration was triggered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+pub enum CalibrationMethod {
+    /// Reference value supplied by the application, e.g. measured by another device.
+    Manual = 0,
+    /// Automatic recalibration assuming fresh air.
+    FreshAir = 1,
+}
+
+impl TryFrom<u8> for CalibrationMethod {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Manual),
+            1 => Ok(Self::FreshAir),
+            v => Err(v),
+        }
+    }
+}
+
+/// Record of the last calibration written to the sensor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CalibrationRecord {
+    /// CO2 value (in ppm) written to the sensor.
+    pub reference_ppm: f32,
+    pub method: CalibrationMethod,
+    /// Time (in millis) of the calibration, if a clock was supplied.
+    pub timestamp_ms: Option<u64>,
+}
+
+impl CalibrationRecord {
+    /// Size of the serialized record in bytes.
+    pub const SERIALIZED_LEN: usize = 14;
+
+    /// Serialize the record.
+    ///
+    /// Layout: method, timestamp flag, reference ppm (`f32` LE), timestamp (`u64` LE).
+    pub fn to_bytes(&self) -> [u8; Self::SERIALIZED_LEN] {
+        let mut bytes = [0u8; Self::SERIALIZED_LEN];
+        bytes[0] = self.method as u8;
+        bytes[1] = u8::from(self.timestamp_ms.is_some());
+        bytes[2..6].copy_from_slice(&self.reference_ppm.to_le_bytes());
+        bytes[6..].copy_from_slice(&self.timestamp_ms.unwrap_or(0).to_le_bytes());
+        bytes
+    }
+
+    /// Deserialize a record written by [CalibrationRecord::to_bytes()].
+    ///
+    /// Returns `None` if the data is too short or invalid.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let [method, has_timestamp, p0, p1, p2, p3, t0, t1, t2, t3, t4, t5, t6, t7, ..] = *bytes
+        else {
+            return None;
+        };
+        Some(Self {
+            reference_ppm: f32::from_le_bytes([p0, p1, p2, p3]),
+            method: CalibrationMethod::try_from(method).ok()?,
+            timestamp_ms: (has_timestamp != 0)
+                .then(|| u64::from_le_bytes([t0, t1, t2, t3, t4, t5, t6, t7])),
+        })
+    }
+}
+
+/// Snapshot of the driver configuration and calibration state.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StateSnapshot {
+    pub config: Config,
+    /// Last calibration written to the sensor by this driver.
+    pub calibration: Option<CalibrationRecord>,
+}
+
+impl StateSnapshot {
+    /// Version of the byte layout written by [StateSnapshot::to_bytes()].
+    pub const VERSION: u8 = 5;
+    /// Size of the serialized snapshot in bytes.
+    pub const SERIALIZED_LEN: usize = 10 + CalibrationRecord::SERIALIZED_LEN;
+
+    /// Bit of the flags byte set if [Config::strict_range] is enabled.
+    const FLAG_STRICT_RANGE: u8 = 1 << 0;
+
+    /// Serialize the snapshot.
+    ///
+    /// Layout: version, address, wait time (`u16` LE), retries, flags, raw offset, raw span,
+    /// lenient checksum threshold, calibration flag, calibration record (see
+    /// [CalibrationRecord::to_bytes()]).
+    pub fn to_bytes(&self) -> [u8; Self::SERIALIZED_LEN] {
+        let mut bytes = [0u8; Self::SERIALIZED_LEN];
+        let [wait_l, wait_h] = self.config.wait_time_ms.to_le_bytes();
+        let flags = if self.config.strict_range {
+            Self::FLAG_STRICT_RANGE
+        } else {
+            0
+        };
+        bytes[..10].copy_from_slice(&[
+            Self::VERSION,
+            self.config.address,
+            wait_l,
+            wait_h,
+            self.config.retries,
+            flags,
+            self.config.raw_offset,
+            self.config.raw_span.get(),
+            self.config.lenient_checksum_after,
+            u8::from(self.calibration.is_some()),
+        ]);
+        if let Some(calibration) = self.calibration {
+            bytes[10..].copy_from_slice(&calibration.to_bytes());
+        }
+        bytes
+    }
+
+    /// Deserialize a snapshot written by [StateSnapshot::to_bytes()] of this or a previous
+    /// version.
+    ///
+    /// Returns `None` if the data is too short, invalid (e.g. a raw span of `0`) or of an unknown
+    /// version.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let [version, address, wait_l, wait_h, retries, ref rest @ ..] = *bytes else {
+            return None;
+        };
+        // versions before 3 have no flags byte, versions before 4 no raw offset and span and
+        // versions before 5 no lenient checksum threshold
+        let defaults = Config::default();
+        let (flags, raw_offset, raw_span, lenient, rest) = match (version, rest) {
+            (1 | 2, rest) => (0, defaults.raw_offset, defaults.raw_span, 0, rest),
+            (3, [flags, rest @ ..]) => (*flags, defaults.raw_offset, defaults.raw_span, 0, rest),
+            (4, [flags, offset, span, rest @ ..]) => {
+                (*flags, *offset, NonZeroU8::new(*span)?, 0, rest)
+            }
+            (5, [flags, offset, span, lenient, rest @ ..]) => {
+                (*flags, *offset, NonZeroU8::new(*span)?, *lenient, rest)
+            }
+            _ => return None,
+        };
+        let [calibrated, ref record @ ..] = *rest else {
+            return None;
+        };
+        let config = Config {
+            address,
+            wait_time_ms: u16::from_le_bytes([wait_l, wait_h]),
+            retries,
+            strict_range: flags & Self::FLAG_STRICT_RANGE != 0,
+            raw_offset,
+            raw_span,
+            lenient_checksum_after: lenient,
+        };
+        let calibration = match (version, calibrated != 0, record) {
+            (_, false, _) => None,
+            (1, true, [c0, c1, c2, c3, ..]) => Some(CalibrationRecord {
+                reference_ppm: f32::from_le_bytes([*c0, *c1, *c2, *c3]),
+                method: CalibrationMethod::Manual,
+                timestamp_ms: None,
+            }),
+            (2..=5, true, record) => Some(CalibrationRecord::from_bytes(record)?),
+            _ => return None,
+        };
+        Some(Self {
+            config,
+            calibration,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{CalibrationMethod, CalibrationRecord, Config, StateSnapshot};
+    use core::{assert_eq, num::NonZeroU8};
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let snapshot = StateSnapshot {
+            config: Config {
+                address: 0x71,
+                wait_time_ms: 150,
+                retries: 2,
+                strict_range: true,
+                raw_offset: 15,
+                raw_span: NonZeroU8::new(225).unwrap(),
+                lenient_checksum_after: 3,
+            },
+            calibration: Some(CalibrationRecord {
+                reference_ppm: 812.5,
+                method: CalibrationMethod::FreshAir,
+                timestamp_ms: Some(86_400_000),
+            }),
+        };
+
+        let bytes = snapshot.to_bytes();
+        assert_eq!(StateSnapshot::from_bytes(&bytes), Some(snapshot));
+
+        let mut unknown_version = bytes;
+        unknown_version[0] = 0;
+        assert_eq!(StateSnapshot::from_bytes(&unknown_version), None);
+        assert_eq!(StateSnapshot::from_bytes(&bytes[..4]), None);
+
+        let mut zero_span = bytes;
+        zero_span[7] = 0;
+        assert_eq!(StateSnapshot::from_bytes(&zero_span), None);
+    }
+
+    #[test]
+    fn test_snapshot_from_version_4() {
+        let mut bytes = [0u8; 9 + CalibrationRecord::SERIALIZED_LEN];
+        bytes[..9].copy_from_slice(&[4, 0x71, 150, 0, 2, 1, 15, 225, 1]);
+        let record = CalibrationRecord {
+            reference_ppm: 812.5,
+            method: CalibrationMethod::FreshAir,
+            timestamp_ms: Some(86_400_000),
+        };
+        bytes[9..].copy_from_slice(&record.to_bytes());
+
+        let snapshot = StateSnapshot::from_bytes(&bytes).unwrap();
+
+        assert_eq!(
+            snapshot.config,
+            Config {
+                address: 0x71,
+                wait_time_ms: 150,
+                retries: 2,
+                strict_range: true,
+                raw_offset: 15,
+                raw_span: NonZeroU8::new(225).unwrap(),
+                lenient_checksum_after: 0,
+            }
+        );
+        assert_eq!(snapshot.calibration, Some(record));
+    }
+
+    #[test]
+    fn test_snapshot_from_version_3() {
+        let mut bytes = [0u8; 7 + CalibrationRecord::SERIALIZED_LEN];
+        bytes[..7].copy_from_slice(&[3, 0x71, 150, 0, 2, 1, 0]);
+
+        let snapshot = StateSnapshot::from_bytes(&bytes).unwrap();
+
+        assert_eq!(
+            snapshot.config,
+            Config {
+                address: 0x71,
+                wait_time_ms: 150,
+                retries: 2,
+                strict_range: true,
+                ..Config::default()
+            }
+        );
+        assert_eq!(snapshot.config.raw_offset, 13);
+        assert_eq!(snapshot.calibration, None);
+    }
+
+    #[test]
+    fn test_snapshot_from_version_2() {
+        let mut bytes = [0u8; 6 + CalibrationRecord::SERIALIZED_LEN];
+        bytes[..6].copy_from_slice(&[2, 0x71, 150, 0, 2, 1]);
+        let record = CalibrationRecord {
+            reference_ppm: 812.5,
+            method: CalibrationMethod::FreshAir,
+            timestamp_ms: None,
+        };
+        bytes[6..].copy_from_slice(&record.to_bytes());
+
+        let snapshot = StateSnapshot::from_bytes(&bytes).unwrap();
+
+        assert_eq!(
+            snapshot.config,
+            Config {
+                address: 0x71,
+                wait_time_ms: 150,
+                retries: 2,
+                ..Config::default()
+            }
+        );
+        assert_eq!(snapshot.calibration, Some(record));
+    }
+
+    #[test]
+    fn test_snapshot_from_version_1() {
+        let mut bytes = [1, 0x70, 100, 0, 0, 1, 0, 0, 0, 0];
+        bytes[6..].copy_from_slice(&812.5f32.to_le_bytes());
+
+        let snapshot = StateSnapshot::from_bytes(&bytes).unwrap();
+
+        assert_eq!(snapshot.config, Config::default());
+        assert_eq!(
+            snapshot.calibration,
+            Some(CalibrationRecord {
+                reference_ppm: 812.5,
+                method: CalibrationMethod::Manual,
+                timestamp_ms: None,
+            })
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_snapshot_postcard() {
+        let snapshot = StateSnapshot {
+            config: Config {
+                raw_span: NonZeroU8::new(225).unwrap(),
+                ..Config::default()
+            },
+            calibration: Some(CalibrationRecord {
+                reference_ppm: 400.0,
+                method: CalibrationMethod::Manual,
+                timestamp_ms: None,
+            }),
+        };
+        let mut buffer = [0u8; 64];
+        let bytes = postcard::to_slice(&snapshot, &mut buffer).unwrap();
+        assert_eq!(postcard::from_bytes::<StateSnapshot>(bytes), Ok(snapshot));
+
+        // a zero raw span is rejected like by `from_bytes`, the wait time is a one byte varint
+        let mut config = postcard::to_slice(&Config::default(), &mut buffer)
+            .unwrap()
+            .to_vec();
+        assert_eq!(config[5], 229);
+        config[5] = 0;
+        assert!(postcard::from_bytes::<Config>(&config).is_err());
+    }
+}
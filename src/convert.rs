@@ -0,0 +1,97 @@
+//! Pure conversion functions between raw sensor bytes and concentrations.
+//!
+//! These are the exact formulas the driver uses, exposed as `const fn` so host tools, tests and
+//! FFI consumers can share them instead of re-deriving the math. Raw bytes below the raw offset
+//! saturate at the lower end of the range.
+//!
+//! # Example Usage
+//! ```ignore
+//! const FLOOR_PPM: f32 = convert::convert_co2(13); // 400.0
+//! let co2 = convert::convert_co2(response[1]);
+//! let voc = convert::convert_voc_scaled(response[0], config.raw_offset, config.raw_span);
+//! let raw = convert::calibration_byte(1000.0);
+//! ```
+
+use core::num::NonZeroU8;
+
+use crate::{CO2_MAX, CO2_MIN, RAW_MIN, RAW_SPAN, VOC_MAX, VOC_MIN};
+
+/// Convert a raw CO2 byte to ppm with the datasheet offset and span.
+pub const fn convert_co2(raw: u8) -> f32 {
+    convert_co2_scaled(raw, RAW_MIN, RAW_SPAN)
+}
+
+/// Convert a raw VOC byte to ppb with the datasheet offset and span.
+pub const fn convert_voc(raw: u8) -> f32 {
+    convert_voc_scaled(raw, RAW_MIN, RAW_SPAN)
+}
+
+/// Convert a raw CO2 byte to ppm with a custom raw offset and span, see
+/// [Config::raw_offset](crate::config::Config::raw_offset).
+pub const fn convert_co2_scaled(raw: u8, offset: u8, span: NonZeroU8) -> f32 {
+    scale_raw(above(raw, offset), span, CO2_MIN, CO2_MAX)
+}
+
+/// Convert a raw VOC byte to ppb with a custom raw offset and span, see
+/// [Config::raw_offset](crate::config::Config::raw_offset).
+pub const fn convert_voc_scaled(raw: u8, offset: u8, span: NonZeroU8) -> f32 {
+    scale_raw(above(raw, offset), span, VOC_MIN, VOC_MAX)
+}
+
+/// Raw byte written by the calibration command for a CO2 value in ppm. The value is clamped
+/// to [CO2_RANGE_PPM](crate::CO2_RANGE_PPM) and truncated to the raw step below.
+pub const fn calibration_byte(ppm: f32) -> u8 {
+    let ppm = ppm.clamp(CO2_MIN, CO2_MAX);
+    ((ppm - CO2_MIN) / ((CO2_MAX - CO2_MIN) / RAW_SPAN.get() as f32) + RAW_MIN as f32) as u8
+}
+
+const fn above(raw: u8, offset: u8) -> f32 {
+    (raw as f32 - offset as f32).max(0.0)
+}
+
+/// Map a raw value above the raw offset linearly, `span` raw steps covering `min` to `max`.
+pub(crate) const fn scale_raw(raw: f32, span: NonZeroU8, min: f32, max: f32) -> f32 {
+    raw * ((max - min) / span.get() as f32) + min
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{calibration_byte, convert_co2, convert_co2_scaled, convert_voc};
+    use crate::{fixed::IntMeasurements, protocol::Command, raw_to_voc, Measurements};
+    use core::{assert_eq, num::NonZeroU8};
+
+    const FLOOR_PPM: f32 = convert_co2(13);
+
+    #[test]
+    fn test_matches_other_conversions() {
+        assert_eq!(FLOOR_PPM, 400.0);
+        for raw in 0..=u8::MAX {
+            let m = Measurements {
+                co2: convert_co2(raw),
+                voc: convert_voc(raw),
+            };
+            let int = IntMeasurements::from_response(&[raw, raw, 0, 0, 0, 0, 0]);
+            assert_eq!(
+                (m.co2_ppm_u16(), m.voc_ppb_u16()),
+                (int.co2_ppm, int.voc_ppb)
+            );
+            assert_eq!(m.voc, raw_to_voc(f32::from(raw)));
+        }
+        assert_eq!(convert_co2(242), 2000.0);
+        let span = NonZeroU8::new(225).unwrap();
+        assert_eq!(convert_co2_scaled(10, 15, span), 400.0);
+        assert_eq!(convert_co2_scaled(20, 15, NonZeroU8::MIN), 8400.0);
+    }
+
+    #[test]
+    fn test_calibration_byte() {
+        assert_eq!(calibration_byte(1000.0), 0x62);
+        assert_eq!(calibration_byte(0.0), 13);
+        assert_eq!(calibration_byte(5000.0), 242);
+        assert_eq!(
+            Command::set_calibration_ppm(812.5),
+            Command::SetCalibrationPpm(calibration_byte(812.5))
+        );
+    }
+}
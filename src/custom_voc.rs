@@ -0,0 +1,277 @@
+//! Hook for custom VOC algorithms working on the raw sensor resistance.
+//!
+//! The sensor derives its CO2 equivalent and VOC values with an internal algorithm. For research
+//! purposes a different mapping may be wanted. [CustomVoc] reads the raw resistance `Rs` with
+//! the measurements, smooths it, normalizes it to the clean-air resistance `R0` and passes the
+//! ratio `Rs / R0` to a user supplied [VocAlgorithm]. The result replaces the VOC value of the
+//! sensor, the CO2 value is kept.
+//!
+//! If no `R0` is given, the highest smoothed resistance seen so far is used, as the resistance
+//! of the sensor drops with rising VOC concentration.
+//!
+//! [CustomVoc::with_resistance()] turns it into a [MeasurementFilter] stage reading the
+//! resistance from a closure, e.g. when the resistance is read by another task.
+//!
+//! # Example Usage
+//! ```ignore
+//! // power law fitted to a reference instrument
+//! let algorithm = |ratio: f32, _: &Measurements| 25.0 * (1.0 / ratio - 1.0) * (1.0 / ratio);
+//! let mut voc = CustomVoc::new(algorithm).with_smoothing(0.3);
+//! loop {
+//!     let measurements = voc.read(&mut device, &mut delay)?;
+//! }
+//! ```
+
+use crate::{
+    delay::Delay, error::PacketParseError, filter::MeasurementFilter, transport::Transport,
+    Measurements, MicsVz89Te,
+};
+
+/// A mapping from the normalized sensor resistance to a VOC value.
+pub trait VocAlgorithm {
+    /// VOC value (in ppb) for the resistance ratio `Rs / R0`. `measurements` are the values
+    /// computed by the sensor, e.g. to augment instead of replace its algorithm.
+    fn voc(&mut self, ratio: f32, measurements: &Measurements) -> f32;
+}
+
+impl<F> VocAlgorithm for F
+where
+    F: FnMut(f32, &Measurements) -> f32,
+{
+    fn voc(&mut self, ratio: f32, measurements: &Measurements) -> f32 {
+        self(ratio, measurements)
+    }
+}
+
+/// Applies a [VocAlgorithm] to the raw sensor resistance.
+#[derive(Debug, Clone)]
+pub struct CustomVoc<A> {
+    algorithm: A,
+    r0_ohm: Option<f32>,
+    fixed_r0: bool,
+    alpha: f32,
+    resistance_ohm: Option<f32>,
+}
+
+impl<A: VocAlgorithm> CustomVoc<A> {
+    /// Create a hook without smoothing, tracking `R0` as the highest resistance seen.
+    pub fn new(algorithm: A) -> Self {
+        Self {
+            algorithm,
+            r0_ohm: None,
+            fixed_r0: false,
+            alpha: 1.0,
+            resistance_ohm: None,
+        }
+    }
+
+    /// Use a fixed clean-air resistance `R0` (in Ohms) instead of tracking it.
+    pub fn with_r0(mut self, r0_ohm: u32) -> Self {
+        self.set_r0(r0_ohm);
+        self
+    }
+
+    /// Smooth the resistance with an exponential moving average with factor `alpha`, clamped
+    /// to `0.0..=1.0`. See [ExponentialSmoothing](crate::filter::ExponentialSmoothing).
+    pub fn with_smoothing(mut self, alpha: f32) -> Self {
+        self.alpha = alpha.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set a fixed clean-air resistance `R0` (in Ohms), e.g. after a calibration.
+    pub fn set_r0(&mut self, r0_ohm: u32) {
+        self.r0_ohm = Some(r0_ohm as f32);
+        self.fixed_r0 = true;
+    }
+
+    /// The clean-air resistance `R0` (in Ohms) used for normalization.
+    pub fn r0_ohm(&self) -> Option<f32> {
+        self.r0_ohm
+    }
+
+    /// The smoothed resistance (in Ohms) of the last update.
+    pub fn resistance_ohm(&self) -> Option<f32> {
+        self.resistance_ohm
+    }
+
+    /// Forget the smoothing state and, unless fixed, the tracked `R0`.
+    pub fn reset(&mut self) {
+        self.resistance_ohm = None;
+        if !self.fixed_r0 {
+            self.r0_ohm = None;
+        }
+    }
+
+    /// Process the measurements and the raw resistance (in Ohms) read with them and return the
+    /// measurements with the VOC value of the algorithm.
+    pub fn update(&mut self, measurements: Measurements, resistance_ohm: u32) -> Measurements {
+        let raw = resistance_ohm as f32;
+        let resistance = match self.resistance_ohm {
+            Some(last) => last + (raw - last) * self.alpha,
+            None => raw,
+        };
+        self.resistance_ohm = Some(resistance);
+
+        let r0 = match self.r0_ohm {
+            Some(r0) if self.fixed_r0 => r0,
+            Some(r0) => r0.max(resistance),
+            None => resistance,
+        };
+        self.r0_ohm = Some(r0);
+
+        let ratio = if r0 > 0.0 { resistance / r0 } else { 1.0 };
+        Measurements {
+            voc: self.algorithm.voc(ratio, &measurements),
+            ..measurements
+        }
+    }
+
+    /// Read the measurements and resistance from the sensor and [CustomVoc::update()] with them.
+    ///
+    /// This function blocks a minimum time of [MicsVz89Te::WAIT_ON_RESPONSE_TIME].
+    pub fn read<I2C, E>(
+        &mut self,
+        driver: &mut MicsVz89Te<I2C>,
+        delay: &mut impl Delay,
+    ) -> Result<Measurements, PacketParseError<E>>
+    where
+        I2C: Transport<Error = E>,
+    {
+        let (measurements, resistance_ohm) = driver.read_measurements_with_resistance(delay)?;
+        Ok(self.update(measurements, resistance_ohm))
+    }
+
+    #[cfg(any(feature = "unproven", doc, test))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "unproven")))]
+    /// Use the calibration value R0 stored in the sensor as fixed `R0`.
+    ///
+    /// This function blocks a minimum time of [MicsVz89Te::WAIT_ON_RESPONSE_TIME].
+    pub fn load_r0<I2C, E>(
+        &mut self,
+        driver: &mut MicsVz89Te<I2C>,
+        delay: &mut impl Delay,
+    ) -> Result<(), PacketParseError<E>>
+    where
+        I2C: Transport<Error = E>,
+    {
+        let r0_kohm = driver.read_calibration_r0(delay)?;
+        self.set_r0(u32::from(r0_kohm) * 1000);
+        Ok(())
+    }
+
+    /// Use as [MeasurementFilter] stage with the raw resistance (in Ohms) returned by
+    /// `resistance`. Measurements are held back while it returns `None`.
+    pub fn with_resistance<R>(self, resistance: R) -> CustomVocStage<A, R>
+    where
+        R: FnMut() -> Option<u32>,
+    {
+        CustomVocStage {
+            voc: self,
+            resistance,
+        }
+    }
+}
+
+/// [CustomVoc] as filter stage, created with [CustomVoc::with_resistance()].
+#[derive(Debug, Clone)]
+pub struct CustomVocStage<A, R> {
+    voc: CustomVoc<A>,
+    resistance: R,
+}
+
+impl<A, R> CustomVocStage<A, R> {
+    /// The wrapped hook, e.g. to read the tracked `R0`.
+    pub fn custom_voc(&self) -> &CustomVoc<A> {
+        &self.voc
+    }
+}
+
+impl<A, R> MeasurementFilter for CustomVocStage<A, R>
+where
+    A: VocAlgorithm,
+    R: FnMut() -> Option<u32>,
+{
+    fn update(&mut self, measurements: Measurements) -> Option<Measurements> {
+        let resistance_ohm = (self.resistance)()?;
+        Some(self.voc.update(measurements, resistance_ohm))
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::CustomVoc;
+    use crate::{filter::MeasurementFilter, Measurements, MicsVz89Te};
+    use core::{assert_eq, cell::Cell};
+    use embedded_hal_mock::{
+        delay::MockNoop as DelayMock,
+        i2c::{Mock as I2cMock, Transaction as I2cTransaction},
+    };
+    use std::vec;
+
+    fn m(voc: f32) -> Measurements {
+        Measurements { co2: 500.0, voc }
+    }
+
+    #[test]
+    fn test_tracks_r0_and_smooths() {
+        let mut hook = CustomVoc::new(|ratio: f32, _: &Measurements| (1.0 - ratio) * 1000.0)
+            .with_smoothing(0.5);
+
+        assert_eq!(hook.update(m(10.0), 100_000).voc, 0.0);
+        // smoothed resistance 75k, R0 stays at 100k
+        let out = hook.update(m(10.0), 50_000);
+        assert_eq!(out.voc, 250.0);
+        assert_eq!(out.co2, 500.0);
+        assert_eq!(hook.resistance_ohm(), Some(75_000.0));
+
+        // higher resistance raises R0
+        hook.update(m(10.0), 200_000);
+        assert_eq!(hook.r0_ohm(), Some(137_500.0));
+
+        hook.reset();
+        assert_eq!(hook.r0_ohm(), None);
+    }
+
+    #[test]
+    fn test_fixed_r0_augments_sensor_value() {
+        let mut hook = CustomVoc::new(|ratio: f32, m: &Measurements| m.voc * ratio).with_r0(50_000);
+        assert_eq!(hook.update(m(100.0), 100_000).voc, 200.0);
+        hook.reset();
+        assert_eq!(hook.r0_ohm(), Some(50_000.0));
+    }
+
+    #[test]
+    fn test_read_and_load_r0() {
+        let expectations = [
+            I2cTransaction::write(0x70, vec![0x10, 0, 0, 0, 0, 0xEF]),
+            I2cTransaction::read(0x70, vec![0xFB, 0x01, 0, 0, 0, 0, 0x03]),
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+        ];
+        let mut device = MicsVz89Te::new(I2cMock::new(&expectations));
+        let mut delay = DelayMock::new();
+
+        let mut hook = CustomVoc::new(|ratio: f32, _: &Measurements| ratio);
+        hook.load_r0(&mut device, &mut delay).unwrap();
+        assert_eq!(hook.r0_ohm(), Some(507_000.0));
+
+        let ratio = hook.read(&mut device, &mut delay).unwrap().voc;
+        assert!((ratio - 478_020.0 / 507_000.0).abs() < 1e-5);
+        device.release().done();
+    }
+
+    #[test]
+    fn test_filter_stage() {
+        let resistance = Cell::new(None);
+        let ratio_voc = |ratio: f32, _: &Measurements| 100.0 * ratio;
+        let mut stage = CustomVoc::new(ratio_voc)
+            .with_r0(200_000)
+            .with_resistance(|| resistance.get());
+
+        assert_eq!(stage.update(m(10.0)), None);
+        resistance.set(Some(100_000));
+        assert_eq!(stage.update(m(10.0)), Some(m(50.0)));
+        assert_eq!(stage.custom_voc().resistance_ohm(), Some(100_000.0));
+    }
+}
@@ -0,0 +1,215 @@
+//! Reads bounded by a deadline.
+//!
+//! For control loops with a fixed time budget, [MicsVz89Te::read_measurements_by()] only starts
+//! a request (or a retry) if its response wait ends before the deadline, and only reads the
+//! response if the wait didn't overrun the deadline. The worst-case execution time is the
+//! deadline plus the duration of the bus transfers of one request.
+//!
+//! With the `fugit` feature, `MicsVz89Te::read_measurements_by_instant()` takes the deadline as
+//! `fugit` instant of a monotonic timer.
+//!
+//! # Example Usage
+//! ```ignore
+//! let deadline_ms = clock.now_ms() + 150;
+//! match device.read_measurements_by(&mut delay, &clock, deadline_ms) {
+//!     Ok(m) => control(m),
+//!     Err(DeadlineError::Timeout) => control_with_last_value(),
+//!     Err(DeadlineError::Read(e)) => report(e),
+//! }
+//! ```
+
+use crate::{
+    clock::Clock, delay::Delay, error::PacketParseError, frontend, transport::Transport,
+    Measurements, MicsVz89Te,
+};
+
+/// Errors of a read bounded by a deadline.
+#[derive(Debug)]
+pub enum DeadlineError<E> {
+    /// No request could be completed before the deadline.
+    Timeout,
+    /// The last request failed and there was no time left for a retry.
+    Read(PacketParseError<E>),
+}
+
+impl<E> From<PacketParseError<E>> for DeadlineError<E> {
+    fn from(e: PacketParseError<E>) -> Self {
+        Self::Read(e)
+    }
+}
+
+impl<I2C, E> MicsVz89Te<I2C>
+where
+    I2C: Transport<Error = E>,
+{
+    /// Read measurements, returning by `deadline_ms` (time of `clock`).
+    ///
+    /// A request is only started if the time until the deadline covers
+    /// [Config::wait_time_ms](crate::config::Config::wait_time_ms). Failed requests are repeated
+    /// up to [Config::retries](crate::config::Config::retries) times as long as there is time
+    /// left. If the response wait ends after the deadline, the response isn't read.
+    pub fn read_measurements_by(
+        &mut self,
+        delay: &mut impl Delay,
+        clock: &impl Clock,
+        deadline_ms: u64,
+    ) -> Result<Measurements, DeadlineError<E>> {
+        let wait_ms = self.config.wait_time_ms;
+        let time_left = || clock.now_ms().saturating_add(u64::from(wait_ms)) <= deadline_ms;
+        if !time_left() {
+            return Err(DeadlineError::Timeout);
+        }
+        let result = frontend::with_retries!(
+            self,
+            self.start_measurement().and_then(|()| {
+                delay.delay_ms(wait_ms);
+                if clock.now_ms() > deadline_ms {
+                    return Ok(None);
+                }
+                self.get_measurement_result().map(Some)
+            }),
+            time_left()
+        );
+        match result {
+            Ok(Some(measurements)) => Ok(measurements),
+            Ok(None) => Err(DeadlineError::Timeout),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Read measurements like [MicsVz89Te::read_measurements_by()], returning by the `deadline`
+    /// of the monotonic timer `now`.
+    ///
+    /// The instants are converted to millis rounded down and the deadline is moved one milli
+    /// earlier, so the rounding can't extend it.
+    #[cfg(feature = "fugit")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "fugit")))]
+    pub fn read_measurements_by_instant<const NOM: u32, const DENOM: u32>(
+        &mut self,
+        delay: &mut impl Delay,
+        now: impl Fn() -> fugit::Instant<u64, NOM, DENOM>,
+        deadline: fugit::Instant<u64, NOM, DENOM>,
+    ) -> Result<Measurements, DeadlineError<E>> {
+        let clock = || now().duration_since_epoch().to_millis();
+        let deadline_ms = deadline
+            .duration_since_epoch()
+            .to_millis()
+            .saturating_sub(1);
+        self.read_measurements_by(delay, &clock, deadline_ms)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::DeadlineError;
+    use crate::delay::Delay;
+    use crate::{config::Config, error::PacketParseError, MicsVz89Te};
+    use assert_matches::assert_matches;
+    use core::{assert_eq, cell::Cell};
+    use embedded_hal_mock::i2c::{Mock as I2cMock, Transaction as I2cTransaction};
+    use std::vec;
+
+    struct FakeTime<'a>(&'a Cell<u64>);
+
+    /// Delay taking 20 ms longer than requested, e.g. because of an interrupt.
+    struct SlowTime<'a>(&'a Cell<u64>);
+
+    impl Delay for SlowTime<'_> {
+        fn delay_ms(&mut self, ms: u16) {
+            self.0.set(self.0.get() + u64::from(ms) + 20);
+        }
+    }
+
+    impl Delay for FakeTime<'_> {
+        fn delay_ms(&mut self, ms: u16) {
+            self.0.set(self.0.get() + u64::from(ms));
+        }
+    }
+
+    #[test]
+    fn test_read_measurements_by() {
+        let status = || I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]);
+        let expectations = [
+            status(),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x26]),
+            status(),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+            status(),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x26]),
+        ];
+        let config = Config {
+            retries: 3,
+            ..Config::default()
+        };
+        let mut device = MicsVz89Te::with_config(I2cMock::new(&expectations), config);
+        let time = Cell::new(0);
+        let clock = || time.get();
+        let mut delay = FakeTime(&time);
+
+        // retried within the deadline
+        let m = device
+            .read_measurements_by(&mut delay, &clock, 200)
+            .unwrap();
+        assert_eq!(m.co2_ppm_u16(), 728);
+        assert_eq!(device.state().retries_used, 1);
+
+        // no time left for a retry
+        assert_matches!(
+            device.read_measurements_by(&mut delay, &clock, 350),
+            Err(DeadlineError::Read(PacketParseError::WrongChecksum))
+        );
+        assert_eq!(time.get(), 300);
+
+        // no time for a request at all
+        assert_matches!(
+            device.read_measurements_by(&mut delay, &clock, 399),
+            Err(DeadlineError::Timeout)
+        );
+        device.release().done();
+    }
+
+    #[test]
+    fn test_wait_overruns_deadline() {
+        let expectations = [I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3])];
+        let mut device = MicsVz89Te::new(I2cMock::new(&expectations));
+        let time = Cell::new(0);
+        let clock = || time.get();
+        let mut delay = SlowTime(&time);
+
+        assert_matches!(
+            device.read_measurements_by(&mut delay, &clock, 110),
+            Err(DeadlineError::Timeout)
+        );
+        assert_eq!(time.get(), 120);
+        device.release().done();
+    }
+
+    #[cfg(feature = "fugit")]
+    #[test]
+    fn test_read_measurements_by_instant() {
+        use fugit::TimerInstantU64;
+
+        let expectations = [
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+        ];
+        let mut device = MicsVz89Te::new(I2cMock::new(&expectations));
+        let time = Cell::new(0);
+        let now = || TimerInstantU64::<1_000_000>::from_ticks(time.get() * 1000);
+        let mut delay = FakeTime(&time);
+
+        // 100.5 ms is rounded down and moved one milli earlier
+        let deadline = TimerInstantU64::<1_000_000>::from_ticks(100_500);
+        assert_matches!(
+            device.read_measurements_by_instant(&mut delay, now, deadline),
+            Err(DeadlineError::Timeout)
+        );
+        let deadline = TimerInstantU64::<1_000_000>::from_ticks(101_000);
+        let m = device
+            .read_measurements_by_instant(&mut delay, now, deadline)
+            .unwrap();
+        assert_eq!(m.co2_ppm_u16(), 728);
+        device.release().done();
+    }
+}
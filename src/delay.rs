@@ -0,0 +1,39 @@
+//! Delay waiting for the response of the sensor.
+//!
+//! The blocking driver only needs to wait whole millis, so it takes any [Delay] instead of a
+//! HAL delay trait. With the `eh0` feature (enabled by default) every embedded-hal 0.2
+//! `DelayMs<u16>` is a [Delay], so nothing changes for embedded-hal 0.2 users. embedded-hal 1.0
+//! delays are wrapped in `Eh1Delay` (`eh1` feature), so a build without `eh0` doesn't depend on
+//! embedded-hal 0.2 at all.
+//!
+//! # Example Usage
+//! ```ignore
+//! struct Rtos;
+//!
+//! impl Delay for Rtos {
+//!     fn delay_ms(&mut self, ms: u16) {
+//!         rtos::task_delay(rtos::ms_to_ticks(ms));
+//!     }
+//! }
+//!
+//! let measurements = device.read_measurements(&mut Rtos)?;
+//! ```
+
+#[cfg(any(feature = "eh0", test))]
+use embedded_hal::blocking::delay::DelayMs;
+
+/// Blocking delay in millis.
+pub trait Delay {
+    /// Block for at least `ms` millis.
+    fn delay_ms(&mut self, ms: u16);
+}
+
+#[cfg(any(feature = "eh0", test))]
+impl<D> Delay for D
+where
+    D: DelayMs<u16>,
+{
+    fn delay_ms(&mut self, ms: u16) {
+        DelayMs::delay_ms(self, ms)
+    }
+}
@@ -0,0 +1,112 @@
+//! Diagnostics report of the driver.
+
+use crate::{
+    checksum::ChecksumStats,
+    config::{CalibrationRecord, Config},
+    protocol::Command,
+    r0_drift::R0Drift,
+    Clamped, MicsVz89Te,
+};
+
+/// Report of the driver state for logs and support bundles.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Diagnostics {
+    pub config: Config,
+    /// Last calibration written to the sensor, see [MicsVz89Te::last_calibration()].
+    pub last_calibration: Option<CalibrationRecord>,
+    /// Estimated drift of R0, see [Diagnostics::with_r0_drift()].
+    pub r0_drift: Option<R0Drift>,
+}
+
+/// Internal state of the driver, see [MicsVz89Te::state()].
+///
+/// Its [Debug] output is meant for crash logs and support bundles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DriverState {
+    pub config: Config,
+    /// Command which was sent, but whose response wasn't read yet, e.g. between
+    /// [MicsVz89Te::start_measurement()] and [MicsVz89Te::get_measurement_result()].
+    pub pending: Option<Command>,
+    /// Number of retries used by the last request, see [Config::retries].
+    pub retries_used: u8,
+    /// Sixth byte of the last valid response, the status byte of [Command::GetStatus].
+    pub last_status: Option<u8>,
+    /// Channels clamped in the last read measurements, see [MicsVz89Te::last_clamped()].
+    pub last_clamped: Clamped,
+    /// Checksum statistics, see [MicsVz89Te::checksum_stats()].
+    pub checksum: ChecksumStats,
+}
+
+impl<I2C> MicsVz89Te<I2C> {
+    /// Create a diagnostics report of the driver.
+    pub fn diagnostics(&self) -> Diagnostics {
+        Diagnostics {
+            config: *self.config(),
+            last_calibration: self.last_calibration().copied(),
+            r0_drift: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::{
+        config::{CalibrationMethod, CalibrationRecord, Config},
+        protocol::Command,
+        MicsVz89Te,
+    };
+    use core::assert_eq;
+    use embedded_hal_mock::{
+        delay::MockNoop as DelayMock,
+        i2c::{Mock as I2cMock, Transaction as I2cTransaction},
+    };
+    use std::vec;
+
+    #[test]
+    fn test_diagnostics_calibration() {
+        let expectations = [I2cTransaction::write(0x70, vec![0x08, 0x62, 0, 0, 0, 0x95])];
+        let mut device = MicsVz89Te::new(I2cMock::new(&expectations));
+        assert_eq!(device.diagnostics().last_calibration, None);
+
+        device
+            .write_calibration(1000.0, CalibrationMethod::Manual, &|| 42_000)
+            .unwrap();
+
+        assert_eq!(
+            device.diagnostics().last_calibration,
+            Some(CalibrationRecord {
+                reference_ppm: 1000.0,
+                method: CalibrationMethod::Manual,
+                timestamp_ms: Some(42_000),
+            })
+        );
+    }
+
+    #[test]
+    fn test_state() {
+        let expectations = [
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0x01, 0x26]),
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0x01, 0x27]),
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+        ];
+        let config = Config {
+            retries: 1,
+            ..Config::default()
+        };
+        let mut device = MicsVz89Te::with_config(I2cMock::new(&expectations), config);
+        assert_eq!(device.state().last_status, None);
+
+        device.read_measurements(&mut DelayMock::new()).unwrap();
+        let state = device.state();
+        assert_eq!(state.retries_used, 1);
+        assert_eq!(state.last_status, Some(0x01));
+        assert_eq!(state.pending, None);
+
+        device.start_measurement().unwrap();
+        assert_eq!(device.state().pending, Some(Command::GetStatus));
+        device.release().done();
+    }
+}
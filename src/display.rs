@@ -0,0 +1,136 @@
+//! Rendering of measurements into fixed-capacity text for character displays.
+//!
+//! # Example Usage
+//! ```ignore
+//! let mut line = TextBuffer::<20>::new();
+//! render("CO2: {co2}ppm", &measurements, &mut line).unwrap();
+//! lcd.write_str(line.as_str());
+//! ```
+
+use core::fmt::{Error, Result, Write};
+
+use crate::{format, Measurements};
+
+/// Write `template` to `w`, replacing `{co2}` by the CO2 value in ppm and `{voc}` by the
+/// VOC value in ppb.
+///
+/// Values are rounded to integers like [Measurements::co2_ppm_u16()]. With a number of
+/// decimal places, e.g. `{voc.1}`, they are written by [format::write_fixed()]. Either way no
+/// float formatting is involved. Any other text, including unknown placeholders, is written
+/// unchanged.
+pub fn render(template: &str, measurements: &Measurements, w: &mut impl Write) -> Result {
+    let mut rest = template;
+    while let Some((text, placeholder)) = rest.split_once('{') {
+        w.write_str(text)?;
+        rest = placeholder;
+        if let Some(r) = rest.strip_prefix("co2}") {
+            format::write_u16(w, measurements.co2_ppm_u16(), 1)?;
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix("voc}") {
+            format::write_u16(w, measurements.voc_ppb_u16(), 1)?;
+            rest = r;
+        } else if let Some((value, decimals, r)) = fixed_placeholder(rest, measurements) {
+            format::write_fixed(w, value, decimals)?;
+            rest = r;
+        } else {
+            w.write_char('{')?;
+        }
+    }
+    w.write_str(rest)
+}
+
+/// Value and decimal places of a `co2.N}` or `voc.N}` placeholder (after its `{`) at the start
+/// of `text`, with the text after it.
+fn fixed_placeholder<'t>(text: &'t str, measurements: &Measurements) -> Option<(f32, u8, &'t str)> {
+    let (value, rest) = if let Some(r) = text.strip_prefix("co2.") {
+        (measurements.co2, r)
+    } else {
+        (measurements.voc, text.strip_prefix("voc.")?)
+    };
+    let mut chars = rest.chars();
+    let decimals = chars.next()?.to_digit(10)?;
+    Some((value, decimals as u8, chars.as_str().strip_prefix('}')?))
+}
+
+/// Fixed-capacity text buffer of `N` bytes implementing [core::fmt::Write].
+///
+/// A write which doesn't fit into the remaining capacity fails and leaves the buffer unchanged.
+#[derive(Debug, Clone)]
+pub struct TextBuffer<const N: usize> {
+    buffer: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> Default for TextBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> TextBuffer<N> {
+    /// Create an empty buffer.
+    pub const fn new() -> Self {
+        Self {
+            buffer: [0; N],
+            len: 0,
+        }
+    }
+
+    /// The written text.
+    pub fn as_str(&self) -> &str {
+        // only complete `str`s are copied into the buffer
+        let written = self.buffer.get(..self.len).unwrap_or_default();
+        core::str::from_utf8(written).unwrap_or_default()
+    }
+
+    /// Remove the written text.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+impl<const N: usize> Write for TextBuffer<N> {
+    fn write_str(&mut self, s: &str) -> Result {
+        let end = self.len.checked_add(s.len()).ok_or(Error)?;
+        let target = self.buffer.get_mut(self.len..end).ok_or(Error)?;
+        target.copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{render, TextBuffer};
+    use crate::Measurements;
+    use core::{assert_eq, fmt::Write};
+
+    #[test]
+    fn test_render() {
+        let measurements = Measurements {
+            co2: 728.2,
+            voc: 113.0,
+        };
+        let mut line = TextBuffer::<32>::new();
+        render("CO2: {co2}ppm VOC: {voc}ppb {x}", &measurements, &mut line).unwrap();
+
+        assert_eq!(line.as_str(), "CO2: 728ppm VOC: 113ppb {x}");
+
+        line.clear();
+        render("{co2.1} {voc.2} {voc.x}", &measurements, &mut line).unwrap();
+        assert_eq!(line.as_str(), "728.2 113.00 {voc.x}");
+
+        line.clear();
+        render("{{co2} {", &measurements, &mut line).unwrap();
+        assert_eq!(line.as_str(), "{728 {");
+    }
+
+    #[test]
+    fn test_text_buffer_overflow() {
+        let mut line = TextBuffer::<4>::new();
+        line.write_str("abc").unwrap();
+        assert!(line.write_str("de").is_err());
+        assert_eq!(line.as_str(), "abc");
+    }
+}
@@ -0,0 +1,266 @@
+//! Accumulated exposure dose.
+//!
+//! Occupational-health style reports need the cumulative exposure over a shift or a day rather
+//! than instantaneous values. [ExposureDose] integrates the measurements over time into a [Dose]
+//! in ppm·hours (CO2) and ppb·hours (VOC) per period. Periods are aligned to multiples of their
+//! length on the clock, e.g. calendar days for a period of 24 hours on a clock counting from
+//! midnight.
+//!
+//! Each measurement counts until the next one. Intervals longer than the maximum gap (missed
+//! samples, power loss) aren't counted, [Dose::covered_ms] tells how much of the period is
+//! covered. To survive a reset, the current dose can be persisted with [Dose::to_bytes()] and
+//! restored with [ExposureDose::with_dose()].
+//!
+//! # Example Usage
+//! ```ignore
+//! // 8 hour shifts, dose restored after a reset
+//! let mut dose = ExposureDose::new(8 * 60 * 60 * 1000);
+//! if let Some(bytes) = flash.load() {
+//!     dose = dose.with_dose(Dose::from_bytes(&bytes));
+//! }
+//! loop {
+//!     let m = device.read_measurements(&mut delay)?;
+//!     if let Some(shift) = dose.push(clock.now_ms(), &m) {
+//!         report(shift.co2_ppm_h(), shift.co2_average_ppm());
+//!     }
+//!     flash.store(&dose.current().unwrap().to_bytes());
+//! }
+//! ```
+
+use crate::{units, Measurements};
+
+const MS_PER_HOUR: f32 = 60.0 * 60.0 * 1000.0;
+
+/// Exposure accumulated over one period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dose {
+    /// Start of the period in millis.
+    pub start_ms: u64,
+    /// Time of the period (in millis) covered by measurements.
+    pub covered_ms: u64,
+    /// CO2 exposure in ppm·millis.
+    pub co2_ppm_ms: u64,
+    /// VOC exposure in ppb·millis.
+    pub voc_ppb_ms: u64,
+}
+
+impl Dose {
+    /// Size of a serialized dose in bytes.
+    pub const ENCODED_LEN: usize = 32;
+
+    /// Dose of a period starting at `start_ms` without exposure.
+    pub const fn empty(start_ms: u64) -> Self {
+        Self {
+            start_ms,
+            covered_ms: 0,
+            co2_ppm_ms: 0,
+            voc_ppb_ms: 0,
+        }
+    }
+
+    /// CO2 exposure in ppm·hours.
+    pub fn co2_ppm_h(&self) -> f32 {
+        self.co2_ppm_ms as f32 / MS_PER_HOUR
+    }
+
+    /// VOC exposure in ppb·hours.
+    pub fn voc_ppb_h(&self) -> f32 {
+        self.voc_ppb_ms as f32 / MS_PER_HOUR
+    }
+
+    /// Time-weighted average of CO2 in ppm over the covered time.
+    pub fn co2_average_ppm(&self) -> Option<f32> {
+        (self.covered_ms > 0).then(|| self.co2_ppm_ms as f32 / self.covered_ms as f32)
+    }
+
+    /// Time-weighted average of VOC in ppb over the covered time.
+    pub fn voc_average_ppb(&self) -> Option<f32> {
+        (self.covered_ms > 0).then(|| self.voc_ppb_ms as f32 / self.covered_ms as f32)
+    }
+
+    /// Serialize the dose: start, covered time, CO2 and VOC exposure, each as `u64` LE.
+    pub fn to_bytes(&self) -> [u8; Self::ENCODED_LEN] {
+        let mut bytes = [0; Self::ENCODED_LEN];
+        let fields = [
+            self.start_ms,
+            self.covered_ms,
+            self.co2_ppm_ms,
+            self.voc_ppb_ms,
+        ];
+        for (chunk, field) in bytes.chunks_exact_mut(8).zip(fields) {
+            chunk.copy_from_slice(&field.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Deserialize a dose written by [Dose::to_bytes()].
+    pub fn from_bytes(bytes: &[u8; Self::ENCODED_LEN]) -> Self {
+        let mut fields = [0; 4];
+        for (field, chunk) in fields.iter_mut().zip(bytes.chunks_exact(8)) {
+            let mut le = [0; 8];
+            le.copy_from_slice(chunk);
+            *field = u64::from_le_bytes(le);
+        }
+        let [start_ms, covered_ms, co2_ppm_ms, voc_ppb_ms] = fields;
+        Self {
+            start_ms,
+            covered_ms,
+            co2_ppm_ms,
+            voc_ppb_ms,
+        }
+    }
+
+    fn add(&mut self, measurements: &Measurements, duration_ms: u64) {
+        // rounded to whole ppm and ppb, so the sums are exact
+        let co2 = u64::from(units::round_to_u32(measurements.co2));
+        let voc = u64::from(units::round_to_u32(measurements.voc));
+        self.covered_ms = self.covered_ms.saturating_add(duration_ms);
+        self.co2_ppm_ms = self.co2_ppm_ms.saturating_add(co2 * duration_ms);
+        self.voc_ppb_ms = self.voc_ppb_ms.saturating_add(voc * duration_ms);
+    }
+}
+
+/// Accumulator of the exposure dose per period.
+#[derive(Debug, Clone)]
+pub struct ExposureDose {
+    period_ms: u64,
+    max_gap_ms: u64,
+    current: Option<Dose>,
+    last: Option<(u64, Measurements)>,
+}
+
+impl ExposureDose {
+    /// Accumulate over periods of `period_ms`. The maximum gap defaults to 10 minutes.
+    pub fn new(period_ms: u64) -> Self {
+        let period_ms = period_ms.max(1);
+        Self {
+            period_ms,
+            max_gap_ms: period_ms.min(10 * 60 * 1000),
+            current: None,
+            last: None,
+        }
+    }
+
+    /// Don't count intervals between measurements longer than `max_gap_ms` (at most one
+    /// period).
+    pub fn with_max_gap(mut self, max_gap_ms: u64) -> Self {
+        self.max_gap_ms = max_gap_ms.min(self.period_ms);
+        self
+    }
+
+    /// Continue accumulating a persisted dose. If its period is over, the first measurement
+    /// returns it as completed.
+    pub fn with_dose(mut self, dose: Dose) -> Self {
+        self.current = Some(dose);
+        self
+    }
+
+    /// Add measurements read at `timestamp_ms`. Returns the dose of the previous period once a
+    /// measurement falls into a new period.
+    pub fn push(&mut self, timestamp_ms: u64, measurements: &Measurements) -> Option<Dose> {
+        let interval = self.last.filter(|(last_ms, _)| {
+            (*last_ms..=last_ms.saturating_add(self.max_gap_ms)).contains(&timestamp_ms)
+        });
+        let completed = match interval {
+            Some((last_ms, last)) => {
+                // the interval crosses at most one period boundary
+                let boundary = self.period_start(timestamp_ms);
+                if last_ms < boundary {
+                    self.dose_mut(last_ms).add(&last, boundary - last_ms);
+                }
+                let completed = self.roll(timestamp_ms);
+                let from_ms = last_ms.max(boundary);
+                self.dose_mut(from_ms).add(&last, timestamp_ms - from_ms);
+                completed
+            }
+            None => {
+                let completed = self.roll(timestamp_ms);
+                self.dose_mut(timestamp_ms);
+                completed
+            }
+        };
+        self.last = Some((timestamp_ms, *measurements));
+        completed
+    }
+
+    /// Dose of the current period so far.
+    pub fn current(&self) -> Option<&Dose> {
+        self.current.as_ref()
+    }
+
+    fn period_start(&self, timestamp_ms: u64) -> u64 {
+        timestamp_ms - timestamp_ms % self.period_ms
+    }
+
+    /// Take the current dose if `timestamp_ms` is outside its period.
+    fn roll(&mut self, timestamp_ms: u64) -> Option<Dose> {
+        let start_ms = self.period_start(timestamp_ms);
+        match self.current {
+            Some(dose) if dose.start_ms != start_ms => self.current.take(),
+            _ => None,
+        }
+    }
+
+    fn dose_mut(&mut self, timestamp_ms: u64) -> &mut Dose {
+        let start_ms = self.period_start(timestamp_ms);
+        self.current.get_or_insert(Dose::empty(start_ms))
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{Dose, ExposureDose};
+    use crate::Measurements;
+    use core::assert_eq;
+
+    const HOUR_MS: u64 = 60 * 60 * 1000;
+
+    fn m(co2: f32, voc: f32) -> Measurements {
+        Measurements { co2, voc }
+    }
+
+    #[test]
+    fn test_dose_per_period() {
+        let mut dose = ExposureDose::new(8 * HOUR_MS).with_max_gap(HOUR_MS);
+
+        assert_eq!(dose.push(0, &m(400.0, 100.0)), None);
+        assert_eq!(dose.push(HOUR_MS / 2, &m(800.0, 0.0)), None);
+        // the gap isn't counted
+        assert_eq!(dose.push(3 * HOUR_MS, &m(1000.0, 0.0)), None);
+        assert_eq!(dose.current().unwrap().covered_ms, HOUR_MS / 2);
+        assert_eq!(dose.push(HOUR_MS * 7 / 2, &m(600.0, 0.0)), None);
+        assert_eq!(dose.push(4 * HOUR_MS, &m(600.0, 0.0)), None);
+        assert_eq!(dose.push(HOUR_MS * 15 / 2, &m(600.0, 0.0)), None);
+
+        // the last interval is split at the end of the period
+        let shift = dose.push(HOUR_MS * 17 / 2, &m(0.0, 0.0)).unwrap();
+        assert_eq!(shift.start_ms, 0);
+        assert_eq!(shift.covered_ms, 2 * HOUR_MS);
+        assert_eq!(shift.co2_ppm_ms, (200 + 500 + 300 + 300) * HOUR_MS);
+        assert_eq!(shift.voc_ppb_h(), 50.0);
+        assert!((shift.co2_average_ppm().unwrap() - 650.0).abs() < 1e-3);
+
+        let next = dose.current().unwrap();
+        assert_eq!(next.start_ms, 8 * HOUR_MS);
+        assert_eq!(next.co2_ppm_ms, 300 * HOUR_MS);
+    }
+
+    #[test]
+    fn test_restore_dose() {
+        let mut dose = ExposureDose::new(24 * HOUR_MS);
+        dose.push(HOUR_MS, &m(500.0, 50.0));
+        dose.push(HOUR_MS + 60_000, &m(500.0, 50.0));
+        let bytes = dose.current().unwrap().to_bytes();
+        assert_eq!(Dose::from_bytes(&bytes), *dose.current().unwrap());
+
+        // reset, the time until the first measurement isn't counted
+        let mut dose = ExposureDose::new(24 * HOUR_MS).with_dose(Dose::from_bytes(&bytes));
+        dose.push(2 * HOUR_MS, &m(1000.0, 0.0));
+        dose.push(2 * HOUR_MS + 60_000, &m(1000.0, 0.0));
+        let current = dose.current().unwrap();
+        assert_eq!(current.covered_ms, 120_000);
+        assert_eq!(current.co2_ppm_ms, 1500 * 60_000);
+        assert_eq!(current.voc_average_ppb(), Some(25.0));
+    }
+}
@@ -0,0 +1,110 @@
+//! Adapters for embedded-hal 1.0 buses and delays.
+//!
+//! The driver is written against the HAL independent [Transport] and [Delay], implemented for
+//! the embedded-hal 0.2 traits with the `eh0` feature (enabled by default). Boards already
+//! migrated to embedded-hal 1.0 wrap their bus in [Eh1Bus] and their delay in [Eh1Delay]; the
+//! command and parse logic is the same for both HAL versions. With both features enabled, 0.2
+//! and 1.0 buses can be mixed in one application, with only `eh1` embedded-hal 0.2 isn't a
+//! dependency at all.
+//!
+//! # Example Usage
+//! ```ignore
+//! let i2c = esp_hal::i2c::master::I2c::new(peripherals.I2C0, config)?;
+//! let mut delay = Eh1Delay(esp_hal::delay::Delay::new());
+//! let mut device = MicsVz89Te::new(Eh1Bus(i2c));
+//! let measurements = device.read_measurements(&mut delay)?;
+//! ```
+
+use embedded_hal_1::{delay::DelayNs, i2c::I2c};
+
+use crate::{delay::Delay, transport::Transport};
+
+/// Transport over an embedded-hal 1.0 I2C bus.
+#[derive(Debug)]
+pub struct Eh1Bus<I2C>(pub I2C);
+
+impl<I2C: I2c> Transport for Eh1Bus<I2C> {
+    type Error = I2C::Error;
+
+    fn write_frame(&mut self, address: u8, frame: &[u8]) -> Result<(), I2C::Error> {
+        self.0.write(address, frame)
+    }
+
+    fn read_frame(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), I2C::Error> {
+        self.0.read(address, buffer)
+    }
+}
+
+/// Delay for the driver backed by an embedded-hal 1.0 delay.
+#[derive(Debug)]
+pub struct Eh1Delay<D>(pub D);
+
+impl<D: DelayNs> Delay for Eh1Delay<D> {
+    fn delay_ms(&mut self, ms: u16) {
+        self.0.delay_ms(u32::from(ms));
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{Eh1Bus, Eh1Delay};
+    use crate::MicsVz89Te;
+    use core::{assert_eq, convert::Infallible};
+    use embedded_hal_1::{
+        delay::DelayNs,
+        i2c::{ErrorType, I2c, Operation, SevenBitAddress},
+    };
+    use std::vec::Vec;
+
+    /// embedded-hal 1.0 bus answering every read with a status frame.
+    #[derive(Default)]
+    struct Bus {
+        written: Vec<u8>,
+    }
+
+    impl ErrorType for Bus {
+        type Error = Infallible;
+    }
+
+    impl I2c for Bus {
+        fn transaction(
+            &mut self,
+            address: SevenBitAddress,
+            operations: &mut [Operation<'_>],
+        ) -> Result<(), Infallible> {
+            assert_eq!(address, 0x70);
+            for operation in operations {
+                match operation {
+                    Operation::Write(bytes) => self.written.extend_from_slice(bytes),
+                    Operation::Read(buffer) => {
+                        buffer.copy_from_slice(&[0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27])
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct Delay {
+        ns: u64,
+    }
+
+    impl DelayNs for Delay {
+        fn delay_ns(&mut self, ns: u32) {
+            self.ns += u64::from(ns);
+        }
+    }
+
+    #[test]
+    fn test_eh1_bus() {
+        let mut device = MicsVz89Te::new(Eh1Bus(Bus::default()));
+        let mut delay = Eh1Delay(Delay::default());
+
+        let m = device.read_measurements(&mut delay).unwrap();
+        assert_eq!(m.co2_ppm_u16(), 728);
+        assert_eq!(delay.0.ns, 100_000_000);
+        assert_eq!(device.release().0.written, [0x0C, 0, 0, 0, 0, 0xF3]);
+    }
+}
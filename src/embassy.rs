@@ -0,0 +1,222 @@
+//! Ready-made tasks for the embassy executor.
+//!
+//! The driver is shared between tasks behind an `embassy_sync` [Mutex]. [health_task()] runs the
+//! [HealthMonitor] on the embassy timer and sends every health transition into an
+//! `embassy_sync` [Watch]. embassy tasks can't be generic, so the application spawns a
+//! one-line task wrapping it.
+//!
+//! [publish_task()] reads the sensor at a fixed rate and hands every [Sample] to a [Sink]. Next
+//! to the sinks of the [publish](crate::publish) module, an `embassy_sync` [Watch] (latest
+//! sample for any number of receivers) and a [PubSubChannel] (every sample for each subscriber,
+//! the oldest sample is dropped when a subscriber lags) are sinks. The response time of the
+//! sensor is awaited with the embassy timer through [MicsVz89Te::awaiting()], other tasks run
+//! meanwhile.
+//!
+//! The self-test is blocking, it waits for the responses busy-waiting on the embassy timer while
+//! holding the lock of the driver.
+//!
+//! # Example Usage
+//! ```ignore
+//! type Device = Mutex<CriticalSectionRawMutex, MicsVz89Te<I2c<'static, Blocking>>>;
+//! static HEALTH: Watch<CriticalSectionRawMutex, Health, 2> = Watch::new();
+//!
+//! #[embassy_executor::task]
+//! async fn health(device: &'static Device) -> ! {
+//!     embassy::health_task(device, HealthMonitor::new(60_000, 3), &HEALTH).await
+//! }
+//!
+//! spawner.spawn(health(device))?;
+//! let mut receiver = HEALTH.receiver().unwrap();
+//! let health = receiver.changed().await;
+//!
+//! static SAMPLES: PubSubChannel<CriticalSectionRawMutex, Sample, 4, 2, 1> = PubSubChannel::new();
+//!
+//! #[embassy_executor::task]
+//! async fn sampling(device: &'static Device) -> ! {
+//!     embassy::publish_task(device, 5000, &SAMPLES).await
+//! }
+//! ```
+
+use embassy_sync::{
+    blocking_mutex::raw::RawMutex, mutex::Mutex, pubsub::PubSubChannel, watch::Watch,
+};
+use embassy_time::{Delay, Duration, Instant, Ticker, Timer};
+
+use crate::{
+    delay,
+    event::Fault,
+    health::{Health, HealthMonitor},
+    publish::{Sample, Sink},
+    transport::Transport,
+    MicsVz89Te,
+};
+
+impl<M: RawMutex, const N: usize> Sink for &Watch<M, Sample, N> {
+    fn publish(&mut self, sample: Sample) {
+        self.sender().send(sample)
+    }
+}
+
+impl<M: RawMutex, const CAP: usize, const SUBS: usize, const PUBS: usize> Sink
+    for &PubSubChannel<M, Sample, CAP, SUBS, PUBS>
+{
+    fn publish(&mut self, sample: Sample) {
+        self.immediate_publisher().publish_immediate(sample)
+    }
+}
+
+/// Busy-waiting delay of the blocking reads.
+struct BlockingDelay;
+
+impl delay::Delay for BlockingDelay {
+    fn delay_ms(&mut self, ms: u16) {
+        embassy_time::block_for(Duration::from_millis(u64::from(ms)));
+    }
+}
+
+/// Run `monitor` forever, sending every health transition to `health`.
+pub async fn health_task<M, I2C, E, const N: usize>(
+    device: &Mutex<M, MicsVz89Te<I2C>>,
+    mut monitor: HealthMonitor,
+    health: &Watch<M, Health, N>,
+) -> !
+where
+    M: RawMutex,
+    I2C: Transport<Error = E>,
+{
+    let sender = health.sender();
+    loop {
+        let now_ms = Instant::now().as_millis();
+        let transition = monitor.poll(now_ms, &mut *device.lock().await, &mut BlockingDelay);
+        if let Some(transition) = transition {
+            sender.send(transition);
+        }
+        Timer::after_millis(monitor.interval_ms()).await;
+    }
+}
+
+/// Read the sensor every `interval_ms` forever and publish the samples, timestamped with the
+/// start of the read, to `sink`.
+pub async fn publish_task<M, I2C, E>(
+    device: &Mutex<M, MicsVz89Te<I2C>>,
+    interval_ms: u64,
+    mut sink: impl Sink,
+) -> !
+where
+    M: RawMutex,
+    I2C: Transport<Error = E>,
+{
+    let mut ticker = Ticker::every(Duration::from_millis(interval_ms));
+    loop {
+        let timestamp_ms = Instant::now().as_millis();
+        // the lock is held for the whole read, no other task can interleave a request
+        let result = device
+            .lock()
+            .await
+            .awaiting()
+            .read_measurements(&mut Delay)
+            .await;
+        sink.publish(Sample {
+            timestamp_ms,
+            result: result.map_err(|e| Fault::from(&e)),
+        });
+        ticker.next().await;
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{health_task, publish_task};
+    use crate::{
+        config::Config,
+        event::Fault,
+        health::{Health, HealthMonitor},
+        publish::Sample,
+        MicsVz89Te,
+    };
+    use assert_matches::assert_matches;
+    use core::assert_eq;
+    use embassy_futures::{block_on, select::select};
+    use embassy_sync::{
+        blocking_mutex::raw::NoopRawMutex, mutex::Mutex, pubsub::PubSubChannel, watch::Watch,
+    };
+    use embedded_hal_mock::i2c::{Mock as I2cMock, Transaction as I2cTransaction};
+    use std::vec;
+
+    #[test]
+    fn test_health_task() {
+        let expectations = [
+            // checksum step has no bus traffic, revision, measurement and r0
+            I2cTransaction::write(0x70, vec![0x0D, 0, 0, 0, 0, 0xF2]),
+            I2cTransaction::read(0x70, vec![0x10, 0x03, 0x11, 0x48, 0, 0, 0x93]),
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+            I2cTransaction::write(0x70, vec![0x10, 0, 0, 0, 0, 0xEF]),
+            I2cTransaction::read(0x70, vec![0xFB, 0x01, 0, 0, 0, 0, 0x03]),
+        ];
+        let config = Config {
+            wait_time_ms: 1,
+            ..Config::default()
+        };
+        let device: Mutex<NoopRawMutex, _> =
+            Mutex::new(MicsVz89Te::with_config(I2cMock::new(&expectations), config));
+        let health: Watch<NoopRawMutex, Health, 1> = Watch::new();
+        let mut receiver = health.receiver().unwrap();
+
+        block_on(select(
+            health_task(&device, HealthMonitor::new(60_000, 1), &health),
+            async { assert_eq!(receiver.changed().await, Health::Healthy) },
+        ));
+        device.into_inner().release().done();
+    }
+
+    #[test]
+    fn test_publish_task() {
+        let status = || I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]);
+        let expectations = [
+            status(),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+            status(),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x26]),
+        ];
+        let config = Config {
+            wait_time_ms: 1,
+            ..Config::default()
+        };
+        let device: Mutex<NoopRawMutex, _> =
+            Mutex::new(MicsVz89Te::with_config(I2cMock::new(&expectations), config));
+        let samples: PubSubChannel<NoopRawMutex, Sample, 2, 1, 0> = PubSubChannel::new();
+        let mut subscriber = samples.subscriber().unwrap();
+
+        block_on(select(publish_task(&device, 5, &samples), async {
+            let first = subscriber.next_message_pure().await;
+            assert_matches!(first.result, Ok(m) if m.co2_ppm_u16() == 728);
+            let second = subscriber.next_message_pure().await;
+            assert_eq!(second.result, Err(Fault::WrongChecksum));
+            assert!(second.timestamp_ms >= first.timestamp_ms + 5);
+        }));
+        device.into_inner().release().done();
+    }
+
+    #[test]
+    fn test_publish_task_watch() {
+        let expectations = [
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+        ];
+        let config = Config {
+            wait_time_ms: 1,
+            ..Config::default()
+        };
+        let device: Mutex<NoopRawMutex, _> =
+            Mutex::new(MicsVz89Te::with_config(I2cMock::new(&expectations), config));
+        let latest: Watch<NoopRawMutex, Sample, 1> = Watch::new();
+        let mut receiver = latest.receiver().unwrap();
+
+        block_on(select(publish_task(&device, 60_000, &latest), async {
+            assert_matches!(receiver.changed().await.result, Ok(m) if m.co2_ppm_u16() == 728);
+        }));
+        device.into_inner().release().done();
+    }
+}
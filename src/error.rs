@@ -1,3 +1,5 @@
+use crate::event::Fault;
+
 /// Represents errors which can occur while communicating with the sensor.
 #[cfg_attr(feature = "std", derive(std::fmt::Debug))]
 #[repr(u8)]
@@ -42,8 +44,110 @@ impl<E: core::fmt::Debug> core::fmt::Debug for PacketParseError<E> {
     }
 }
 
+impl<E> PacketParseError<E> {
+    /// Kind of the error without the bus error, e.g. to store or compare it.
+    pub fn kind(&self) -> Fault {
+        Fault::from(self)
+    }
+
+    /// The bus error, if this is a [PacketParseError::BusError].
+    pub fn bus_error(&self) -> Option<&E> {
+        match self {
+            Self::BusError(e) => Some(e),
+            Self::WrongChecksum => None,
+        }
+    }
+
+    /// Convert the bus error, e.g. into the error type of the application or HAL.
+    pub fn map_bus<F>(self, f: impl FnOnce(E) -> F) -> PacketParseError<F> {
+        match self {
+            Self::BusError(e) => PacketParseError::BusError(f(e)),
+            Self::WrongChecksum => PacketParseError::WrongChecksum,
+        }
+    }
+
+    /// Write a short description of the error without using format strings.
+    ///
+    /// The bus error itself isn't written, as its type isn't known to be printable.
+    pub fn write_to(&self, w: &mut impl core::fmt::Write) -> core::fmt::Result {
+        w.write_str(match self {
+            Self::BusError(_) => "bus error",
+            Self::WrongChecksum => "wrong checksum",
+        })
+    }
+}
+
 impl<E> From<E> for PacketParseError<E> {
     fn from(e: E) -> Self {
         Self::BusError(e)
     }
 }
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+/// Bus errors map to [std::io::ErrorKind::Other], wrong checksums to
+/// [std::io::ErrorKind::InvalidData].
+impl<E> From<PacketParseError<E>> for std::io::Error
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    fn from(e: PacketParseError<E>) -> Self {
+        let kind = match e {
+            PacketParseError::BusError(_) => std::io::ErrorKind::Other,
+            PacketParseError::WrongChecksum => std::io::ErrorKind::InvalidData,
+        };
+        std::io::Error::new(kind, e)
+    }
+}
+
+#[cfg(feature = "eh1")]
+#[cfg_attr(docsrs, doc(cfg(feature = "eh1")))]
+/// Bus errors keep their kind, wrong checksums are [ErrorKind::Other], so the driver errors can
+/// be passed on where an embedded-hal 1.0 I2C error is expected.
+///
+/// [ErrorKind::Other]: embedded_hal_1::i2c::ErrorKind::Other
+impl<E> embedded_hal_1::i2c::Error for PacketParseError<E>
+where
+    E: embedded_hal_1::i2c::Error,
+{
+    fn kind(&self) -> embedded_hal_1::i2c::ErrorKind {
+        match self {
+            Self::BusError(e) => e.kind(),
+            Self::WrongChecksum => embedded_hal_1::i2c::ErrorKind::Other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::PacketParseError;
+    use crate::event::Fault;
+    use core::assert_eq;
+
+    #[test]
+    fn test_map_bus() {
+        let e = PacketParseError::BusError(7u8);
+        assert_eq!(e.kind(), Fault::Bus);
+        assert_eq!(e.bus_error(), Some(&7));
+        let mapped: PacketParseError<u16> = e.map_bus(|e| u16::from(e) * 2);
+        assert_eq!(mapped.bus_error(), Some(&14));
+
+        let e = PacketParseError::<u8>::WrongChecksum.map_bus(u16::from);
+        assert_eq!(e.kind(), Fault::WrongChecksum);
+        assert_eq!(e.bus_error(), None);
+    }
+
+    #[cfg(feature = "eh1")]
+    #[test]
+    fn test_eh1_error_kind() {
+        use embedded_hal_1::i2c::{Error, ErrorKind, NoAcknowledgeSource};
+
+        let nack = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+        assert_eq!(Error::kind(&PacketParseError::BusError(nack)), nack);
+        assert_eq!(
+            Error::kind(&PacketParseError::<ErrorKind>::WrongChecksum),
+            ErrorKind::Other
+        );
+    }
+}
@@ -0,0 +1,235 @@
+//! Fixed-capacity queue of timestamped sensor events.
+//!
+//! The sampling side pushes events (e.g. from an interrupt or a high priority task) and the
+//! application drains them at its own pace. The queue doesn't synchronize by itself, share it
+//! e.g. inside a critical section mutex.
+//!
+//! # Example Usage
+//! ```ignore
+//! let mut threshold = Threshold::new(Channel::Co2, 1000.0, 50.0);
+//! let mut events = EventQueue::<8>::new();
+//!
+//! match device.read_measurements(&mut delay) {
+//!     Ok(m) => {
+//!         if let Some(crossing) = threshold.update(&m) {
+//!             events.push(Event::threshold(now_ms, &threshold, crossing, &m));
+//!         }
+//!     }
+//!     Err(e) => events.push(Event::new(now_ms, EventKind::SensorFault(Fault::from(&e)))),
+//! }
+//!
+//! for event in events.drain() {
+//!     handle(event);
+//! }
+//! ```
+
+use crate::{
+    error::PacketParseError,
+    reset::ResetCause,
+    temperature::TemperatureStatus,
+    threshold::{Crossing, Threshold},
+    Channel, Measurements,
+};
+
+/// Kind of a failed communication with the sensor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// The I2C bus reported an error.
+    Bus,
+    /// The response of the sensor had a wrong checksum.
+    WrongChecksum,
+}
+
+impl<E> From<&PacketParseError<E>> for Fault {
+    fn from(e: &PacketParseError<E>) -> Self {
+        match e {
+            PacketParseError::BusError(_) => Self::Bus,
+            PacketParseError::WrongChecksum => Self::WrongChecksum,
+        }
+    }
+}
+
+/// What happened.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EventKind {
+    /// A threshold on `channel` was raised by `value`.
+    ThresholdRaised { channel: Channel, value: f32 },
+    /// A threshold on `channel` was cleared by `value`.
+    ThresholdCleared { channel: Channel, value: f32 },
+    /// Communication with the sensor failed.
+    SensorFault(Fault),
+    /// The sensor reset unexpectedly. See [ResetDetector](crate::reset::ResetDetector).
+    SensorReset(ResetCause),
+    /// The ambient temperature entered or left the operating range. See
+    /// [TemperatureMonitor](crate::temperature::TemperatureMonitor).
+    OperatingTemperature {
+        status: TemperatureStatus,
+        celsius: f32,
+    },
+    /// The zone of `channel` changed. See [ZoneClassifier](crate::zone::ZoneClassifier).
+    ZoneChanged {
+        channel: Channel,
+        from: Option<usize>,
+        to: usize,
+    },
+}
+
+/// An event with the time (in millis) it occurred at.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Event {
+    pub timestamp_ms: u64,
+    pub kind: EventKind,
+}
+
+impl Event {
+    /// Create an event occurred at `timestamp_ms`.
+    pub const fn new(timestamp_ms: u64, kind: EventKind) -> Self {
+        Self { timestamp_ms, kind }
+    }
+
+    /// Create the event for a crossing of `threshold` caused by `measurements`.
+    pub fn threshold(
+        timestamp_ms: u64,
+        threshold: &Threshold,
+        crossing: Crossing,
+        measurements: &Measurements,
+    ) -> Self {
+        let channel = threshold.channel();
+        let value = measurements.get(channel);
+        let kind = match crossing {
+            Crossing::Raised => EventKind::ThresholdRaised { channel, value },
+            Crossing::Cleared => EventKind::ThresholdCleared { channel, value },
+        };
+        Self::new(timestamp_ms, kind)
+    }
+}
+
+/// Bounded FIFO queue of [Event]s.
+///
+/// If the queue is full, pushing drops the oldest event. The number of dropped events is
+/// counted and can be read with [EventQueue::dropped()].
+#[derive(Debug, Clone)]
+pub struct EventQueue<const N: usize> {
+    buffer: [Option<Event>; N],
+    start: usize,
+    len: usize,
+    dropped: u32,
+}
+
+impl<const N: usize> Default for EventQueue<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> EventQueue<N> {
+    /// Create an empty queue.
+    pub const fn new() -> Self {
+        Self {
+            buffer: [None; N],
+            start: 0,
+            len: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Append an event. Returns `false` if the oldest event had to be dropped.
+    pub fn push(&mut self, event: Event) -> bool {
+        if N == 0 {
+            self.dropped = self.dropped.saturating_add(1);
+            return false;
+        }
+        let has_space = self.len < N;
+        if has_space {
+            self.len += 1;
+        } else {
+            self.start = (self.start + 1) % N;
+            self.dropped = self.dropped.saturating_add(1);
+        }
+        self.buffer[(self.start + self.len - 1) % N] = Some(event);
+        has_space
+    }
+
+    /// Remove and return the oldest event.
+    pub fn pop(&mut self) -> Option<Event> {
+        if self.len == 0 {
+            return None;
+        }
+        let event = self.buffer[self.start].take();
+        self.start = (self.start + 1) % N;
+        self.len -= 1;
+        event
+    }
+
+    /// Remove and iterate over all queued events, oldest first.
+    pub fn drain(&mut self) -> impl Iterator<Item = Event> + '_ {
+        core::iter::from_fn(move || self.pop())
+    }
+
+    /// Number of queued events.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no event is queued.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of events dropped because the queue was full.
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{Event, EventKind, EventQueue, Fault};
+    use crate::{
+        error::PacketParseError,
+        threshold::{Crossing, Threshold},
+        Channel, Measurements,
+    };
+    use core::assert_eq;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_queue_drops_oldest() {
+        let mut queue = EventQueue::<2>::new();
+        let fault = |t| Event::new(t, EventKind::SensorFault(Fault::Bus));
+
+        assert!(queue.push(fault(1)));
+        assert!(queue.push(fault(2)));
+        assert!(!queue.push(fault(3)));
+
+        assert_eq!(queue.dropped(), 1);
+        let timestamps = queue.drain().map(|e| e.timestamp_ms).collect::<Vec<_>>();
+        assert_eq!(timestamps, [2, 3]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_events() {
+        let threshold = Threshold::new(Channel::Voc, 500.0, 0.0);
+        let event = Event::threshold(
+            10,
+            &threshold,
+            Crossing::Raised,
+            &Measurements {
+                co2: 400.0,
+                voc: 600.0,
+            },
+        );
+        assert_eq!(
+            event.kind,
+            EventKind::ThresholdRaised {
+                channel: Channel::Voc,
+                value: 600.0
+            }
+        );
+
+        let error = PacketParseError::<()>::WrongChecksum;
+        assert_eq!(Fault::from(&error), Fault::WrongChecksum);
+    }
+}
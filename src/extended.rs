@@ -0,0 +1,145 @@
+//! Pass-through conversion beyond the documented ranges.
+//!
+//! The regular conversion saturates raw values below [RAW_RANGE](crate::RAW_RANGE), so CO2
+//! never drops below 400 ppm and VOC never below 0 ppb. [ExtendedMeasurements] applies the same
+//! linear formula to every raw byte instead, exposing whatever the sensor reports. Values outside
+//! of [CO2_RANGE_PPM](crate::CO2_RANGE_PPM) and [VOC_RANGE_PPB](crate::VOC_RANGE_PPB) are out of
+//! spec and flagged by [ExtendedMeasurements::out_of_spec()]; they are meant for research on
+//! the sensor behavior, not for decisions.
+
+use core::num::NonZeroU8;
+
+use crate::{
+    convert::scale_raw,
+    delay::Delay,
+    error::PacketParseError,
+    protocol::{Command, RESPONSE_LEN},
+    transport::Transport,
+    Clamped, Measurements, MicsVz89Te, CO2_MAX, CO2_MIN, RAW_MIN, RAW_SPAN, VOC_MAX, VOC_MIN,
+};
+
+/// Measurements converted without saturation. Can be negative (VOC) or below 400 ppm (CO2).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExtendedMeasurements {
+    /// CO2 in ppm.
+    pub co2: f32,
+    /// VOC in ppb.
+    pub voc: f32,
+}
+
+impl ExtendedMeasurements {
+    /// Convert the response to [Command::GetStatus].
+    pub fn from_response(response: &[u8; RESPONSE_LEN]) -> Self {
+        Self::from_response_scaled(response, RAW_MIN, RAW_SPAN)
+    }
+
+    /// Convert with a custom raw offset and span, see
+    /// [Config::raw_offset](crate::config::Config::raw_offset).
+    pub(crate) fn from_response_scaled(
+        response: &[u8; RESPONSE_LEN],
+        offset: u8,
+        span: NonZeroU8,
+    ) -> Self {
+        let raw = |byte: u8| f32::from(byte) - f32::from(offset);
+        Self {
+            co2: scale_raw(raw(response[1]), span, CO2_MIN, CO2_MAX),
+            voc: scale_raw(raw(response[0]), span, VOC_MIN, VOC_MAX),
+        }
+    }
+
+    /// Channels outside of the documented range, i.e. which
+    /// [Config::strict_range](crate::config::Config::strict_range) would clamp.
+    pub fn out_of_spec(&self) -> Clamped {
+        self.unchecked().clamp_to_range().1
+    }
+
+    /// The measurements if both channels are within the documented range.
+    pub fn in_spec(&self) -> Option<Measurements> {
+        (!self.out_of_spec().any()).then(|| self.unchecked())
+    }
+
+    /// The values as [Measurements] without any check.
+    pub fn unchecked(&self) -> Measurements {
+        Measurements {
+            co2: self.co2,
+            voc: self.voc,
+        }
+    }
+}
+
+impl<I2C, E> MicsVz89Te<I2C>
+where
+    I2C: Transport<Error = E>,
+{
+    /// Read measurements without saturating values outside of the documented ranges. See
+    /// [ExtendedMeasurements].
+    ///
+    /// This function blocks a minimum time of [MicsVz89Te::WAIT_ON_RESPONSE_TIME].
+    pub fn read_measurements_extended(
+        &mut self,
+        delay: &mut impl Delay,
+    ) -> Result<ExtendedMeasurements, PacketParseError<E>> {
+        let response = self.request_data(Command::GetStatus, delay)?;
+        Ok(ExtendedMeasurements::from_response_scaled(
+            &response,
+            self.config.raw_offset,
+            self.config.raw_span,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::ExtendedMeasurements;
+    use crate::{Clamped, Measurements, MicsVz89Te};
+    use core::assert_eq;
+    use embedded_hal_mock::{
+        delay::MockNoop as DelayMock,
+        i2c::{Mock as I2cMock, Transaction as I2cTransaction},
+    };
+    use std::vec;
+
+    #[test]
+    fn test_matches_regular_conversion_in_range() {
+        for raw in 13..=242 {
+            let response = [raw, raw, 0, 0, 0, 0, 0];
+            let extended = ExtendedMeasurements::from_response(&response);
+            assert_eq!(
+                extended.in_spec(),
+                Some(Measurements::from_response(&response))
+            );
+        }
+    }
+
+    #[test]
+    fn test_out_of_spec() {
+        let extended = ExtendedMeasurements::from_response(&[5, 250, 0, 0, 0, 0, 0]);
+        assert!((extended.co2 - 2055.895).abs() < 1e-2);
+        assert!((extended.voc + 34.934).abs() < 1e-2);
+        assert_eq!(
+            extended.out_of_spec(),
+            Clamped {
+                co2: true,
+                voc: true
+            }
+        );
+        assert_eq!(extended.in_spec(), None);
+    }
+
+    #[test]
+    fn test_read_measurements_extended() {
+        let expectations = [
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0xFA, 0, 0xBA, 0xBA, 0, 0x68]),
+        ];
+        let mut device = MicsVz89Te::new(I2cMock::new(&expectations));
+
+        let extended = device
+            .read_measurements_extended(&mut DelayMock::new())
+            .unwrap();
+        assert!(extended.out_of_spec().co2);
+        assert!(extended.co2 > 2000.0);
+        device.release().done();
+    }
+}
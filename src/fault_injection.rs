@@ -0,0 +1,182 @@
+//! Bus wrapper injecting faults for resilience testing.
+//!
+//! [FaultyBus] wraps any bus and injects NACKs, corrupted checksums and delayed responses
+//! in a deterministic pattern, so retry and recovery logic of an application can be tested
+//! against realistic failures.
+//!
+//! # Example Usage
+//! ```ignore
+//! let bus = FaultyBus::new(i2c, delay.clone())
+//!     .nack_every(5)
+//!     .corrupt_every(7)
+//!     .delay_reads(20);
+//! let mut device = MicsVz89Te::new(bus);
+//! ```
+
+use embedded_hal::blocking::i2c::{Read, Write};
+
+use crate::{delay::Delay, protocol::RESPONSE_LEN};
+
+/// Error of a [FaultyBus].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InjectedError<E> {
+    /// Injected NACK.
+    Nack,
+    /// Error of the wrapped bus.
+    Bus(E),
+}
+
+/// Bus wrapper injecting faults into the transactions of the wrapped bus.
+///
+/// Faults are disabled by default. Patterns count transactions starting at 1, e.g. with
+/// [FaultyBus::nack_every()] `(3)` the 3rd, 6th, ... transaction fails.
+pub struct FaultyBus<I2C, D> {
+    i2c: I2C,
+    delay: D,
+    nack_every: u32,
+    corrupt_every: u32,
+    read_delay_ms: u16,
+    transactions: u32,
+    responses: u32,
+    injected: u32,
+}
+
+impl<I2C, D> FaultyBus<I2C, D> {
+    /// Wrap `i2c`. `delay` is used to delay responses.
+    pub fn new(i2c: I2C, delay: D) -> Self {
+        Self {
+            i2c,
+            delay,
+            nack_every: 0,
+            corrupt_every: 0,
+            read_delay_ms: 0,
+            transactions: 0,
+            responses: 0,
+            injected: 0,
+        }
+    }
+
+    /// Fail every `n`th transaction (read or write) with [InjectedError::Nack]. `0` disables it.
+    pub fn nack_every(mut self, n: u32) -> Self {
+        self.nack_every = n;
+        self
+    }
+
+    /// Flip a bit of the checksum of every `n`th response. `0` disables it.
+    pub fn corrupt_every(mut self, n: u32) -> Self {
+        self.corrupt_every = n;
+        self
+    }
+
+    /// Delay every read by `ms` millis.
+    pub fn delay_reads(mut self, ms: u16) -> Self {
+        self.read_delay_ms = ms;
+        self
+    }
+
+    /// Number of injected NACKs and corruptions.
+    pub fn injected(&self) -> u32 {
+        self.injected
+    }
+
+    /// Destroy the wrapper and return the wrapped bus.
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+
+    fn inject_nack(&mut self) -> bool {
+        self.transactions = self.transactions.wrapping_add(1);
+        let nack = is_nth(self.transactions, self.nack_every);
+        if nack {
+            self.injected = self.injected.saturating_add(1);
+        }
+        nack
+    }
+}
+
+fn is_nth(count: u32, n: u32) -> bool {
+    n != 0 && count.is_multiple_of(n)
+}
+
+impl<I2C, D, E> Write for FaultyBus<I2C, D>
+where
+    I2C: Write<Error = E>,
+{
+    type Error = InjectedError<E>;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        if self.inject_nack() {
+            return Err(InjectedError::Nack);
+        }
+        self.i2c.write(address, bytes).map_err(InjectedError::Bus)
+    }
+}
+
+impl<I2C, D, E> Read for FaultyBus<I2C, D>
+where
+    I2C: Read<Error = E>,
+    D: Delay,
+{
+    type Error = InjectedError<E>;
+
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        if self.read_delay_ms > 0 {
+            self.delay.delay_ms(self.read_delay_ms);
+        }
+        if self.inject_nack() {
+            return Err(InjectedError::Nack);
+        }
+        self.i2c.read(address, buffer).map_err(InjectedError::Bus)?;
+
+        self.responses = self.responses.wrapping_add(1);
+        if is_nth(self.responses, self.corrupt_every) {
+            if let Some(checksum) = buffer.get_mut(RESPONSE_LEN - 1) {
+                *checksum ^= 0x01;
+                self.injected = self.injected.saturating_add(1);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{FaultyBus, InjectedError};
+    use crate::{error::PacketParseError, MicsVz89Te};
+    use assert_matches::assert_matches;
+    use core::assert_eq;
+    use embedded_hal_mock::{
+        delay::MockNoop as DelayMock,
+        i2c::{Mock as I2cMock, Transaction as I2cTransaction},
+    };
+    use std::{vec, vec::Vec};
+
+    #[test]
+    fn test_injected_faults() {
+        let read = [
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+        ];
+        // the 3rd transaction (2nd write) is NACKed and never reaches the bus
+        let expectations = [read.clone(), read].concat();
+        let bus = FaultyBus::new(I2cMock::new(&expectations), DelayMock::new())
+            .nack_every(3)
+            .corrupt_every(2)
+            .delay_reads(10);
+        let mut device = MicsVz89Te::new(bus);
+        let mut delay = DelayMock::new();
+
+        let results = (0..3)
+            .map(|_| device.read_measurements(&mut delay))
+            .collect::<Vec<_>>();
+
+        assert_matches!(results[0], Ok(_));
+        assert_matches!(
+            results[1],
+            Err(PacketParseError::BusError(InjectedError::Nack))
+        );
+        assert_matches!(results[2], Err(PacketParseError::WrongChecksum));
+        assert_eq!(device.release().injected(), 2);
+    }
+}
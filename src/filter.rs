@@ -0,0 +1,143 @@
+//! Composable processing stages for measurements.
+//!
+//! A [MeasurementFilter] takes a measurement and returns a (possibly changed) measurement or
+//! `None` if it holds it back, e.g. until a window is complete. Built-in stages and custom
+//! stages (any `FnMut(Measurements) -> Option<Measurements>`) can be chained with
+//! [MeasurementFilter::then()] and applied to a sampler with
+//! [Samples::filtered()](crate::sampler::Samples::filtered()).
+//!
+//! Stages which need more than the measurement take it from a source given when creating the
+//! stage: [PeakHold::clocked()](crate::peak::PeakHold::clocked()),
+//! [HumidityCorrection::with_humidity()](crate::humidity::HumidityCorrection::with_humidity())
+//! and [CustomVoc::with_resistance()](crate::custom_voc::CustomVoc::with_resistance()).
+//!
+//! # Example Usage
+//! ```ignore
+//! let clamp_voc = |mut m: Measurements| {
+//!     m.voc = m.voc.min(500.0);
+//!     Some(m)
+//! };
+//! let pipeline = ExponentialSmoothing::new(0.2).then(clamp_voc).then(Downsampler::new(60));
+//! for sample in device.samples(&mut delay, &clock).filtered(pipeline) {
+//!     let (timestamp_ms, minute_mean) = sample.unwrap();
+//! }
+//! ```
+
+use crate::{aggregate::Downsampler, Measurements};
+
+/// A processing stage for measurements.
+pub trait MeasurementFilter {
+    /// Process a measurement. Returns `None` if no measurement is passed on for this input.
+    fn update(&mut self, measurements: Measurements) -> Option<Measurements>;
+
+    /// Feed the output of this stage into `next`.
+    fn then<F>(self, next: F) -> Chain<Self, F>
+    where
+        Self: Sized,
+        F: MeasurementFilter,
+    {
+        Chain {
+            first: self,
+            second: next,
+        }
+    }
+}
+
+impl<F> MeasurementFilter for F
+where
+    F: FnMut(Measurements) -> Option<Measurements>,
+{
+    fn update(&mut self, measurements: Measurements) -> Option<Measurements> {
+        self(measurements)
+    }
+}
+
+/// Two stages in a row. Created with [MeasurementFilter::then()].
+#[derive(Debug, Clone)]
+pub struct Chain<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A, B> MeasurementFilter for Chain<A, B>
+where
+    A: MeasurementFilter,
+    B: MeasurementFilter,
+{
+    fn update(&mut self, measurements: Measurements) -> Option<Measurements> {
+        self.first
+            .update(measurements)
+            .and_then(|m| self.second.update(m))
+    }
+}
+
+/// Exponential moving average: `out = alpha * in + (1 - alpha) * previous out`.
+#[derive(Debug, Clone)]
+pub struct ExponentialSmoothing {
+    alpha: f32,
+    state: Option<Measurements>,
+}
+
+impl ExponentialSmoothing {
+    /// Create a filter with smoothing factor `alpha`, clamped to `0.0..=1.0`. Smaller values
+    /// smooth more; `1.0` passes measurements unchanged.
+    pub fn new(alpha: f32) -> Self {
+        Self {
+            alpha: alpha.clamp(0.0, 1.0),
+            state: None,
+        }
+    }
+
+    /// Forget the state, so the next measurement is passed unchanged.
+    pub fn reset(&mut self) {
+        self.state = None;
+    }
+}
+
+impl MeasurementFilter for ExponentialSmoothing {
+    fn update(&mut self, measurements: Measurements) -> Option<Measurements> {
+        let next = match self.state {
+            Some(state) => state + (measurements - state) * self.alpha,
+            None => measurements,
+        };
+        self.state = Some(next);
+        Some(next)
+    }
+}
+
+/// Passes on the mean of each completed window.
+impl MeasurementFilter for Downsampler {
+    fn update(&mut self, measurements: Measurements) -> Option<Measurements> {
+        self.push(measurements).map(|s| s.mean)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{ExponentialSmoothing, MeasurementFilter};
+    use crate::{aggregate::Downsampler, Measurements};
+    use core::assert_eq;
+    use std::vec::Vec;
+
+    fn m(co2: f32) -> Measurements {
+        Measurements { co2, voc: 0.0 }
+    }
+
+    #[test]
+    fn test_pipeline() {
+        let drop_high = |m: Measurements| (m.co2 < 1500.0).then_some(m);
+        let mut pipeline = ExponentialSmoothing::new(0.5)
+            .then(drop_high)
+            .then(Downsampler::new(2));
+
+        let out = [400.0, 600.0, 3000.0, 800.0, 800.0]
+            .into_iter()
+            .filter_map(|co2| pipeline.update(m(co2)))
+            .map(|m| m.co2)
+            .collect::<Vec<_>>();
+
+        // smoothed: 400, 500, 1750 (dropped), 1275, 1037.5
+        assert_eq!(out, [450.0, 1156.25]);
+    }
+}
@@ -0,0 +1,269 @@
+//! Conversion of raw values into Q16.16 fixed-point without floating point arithmetic.
+//!
+//! For targets without FPU where soft-float is expensive. The conversion uses only integer
+//! multiplication and addition on `u32`. Compared to the exact conversion the maximum error
+//! is below 0.002 ppm (CO2) and 0.002 ppb (VOC), far below the resolution of the sensor.
+//!
+//! On 8-bit targets like the ATmega328 even `u32` math is costly. [IntMeasurements] converts
+//! directly into whole ppm and ppb using only `u16` multiplication and division, with the same
+//! results as [Measurements::co2_ppm_u16()](crate::Measurements::co2_ppm_u16()).
+//!
+//! The driver reads apply [Config::raw_offset](crate::config::Config::raw_offset) and
+//! [Config::raw_span](crate::config::Config::raw_span) like the floating point conversion.
+
+use core::num::NonZeroU8;
+
+use crate::{
+    delay::Delay,
+    error::PacketParseError,
+    protocol::{Command, RESPONSE_LEN},
+    transport::Transport,
+    MicsVz89Te, CO2_RANGE_PPM, RAW_MIN, RAW_SPAN, VOC_RANGE_PPB,
+};
+
+/// Number of fractional bits.
+pub const FRAC_BITS: u32 = 16;
+
+const CO2_MIN_U16: u16 = *CO2_RANGE_PPM.start();
+const VOC_MIN_U16: u16 = *VOC_RANGE_PPB.start();
+const CO2_SPAN_U16: u16 = *CO2_RANGE_PPM.end() - *CO2_RANGE_PPM.start();
+const VOC_SPAN_U16: u16 = *VOC_RANGE_PPB.end() - *VOC_RANGE_PPB.start();
+
+/// `min + (raw - raw_offset) * span / raw_span` in Q16.16, the step rounded to nearest.
+/// Saturates instead of overflowing for raw values far above the span.
+fn scale_q(raw: u8, raw_offset: u8, raw_span: NonZeroU8, min: u16, span: u16) -> u32 {
+    let raw_span = u32::from(raw_span.get());
+    let step = ((u32::from(span) << FRAC_BITS) + raw_span / 2) / raw_span;
+    let raw = u32::from(raw.saturating_sub(raw_offset));
+    raw.saturating_mul(step)
+        .saturating_add(u32::from(min) << FRAC_BITS)
+}
+
+/// `min + (raw - raw_offset) * span / raw_span` rounded to nearest, without intermediate values
+/// above `u16`.
+///
+/// The step is split into its whole part and remainder, so the largest product is
+/// `(raw_span - 1) * 255`. Saturates instead of overflowing for raw values far above the span.
+const fn scale_u16(raw: u8, raw_offset: u8, raw_span: NonZeroU8, min: u16, span: u16) -> u16 {
+    let raw = raw.saturating_sub(raw_offset) as u16;
+    let raw_span = raw_span.get() as u16;
+    let whole = span / raw_span;
+    let rem = span % raw_span;
+    min.saturating_add(whole.saturating_mul(raw))
+        .saturating_add((rem * raw + raw_span / 2) / raw_span)
+}
+
+/// Measurements in Q16.16 fixed-point, i.e. the value multiplied by `2^16`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedMeasurements {
+    /// CO2 in ppm, Q16.16.
+    pub co2: u32,
+    /// VOC in ppb, Q16.16.
+    pub voc: u32,
+}
+
+impl FixedMeasurements {
+    /// Convert the response to [Command::GetStatus].
+    pub fn from_response(response: &[u8; RESPONSE_LEN]) -> Self {
+        Self::from_response_scaled(response, RAW_MIN, RAW_SPAN)
+    }
+
+    /// Convert the response to [Command::GetStatus] with a custom raw offset and span, see
+    /// [Config::raw_offset](crate::config::Config::raw_offset).
+    pub fn from_response_scaled(
+        response: &[u8; RESPONSE_LEN],
+        raw_offset: u8,
+        raw_span: NonZeroU8,
+    ) -> Self {
+        Self {
+            co2: scale_q(response[1], raw_offset, raw_span, CO2_MIN_U16, CO2_SPAN_U16),
+            voc: scale_q(response[0], raw_offset, raw_span, VOC_MIN_U16, VOC_SPAN_U16),
+        }
+    }
+
+    /// CO2 in ppm rounded to the nearest integer.
+    pub fn co2_ppm(&self) -> u16 {
+        round(self.co2)
+    }
+
+    /// VOC in ppb rounded to the nearest integer.
+    pub fn voc_ppb(&self) -> u16 {
+        round(self.voc)
+    }
+}
+
+fn round(q: u32) -> u16 {
+    let value = q.saturating_add(1 << (FRAC_BITS - 1)) >> FRAC_BITS;
+    u16::try_from(value).unwrap_or(u16::MAX)
+}
+
+/// Measurements in whole ppm and ppb, converted with `u16` arithmetic only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntMeasurements {
+    /// CO2 in ppm.
+    pub co2_ppm: u16,
+    /// VOC in ppb.
+    pub voc_ppb: u16,
+}
+
+impl IntMeasurements {
+    /// Convert the response to [Command::GetStatus].
+    pub const fn from_response(response: &[u8; RESPONSE_LEN]) -> Self {
+        Self::from_response_scaled(response, RAW_MIN, RAW_SPAN)
+    }
+
+    /// Convert the response to [Command::GetStatus] with a custom raw offset and span, see
+    /// [Config::raw_offset](crate::config::Config::raw_offset).
+    pub const fn from_response_scaled(
+        response: &[u8; RESPONSE_LEN],
+        raw_offset: u8,
+        raw_span: NonZeroU8,
+    ) -> Self {
+        Self {
+            co2_ppm: scale_u16(response[1], raw_offset, raw_span, CO2_MIN_U16, CO2_SPAN_U16),
+            voc_ppb: scale_u16(response[0], raw_offset, raw_span, VOC_MIN_U16, VOC_SPAN_U16),
+        }
+    }
+}
+
+impl<I2C, E> MicsVz89Te<I2C>
+where
+    I2C: Transport<Error = E>,
+{
+    /// Read measurements as fixed-point values. See [FixedMeasurements].
+    ///
+    /// This function blocks a minimum time of [MicsVz89Te::WAIT_ON_RESPONSE_TIME].
+    pub fn read_measurements_fixed(
+        &mut self,
+        delay: &mut impl Delay,
+    ) -> Result<FixedMeasurements, PacketParseError<E>> {
+        let response = self.request_data(Command::GetStatus, delay)?;
+        Ok(FixedMeasurements::from_response_scaled(
+            &response,
+            self.config.raw_offset,
+            self.config.raw_span,
+        ))
+    }
+
+    /// Read measurements as whole ppm and ppb. See [IntMeasurements].
+    ///
+    /// This function blocks a minimum time of [MicsVz89Te::WAIT_ON_RESPONSE_TIME].
+    pub fn read_measurements_int(
+        &mut self,
+        delay: &mut impl Delay,
+    ) -> Result<IntMeasurements, PacketParseError<E>> {
+        let response = self.request_data(Command::GetStatus, delay)?;
+        Ok(IntMeasurements::from_response_scaled(
+            &response,
+            self.config.raw_offset,
+            self.config.raw_span,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{FixedMeasurements, IntMeasurements};
+    use crate::{config::Config, protocol::GET_STATUS_FRAME, Measurements, MicsVz89Te};
+    use core::{assert_eq, num::NonZeroU8};
+    use embedded_hal_mock::{
+        delay::MockNoop as DelayMock,
+        i2c::{Mock as I2cMock, Transaction as I2cTransaction},
+    };
+    use std::vec;
+
+    #[test]
+    fn test_matches_float_conversion() {
+        for raw in 0..=u8::MAX {
+            let response = [raw, raw, 0, 0, 0, 0, 0];
+            let fixed = FixedMeasurements::from_response(&response);
+            let float = Measurements::from_response(&response);
+
+            let exact_co2 = f64::from(raw.saturating_sub(13)) * 1600.0 / 229.0 + 400.0;
+            let exact_voc = f64::from(raw.saturating_sub(13)) * 1000.0 / 229.0;
+            assert!((f64::from(fixed.co2) / 65536.0 - exact_co2).abs() < 0.002);
+            assert!((f64::from(fixed.voc) / 65536.0 - exact_voc).abs() < 0.002);
+            assert_eq!(fixed.co2_ppm(), float.co2_ppm_u16());
+            assert_eq!(fixed.voc_ppb(), float.voc_ppb_u16());
+        }
+    }
+
+    #[test]
+    fn test_int_matches_float_conversion() {
+        for raw in 0..=u8::MAX {
+            let response = [raw, raw, 0, 0, 0, 0, 0];
+            let int = IntMeasurements::from_response(&response);
+            let float = Measurements::from_response(&response);
+
+            assert_eq!(int.co2_ppm, float.co2_ppm_u16());
+            assert_eq!(int.voc_ppb, float.voc_ppb_u16());
+        }
+    }
+
+    #[test]
+    fn test_read_measurements_int() {
+        let expectations = [
+            I2cTransaction::write(0x70, GET_STATUS_FRAME.to_vec()),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+        ];
+        let mut device = MicsVz89Te::new(I2cMock::new(&expectations));
+
+        let measurements = device.read_measurements_int(&mut DelayMock::new()).unwrap();
+        assert_eq!(
+            measurements,
+            IntMeasurements {
+                co2_ppm: 728,
+                voc_ppb: 114
+            }
+        );
+        device.release().done();
+    }
+
+    #[test]
+    fn test_custom_raw_scale() {
+        for span in [1, 100, 225, u8::MAX] {
+            let span = NonZeroU8::new(span).unwrap();
+            for raw in 0..=u8::MAX {
+                let response = [raw, raw, 0, 0, 0, 0, 0];
+                let float = Measurements::from_response_scaled(&response, 15, span);
+                let int = IntMeasurements::from_response_scaled(&response, 15, span);
+                let fixed = FixedMeasurements::from_response_scaled(&response, 15, span);
+
+                assert_eq!(int.co2_ppm, float.co2_ppm_u16());
+                assert_eq!(int.voc_ppb, float.voc_ppb_u16());
+                assert_eq!(fixed.co2_ppm(), float.co2_ppm_u16());
+                assert_eq!(fixed.voc_ppb(), float.voc_ppb_u16());
+            }
+        }
+    }
+
+    #[test]
+    fn test_reads_apply_config() {
+        let status = || I2cTransaction::write(0x70, GET_STATUS_FRAME.to_vec());
+        let response = || I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]);
+        let expectations = [
+            status(),
+            response(),
+            status(),
+            response(),
+            status(),
+            response(),
+        ];
+        let config = Config {
+            raw_offset: 15,
+            raw_span: NonZeroU8::new(225).unwrap(),
+            ..Config::default()
+        };
+        let mut device = MicsVz89Te::with_config(I2cMock::new(&expectations), config);
+        let mut delay = DelayMock::new();
+
+        let float = device.read_measurements(&mut delay).unwrap();
+        let int = device.read_measurements_int(&mut delay).unwrap();
+        let fixed = device.read_measurements_fixed(&mut delay).unwrap();
+        assert_eq!(float.co2_ppm_u16(), 720);
+        assert_eq!(int.co2_ppm, 720);
+        assert_eq!(fixed.co2_ppm(), 720);
+        device.release().done();
+    }
+}
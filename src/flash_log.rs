@@ -0,0 +1,336 @@
+//! Ring buffer of aggregated records on NOR flash.
+//!
+//! [FlashLog] appends fixed-size records of [Summary]s to a flash region and overwrites the
+//! oldest sector when the region is full. Sectors are used strictly in turn, so erases are
+//! spread evenly over the region. After a power loss the log is recovered with
+//! [FlashLog::mount()] from the sequence numbers stored in each record.
+//!
+//! The storage is any `NorFlash` of [embedded-storage](https://docs.rs/embedded-storage),
+//! enabled by the `embedded-storage` feature, e.g. a flash region of the MCU HAL or an external
+//! SPI NOR flash driver. Records are read and written whole, so the read and write sizes of the
+//! flash have to divide [RECORD_LEN].
+//!
+//! Record layout ([RECORD_LEN] bytes, little endian):
+//!
+//! | byte   | content                              |
+//! |--------|--------------------------------------|
+//! | 0      | marker `0x5A`                        |
+//! | 1..5   | sequence number (`u32`)              |
+//! | 5..13  | timestamp in millis (`u64`)          |
+//! | 13..25 | mean, min, max of CO2 and VOC (`u16`) |
+//! | 25..29 | sample count (`u32`)                 |
+//! | 29     | checksum over bytes `0..29`          |
+//! | 30..32 | unused (`0xFF`)                      |
+
+use embedded_storage::nor_flash::NorFlash;
+
+use crate::{aggregate::Summary, protocol::gen_checksum, Measurements};
+
+/// Size of a record in bytes.
+pub const RECORD_LEN: usize = 32;
+
+const MARKER: u8 = 0x5A;
+
+/// Errors of a [FlashLog].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlashLogError<E> {
+    /// The storage failed.
+    Storage(E),
+    /// The region has less than two sectors, the sector size isn't a multiple of [RECORD_LEN]
+    /// or [RECORD_LEN] isn't a multiple of the read or write size.
+    InvalidGeometry,
+}
+
+/// A stored record.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LogRecord {
+    pub timestamp_ms: u64,
+    /// Summary with values rounded to 1 ppm (CO2) and 1 ppb (VOC).
+    pub summary: Summary,
+}
+
+impl LogRecord {
+    fn to_bytes(self, sequence: u32) -> [u8; RECORD_LEN] {
+        let mut bytes = [0xFF; RECORD_LEN];
+        bytes[0] = MARKER;
+        bytes[1..5].copy_from_slice(&sequence.to_le_bytes());
+        bytes[5..13].copy_from_slice(&self.timestamp_ms.to_le_bytes());
+        let s = &self.summary;
+        for (i, m) in [s.mean, s.min, s.max].iter().enumerate() {
+            bytes[13 + 4 * i..15 + 4 * i].copy_from_slice(&m.co2_ppm_u16().to_le_bytes());
+            bytes[15 + 4 * i..17 + 4 * i].copy_from_slice(&m.voc_ppb_u16().to_le_bytes());
+        }
+        bytes[25..29].copy_from_slice(&s.count.to_le_bytes());
+        bytes[29] = gen_checksum(&bytes[..29]);
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8; RECORD_LEN]) -> Option<(u32, Self)> {
+        if bytes[0] != MARKER || bytes[29] != gen_checksum(&bytes[..29]) {
+            return None;
+        }
+        let u16_at = |i: usize| f32::from(u16::from_le_bytes([bytes[i], bytes[i + 1]]));
+        let m = |i: usize| Measurements {
+            co2: u16_at(i),
+            voc: u16_at(i + 2),
+        };
+        let sequence = u32::from_le_bytes(bytes[1..5].try_into().ok()?);
+        let record = Self {
+            timestamp_ms: u64::from_le_bytes(bytes[5..13].try_into().ok()?),
+            summary: Summary {
+                mean: m(13),
+                min: m(17),
+                max: m(21),
+                count: u32::from_le_bytes(bytes[25..29].try_into().ok()?),
+            },
+        };
+        Some((sequence, record))
+    }
+}
+
+/// Ring buffer of [LogRecord]s on a NOR flash, see the [module docs](self).
+///
+/// # Example Usage
+/// ```ignore
+/// let mut log = FlashLog::mount(flash)?;
+/// let mut downsampler = Downsampler::new(60);
+/// loop {
+///     let measurements = device.read_measurements(&mut delay).unwrap();
+///     if let Some(summary) = downsampler.push(measurements) {
+///         log.append(&LogRecord { timestamp_ms: clock.now_ms(), summary })?;
+///     }
+/// }
+/// ```
+pub struct FlashLog<S> {
+    storage: S,
+    slots: u32,
+    next_slot: u32,
+    next_sequence: u32,
+}
+
+impl<S: NorFlash> FlashLog<S> {
+    /// Open the log stored in `storage`, continuing after the newest valid record.
+    pub fn mount(mut storage: S) -> Result<Self, FlashLogError<S::Error>> {
+        let capacity = storage.capacity();
+        if S::ERASE_SIZE == 0
+            || !S::ERASE_SIZE.is_multiple_of(RECORD_LEN)
+            || !RECORD_LEN.is_multiple_of(S::READ_SIZE)
+            || !RECORD_LEN.is_multiple_of(S::WRITE_SIZE)
+            || capacity / S::ERASE_SIZE < 2
+        {
+            return Err(FlashLogError::InvalidGeometry);
+        }
+        let slots = ((capacity / S::ERASE_SIZE * S::ERASE_SIZE) / RECORD_LEN) as u32;
+
+        let mut newest: Option<(u32, u32)> = None;
+        for slot in 0..slots {
+            if let Some((sequence, _)) = read_slot(&mut storage, slot)? {
+                if newest.is_none_or(|(s, _)| sequence.wrapping_sub(s) as i32 > 0) {
+                    newest = Some((sequence, slot));
+                }
+            }
+        }
+
+        let (next_slot, next_sequence) = match newest {
+            Some((sequence, slot)) => ((slot + 1) % slots, sequence.wrapping_add(1)),
+            None => (0, 0),
+        };
+        Ok(Self {
+            storage,
+            slots,
+            next_slot,
+            next_sequence,
+        })
+    }
+
+    /// Maximum number of records which can be stored at once. When the region is full, a
+    /// whole sector of the oldest records is dropped.
+    pub fn capacity(&self) -> u32 {
+        self.slots
+    }
+
+    /// Append a record.
+    pub fn append(&mut self, record: &LogRecord) -> Result<(), FlashLogError<S::Error>> {
+        let offset = self.next_slot * RECORD_LEN as u32;
+        if (offset as usize).is_multiple_of(S::ERASE_SIZE) {
+            self.storage
+                .erase(offset, offset + S::ERASE_SIZE as u32)
+                .map_err(FlashLogError::Storage)?;
+        }
+        self.storage
+            .write(offset, &record.to_bytes(self.next_sequence))
+            .map_err(FlashLogError::Storage)?;
+        self.next_slot = (self.next_slot + 1) % self.slots;
+        self.next_sequence = self.next_sequence.wrapping_add(1);
+        Ok(())
+    }
+
+    /// Iterate over the stored records, oldest first.
+    pub fn records(
+        &mut self,
+    ) -> impl Iterator<Item = Result<LogRecord, FlashLogError<S::Error>>> + '_ {
+        let start = self.next_slot;
+        let slots = self.slots;
+        let storage = &mut self.storage;
+        (0..slots).filter_map(move |i| {
+            read_slot(storage, (start + i) % slots)
+                .map(|r| r.map(|(_, record)| record))
+                .transpose()
+        })
+    }
+
+    /// Erase the whole region.
+    pub fn clear(&mut self) -> Result<(), FlashLogError<S::Error>> {
+        self.storage
+            .erase(0, self.slots * RECORD_LEN as u32)
+            .map_err(FlashLogError::Storage)?;
+        self.next_slot = 0;
+        Ok(())
+    }
+
+    /// Destroy the log and return the storage.
+    pub fn release(self) -> S {
+        self.storage
+    }
+}
+
+fn read_slot<S: NorFlash>(
+    storage: &mut S,
+    slot: u32,
+) -> Result<Option<(u32, LogRecord)>, FlashLogError<S::Error>> {
+    let mut bytes = [0u8; RECORD_LEN];
+    storage
+        .read(slot * RECORD_LEN as u32, &mut bytes)
+        .map_err(FlashLogError::Storage)?;
+    Ok(LogRecord::from_bytes(&bytes))
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{FlashLog, FlashLogError, LogRecord};
+    use crate::{aggregate::Summary, Measurements};
+    use core::assert_eq;
+    use embedded_storage::nor_flash::{ErrorType, NorFlash, NorFlashErrorKind, ReadNorFlash};
+    use std::vec::Vec;
+
+    /// RAM emulation of NOR flash with 2 sectors of 2 records.
+    struct RamFlash([u8; 128]);
+
+    impl ErrorType for RamFlash {
+        type Error = NorFlashErrorKind;
+    }
+
+    impl ReadNorFlash for RamFlash {
+        const READ_SIZE: usize = 1;
+
+        fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+            let offset = offset as usize;
+            bytes.copy_from_slice(&self.0[offset..offset + bytes.len()]);
+            Ok(())
+        }
+
+        fn capacity(&self) -> usize {
+            self.0.len()
+        }
+    }
+
+    impl NorFlash for RamFlash {
+        const WRITE_SIZE: usize = 4;
+        const ERASE_SIZE: usize = 64;
+
+        fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+            self.0[from as usize..to as usize].fill(0xFF);
+            Ok(())
+        }
+
+        fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+            let offset = offset as usize;
+            for (cell, byte) in self.0[offset..].iter_mut().zip(bytes) {
+                *cell &= byte;
+            }
+            Ok(())
+        }
+    }
+
+    fn record(timestamp_ms: u64) -> LogRecord {
+        let m = Measurements {
+            co2: 400.0 + timestamp_ms as f32,
+            voc: 20.0,
+        };
+        LogRecord {
+            timestamp_ms,
+            summary: Summary {
+                mean: m,
+                min: m,
+                max: m,
+                count: 60,
+            },
+        }
+    }
+
+    fn timestamps(log: &mut FlashLog<RamFlash>) -> Vec<u64> {
+        log.records().map(|r| r.unwrap().timestamp_ms).collect()
+    }
+
+    #[test]
+    fn test_append_and_wrap() {
+        let mut log = FlashLog::mount(RamFlash([0xFF; 128])).unwrap();
+        assert_eq!(log.capacity(), 4);
+        assert!(timestamps(&mut log).is_empty());
+
+        for t in 1..=4 {
+            log.append(&record(t)).unwrap();
+        }
+        assert_eq!(timestamps(&mut log), [1, 2, 3, 4]);
+        assert_eq!(log.records().next().unwrap(), Ok(record(1)));
+
+        // overwrites the first sector
+        log.append(&record(5)).unwrap();
+        assert_eq!(timestamps(&mut log), [3, 4, 5]);
+
+        // power loss
+        let mut log = FlashLog::mount(log.release()).unwrap();
+        log.append(&record(6)).unwrap();
+        log.append(&record(7)).unwrap();
+        assert_eq!(timestamps(&mut log), [5, 6, 7]);
+    }
+
+    #[test]
+    fn test_invalid_geometry() {
+        /// Flash of `CAPACITY` bytes in sectors of 192 bytes with a write size of `WRITE_SIZE`.
+        struct Flash<const CAPACITY: usize, const WRITE_SIZE: usize>;
+        impl<const C: usize, const W: usize> ErrorType for Flash<C, W> {
+            type Error = NorFlashErrorKind;
+        }
+        impl<const C: usize, const W: usize> ReadNorFlash for Flash<C, W> {
+            const READ_SIZE: usize = 1;
+            fn read(&mut self, _: u32, _: &mut [u8]) -> Result<(), Self::Error> {
+                Ok(())
+            }
+            fn capacity(&self) -> usize {
+                C
+            }
+        }
+        impl<const C: usize, const W: usize> NorFlash for Flash<C, W> {
+            const WRITE_SIZE: usize = W;
+            const ERASE_SIZE: usize = 192;
+            fn erase(&mut self, _: u32, _: u32) -> Result<(), Self::Error> {
+                Ok(())
+            }
+            fn write(&mut self, _: u32, _: &[u8]) -> Result<(), Self::Error> {
+                Ok(())
+            }
+        }
+        assert!(FlashLog::mount(Flash::<384, 32>).is_ok());
+        assert!(matches!(
+            FlashLog::mount(Flash::<192, 32>),
+            Err(FlashLogError::InvalidGeometry)
+        ));
+        // a 64 bytes write doesn't fit a record
+        assert!(matches!(
+            FlashLog::mount(Flash::<384, 64>),
+            Err(FlashLogError::InvalidGeometry)
+        ));
+    }
+}
@@ -0,0 +1,375 @@
+//! Registry of many sensors behind one interface.
+//!
+//! A gateway reads sensors on different buses, behind multiplexers or on other transports.
+//! Drivers of different bus types are different types, so the registry talks to them through
+//! the object safe [FleetSensor] trait, which every [MicsVz89Te] implements. Sensors behind a
+//! multiplexer are wrapped in [Muxed], which selects the channel before each read.
+//!
+//! [Fleet] polls the sensors round-robin, one per call, tracks the [DeviceHealth] of each and
+//! summarizes the latest readings of all sensors into a [FleetReport]. With the `alloc`
+//! feature, sensors can be added to a [VecFleet] at runtime.
+//!
+//! # Example Usage
+//! ```ignore
+//! let mut office = MicsVz89Te::new(bus.acquire_i2c());
+//! let mut lab = Muxed::new(MicsVz89Te::new(bus.acquire_i2c()), |i2c| i2c.write(0x74, &[0x02]));
+//! let mut remote = MicsVz89Te::new(UartBridge::new(serial, &clock));
+//! let mut fleet = Fleet::new(
+//!     [
+//!         (SensorIdentity::new("office"), &mut office as &mut dyn FleetSensor),
+//!         (SensorIdentity::new("lab"), &mut lab),
+//!         (SensorIdentity::new("cellar"), &mut remote),
+//!     ],
+//!     3,
+//! );
+//! loop {
+//!     fleet.poll_next(clock.now_ms(), &mut delay);
+//!     let report = fleet.report(clock.now_ms(), 60_000);
+//! }
+//! ```
+
+#[cfg(any(feature = "alloc", doc, test))]
+use alloc::vec::Vec;
+
+use crate::{
+    aggregate::Summary, clock::Timestamped, delay::Delay, event::Fault, identity::SensorIdentity,
+    transport::Transport, Measurements, MicsVz89Te,
+};
+
+/// A sensor which can be read by a [Fleet].
+pub trait FleetSensor {
+    /// Read the measurements of the sensor.
+    fn read(&mut self, delay: &mut dyn Delay) -> Result<Measurements, Fault>;
+}
+
+/// Sized wrapper to pass a `dyn` delay to the driver.
+struct DynDelay<'a>(&'a mut dyn Delay);
+
+impl Delay for DynDelay<'_> {
+    fn delay_ms(&mut self, ms: u16) {
+        self.0.delay_ms(ms)
+    }
+}
+
+impl<I2C, E> FleetSensor for MicsVz89Te<I2C>
+where
+    I2C: Transport<Error = E>,
+{
+    fn read(&mut self, delay: &mut dyn Delay) -> Result<Measurements, Fault> {
+        self.read_measurements(&mut DynDelay(delay))
+            .map_err(|e| Fault::from(&e))
+    }
+}
+
+/// Sensor behind an I2C multiplexer. `select` switches the multiplexer to the channel of the
+/// sensor before each read.
+pub struct Muxed<I2C, F> {
+    driver: MicsVz89Te<I2C>,
+    select: F,
+}
+
+impl<I2C, F, E> Muxed<I2C, F>
+where
+    F: FnMut(&mut I2C) -> Result<(), E>,
+{
+    /// Wrap a driver whose bus reaches the sensor through a multiplexer.
+    pub fn new(driver: MicsVz89Te<I2C>, select: F) -> Self {
+        Self { driver, select }
+    }
+
+    /// Destroy the wrapper and return the driver.
+    pub fn release(self) -> MicsVz89Te<I2C> {
+        self.driver
+    }
+}
+
+impl<I2C, F, E> FleetSensor for Muxed<I2C, F>
+where
+    I2C: Transport<Error = E>,
+    F: FnMut(&mut I2C) -> Result<(), E>,
+{
+    fn read(&mut self, delay: &mut dyn Delay) -> Result<Measurements, Fault> {
+        (self.select)(self.driver.bus_mut()).map_err(|_| Fault::Bus)?;
+        self.driver.read(delay)
+    }
+}
+
+/// Read statistics of a sensor of a [Fleet].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DeviceHealth {
+    /// Number of reads.
+    pub reads: u32,
+    /// Number of failed reads.
+    pub failures: u32,
+    /// Number of failed reads since the last successful one.
+    pub consecutive_failures: u8,
+    /// Fault of the last failed read.
+    pub last_fault: Option<Fault>,
+    /// Measurements of the last successful read.
+    pub last_measurements: Option<Timestamped<Measurements>>,
+}
+
+impl DeviceHealth {
+    fn record(&mut self, timestamp_ms: u64, result: &Result<Measurements, Fault>) {
+        self.reads = self.reads.saturating_add(1);
+        match result {
+            Ok(m) => {
+                self.consecutive_failures = 0;
+                self.last_measurements = Some(Timestamped::new(timestamp_ms, *m));
+            }
+            Err(fault) => {
+                self.failures = self.failures.saturating_add(1);
+                self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+                self.last_fault = Some(*fault);
+            }
+        }
+    }
+}
+
+/// Member of a [Fleet].
+pub struct Member<'a> {
+    pub identity: SensorIdentity<'a>,
+    pub health: DeviceHealth,
+    sensor: &'a mut dyn FleetSensor,
+}
+
+impl<'a> Member<'a> {
+    fn new(identity: SensorIdentity<'a>, sensor: &'a mut dyn FleetSensor) -> Self {
+        Self {
+            identity,
+            health: DeviceHealth::default(),
+            sensor,
+        }
+    }
+}
+
+/// Summary of the latest readings of a [Fleet].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FleetReport {
+    /// Number of sensors which didn't fail the configured number of reads in a row.
+    pub healthy: usize,
+    /// Number of sensors without a reading within the maximum age.
+    pub stale: usize,
+    /// Summary of the readings within the maximum age, `None` if there are none.
+    pub summary: Option<Summary>,
+}
+
+/// Round-robin poller and health tracker of `N` sensors.
+pub struct Fleet<'a, const N: usize> {
+    members: [Member<'a>; N],
+    max_failures: u8,
+    next: usize,
+}
+
+impl<'a, const N: usize> Fleet<'a, N> {
+    /// Create a registry of `sensors`. A sensor is unhealthy after `max_failures` (at least 1)
+    /// failed reads in a row.
+    pub fn new(
+        sensors: [(SensorIdentity<'a>, &'a mut dyn FleetSensor); N],
+        max_failures: u8,
+    ) -> Self {
+        Self {
+            members: sensors.map(|(identity, sensor)| Member::new(identity, sensor)),
+            max_failures: max_failures.max(1),
+            next: 0,
+        }
+    }
+
+    /// Read the next sensor in turn at `now_ms`. Returns its index and the result.
+    pub fn poll_next(
+        &mut self,
+        now_ms: u64,
+        delay: &mut dyn Delay,
+    ) -> Option<(usize, Result<Measurements, Fault>)> {
+        poll_next(&mut self.members, &mut self.next, now_ms, delay)
+    }
+
+    /// The members in registration order.
+    pub fn members(&self) -> &[Member<'a>] {
+        &self.members
+    }
+
+    /// Returns `true` if the sensor at `index` didn't fail too many reads in a row.
+    pub fn is_healthy(&self, index: usize) -> bool {
+        is_healthy(&self.members, self.max_failures, index)
+    }
+
+    /// Summarize the readings not older than `max_age_ms` at `now_ms`.
+    pub fn report(&self, now_ms: u64, max_age_ms: u64) -> FleetReport {
+        report(&self.members, self.max_failures, now_ms, max_age_ms)
+    }
+}
+
+#[cfg(any(feature = "alloc", doc, test))]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+/// Heap-backed [Fleet] to which sensors are added at runtime, e.g. from a configuration file.
+pub struct VecFleet<'a> {
+    members: Vec<Member<'a>>,
+    max_failures: u8,
+    next: usize,
+}
+
+#[cfg(any(feature = "alloc", doc, test))]
+impl<'a> VecFleet<'a> {
+    /// Create an empty registry. A sensor is unhealthy after `max_failures` (at least 1) failed
+    /// reads in a row.
+    pub fn new(max_failures: u8) -> Self {
+        Self {
+            members: Vec::new(),
+            max_failures: max_failures.max(1),
+            next: 0,
+        }
+    }
+
+    /// Add a sensor after the registered ones.
+    pub fn add(&mut self, identity: SensorIdentity<'a>, sensor: &'a mut dyn FleetSensor) {
+        self.members.push(Member::new(identity, sensor));
+    }
+
+    /// Read the next sensor in turn at `now_ms`. Returns its index and the result, `None` if
+    /// the registry is empty.
+    pub fn poll_next(
+        &mut self,
+        now_ms: u64,
+        delay: &mut dyn Delay,
+    ) -> Option<(usize, Result<Measurements, Fault>)> {
+        poll_next(&mut self.members, &mut self.next, now_ms, delay)
+    }
+
+    /// The members in registration order.
+    pub fn members(&self) -> &[Member<'a>] {
+        &self.members
+    }
+
+    /// Returns `true` if the sensor at `index` didn't fail too many reads in a row.
+    pub fn is_healthy(&self, index: usize) -> bool {
+        is_healthy(&self.members, self.max_failures, index)
+    }
+
+    /// Summarize the readings not older than `max_age_ms` at `now_ms`.
+    pub fn report(&self, now_ms: u64, max_age_ms: u64) -> FleetReport {
+        report(&self.members, self.max_failures, now_ms, max_age_ms)
+    }
+}
+
+fn poll_next(
+    members: &mut [Member],
+    next: &mut usize,
+    now_ms: u64,
+    delay: &mut dyn Delay,
+) -> Option<(usize, Result<Measurements, Fault>)> {
+    let index = *next;
+    let member = members.get_mut(index)?;
+    let result = member.sensor.read(delay);
+    member.health.record(now_ms, &result);
+    *next = (index + 1) % members.len();
+    Some((index, result))
+}
+
+fn is_healthy(members: &[Member], max_failures: u8, index: usize) -> bool {
+    members
+        .get(index)
+        .is_some_and(|m| m.health.consecutive_failures < max_failures)
+}
+
+fn report(members: &[Member], max_failures: u8, now_ms: u64, max_age_ms: u64) -> FleetReport {
+    let fresh = || {
+        members.iter().filter_map(move |m| {
+            m.health
+                .last_measurements
+                .as_ref()
+                .filter(|t| now_ms.saturating_sub(t.timestamp_ms) <= max_age_ms)
+                .map(|t| &t.value)
+        })
+    };
+    FleetReport {
+        healthy: (0..members.len())
+            .filter(|i| is_healthy(members, max_failures, *i))
+            .count(),
+        stale: members.len() - fresh().count(),
+        summary: Summary::from_measurements(fresh()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{Fleet, FleetSensor, Muxed, VecFleet};
+    use crate::{event::Fault, identity::SensorIdentity, MicsVz89Te};
+    use core::assert_eq;
+    use embedded_hal::blocking::i2c::Write;
+    use embedded_hal_mock::{
+        delay::MockNoop as DelayMock,
+        i2c::{Mock as I2cMock, Transaction as I2cTransaction},
+        MockError,
+    };
+    use std::{io::ErrorKind, vec};
+
+    #[test]
+    fn test_round_robin() {
+        let status = || I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]);
+        let response = || I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]);
+        let select = || I2cTransaction::write(0x74, vec![0x02]);
+        let mut office =
+            MicsVz89Te::new(I2cMock::new(&[status(), response(), status(), response()]));
+        let mut lab = Muxed::new(
+            MicsVz89Te::new(I2cMock::new(&[
+                select(),
+                status(),
+                response(),
+                select().with_error(MockError::Io(ErrorKind::Other)),
+            ])),
+            |i2c: &mut I2cMock| i2c.write(0x74, &[0x02]),
+        );
+        let mut delay = DelayMock::new();
+        let mut fleet = Fleet::new(
+            [
+                (
+                    SensorIdentity::new("office"),
+                    &mut office as &mut dyn FleetSensor,
+                ),
+                (SensorIdentity::new("lab"), &mut lab),
+            ],
+            1,
+        );
+
+        for (now_ms, index) in [(0, 0), (1000, 1), (2000, 0), (3000, 1)] {
+            assert_eq!(fleet.poll_next(now_ms, &mut delay).unwrap().0, index);
+        }
+
+        let lab_health = &fleet.members()[1].health;
+        assert_eq!((lab_health.reads, lab_health.failures), (2, 1));
+        assert_eq!(lab_health.last_fault, Some(Fault::Bus));
+        assert!(fleet.is_healthy(0));
+        assert!(!fleet.is_healthy(1));
+
+        let report = fleet.report(3000, 1500);
+        assert_eq!((report.healthy, report.stale), (1, 1));
+        assert_eq!(report.summary.map(|s| s.count), Some(1));
+
+        office.release().done();
+        lab.release().release().done();
+    }
+
+    #[test]
+    fn test_vec_fleet() {
+        let expectations = [
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+        ];
+        let mut office = MicsVz89Te::new(I2cMock::new(&expectations));
+        let mut delay = DelayMock::new();
+        let mut fleet = VecFleet::new(1);
+        assert!(fleet.poll_next(0, &mut delay).is_none());
+
+        fleet.add(SensorIdentity::new("office"), &mut office);
+        let (index, result) = fleet.poll_next(0, &mut delay).unwrap();
+        assert_eq!(index, 0);
+        assert_eq!(result.map(|m| m.co2_ppm_u16()), Ok(728));
+        assert_eq!(fleet.members()[0].identity.label, "office");
+        let report = fleet.report(0, 1000);
+        assert_eq!((report.healthy, report.stale), (1, 0));
+
+        office.release().done();
+    }
+}
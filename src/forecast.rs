@@ -0,0 +1,102 @@
+//! Short-term forecast of threshold crossings.
+//!
+//! A line is fitted through the recent samples and extrapolated to estimate when a channel
+//! will cross a limit, e.g. to ventilate before CO2 reaches 1000 ppm.
+//!
+//! # Example Usage
+//! ```ignore
+//! let forecaster = Forecaster::new(1000, 30 * 60 * 1000);
+//! if let Forecast::Crossing { in_ms } = forecaster.forecast(history.iter(), Channel::Co2, 1000.0) {
+//!     show_hint(in_ms / 60_000); // "ventilate within ~12 minutes"
+//! }
+//! ```
+
+use crate::{math, Channel, Measurements};
+
+/// Result of [Forecaster::forecast()].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Forecast {
+    /// The fitted current value is already at or above the limit.
+    AlreadyAbove,
+    /// The limit is expected to be reached in `in_ms` millis.
+    Crossing { in_ms: u64 },
+    /// The value doesn't rise or won't reach the limit within the horizon.
+    NotCrossing,
+    /// Less than 3 samples.
+    InsufficientData,
+}
+
+/// Linear extrapolation of equidistant samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Forecaster {
+    sample_interval_ms: u32,
+    horizon_ms: u64,
+}
+
+impl Forecaster {
+    /// Create a forecaster for samples taken every `sample_interval_ms`, looking at most
+    /// `horizon_ms` ahead. Extrapolating far beyond the sampled window is unreliable, so keep
+    /// the horizon in the order of the window length.
+    pub const fn new(sample_interval_ms: u32, horizon_ms: u64) -> Self {
+        Self {
+            sample_interval_ms,
+            horizon_ms,
+        }
+    }
+
+    /// Estimate when `channel` reaches `limit`, based on `samples` ordered oldest first.
+    pub fn forecast<'a>(
+        &self,
+        samples: impl Iterator<Item = &'a Measurements> + Clone,
+        channel: Channel,
+        limit: f32,
+    ) -> Forecast {
+        let Some(fit) = math::linear_fit(samples.map(|m| m.get(channel))) else {
+            return Forecast::InsufficientData;
+        };
+        if fit.last >= limit {
+            return Forecast::AlreadyAbove;
+        }
+        if fit.slope <= 0.0 {
+            return Forecast::NotCrossing;
+        }
+        let in_ms = (limit - fit.last) / fit.slope * self.sample_interval_ms as f32;
+        if in_ms > self.horizon_ms as f32 {
+            return Forecast::NotCrossing;
+        }
+        Forecast::Crossing {
+            in_ms: in_ms as u64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{Forecast, Forecaster};
+    use crate::{Channel, Measurements};
+    use core::assert_eq;
+    use std::vec::Vec;
+
+    fn samples(co2: &[f32]) -> Vec<Measurements> {
+        co2.iter()
+            .map(|&co2| Measurements { co2, voc: 0.0 })
+            .collect()
+    }
+
+    #[test]
+    fn test_forecast() {
+        // one sample per minute, 30 minutes ahead
+        let forecaster = Forecaster::new(60_000, 30 * 60_000);
+        let forecast = |co2: &[f32]| forecaster.forecast(samples(co2).iter(), Channel::Co2, 1000.0);
+
+        assert_eq!(
+            forecast(&[700.0, 720.0, 740.0, 760.0]),
+            Forecast::Crossing { in_ms: 12 * 60_000 }
+        );
+        assert_eq!(forecast(&[700.0, 701.0, 702.0]), Forecast::NotCrossing);
+        assert_eq!(forecast(&[900.0, 850.0, 800.0]), Forecast::NotCrossing);
+        assert_eq!(forecast(&[980.0, 1000.0, 1020.0]), Forecast::AlreadyAbove);
+        assert_eq!(forecast(&[900.0, 950.0]), Forecast::InsufficientData);
+    }
+}
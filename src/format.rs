@@ -0,0 +1,118 @@
+//! Allocation-free formatting helpers without format strings.
+//!
+//! Formatting an `f32` with `{:.1}` links the float formatting machinery of `core`, which
+//! doesn't fit into the flash of the smallest parts. [write_fixed()] writes a fixed number of
+//! decimal places using integer arithmetic only.
+
+use core::{
+    fmt::{Result, Write},
+    num::NonZeroU32,
+};
+
+use crate::units;
+
+/// Maximum number of decimal places written by [write_fixed()].
+pub const MAX_DECIMALS: u8 = 4;
+
+/// Write `value` in decimal, left-padded with zeros to at least `min_digits` digits.
+pub(crate) fn write_u16(w: &mut impl Write, value: u16, min_digits: usize) -> Result {
+    write_u32(w, u32::from(value), min_digits)
+}
+
+/// Write `value` in decimal, left-padded with zeros to at least `min_digits` digits.
+fn write_u32(w: &mut impl Write, value: u32, min_digits: usize) -> Result {
+    let mut digits = [b'0'; 10];
+    let mut value = value;
+    let mut len = 0;
+    for digit in digits.iter_mut().rev() {
+        *digit = b'0' + (value % 10) as u8;
+        value /= 10;
+        len += 1;
+        if value == 0 {
+            break;
+        }
+    }
+    let start = digits.len().saturating_sub(len.max(min_digits));
+    // only ASCII digits are written into the buffer
+    let written = digits.get(start..).unwrap_or_default();
+    w.write_str(core::str::from_utf8(written).unwrap_or_default())
+}
+
+/// Powers of ten up to `10^MAX_DECIMALS`, non-zero so dividing by them can't panic.
+const SCALES: [NonZeroU32; MAX_DECIMALS as usize + 1] = {
+    let mut scales = [NonZeroU32::MIN; MAX_DECIMALS as usize + 1];
+    let mut i = 1;
+    while i < scales.len() {
+        scales[i] = scales[i - 1].saturating_mul(NonZeroU32::new(10).unwrap());
+        i += 1;
+    }
+    scales
+};
+
+/// Write `value` rounded to `decimals` decimal places (at most [MAX_DECIMALS]), without
+/// exponent, e.g. `728.2` or `-0.05`.
+///
+/// NaN is written as `NaN`, infinities as `inf` and `-inf`. Finite values beyond the range of
+/// `i32` after scaling saturate, see [units::scaled()].
+pub fn write_fixed(w: &mut impl Write, value: f32, decimals: u8) -> Result {
+    if value.is_nan() {
+        return w.write_str("NaN");
+    }
+    if value.is_infinite() {
+        return w.write_str(if value < 0.0 { "-inf" } else { "inf" });
+    }
+    let decimals = decimals.min(MAX_DECIMALS);
+    let scale = SCALES[usize::from(decimals)];
+    let scaled = units::scaled(value, decimals).unsigned_abs();
+    if value < 0.0 && scaled > 0 {
+        w.write_char('-')?;
+    }
+    write_u32(w, scaled / scale, 1)?;
+    if decimals > 0 {
+        w.write_char('.')?;
+        write_u32(w, scaled % scale, usize::from(decimals))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{write_fixed, write_u16};
+    use core::assert_eq;
+    use std::string::String;
+
+    #[test]
+    fn test_write_u16() {
+        let mut s = String::new();
+        write_u16(&mut s, 0, 1).unwrap();
+        s.push(' ');
+        write_u16(&mut s, 7, 2).unwrap();
+        s.push(' ');
+        write_u16(&mut s, 65535, 1).unwrap();
+        s.push(' ');
+        write_u16(&mut s, 2016, 4).unwrap();
+        assert_eq!(s, "0 07 65535 2016");
+    }
+
+    #[test]
+    fn test_write_fixed() {
+        let mut s = String::new();
+        for (value, decimals) in [
+            (728.24, 1),
+            (113.0, 1),
+            (0.96, 1),
+            (-0.04, 1),
+            (-12.346, 2),
+            (2000.0, 0),
+            (1.5, 9),
+        ] {
+            write_fixed(&mut s, value, decimals).unwrap();
+            s.push(' ');
+        }
+        write_fixed(&mut s, f32::NAN, 1).unwrap();
+        s.push(' ');
+        write_fixed(&mut s, f32::NEG_INFINITY, 1).unwrap();
+        assert_eq!(s, "728.2 113.0 1.0 0.0 -12.35 2000 1.5000 NaN -inf");
+    }
+}
@@ -0,0 +1,188 @@
+//! Integrity-protected frames for forwarding readings over lossy links (UART, RF).
+//!
+//! A frame carries either a raw sensor response or a [wire](crate::wire) record together
+//! with a sequence number, so the receiver can detect corrupted and lost frames.
+//!
+//! | byte     | content                                             |
+//! |----------|-----------------------------------------------------|
+//! | 0        | sync `0xA5`                                         |
+//! | 1        | payload kind: `0` raw response (7 bytes), `1` wire record (4 bytes) |
+//! | 2..4     | sequence number, `u16` LE                           |
+//! | 4..4+n   | payload                                             |
+//! | 4+n..6+n | CRC-16/CCITT-FALSE over bytes `0..4+n`, `u16` LE    |
+//!
+//! Frames have no separate schema version, the payload kind is one: an incompatible payload
+//! gets a new kind, which older receivers reject with [FrameError::UnknownKind]. That is also
+//! why kind `1` holds the plain wire record, the kind already fixes its version.
+
+use crate::{protocol::RESPONSE_LEN, wire};
+
+/// Maximum size of a frame in bytes.
+pub const MAX_FRAME_LEN: usize = HEADER_LEN + RESPONSE_LEN + CRC_LEN;
+
+const SYNC: u8 = 0xA5;
+const HEADER_LEN: usize = 4;
+const CRC_LEN: usize = 2;
+const KIND_RESPONSE: u8 = 0;
+const KIND_WIRE: u8 = 1;
+
+/// Content of a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Payload {
+    /// Raw 7 byte response of the sensor, including its checksum.
+    Response([u8; RESPONSE_LEN]),
+    /// Record of the [wire](crate::wire) encoding.
+    Wire([u8; wire::ENCODED_LEN]),
+}
+
+impl Payload {
+    fn kind(&self) -> u8 {
+        match self {
+            Self::Response(_) => KIND_RESPONSE,
+            Self::Wire(_) => KIND_WIRE,
+        }
+    }
+
+    fn bytes(&self) -> &[u8] {
+        match self {
+            Self::Response(b) => b,
+            Self::Wire(b) => b,
+        }
+    }
+}
+
+/// A decoded frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    pub sequence: u16,
+    pub payload: Payload,
+}
+
+/// Errors of [parse()].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameError {
+    /// The data doesn't start with the sync byte.
+    NoSync,
+    /// The payload kind is unknown.
+    UnknownKind(u8),
+    /// More data is needed to parse the frame.
+    Incomplete,
+    /// The CRC doesn't match.
+    WrongCrc,
+}
+
+/// Builds frames with increasing sequence numbers.
+#[derive(Debug, Clone, Default)]
+pub struct FrameEncoder {
+    sequence: u16,
+}
+
+impl FrameEncoder {
+    /// Create an encoder starting at sequence number `0`.
+    pub const fn new() -> Self {
+        Self { sequence: 0 }
+    }
+
+    /// Encode `payload` into `out` and return the frame length. Returns `None` if `out` is
+    /// too small. The sequence number is only advanced if the frame was written.
+    pub fn encode(&mut self, payload: &Payload, out: &mut [u8]) -> Option<usize> {
+        let data = payload.bytes();
+        let len = HEADER_LEN + data.len() + CRC_LEN;
+        let frame = out.get_mut(..len)?;
+        let [seq_l, seq_h] = self.sequence.to_le_bytes();
+        frame[..HEADER_LEN].copy_from_slice(&[SYNC, payload.kind(), seq_l, seq_h]);
+        frame[HEADER_LEN..len - CRC_LEN].copy_from_slice(data);
+        let crc = crc16(&frame[..len - CRC_LEN]);
+        frame[len - CRC_LEN..].copy_from_slice(&crc.to_le_bytes());
+        self.sequence = self.sequence.wrapping_add(1);
+        Some(len)
+    }
+}
+
+/// Parse the frame at the start of `data`. Returns the frame and its length.
+///
+/// On [FrameError::NoSync] or [FrameError::WrongCrc] a receiver should drop one byte and
+/// search for the next sync byte.
+pub fn parse(data: &[u8]) -> Result<(Frame, usize), FrameError> {
+    let [sync, kind, seq_l, seq_h, ..] = *data else {
+        return match data.first() {
+            Some(&b) if b != SYNC => Err(FrameError::NoSync),
+            _ => Err(FrameError::Incomplete),
+        };
+    };
+    if sync != SYNC {
+        return Err(FrameError::NoSync);
+    }
+    let payload_len = match kind {
+        KIND_RESPONSE => RESPONSE_LEN,
+        KIND_WIRE => wire::ENCODED_LEN,
+        k => return Err(FrameError::UnknownKind(k)),
+    };
+    let len = HEADER_LEN + payload_len + CRC_LEN;
+    let frame = data.get(..len).ok_or(FrameError::Incomplete)?;
+    let crc = u16::from_le_bytes([frame[len - 2], frame[len - 1]]);
+    if crc != crc16(&frame[..len - CRC_LEN]) {
+        return Err(FrameError::WrongCrc);
+    }
+
+    let payload = &frame[HEADER_LEN..len - CRC_LEN];
+    let payload = match kind {
+        KIND_RESPONSE => Payload::Response(payload.try_into().map_err(|_| FrameError::Incomplete)?),
+        _ => Payload::Wire(payload.try_into().map_err(|_| FrameError::Incomplete)?),
+    };
+    Ok((
+        Frame {
+            sequence: u16::from_le_bytes([seq_l, seq_h]),
+            payload,
+        },
+        len,
+    ))
+}
+
+/// CRC-16/CCITT-FALSE (polynomial `0x1021`, init `0xFFFF`).
+pub fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0xFFFF, |crc, &byte| {
+        (0..8).fold(crc ^ (u16::from(byte) << 8), |crc, _| {
+            if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{crc16, parse, FrameEncoder, FrameError, Payload, MAX_FRAME_LEN};
+    use core::assert_eq;
+
+    #[test]
+    fn test_crc16() {
+        assert_eq!(crc16(b"123456789"), 0x29B1);
+    }
+
+    #[test]
+    fn test_encode_parse() {
+        let mut encoder = FrameEncoder::new();
+        let mut buffer = [0u8; 2 * MAX_FRAME_LEN];
+
+        let response = Payload::Response([0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]);
+        let first = encoder.encode(&response, &mut buffer).unwrap();
+        let wire = Payload::Wire([0xD8, 0x02, 0x72, 0x00]);
+        let second = encoder.encode(&wire, &mut buffer[first..]).unwrap();
+        assert_eq!((first, second), (13, 10));
+
+        let (frame, len) = parse(&buffer).unwrap();
+        assert_eq!((frame.sequence, frame.payload, len), (0, response, 13));
+        let (frame, _) = parse(&buffer[len..]).unwrap();
+        assert_eq!((frame.sequence, frame.payload), (1, wire));
+
+        assert_eq!(parse(&buffer[..12]), Err(FrameError::Incomplete));
+        assert_eq!(parse(&buffer[1..]), Err(FrameError::NoSync));
+        buffer[5] ^= 0x10;
+        assert_eq!(parse(&buffer), Err(FrameError::WrongCrc));
+        assert!(encoder.encode(&response, &mut [0u8; 12]).is_none());
+    }
+}
@@ -0,0 +1,301 @@
+//! Blocking and async frontends of the driver, generated from one source.
+//!
+//! [impl_requests!] expands the request sequence (retries, cancellation bookkeeping, checksum
+//! check) and the read and calibration API into the `impl` block of a frontend, once without
+//! and once with `async`/`.await`. [impl_state!] expands the accessors of the driver state.
+//! Both expect the fields of [MicsVz89Te](crate::MicsVz89Te) and, in the frontend, the
+//! primitives
+//!
+//! - `send_frame(command)`: write the command frame,
+//! - `receive_frame()`: read a response frame,
+//! - `wait(delay)`: wait [Config::wait_time_ms](crate::config::Config::wait_time_ms),
+//! - `settle_pending()`: get rid of the response of an abandoned request,
+//!
+//! which have the same asyncness as the generated functions. [with_retries!] is the retry loop
+//! shared by the generated functions and the reads which add steps of their own.
+
+/// Evaluate `$attempt` and repeat it up to [Config::retries](crate::config::Config::retries)
+/// times on failure, as long as `$may_retry` holds. Records the retries in `retries_used` of
+/// `$driver`.
+macro_rules! with_retries {
+    ($driver:expr, $attempt:expr) => {
+        $crate::frontend::with_retries!($driver, $attempt, true)
+    };
+    ($driver:expr, $attempt:expr, $may_retry:expr) => {{
+        let mut retries = $driver.config.retries;
+        $driver.retries_used = 0;
+        loop {
+            match $attempt {
+                Err(_) if retries > 0 && $may_retry => {
+                    retries -= 1;
+                    $driver.retries_used += 1;
+                }
+                result => break result,
+            }
+        }
+    }};
+}
+
+/// Request sequence and read API of a frontend, see the [module](self) documentation.
+///
+/// `[$($asyncness)*]` is empty or `async`, `[$($await)*]` is empty or `.await` and `$delay` is
+/// the delay type of the frontend.
+macro_rules! impl_requests {
+    ([$($asyncness:tt)*] [$($await:tt)*] $delay:ty) => {
+        /// Read measurements from sensor.
+        ///
+        /// Waits [Config::wait_time_ms]($crate::config::Config::wait_time_ms) for the response.
+        pub $($asyncness)* fn read_measurements(
+            &mut self,
+            delay: &mut $delay,
+        ) -> Result<$crate::Measurements, $crate::error::PacketParseError<E>> {
+            let response = self
+                .request_data($crate::protocol::Command::GetStatus, delay)
+                $($await)*?;
+            Ok(self.decode_measurements(&response))
+        }
+
+        /// Read measurements together with the raw sensor resistance in Ohms.
+        ///
+        /// Waits [Config::wait_time_ms]($crate::config::Config::wait_time_ms) for the response.
+        pub $($asyncness)* fn read_measurements_with_resistance(
+            &mut self,
+            delay: &mut $delay,
+        ) -> Result<($crate::Measurements, u32), $crate::error::PacketParseError<E>> {
+            let response = self
+                .request_data($crate::protocol::Command::GetStatus, delay)
+                $($await)*?;
+            Ok((
+                self.decode_measurements(&response),
+                $crate::protocol::decode_resistance(&response),
+            ))
+        }
+
+        /// Read measurements together with the caveats of the read, see
+        /// [Warnings]($crate::warnings::Warnings). Responses with a wrong checksum are accepted
+        /// after [Config::lenient_checksum_after]($crate::config::Config::lenient_checksum_after)
+        /// failures in a row, flagged as
+        /// [Warnings::UNVERIFIED]($crate::warnings::Warnings::UNVERIFIED).
+        ///
+        /// Waits [Config::wait_time_ms]($crate::config::Config::wait_time_ms) for the response.
+        pub $($asyncness)* fn read_measurements_with_warnings(
+            &mut self,
+            delay: &mut $delay,
+        ) -> Result<$crate::warnings::WarnedMeasurements, $crate::error::PacketParseError<E>> {
+            let lenient_after = self.config.lenient_checksum_after;
+            let response = self
+                .request_data_lenient($crate::protocol::Command::GetStatus, delay, lenient_after)
+                $($await)*?;
+            let measurements = self.decode_measurements(&response);
+            Ok($crate::warnings::WarnedMeasurements::from_read(
+                measurements,
+                self.clamped,
+                self.retries_used,
+                &self.checksum,
+            ))
+        }
+
+        /// Read revision date of the sensor.
+        ///
+        /// Waits [Config::wait_time_ms]($crate::config::Config::wait_time_ms) for the response.
+        pub $($asyncness)* fn read_revision(
+            &mut self,
+            delay: &mut $delay,
+        ) -> Result<$crate::RevisionDate, $crate::error::PacketParseError<E>> {
+            let response = self
+                .request_data($crate::protocol::Command::GetRevision, delay)
+                $($await)*?;
+            Ok($crate::protocol::decode_revision(&response))
+        }
+
+        #[cfg(any(feature = "unproven", doc, test))]
+        #[cfg_attr(docsrs, doc(cfg(feature = "unproven")))]
+        /// Read the calibration value R0 of the sensor in kOhms.
+        ///
+        /// Waits [Config::wait_time_ms]($crate::config::Config::wait_time_ms) for the response.
+        pub $($asyncness)* fn read_calibration_r0(
+            &mut self,
+            delay: &mut $delay,
+        ) -> Result<u16, $crate::error::PacketParseError<E>> {
+            let response = self
+                .request_data($crate::protocol::Command::GetCalibrationR0, delay)
+                $($await)*?;
+            Ok($crate::protocol::decode_calibration_r0(&response))
+        }
+
+        #[cfg(any(feature = "unproven", doc, test))]
+        #[cfg_attr(docsrs, doc(cfg(feature = "unproven")))]
+        /// Writes the calibration CO2 value in ppm in range from 400 to 2000 measured by another device.
+        ///
+        /// Values outside of [CO2_RANGE_PPM]($crate::CO2_RANGE_PPM) are clamped, the clamped
+        /// value is recorded in `last_calibration()`.
+        pub $($asyncness)* fn write_calibration_ppm(
+            &mut self,
+            ppm: f32,
+        ) -> Result<(), $crate::error::PacketParseError<E>> {
+            self.settle_pending()$($await)*?;
+            self.send_frame($crate::protocol::Command::set_calibration_ppm(ppm))$($await)*?;
+            // the response to a calibration write is never read
+            self.pending = None;
+            self.calibration = Some($crate::config::CalibrationRecord {
+                reference_ppm: ppm.clamp($crate::CO2_MIN, $crate::CO2_MAX),
+                method: $crate::config::CalibrationMethod::Manual,
+                timestamp_ms: None,
+            });
+            Ok(())
+        }
+
+        #[cfg(any(feature = "unproven", doc, test))]
+        #[cfg_attr(docsrs, doc(cfg(feature = "unproven")))]
+        /// Writes the calibration CO2 value in ppm like [Self::write_calibration_ppm()] and
+        /// records the time and method of the calibration, see `last_calibration()`.
+        pub $($asyncness)* fn write_calibration(
+            &mut self,
+            ppm: f32,
+            method: $crate::config::CalibrationMethod,
+            clock: &impl $crate::clock::Clock,
+        ) -> Result<(), $crate::error::PacketParseError<E>> {
+            self.write_calibration_ppm(ppm)$($await)*?;
+            self.calibration = Some($crate::config::CalibrationRecord {
+                reference_ppm: ppm.clamp($crate::CO2_MIN, $crate::CO2_MAX),
+                method,
+                timestamp_ms: Some(clock.now_ms()),
+            });
+            Ok(())
+        }
+
+        /// Request data, rejecting responses with a wrong checksum.
+        pub(crate) $($asyncness)* fn request_data(
+            &mut self,
+            command: $crate::protocol::Command,
+            delay: &mut $delay,
+        ) -> Result<[u8; $crate::protocol::RESPONSE_LEN], $crate::error::PacketParseError<E>> {
+            self.request_data_lenient(command, delay, 0)$($await)*
+        }
+
+        /// Request data, accepting responses with a wrong checksum after `lenient_after` failures
+        /// in a row (`0` rejects them all). Failed requests are repeated up to
+        /// [Config::retries]($crate::config::Config::retries) times.
+        pub(crate) $($asyncness)* fn request_data_lenient(
+            &mut self,
+            command: $crate::protocol::Command,
+            delay: &mut $delay,
+            lenient_after: u8,
+        ) -> Result<[u8; $crate::protocol::RESPONSE_LEN], $crate::error::PacketParseError<E>> {
+            $crate::frontend::with_retries!(
+                self,
+                self.request_once(command, &mut *delay, lenient_after)$($await)*
+            )
+        }
+
+        $($asyncness)* fn request_once(
+            &mut self,
+            command: $crate::protocol::Command,
+            delay: &mut $delay,
+            lenient_after: u8,
+        ) -> Result<[u8; $crate::protocol::RESPONSE_LEN], $crate::error::PacketParseError<E>> {
+            self.settle_pending()$($await)*?;
+            // set before the write, so an abandoned request leaves its command behind, and
+            // cleared on every error, so it doesn't survive a failed request
+            self.pending = Some(command);
+            if let Err(e) = self.send_frame(command)$($await)* {
+                self.pending = None;
+                return Err(e);
+            }
+            self.wait(delay)$($await)*;
+            let response = self.receive_frame()$($await)*;
+            self.pending = None;
+            let response = response?;
+            self.accept_response(&response, lenient_after)?;
+            Ok(response)
+        }
+
+        /// Check the checksum of a received response and record its status byte. Responses with a
+        /// wrong checksum are accepted after `lenient_after` failures in a row (`0` rejects them
+        /// all), see [Config::lenient_checksum_after]($crate::config::Config::lenient_checksum_after).
+        pub(crate) fn accept_response(
+            &mut self,
+            response: &[u8; $crate::protocol::RESPONSE_LEN],
+            lenient_after: u8,
+        ) -> Result<(), $crate::error::PacketParseError<E>> {
+            match $crate::protocol::check_response(response) {
+                Ok(()) => {
+                    self.checksum.record_valid();
+                    self.last_status = Some(response[5]);
+                }
+                Err(e) => {
+                    if !self.checksum.record_failure(lenient_after) {
+                        return Err(e);
+                    }
+                }
+            }
+            Ok(())
+        }
+    };
+}
+
+/// Accessors of the driver state, see the [module](self) documentation.
+macro_rules! impl_state {
+    () => {
+        /// Current configuration of the driver.
+        pub fn config(&self) -> &$crate::config::Config {
+            &self.config
+        }
+
+        /// Capture the configuration and calibration state of the driver.
+        pub fn snapshot(&self) -> $crate::config::StateSnapshot {
+            $crate::config::StateSnapshot {
+                config: self.config,
+                calibration: self.calibration,
+            }
+        }
+
+        /// Restore a state captured with [Self::snapshot()].
+        ///
+        /// This only updates the driver, nothing is written to the sensor.
+        pub fn apply(&mut self, snapshot: &$crate::config::StateSnapshot) {
+            self.config = snapshot.config;
+            self.calibration = snapshot.calibration;
+        }
+
+        /// Dump of the internal state, e.g. for crash logs. See
+        /// [DriverState]($crate::diagnostics::DriverState).
+        pub fn state(&self) -> $crate::diagnostics::DriverState {
+            $crate::diagnostics::DriverState {
+                config: self.config,
+                pending: self.pending,
+                retries_used: self.retries_used,
+                last_status: self.last_status,
+                last_clamped: self.clamped,
+                checksum: self.checksum,
+            }
+        }
+
+        /// Channels clamped in the last read measurements. Always unclamped unless
+        /// [Config::strict_range]($crate::config::Config::strict_range) is set.
+        pub fn last_clamped(&self) -> $crate::Clamped {
+            self.clamped
+        }
+
+        /// Last calibration written to the sensor by this driver or restored with
+        /// [Self::apply()].
+        pub fn last_calibration(&self) -> Option<&$crate::config::CalibrationRecord> {
+            self.calibration.as_ref()
+        }
+
+        /// Decode the response to [Command::GetStatus]($crate::protocol::Command::GetStatus),
+        /// applying [Config::raw_offset]($crate::config::Config::raw_offset),
+        /// [Config::raw_span]($crate::config::Config::raw_span) and
+        /// [Config::strict_range]($crate::config::Config::strict_range).
+        pub(crate) fn decode_measurements(
+            &mut self,
+            response: &[u8; $crate::protocol::RESPONSE_LEN],
+        ) -> $crate::Measurements {
+            let (measurements, clamped) = $crate::decode_status(&self.config, response);
+            self.clamped = clamped;
+            measurements
+        }
+    };
+}
+
+pub(crate) use {impl_requests, impl_state, with_retries};
@@ -0,0 +1,222 @@
+//! Fusion of redundant, co-located sensors into one trusted reading.
+//!
+//! Critical applications mount two or three sensors side by side. [fuse()] excludes sensors
+//! whose read failed and sensors deviating from the median of all readings by more than a
+//! tolerance, and averages the remaining ones. The reading is only trusted if enough sensors
+//! agree; [Fusion] tells which sensors were used and how far the readings spread.
+//!
+//! # Example Usage
+//! ```ignore
+//! let results = sweep(&mut [a, b, c], &mut delay);
+//! let fusion = fuse(&results, &FusionConfig::default());
+//! match fusion.measurements {
+//!     Some(m) => ventilation.control(m.co2),
+//!     None => ventilation.fail_safe(),
+//! }
+//! if fusion.excluded() > 0 {
+//!     report(fusion.sensors, fusion.spread);
+//! }
+//! ```
+
+use crate::Measurements;
+
+/// Limits of the agreement between sensors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FusionConfig {
+    /// Largest deviation of CO2 in ppm from the median which is still in agreement.
+    pub co2_tolerance_ppm: f32,
+    /// Largest deviation of VOC in ppb from the median which is still in agreement.
+    pub voc_tolerance_ppb: f32,
+    /// Number of agreeing sensors needed for a trusted reading (at least 1).
+    pub min_agreeing: usize,
+}
+
+impl Default for FusionConfig {
+    fn default() -> Self {
+        Self {
+            co2_tolerance_ppm: 100.0,
+            voc_tolerance_ppb: 50.0,
+            min_agreeing: 2,
+        }
+    }
+}
+
+/// Role of a sensor in a [Fusion].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SensorState {
+    /// The reading is part of the fused reading.
+    Used,
+    /// The read failed or returned non-finite values.
+    Faulted,
+    /// The reading deviates too far from the median.
+    Outlier,
+}
+
+/// Result of fusing the readings of `N` sensors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fusion<const N: usize> {
+    /// Mean of the used sensors, `None` if fewer than
+    /// [FusionConfig::min_agreeing] sensors agree.
+    pub measurements: Option<Measurements>,
+    /// Median of all sensors which delivered a reading.
+    pub median: Option<Measurements>,
+    /// Difference between the highest and lowest reading of all sensors which delivered one.
+    pub spread: Measurements,
+    /// Role of each sensor, in the order of the results.
+    pub sensors: [SensorState; N],
+}
+
+impl<const N: usize> Fusion<N> {
+    /// Number of sensors whose reading is used.
+    pub fn used(&self) -> usize {
+        self.count(SensorState::Used)
+    }
+
+    /// Number of faulted sensors and outliers.
+    pub fn excluded(&self) -> usize {
+        N - self.used()
+    }
+
+    fn count(&self, state: SensorState) -> usize {
+        self.sensors.iter().filter(|s| **s == state).count()
+    }
+}
+
+/// Fuse the `results` of co-located sensors, see the [module](self) documentation.
+pub fn fuse<E, const N: usize>(
+    results: &[Result<Measurements, E>; N],
+    config: &FusionConfig,
+) -> Fusion<N> {
+    let readings: [Option<Measurements>; N] = core::array::from_fn(|i| match &results[i] {
+        Ok(m) if m.co2.is_finite() && m.voc.is_finite() => Some(*m),
+        _ => None,
+    });
+    let mut sensors = readings.map(|r| match r {
+        Some(_) => SensorState::Used,
+        None => SensorState::Faulted,
+    });
+
+    let median = median(&readings.map(|r| r.map(|m| m.co2)))
+        .zip(median(&readings.map(|r| r.map(|m| m.voc))));
+    let Some((co2_median, voc_median)) = median else {
+        return Fusion {
+            measurements: None,
+            median: None,
+            spread: Measurements { co2: 0.0, voc: 0.0 },
+            sensors,
+        };
+    };
+
+    let (mut co2_min, mut co2_max) = (f32::INFINITY, f32::NEG_INFINITY);
+    let (mut voc_min, mut voc_max) = (f32::INFINITY, f32::NEG_INFINITY);
+    let (mut co2_sum, mut voc_sum, mut used) = (0.0, 0.0, 0);
+    for (reading, state) in readings.iter().zip(sensors.iter_mut()) {
+        let Some(m) = reading else { continue };
+        co2_min = co2_min.min(m.co2);
+        co2_max = co2_max.max(m.co2);
+        voc_min = voc_min.min(m.voc);
+        voc_max = voc_max.max(m.voc);
+        if (m.co2 - co2_median).abs() > config.co2_tolerance_ppm
+            || (m.voc - voc_median).abs() > config.voc_tolerance_ppb
+        {
+            *state = SensorState::Outlier;
+            continue;
+        }
+        co2_sum += m.co2;
+        voc_sum += m.voc;
+        used += 1;
+    }
+
+    Fusion {
+        measurements: (used > 0 && used >= config.min_agreeing).then(|| Measurements {
+            co2: co2_sum / used as f32,
+            voc: voc_sum / used as f32,
+        }),
+        median: Some(Measurements {
+            co2: co2_median,
+            voc: voc_median,
+        }),
+        spread: Measurements {
+            co2: co2_max - co2_min,
+            voc: voc_max - voc_min,
+        },
+        sensors,
+    }
+}
+
+/// Median of the present values, the mean of the middle two for an even count.
+fn median<const N: usize>(values: &[Option<f32>; N]) -> Option<f32> {
+    let mut sorted = [0.0; N];
+    let mut len = 0;
+    for value in values.iter().flatten() {
+        sorted[len] = *value;
+        len += 1;
+    }
+    let sorted = &mut sorted[..len];
+    sorted.sort_unstable_by(f32::total_cmp);
+    match len {
+        0 => None,
+        len if len % 2 == 1 => Some(sorted[len / 2]),
+        len => Some((sorted[len / 2 - 1] + sorted[len / 2]) / 2.0),
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{fuse, FusionConfig, SensorState};
+    use crate::Measurements;
+    use core::assert_eq;
+
+    fn m(co2: f32, voc: f32) -> Measurements {
+        Measurements { co2, voc }
+    }
+
+    #[test]
+    fn test_outlier_excluded() {
+        let results: [Result<Measurements, ()>; 3] = [
+            Ok(m(800.0, 100.0)),
+            Ok(m(840.0, 120.0)),
+            Ok(m(1400.0, 110.0)),
+        ];
+
+        let fusion = fuse(&results, &FusionConfig::default());
+
+        assert_eq!(fusion.measurements, Some(m(820.0, 110.0)));
+        assert_eq!(fusion.median, Some(m(840.0, 110.0)));
+        assert_eq!(fusion.spread, m(600.0, 20.0));
+        assert_eq!(
+            fusion.sensors,
+            [SensorState::Used, SensorState::Used, SensorState::Outlier]
+        );
+        assert_eq!(fusion.excluded(), 1);
+    }
+
+    #[test]
+    fn test_fault_and_disagreement() {
+        // two sensors left which disagree: the median lies between them
+        let results = [Ok(m(600.0, 100.0)), Err(()), Ok(m(1000.0, 100.0))];
+        let fusion = fuse(&results, &FusionConfig::default());
+        assert_eq!(fusion.measurements, None);
+        assert_eq!(
+            fusion.sensors,
+            [
+                SensorState::Outlier,
+                SensorState::Faulted,
+                SensorState::Outlier
+            ]
+        );
+
+        // a single sensor is enough if configured
+        let results = [Ok(m(600.0, 100.0)), Err(()), Err(())];
+        let config = FusionConfig {
+            min_agreeing: 1,
+            ..FusionConfig::default()
+        };
+        assert_eq!(fuse(&results, &config).measurements, Some(m(600.0, 100.0)));
+        assert_eq!(fuse(&results, &FusionConfig::default()).measurements, None);
+
+        let fusion = fuse::<(), 2>(&[Err(()), Err(())], &config);
+        assert_eq!((fusion.measurements, fusion.median), (None, None));
+    }
+}
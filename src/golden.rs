@@ -0,0 +1,160 @@
+//! Known-good command and response frames with their expected decoded values.
+//!
+//! These are the vectors the crate tests itself against. Re-implementations of the protocol
+//! (e.g. behind an FFI boundary or in another language) can validate against the same data.
+
+use crate::protocol::{Command, COMMAND_LEN, RESPONSE_LEN};
+
+/// A command with its encoded frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandVector {
+    pub command: Command,
+    pub frame: [u8; COMMAND_LEN],
+}
+
+/// A response to [Command::GetStatus] with the decoded values, rounded to integers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatusVector {
+    pub response: [u8; RESPONSE_LEN],
+    pub co2_ppm: u16,
+    pub voc_ppb: u16,
+    pub resistance_ohm: u32,
+}
+
+/// A response to [Command::GetRevision] with the decoded date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RevisionVector {
+    pub response: [u8; RESPONSE_LEN],
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+/// A response to [Command::GetCalibrationR0] with the decoded R0 in kOhms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CalibrationR0Vector {
+    pub response: [u8; RESPONSE_LEN],
+    pub r0_kohm: u16,
+}
+
+/// Command frames.
+pub const COMMANDS: [CommandVector; 6] = [
+    CommandVector {
+        command: Command::GetStatus,
+        frame: [0x0C, 0, 0, 0, 0, 0xF3],
+    },
+    CommandVector {
+        command: Command::GetRevision,
+        frame: [0x0D, 0, 0, 0, 0, 0xF2],
+    },
+    CommandVector {
+        command: Command::GetCalibrationR0,
+        frame: [0x10, 0, 0, 0, 0, 0xEF],
+    },
+    // 420 ppm
+    CommandVector {
+        command: Command::SetCalibrationPpm(0x0F),
+        frame: [0x08, 0x0F, 0, 0, 0, 0xE8],
+    },
+    // 1000 ppm
+    CommandVector {
+        command: Command::SetCalibrationPpm(0x62),
+        frame: [0x08, 0x62, 0, 0, 0, 0x95],
+    },
+    // 2000 ppm
+    CommandVector {
+        command: Command::SetCalibrationPpm(0xF2),
+        frame: [0x08, 0xF2, 0, 0, 0, 0x05],
+    },
+];
+
+/// Responses to [Command::GetStatus], including both ends of the value ranges.
+pub const STATUS_RESPONSES: [StatusVector; 5] = [
+    StatusVector {
+        response: [0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27],
+        co2_ppm: 728,
+        voc_ppb: 114,
+        resistance_ohm: 478_020,
+    },
+    StatusVector {
+        response: [0x27, 0x3C, 0x01, 0x86, 0xA0, 0, 0x74],
+        co2_ppm: 728,
+        voc_ppb: 114,
+        resistance_ohm: 1_000_000,
+    },
+    // distinct resistance bytes pin the byte order: 10 * 0x010203
+    StatusVector {
+        response: [0x27, 0x3C, 0x01, 0x02, 0x03, 0, 0x96],
+        co2_ppm: 728,
+        voc_ppb: 114,
+        resistance_ohm: 660_510,
+    },
+    StatusVector {
+        response: [0x0D, 0x0D, 0, 0, 0, 0, 0xE5],
+        co2_ppm: 400,
+        voc_ppb: 0,
+        resistance_ohm: 0,
+    },
+    StatusVector {
+        response: [0xF2, 0xF2, 0, 0, 0, 0, 0x1A],
+        co2_ppm: 2000,
+        voc_ppb: 1000,
+        resistance_ohm: 0,
+    },
+];
+
+/// Responses to [Command::GetRevision].
+pub const REVISION_RESPONSES: [RevisionVector; 1] = [RevisionVector {
+    response: [0x10, 0x03, 0x11, 0x48, 0, 0, 0x93],
+    year: 2016,
+    month: 3,
+    day: 17,
+}];
+
+/// Responses to [Command::GetCalibrationR0].
+pub const CALIBRATION_R0_RESPONSES: [CalibrationR0Vector; 1] = [CalibrationR0Vector {
+    response: [0xFB, 0x01, 0, 0, 0, 0, 0x03],
+    r0_kohm: 507,
+}];
+
+/// Responses with a wrong checksum, which must be rejected.
+pub const CORRUPTED_RESPONSES: [[u8; RESPONSE_LEN]; 2] = [
+    [0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x26],
+    [0x10, 0x03, 0x11, 0x48, 0, 0, 0x92],
+];
+
+#[cfg(test)]
+mod test {
+
+    use super::{
+        CALIBRATION_R0_RESPONSES, COMMANDS, CORRUPTED_RESPONSES, REVISION_RESPONSES,
+        STATUS_RESPONSES,
+    };
+    use crate::protocol;
+    use core::assert_eq;
+
+    #[test]
+    fn test_golden_vectors() {
+        for v in COMMANDS {
+            assert_eq!(v.command.frame(), v.frame);
+        }
+        for v in STATUS_RESPONSES {
+            assert!(protocol::check_response::<()>(&v.response).is_ok());
+            let m = protocol::decode_measurements(&v.response);
+            assert_eq!((m.co2_ppm_u16(), m.voc_ppb_u16()), (v.co2_ppm, v.voc_ppb));
+            assert_eq!(protocol::decode_resistance(&v.response), v.resistance_ohm);
+        }
+        for v in REVISION_RESPONSES {
+            assert!(protocol::check_response::<()>(&v.response).is_ok());
+            let date = protocol::decode_revision(&v.response);
+            assert_eq!((date.year, date.month, date.day), (v.year, v.month, v.day));
+        }
+        for v in CALIBRATION_R0_RESPONSES {
+            assert!(protocol::check_response::<()>(&v.response).is_ok());
+            assert_eq!(protocol::decode_calibration_r0(&v.response), v.r0_kohm);
+        }
+        for response in CORRUPTED_RESPONSES {
+            assert!(protocol::check_response::<()>(&response).is_err());
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! IEEE 754 half-precision (binary16) encoding for payload-constrained links.
+//!
+//! Half precision has 11 significant bits, so values up to 2048 are exact integers and
+//! the whole CO2 and VOC range is represented with a resolution of 1 ppm/ppb or better. The
+//! conversion (round to nearest, ties to even) is done by the [half](https://docs.rs/half)
+//! crate, enabled by the `half` feature.
+//!
+//! The encoding carries no schema version, unlike the `cbor` and [wire](crate::wire) records:
+//! on links where 2 bytes per value matter a version byte costs a fifth of the payload. Version
+//! it on the link instead, e.g. with the LoRaWAN port.
+
+use half::f16;
+
+use crate::Measurements;
+
+/// Size of [Measurements] encoded by [Measurements::to_f16_bytes()].
+pub const ENCODED_LEN: usize = 4;
+
+impl Measurements {
+    /// Encode as two half-precision values (CO2, VOC), little endian.
+    pub fn to_f16_bytes(&self) -> [u8; ENCODED_LEN] {
+        let [co2_l, co2_h] = f16::from_f32(self.co2).to_le_bytes();
+        let [voc_l, voc_h] = f16::from_f32(self.voc).to_le_bytes();
+        [co2_l, co2_h, voc_l, voc_h]
+    }
+
+    /// Decode from [Measurements::to_f16_bytes()].
+    pub fn from_f16_bytes(bytes: &[u8; ENCODED_LEN]) -> Self {
+        Self {
+            co2: f16::from_le_bytes([bytes[0], bytes[1]]).to_f32(),
+            voc: f16::from_le_bytes([bytes[2], bytes[3]]).to_f32(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::Measurements;
+    use core::assert_eq;
+
+    #[test]
+    fn test_measurements_f16() {
+        let m = Measurements {
+            co2: 728.4,
+            voc: 113.54,
+        };
+        let decoded = Measurements::from_f16_bytes(&m.to_f16_bytes());
+        assert_eq!((decoded.co2, decoded.voc), (728.5, 113.5625));
+        // 2049 is halfway between 2048 and 2050, ties to even
+        let m = Measurements {
+            co2: 2049.0,
+            voc: 0.0,
+        };
+        assert_eq!(m.to_f16_bytes(), [0x00, 0x68, 0x00, 0x00]);
+        assert_eq!(Measurements::from_f16_bytes(&m.to_f16_bytes()).co2, 2048.0);
+    }
+}
@@ -0,0 +1,170 @@
+//! Periodic health supervision of the sensor.
+//!
+//! [HealthMonitor] runs the [self-test](crate::MicsVz89Te::self_test()) in a fixed interval and
+//! reports transitions of the sensor health. It doesn't depend on an executor; the poll
+//! function is meant to be called from a background task loop of the runtime in use. With the
+//! `embassy` feature, `embassy::health_task()` is such a loop for the embassy executor.
+//!
+//! # Example Usage
+//! with an [embassy](https://embassy.dev) task and a channel to publish transitions
+//! ```ignore
+//! #[embassy_executor::task]
+//! async fn health_task(device: &'static Mutex<RawMutex, MicsVz89Te<I2c>>) {
+//!     let mut monitor = HealthMonitor::new(60_000, 3);
+//!     loop {
+//!         let now_ms = Instant::now().as_millis();
+//!         if let Some(health) = monitor.poll(now_ms, &mut *device.lock().await, &mut Delay) {
+//!             HEALTH.send(health).await;
+//!         }
+//!         Timer::after_millis(monitor.interval_ms()).await;
+//!     }
+//! }
+//! ```
+
+use crate::{delay::Delay, self_test::StepResult, transport::Transport, MicsVz89Te};
+
+/// Health of the sensor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Health {
+    /// No self-test has run yet.
+    Unknown,
+    /// The last self-test passed.
+    Healthy,
+    /// The self-test failed a number of times in a row. Holds the first failed step.
+    Unhealthy(StepResult),
+}
+
+/// Runs self-tests periodically and tracks the [Health] of the sensor.
+#[derive(Debug, Clone)]
+pub struct HealthMonitor {
+    interval_ms: u64,
+    max_failures: u8,
+    failures: u8,
+    next_ms: u64,
+    health: Health,
+}
+
+impl HealthMonitor {
+    /// Create a monitor running a self-test every `interval_ms`. The sensor is reported
+    /// unhealthy after `max_failures` (at least 1) failed self-tests in a row.
+    pub const fn new(interval_ms: u64, max_failures: u8) -> Self {
+        Self {
+            interval_ms,
+            max_failures: if max_failures == 0 { 1 } else { max_failures },
+            failures: 0,
+            next_ms: 0,
+            health: Health::Unknown,
+        }
+    }
+
+    /// Interval of the self-tests in millis.
+    pub fn interval_ms(&self) -> u64 {
+        self.interval_ms
+    }
+
+    /// Current health.
+    pub fn health(&self) -> Health {
+        self.health
+    }
+
+    /// Run a self-test if it is due at `now_ms`. Returns the new health if it changed.
+    pub fn poll<I2C, E>(
+        &mut self,
+        now_ms: u64,
+        driver: &mut MicsVz89Te<I2C>,
+        delay: &mut impl Delay,
+    ) -> Option<Health>
+    where
+        I2C: Transport<Error = E>,
+    {
+        if now_ms < self.next_ms {
+            return None;
+        }
+        self.next_ms = now_ms.saturating_add(self.interval_ms);
+
+        let report = driver.self_test(delay);
+        let first_failed = [
+            report.checksum,
+            report.revision,
+            report.measurement,
+            report.calibration_r0,
+        ]
+        .into_iter()
+        .find(|step| !step.is_ok());
+
+        let health = match first_failed {
+            None => {
+                self.failures = 0;
+                Health::Healthy
+            }
+            Some(step) => {
+                self.failures = self.failures.saturating_add(1);
+                if self.failures >= self.max_failures {
+                    Health::Unhealthy(step)
+                } else {
+                    self.health
+                }
+            }
+        };
+
+        if health != self.health {
+            self.health = health;
+            Some(health)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{Health, HealthMonitor};
+    use crate::{event::Fault, self_test::StepResult, MicsVz89Te};
+    use core::assert_eq;
+    use embedded_hal_mock::{
+        delay::MockNoop as DelayMock,
+        i2c::{Mock as I2cMock, Transaction as I2cTransaction},
+        MockError,
+    };
+    use std::{io::ErrorKind, vec, vec::Vec};
+
+    fn self_test(revision_ok: bool) -> Vec<I2cTransaction> {
+        let revision = I2cTransaction::read(0x70, vec![0x10, 0x03, 0x11, 0x48, 00, 0, 0x93]);
+        vec![
+            I2cTransaction::write(0x70, vec![0x0D, 0, 0, 0, 0, 0xF2]),
+            if revision_ok {
+                revision
+            } else {
+                revision.with_error(MockError::Io(ErrorKind::Other))
+            },
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+            I2cTransaction::write(0x70, vec![0x10, 0, 0, 0, 0, 0xEF]),
+            I2cTransaction::read(0x70, vec![0xFB, 0x01, 0, 0, 0, 0, 0x03]),
+        ]
+    }
+
+    #[test]
+    fn test_health_transitions() {
+        let expectations = [self_test(true), self_test(false), self_test(false)].concat();
+        let mut device = MicsVz89Te::new(I2cMock::new(&expectations));
+        let mut delay = DelayMock::new();
+        let mut monitor = HealthMonitor::new(1000, 2);
+
+        assert_eq!(monitor.health(), Health::Unknown);
+        assert_eq!(
+            monitor.poll(0, &mut device, &mut delay),
+            Some(Health::Healthy)
+        );
+        // not due yet
+        assert_eq!(monitor.poll(500, &mut device, &mut delay), None);
+        assert_eq!(monitor.poll(1000, &mut device, &mut delay), None);
+        assert_eq!(
+            monitor.poll(2000, &mut device, &mut delay),
+            Some(Health::Unhealthy(StepResult::Failed(Fault::Bus)))
+        );
+
+        device.release().done();
+    }
+}
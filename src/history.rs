@@ -0,0 +1,283 @@
+//! Fixed-capacity history of measurements.
+//!
+//! The history keeps the last `N` measurements in a ring buffer without any allocation and
+//! provides statistics over this window.
+
+use crate::Measurements;
+#[cfg(any(feature = "alloc", doc, test))]
+use alloc::{collections::VecDeque, vec::Vec};
+
+/// Ring buffer holding the last `N` measurements, oldest first.
+#[derive(Debug, Clone)]
+pub struct History<const N: usize> {
+    buffer: [Measurements; N],
+    start: usize,
+    len: usize,
+}
+
+impl<const N: usize> Default for History<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> History<N> {
+    /// Create an empty history.
+    pub const fn new() -> Self {
+        Self {
+            buffer: [Measurements { co2: 0.0, voc: 0.0 }; N],
+            start: 0,
+            len: 0,
+        }
+    }
+
+    /// Append a measurement. If the history is full, the oldest measurement is dropped.
+    pub fn push(&mut self, measurements: Measurements) {
+        if N == 0 {
+            return;
+        }
+        if self.len < N {
+            self.buffer[(self.start + self.len) % N] = measurements;
+            self.len += 1;
+        } else {
+            self.buffer[self.start] = measurements;
+            self.start = (self.start + 1) % N;
+        }
+    }
+
+    /// Number of stored measurements.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no measurement is stored.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns `true` if the history holds `N` measurements.
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Maximum number of stored measurements.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Remove all stored measurements.
+    pub fn clear(&mut self) {
+        self.start = 0;
+        self.len = 0;
+    }
+
+    /// The most recently pushed measurement.
+    pub fn latest(&self) -> Option<&Measurements> {
+        self.len
+            .checked_sub(1)
+            .map(|i| &self.buffer[(self.start + i) % N])
+    }
+
+    /// Iterate over the stored measurements from oldest to newest.
+    pub fn iter(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = &Measurements> + ExactSizeIterator + Clone {
+        (0..self.len).map(move |i| &self.buffer[(self.start + i) % N])
+    }
+
+    /// Percentile of CO2 and VOC over the window, computed independently for each value.
+    ///
+    /// `percent` is clamped to `0..=100` and the nearest-rank method is used, so the result
+    /// is always one of the stored values. Returns `None` if the history is empty.
+    pub fn percentile(&self, percent: u8) -> Option<Measurements> {
+        let mut scratch = [Measurements { co2: 0.0, voc: 0.0 }; N];
+        let mut len = 0;
+        for (slot, m) in scratch.iter_mut().zip(self.iter()) {
+            *slot = *m;
+            len += 1;
+        }
+        percentile_in(scratch.get_mut(..len)?, percent)
+    }
+
+    /// Median of CO2 and VOC over the window. See [History::percentile()].
+    pub fn median(&self) -> Option<Measurements> {
+        self.percentile(50)
+    }
+}
+
+/// Computes the percentile of `values`, which are reordered: sorted by CO2 for its rank, then
+/// by VOC for its rank.
+fn percentile_in(values: &mut [Measurements], percent: u8) -> Option<Measurements> {
+    let percent = usize::from(percent.min(100));
+    let index = (percent * values.len()).div_ceil(100).saturating_sub(1);
+    values.sort_unstable_by(|a, b| a.co2.total_cmp(&b.co2));
+    let co2 = values.get(index)?.co2;
+    values.sort_unstable_by(|a, b| a.voc.total_cmp(&b.voc));
+    let voc = values.get(index)?.voc;
+    Some(Measurements { co2, voc })
+}
+
+#[cfg(any(feature = "alloc", doc, test))]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+/// Heap-backed history holding the last `capacity` measurements, oldest first.
+///
+/// Same as [History], but the capacity can be chosen at runtime.
+#[derive(Debug, Clone)]
+pub struct VecHistory {
+    buffer: VecDeque<Measurements>,
+    capacity: usize,
+}
+
+#[cfg(any(feature = "alloc", doc, test))]
+impl VecHistory {
+    /// Create an empty history holding at most `capacity` measurements.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            buffer: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Append a measurement. If the history is full, the oldest measurement is dropped.
+    pub fn push(&mut self, measurements: Measurements) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.buffer.len() == self.capacity {
+            self.buffer.pop_front();
+        }
+        self.buffer.push_back(measurements);
+    }
+
+    /// Number of stored measurements.
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Returns `true` if no measurement is stored.
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    /// Returns `true` if the history holds `capacity` measurements.
+    pub fn is_full(&self) -> bool {
+        self.buffer.len() == self.capacity
+    }
+
+    /// Maximum number of stored measurements.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Change the capacity. If it shrinks, the oldest measurements are dropped.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        while self.buffer.len() > capacity {
+            self.buffer.pop_front();
+        }
+        self.capacity = capacity;
+    }
+
+    /// Remove all stored measurements.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+
+    /// The most recently pushed measurement.
+    pub fn latest(&self) -> Option<&Measurements> {
+        self.buffer.back()
+    }
+
+    /// Iterate over the stored measurements from oldest to newest.
+    pub fn iter(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = &Measurements> + ExactSizeIterator + Clone {
+        self.buffer.iter()
+    }
+
+    /// Percentile of CO2 and VOC over the window. See [History::percentile()].
+    pub fn percentile(&self, percent: u8) -> Option<Measurements> {
+        let mut scratch: Vec<_> = self.iter().copied().collect();
+        percentile_in(&mut scratch, percent)
+    }
+
+    /// Median of CO2 and VOC over the window. See [History::percentile()].
+    pub fn median(&self) -> Option<Measurements> {
+        self.percentile(50)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{History, VecHistory};
+    use crate::Measurements;
+    use core::assert_eq;
+
+    fn m(co2: f32, voc: f32) -> Measurements {
+        Measurements { co2, voc }
+    }
+
+    #[test]
+    fn test_push_overwrites_oldest() {
+        let mut history = History::<3>::new();
+        for i in 0..5 {
+            history.push(m(400.0 + i as f32, i as f32));
+        }
+
+        assert_eq!(history.len(), 3);
+        let co2 = history
+            .iter()
+            .map(|m| m.co2 as u32)
+            .collect::<std::vec::Vec<_>>();
+        assert_eq!(co2, [402, 403, 404]);
+        assert_eq!(history.latest().map(|m| m.voc as u32), Some(4));
+    }
+
+    #[test]
+    fn test_percentile() {
+        let mut history = History::<10>::new();
+        assert!(history.median().is_none());
+
+        for i in (1..=10).rev() {
+            history.push(m(400.0 + 100.0 * i as f32, 10.0 * i as f32));
+        }
+
+        let median = history.median().unwrap();
+        assert_eq!(median.co2 as u32, 900);
+        assert_eq!(median.voc as u32, 50);
+
+        let p95 = history.percentile(95).unwrap();
+        assert_eq!(p95.co2 as u32, 1400);
+        assert_eq!(p95.voc as u32, 100);
+
+        let p0 = history.percentile(0).unwrap();
+        assert_eq!(p0.co2 as u32, 500);
+    }
+
+    #[test]
+    fn test_percentile_channels_independent() {
+        let mut history = History::<3>::new();
+        history.push(m(500.0, 30.0));
+        history.push(m(700.0, 10.0));
+        history.push(m(600.0, 20.0));
+
+        assert_eq!(history.percentile(100), Some(m(700.0, 30.0)));
+        assert_eq!(history.median(), Some(m(600.0, 20.0)));
+    }
+
+    #[test]
+    fn test_vec_history() {
+        let mut history = VecHistory::new(4);
+        for i in 1..=6 {
+            history.push(m(400.0 + 100.0 * i as f32, i as f32));
+        }
+
+        assert_eq!(history.len(), 4);
+        assert_eq!(history.median().map(|m| m.co2 as u32), Some(800));
+
+        history.set_capacity(2);
+        assert_eq!(history.len(), 2);
+        assert_eq!(history.iter().next().map(|m| m.voc as u32), Some(5));
+        assert_eq!(history.latest().map(|m| m.voc as u32), Some(6));
+    }
+}
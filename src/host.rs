@@ -0,0 +1,320 @@
+//! Async reads on Linux gateways with tokio.
+//!
+//! Host buses like linux-embedded-hal's `I2cdev` are blocking. [TokioSensor] runs every read,
+//! bus transfers and response time of the sensor, on tokio's blocking pool, so it doesn't stall
+//! the reactor. A gateway can await readings of many sensors from one runtime. The sensor is
+//! shared behind a mutex, clones of a [TokioSensor] talk to the same driver. A read holds the
+//! lock from its command to its response, so reads of clones and the sampler never interleave.
+//!
+//! [TokioSensor::spawn_sampler()] starts a task per sensor which reads it on a tokio interval
+//! and broadcasts every [Sample]. After a number of failed reads in a row the task reopens the
+//! bus, e.g. after the USB adapter of the bus was replugged.
+//!
+//! # Example Usage
+//! ```ignore
+//! let sensor = TokioSensor::new(MicsVz89Te::new(I2cdev::new("/dev/i2c-1")?));
+//! let measurements = sensor.read_measurements().await?;
+//!
+//! let (task, mut samples) = sensor.spawn_sampler(SamplerConfig::default(), || {
+//!     I2cdev::new("/dev/i2c-1").ok()
+//! });
+//! while let Ok(sample) = samples.recv().await {
+//!     mqtt.publish(sample).await;
+//! }
+//! ```
+
+use std::{
+    panic,
+    sync::{Arc, Mutex, PoisonError},
+    thread,
+    time::Duration,
+};
+
+use tokio::{
+    sync::broadcast,
+    task::{self, JoinHandle},
+    time::{self, MissedTickBehavior},
+};
+
+use crate::{
+    delay::Delay, error::PacketParseError, event::Fault, publish::Sample, transport::Transport,
+    Measurements, MicsVz89Te,
+};
+
+/// Options of [TokioSensor::spawn_sampler()].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SamplerConfig {
+    /// Time between two reads. A zero interval is raised to 1 ms.
+    pub interval: Duration,
+    /// Number of failed reads in a row (each after its retries) after which the bus is reopened.
+    /// `0` never reopens it.
+    pub reconnect_after: u8,
+    /// Number of samples buffered for a slow receiver before it lags. A capacity of `0` is
+    /// raised to `1`.
+    pub capacity: usize,
+}
+
+impl Default for SamplerConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(5),
+            reconnect_after: 3,
+            capacity: 16,
+        }
+    }
+}
+
+/// Delay of the reads on the blocking pool.
+struct ThreadSleep;
+
+impl Delay for ThreadSleep {
+    fn delay_ms(&mut self, ms: u16) {
+        thread::sleep(Duration::from_millis(u64::from(ms)));
+    }
+}
+
+/// Driver shared between tokio tasks, see the [module](self) documentation.
+pub struct TokioSensor<I2C> {
+    device: Arc<Mutex<MicsVz89Te<I2C>>>,
+}
+
+impl<I2C> Clone for TokioSensor<I2C> {
+    fn clone(&self) -> Self {
+        Self {
+            device: Arc::clone(&self.device),
+        }
+    }
+}
+
+impl<I2C, E> TokioSensor<I2C>
+where
+    I2C: Transport<Error = E> + Send + 'static,
+    E: Send + 'static,
+{
+    /// Share `device` between tokio tasks.
+    pub fn new(device: MicsVz89Te<I2C>) -> Self {
+        Self {
+            device: Arc::new(Mutex::new(device)),
+        }
+    }
+
+    /// Read measurements from sensor, awaiting the response time and repeating the read up to
+    /// [Config::retries](crate::config::Config::retries) times on failure.
+    pub async fn read_measurements(&self) -> Result<Measurements, PacketParseError<E>> {
+        self.with_device(|device| device.read_measurements(&mut ThreadSleep))
+            .await
+    }
+
+    /// Spawn a task reading the sensor every [SamplerConfig::interval] and broadcasting the
+    /// samples, timestamped with the start of the read in millis since the start of the task.
+    ///
+    /// After [SamplerConfig::reconnect_after] failed reads in a row, `reconnect` is called on
+    /// the blocking pool to open the bus again. The new bus replaces the old one, the
+    /// configuration and calibration state of the driver are kept. If it returns `None`, the
+    /// next failed read tries again. The task ends when all receivers are dropped.
+    pub fn spawn_sampler<R>(
+        &self,
+        config: SamplerConfig,
+        reconnect: R,
+    ) -> (JoinHandle<()>, broadcast::Receiver<Sample>)
+    where
+        R: FnMut() -> Option<I2C> + Send + 'static,
+    {
+        // both panic on zero
+        let capacity = config.capacity.clamp(1, usize::MAX / 2);
+        let period = config.interval.max(Duration::from_millis(1));
+        let (sender, receiver) = broadcast::channel(capacity);
+        let sensor = self.clone();
+        let reconnect = Arc::new(Mutex::new(reconnect));
+        let task = tokio::spawn(async move {
+            let start = time::Instant::now();
+            let mut interval = time::interval(period);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            let mut failures = 0u8;
+            loop {
+                interval.tick().await;
+                let timestamp_ms = start.elapsed().as_millis() as u64;
+                let result = sensor.read_measurements().await;
+                let sample = Sample {
+                    timestamp_ms,
+                    result: result.as_ref().copied().map_err(Fault::from),
+                };
+                if sender.send(sample).is_err() {
+                    return;
+                }
+                if result.is_ok() {
+                    failures = 0;
+                    continue;
+                }
+                failures = failures.saturating_add(1);
+                if config.reconnect_after > 0 && failures >= config.reconnect_after {
+                    let reconnect = Arc::clone(&reconnect);
+                    let reopened = sensor
+                        .with_device(move |device| {
+                            let mut reconnect =
+                                reconnect.lock().unwrap_or_else(PoisonError::into_inner);
+                            reconnect().map(|bus| *device.bus_mut() = bus).is_some()
+                        })
+                        .await;
+                    if reopened {
+                        failures = 0;
+                    }
+                }
+            }
+        });
+        (task, receiver)
+    }
+
+    /// Run `f` with exclusive access to the driver on tokio's blocking pool, e.g. to read the
+    /// revision or to release the bus.
+    pub async fn with_device<T, F>(&self, f: F) -> T
+    where
+        F: FnOnce(&mut MicsVz89Te<I2C>) -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let device = Arc::clone(&self.device);
+        let result = task::spawn_blocking(move || {
+            // a panic while holding the lock doesn't corrupt the driver state
+            let mut device = device.lock().unwrap_or_else(PoisonError::into_inner);
+            f(&mut device)
+        })
+        .await;
+        result.unwrap_or_else(|e| panic::resume_unwind(e.into_panic()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{SamplerConfig, TokioSensor};
+    use crate::{config::Config, event::Fault, MicsVz89Te};
+    use assert_matches::assert_matches;
+    use core::assert_eq;
+    use embedded_hal_mock::{
+        i2c::{Mock as I2cMock, Transaction as I2cTransaction},
+        MockError,
+    };
+    use std::{io::ErrorKind, time::Duration, vec};
+    use tokio::runtime;
+
+    #[test]
+    fn test_read_measurements() {
+        let status = || I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]);
+        let expectations = [
+            status(),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x26]),
+            status(),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+        ];
+        let config = Config {
+            retries: 1,
+            ..Config::default()
+        };
+        let sensor = TokioSensor::new(MicsVz89Te::with_config(I2cMock::new(&expectations), config));
+        let runtime = runtime::Builder::new_current_thread()
+            .enable_time()
+            .start_paused(true)
+            .build()
+            .unwrap();
+
+        runtime.block_on(async {
+            let m = sensor.read_measurements().await.unwrap();
+            assert_eq!(m.co2_ppm_u16(), 728);
+            sensor.with_device(|device| device.bus_mut().done()).await;
+        });
+    }
+
+    #[test]
+    fn test_concurrent_clones() {
+        let status = || I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]);
+        let response = || I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]);
+        // an interleaved command of the other clone would fail the mock
+        let expectations = [status(), response(), status(), response()];
+        let config = Config {
+            wait_time_ms: 20,
+            ..Config::default()
+        };
+        let sensor = TokioSensor::new(MicsVz89Te::with_config(I2cMock::new(&expectations), config));
+        let clone = sensor.clone();
+        let runtime = runtime::Builder::new_current_thread().build().unwrap();
+
+        runtime.block_on(async {
+            let other = tokio::spawn(async move { clone.read_measurements().await });
+            let m = sensor.read_measurements().await;
+            assert_matches!(m, Ok(m) if m.co2_ppm_u16() == 728);
+            assert_matches!(other.await.unwrap(), Ok(m) if m.co2_ppm_u16() == 728);
+            sensor.with_device(|device| device.bus_mut().done()).await;
+        });
+    }
+
+    #[test]
+    fn test_sampler_reconnects() {
+        let status = || I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]);
+        let broken = [status().with_error(MockError::Io(ErrorKind::Other))];
+        let reopened = [
+            status(),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+        ];
+        let sensor = TokioSensor::new(MicsVz89Te::new(I2cMock::new(&broken)));
+        let runtime = runtime::Builder::new_current_thread()
+            .enable_time()
+            .start_paused(true)
+            .build()
+            .unwrap();
+        let config = SamplerConfig {
+            interval: Duration::from_secs(1),
+            reconnect_after: 1,
+            capacity: 4,
+        };
+
+        runtime.block_on(async {
+            let mut reconnects = 0;
+            let (task, mut samples) = sensor.spawn_sampler(config, move || {
+                reconnects += 1;
+                assert_eq!(reconnects, 1);
+                Some(I2cMock::new(&reopened))
+            });
+
+            let sample = samples.recv().await.unwrap();
+            assert_eq!((sample.timestamp_ms, sample.result), (0, Err(Fault::Bus)));
+            let sample = samples.recv().await.unwrap();
+            assert_eq!(sample.timestamp_ms, 1000);
+            assert_matches!(sample.result, Ok(m) if m.co2_ppm_u16() == 728);
+
+            task.abort();
+            sensor.with_device(|device| device.bus_mut().done()).await;
+        });
+    }
+
+    #[test]
+    fn test_sampler_zero_config() {
+        let status = || I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]);
+        let response = || I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]);
+        let expectations = [status(), response(), status(), response()];
+        let config = Config {
+            wait_time_ms: 0,
+            ..Config::default()
+        };
+        let sensor = TokioSensor::new(MicsVz89Te::with_config(I2cMock::new(&expectations), config));
+        let runtime = runtime::Builder::new_current_thread()
+            .enable_time()
+            .start_paused(true)
+            .build()
+            .unwrap();
+        let config = SamplerConfig {
+            interval: Duration::ZERO,
+            reconnect_after: 0,
+            capacity: 0,
+        };
+
+        runtime.block_on(async {
+            let (task, mut samples) = sensor.spawn_sampler(config, || None);
+
+            assert_eq!(samples.recv().await.unwrap().timestamp_ms, 0);
+            assert_eq!(samples.recv().await.unwrap().timestamp_ms, 1);
+
+            task.abort();
+            sensor.with_device(|device| device.bus_mut().done()).await;
+        });
+    }
+}
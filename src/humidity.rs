@@ -0,0 +1,183 @@
+//! Correction of the measurements for ambient humidity.
+//!
+//! The response of the metal-oxide sensor rises with moisture, so the same gas concentration
+//! reads higher in humid air. [HumidityCorrection] scales the measurements with a factor
+//! interpolated linearly from a curve over the relative humidity, which has to be measured by
+//! another sensor. The CO2 equivalent is scaled above its floor of 400 ppm, as it is derived
+//! from the same sensor response.
+//!
+//! The default curves are a rough approximation of typical MOS behavior referenced to 50 %RH.
+//! For accurate results, fit a curve for each device against a reference instrument.
+//!
+//! [HumidityCorrection::with_humidity()] turns it into a [MeasurementFilter] stage reading the
+//! humidity from a closure, e.g. the latest value of the humidity sensor.
+//!
+//! # Example Usage
+//! ```ignore
+//! let correction = HumidityCorrection::default();
+//! let measurements = correction.correct(&device.read_measurements(&mut delay)?, sht.humidity()?);
+//! ```
+
+use crate::{filter::MeasurementFilter, Measurements, CO2_MIN};
+
+/// Correction factor at a relative humidity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CurvePoint {
+    /// Relative humidity in percent.
+    pub relative_humidity: f32,
+    /// Factor the value is multiplied with.
+    pub factor: f32,
+}
+
+const fn cp(relative_humidity: f32, factor: f32) -> CurvePoint {
+    CurvePoint {
+        relative_humidity,
+        factor,
+    }
+}
+
+/// Default correction curve for CO2 equivalent and VOC, `1.0` at 50 %RH.
+pub const DEFAULT_CURVE: [CurvePoint; 5] = [
+    cp(0.0, 1.15),
+    cp(20.0, 1.08),
+    cp(50.0, 1.0),
+    cp(80.0, 0.9),
+    cp(100.0, 0.85),
+];
+
+/// Correction curves of both channels, sorted by relative humidity.
+#[derive(Debug, Clone, Copy)]
+pub struct HumidityCorrection<'a> {
+    pub co2: &'a [CurvePoint],
+    pub voc: &'a [CurvePoint],
+}
+
+impl Default for HumidityCorrection<'static> {
+    fn default() -> Self {
+        Self {
+            co2: &DEFAULT_CURVE,
+            voc: &DEFAULT_CURVE,
+        }
+    }
+}
+
+impl<'a> HumidityCorrection<'a> {
+    /// Use the same curve for both channels.
+    pub const fn new(curve: &'a [CurvePoint]) -> Self {
+        Self {
+            co2: curve,
+            voc: curve,
+        }
+    }
+
+    /// Measurements corrected for `relative_humidity` in percent.
+    pub fn correct(&self, measurements: &Measurements, relative_humidity: f32) -> Measurements {
+        Measurements {
+            co2: CO2_MIN + (measurements.co2 - CO2_MIN) * factor(self.co2, relative_humidity),
+            voc: measurements.voc * factor(self.voc, relative_humidity),
+        }
+    }
+
+    /// Use as [MeasurementFilter] stage, correcting for the relative humidity (in percent)
+    /// returned by `humidity`. Measurements are held back while it returns `None`.
+    pub fn with_humidity<H>(self, humidity: H) -> HumidityStage<'a, H>
+    where
+        H: FnMut() -> Option<f32>,
+    {
+        HumidityStage {
+            correction: self,
+            humidity,
+        }
+    }
+}
+
+/// [HumidityCorrection] as filter stage, created with [HumidityCorrection::with_humidity()].
+#[derive(Debug, Clone, Copy)]
+pub struct HumidityStage<'a, H> {
+    correction: HumidityCorrection<'a>,
+    humidity: H,
+}
+
+impl<H> MeasurementFilter for HumidityStage<'_, H>
+where
+    H: FnMut() -> Option<f32>,
+{
+    fn update(&mut self, measurements: Measurements) -> Option<Measurements> {
+        let relative_humidity = (self.humidity)()?;
+        Some(self.correction.correct(&measurements, relative_humidity))
+    }
+}
+
+/// Factor of `curve` at `relative_humidity`, clamped to the ends of the curve. An empty curve
+/// doesn't correct.
+fn factor(curve: &[CurvePoint], relative_humidity: f32) -> f32 {
+    let (first, last) = match (curve.first(), curve.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return 1.0,
+    };
+    if relative_humidity <= first.relative_humidity {
+        return first.factor;
+    }
+    curve
+        .windows(2)
+        .find(|w| relative_humidity <= w[1].relative_humidity)
+        .map(|w| {
+            let span = w[1].relative_humidity - w[0].relative_humidity;
+            let t = (relative_humidity - w[0].relative_humidity) / span;
+            w[0].factor + (w[1].factor - w[0].factor) * t
+        })
+        .unwrap_or(last.factor)
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{cp, factor, HumidityCorrection, DEFAULT_CURVE};
+    use crate::{filter::MeasurementFilter, Measurements};
+    use core::{assert_eq, cell::Cell};
+
+    #[test]
+    fn test_factor_interpolation() {
+        assert_eq!(factor(&DEFAULT_CURVE, 50.0), 1.0);
+        assert!((factor(&DEFAULT_CURVE, 65.0) - 0.95).abs() < 1e-6);
+        assert_eq!(factor(&DEFAULT_CURVE, -5.0), 1.15);
+        assert_eq!(factor(&DEFAULT_CURVE, 120.0), 0.85);
+        assert_eq!(factor(&[], 30.0), 1.0);
+    }
+
+    #[test]
+    fn test_correct() {
+        let m = Measurements {
+            co2: 1400.0,
+            voc: 200.0,
+        };
+        let corrected = HumidityCorrection::default().correct(&m, 80.0);
+        assert_eq!(corrected.co2, 1300.0);
+        assert_eq!(corrected.voc, 180.0);
+
+        let device_curve = [cp(40.0, 1.0), cp(60.0, 0.5)];
+        let corrected = HumidityCorrection {
+            voc: &device_curve,
+            ..HumidityCorrection::default()
+        }
+        .correct(&m, 60.0);
+        assert_eq!(corrected.voc, 100.0);
+        assert!((corrected.co2 - 1366.6667).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_humidity_stage() {
+        let humidity = Cell::new(None);
+        let mut stage = HumidityCorrection::default().with_humidity(|| humidity.get());
+        let measurements = Measurements {
+            co2: 800.0,
+            voc: 200.0,
+        };
+
+        assert_eq!(stage.update(measurements), None);
+        humidity.set(Some(80.0));
+        let corrected = stage.update(measurements).unwrap();
+        assert!((corrected.co2 - 760.0).abs() < 1e-3);
+        assert!((corrected.voc - 180.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,210 @@
+//! Composite indoor air quality index.
+//!
+//! Combines CO2 and VOC of this sensor with optional inputs of other sensors (PM2.5, relative
+//! humidity) into a single `0..=500` index, lower is better. Each component is scored on its
+//! own (see [aqi](crate::aqi)) and the index is the weighted mean of the available components.
+//!
+//! # Example Usage
+//! ```ignore
+//! let inputs = Inputs { pm2_5: Some(pm_sensor.read()?), relative_humidity: None };
+//! let iaq = IaqCalculator::default().calculate(&measurements, &inputs);
+//! show(iaq.index);
+//! ```
+
+use crate::{
+    aqi::{bp, AqiScale, Breakpoint},
+    Measurements,
+};
+
+/// Breakpoints for PM2.5 in µg/m³ as used by the US EPA AQI.
+pub const PM2_5_BREAKPOINTS: [Breakpoint; 6] = [
+    bp(0.0, 12.0, 0, 50),
+    bp(12.0, 35.4, 51, 100),
+    bp(35.4, 55.4, 101, 150),
+    bp(55.4, 150.4, 151, 200),
+    bp(150.4, 250.4, 201, 300),
+    bp(250.4, 500.4, 301, 500),
+];
+
+/// Relative humidity range in percent considered comfortable (score `0`).
+pub const COMFORT_HUMIDITY_PERCENT: (f32, f32) = (40.0, 60.0);
+
+/// Values of other sensors feeding into the index.
+pub trait ExternalInputs {
+    /// PM2.5 concentration in µg/m³.
+    fn pm2_5(&self) -> Option<f32> {
+        None
+    }
+
+    /// Relative humidity in percent.
+    fn relative_humidity(&self) -> Option<f32> {
+        None
+    }
+}
+
+/// No external inputs.
+impl ExternalInputs for () {}
+
+/// External inputs as plain values.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Inputs {
+    pub pm2_5: Option<f32>,
+    pub relative_humidity: Option<f32>,
+}
+
+impl ExternalInputs for Inputs {
+    fn pm2_5(&self) -> Option<f32> {
+        self.pm2_5
+    }
+
+    fn relative_humidity(&self) -> Option<f32> {
+        self.relative_humidity
+    }
+}
+
+/// Weights of the components. Weights of missing inputs are left out, so the weights of the
+/// available components don't need to add up to anything specific.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IaqWeights {
+    pub co2: f32,
+    pub voc: f32,
+    pub pm2_5: f32,
+    pub relative_humidity: f32,
+}
+
+impl Default for IaqWeights {
+    fn default() -> Self {
+        Self {
+            co2: 0.3,
+            voc: 0.3,
+            pm2_5: 0.3,
+            relative_humidity: 0.1,
+        }
+    }
+}
+
+/// Score of one component and its share of the index.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Component {
+    /// Score of the component in `0..=500`.
+    pub score: u16,
+    /// Weight after normalization over the available components.
+    pub weight: f32,
+    /// `score * weight`; the contributions add up to the index.
+    pub contribution: f32,
+}
+
+/// Composite index and its components.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Iaq {
+    /// Index in `0..=500`, lower is better.
+    pub index: u16,
+    pub co2: Component,
+    pub voc: Component,
+    pub pm2_5: Option<Component>,
+    pub relative_humidity: Option<Component>,
+}
+
+/// Calculates the composite index.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IaqCalculator<'a> {
+    pub scale: AqiScale<'a>,
+    pub weights: IaqWeights,
+}
+
+impl IaqCalculator<'_> {
+    /// Calculate the index of `measurements` and the available external `inputs`.
+    pub fn calculate(&self, measurements: &Measurements, inputs: &impl ExternalInputs) -> Iaq {
+        let scores = self.scale.score(measurements);
+        let pm2_5 = inputs
+            .pm2_5()
+            .map(|pm| crate::aqi::interpolate(&PM2_5_BREAKPOINTS, pm));
+        let humidity = inputs.relative_humidity().map(humidity_score);
+
+        let weights = &self.weights;
+        let total = weights.co2.max(0.0)
+            + weights.voc.max(0.0)
+            + pm2_5.map_or(0.0, |_| weights.pm2_5.max(0.0))
+            + humidity.map_or(0.0, |_| weights.relative_humidity.max(0.0));
+        let component = |score: u16, weight: f32| {
+            let weight = if total > 0.0 {
+                weight.max(0.0) / total
+            } else {
+                0.0
+            };
+            Component {
+                score,
+                weight,
+                contribution: f32::from(score) * weight,
+            }
+        };
+
+        let co2 = component(scores.co2, weights.co2);
+        let voc = component(scores.voc, weights.voc);
+        let pm2_5 = pm2_5.map(|s| component(s, weights.pm2_5));
+        let relative_humidity = humidity.map(|s| component(s, weights.relative_humidity));
+        let index = co2.contribution
+            + voc.contribution
+            + pm2_5.map_or(0.0, |c| c.contribution)
+            + relative_humidity.map_or(0.0, |c| c.contribution);
+
+        Iaq {
+            index: crate::units::round_to_u16(index).min(500),
+            co2,
+            voc,
+            pm2_5,
+            relative_humidity,
+        }
+    }
+}
+
+/// Score of the relative humidity: `0` inside [COMFORT_HUMIDITY_PERCENT], rising by 10 per
+/// percent outside of it.
+fn humidity_score(humidity: f32) -> u16 {
+    let (low, high) = COMFORT_HUMIDITY_PERCENT;
+    let distance = (low - humidity).max(humidity - high).max(0.0);
+    crate::units::round_to_u16(distance * 10.0).min(500)
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{IaqCalculator, Inputs};
+    use crate::Measurements;
+    use core::assert_eq;
+
+    const M: Measurements = Measurements {
+        co2: 800.0,
+        voc: 100.0,
+    };
+
+    #[test]
+    fn test_sensor_only() {
+        let iaq = IaqCalculator::default().calculate(&M, &());
+
+        assert_eq!((iaq.co2.score, iaq.voc.score), (76, 23));
+        assert_eq!(iaq.co2.weight, 0.5);
+        assert_eq!(iaq.index, 50);
+        assert!(iaq.pm2_5.is_none());
+    }
+
+    #[test]
+    fn test_external_inputs() {
+        let inputs = Inputs {
+            pm2_5: Some(35.4),
+            relative_humidity: Some(25.0),
+        };
+        let iaq = IaqCalculator::default().calculate(&M, &inputs);
+
+        let pm2_5 = iaq.pm2_5.unwrap();
+        let humidity = iaq.relative_humidity.unwrap();
+        assert_eq!((pm2_5.score, humidity.score), (100, 150));
+        // 0.3 * 76 + 0.3 * 23 + 0.3 * 100 + 0.1 * 150
+        assert_eq!(iaq.index, 75);
+        let sum = iaq.co2.contribution
+            + iaq.voc.contribution
+            + pm2_5.contribution
+            + humidity.contribution;
+        assert!((sum - 74.7).abs() < 1e-3);
+    }
+}
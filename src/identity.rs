@@ -0,0 +1,63 @@
+//! Identity of a sensor for telemetry.
+//!
+//! Gateways with several sensors need to know which sensor a payload came from. A
+//! [SensorIdentity] holds a user-assigned label and the revision date of the sensor and is
+//! carried through the telemetry encoders, e.g.
+//! `cbor::encode_tagged_measurements()`.
+//!
+//! The [wire](crate::wire) records and [forward](crate::forward) frames don't carry it. They
+//! are fixed-size for the links between nodes (UART, RF), where a label of any length doesn't
+//! fit, and the receiver knows the sender from the link it listens on. The gateway terminating
+//! the link attaches the identity when it encodes the telemetry.
+//!
+//! # Example Usage
+//! ```ignore
+//! let identity = SensorIdentity::new("room-2.14").with_revision(device.read_revision(&mut delay)?);
+//! let len = cbor::encode_tagged_measurements(&m, Some(&identity), &mut payload)?;
+//! ```
+
+use crate::RevisionDate;
+
+/// User-assigned label and revision date of a sensor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SensorIdentity<'a> {
+    pub label: &'a str,
+    pub revision: Option<RevisionDate>,
+}
+
+impl<'a> SensorIdentity<'a> {
+    /// Identity with a label only.
+    pub const fn new(label: &'a str) -> Self {
+        Self {
+            label,
+            revision: None,
+        }
+    }
+
+    /// Add the revision date read from the sensor.
+    pub const fn with_revision(mut self, revision: RevisionDate) -> Self {
+        self.revision = Some(revision);
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::SensorIdentity;
+    use crate::RevisionDate;
+    use core::assert_eq;
+
+    #[test]
+    fn test_identity() {
+        let revision = RevisionDate {
+            year: 2016,
+            month: 3,
+            day: 17,
+        };
+        let identity = SensorIdentity::new("room-2.14").with_revision(revision);
+
+        assert_eq!(identity.label, "room-2.14");
+        assert_eq!(identity.revision.map(|r| r.to_yyyymmdd()), Some(20160317));
+    }
+}
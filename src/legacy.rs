@@ -0,0 +1,186 @@
+//! Driver for the older MICS-VZ-89 (non-TE) module.
+//!
+//! The MICS-VZ-89 uses the same I2C address and raw scaling as the MICS-VZ-89TE, but a different
+//! command set and response layout:
+//!
+//! - request: `0x09 0x00 0x00` without checksum (MICS-VZ-89TE: 6 bytes with checksum)
+//! - response: 6 bytes, CO2, VOC short, tVOC and the resistance in 3 bytes LSB first
+//!   (MICS-VZ-89TE: 7 bytes, tVOC, CO2, the resistance MSB first, status and checksum)
+//!
+//! The responses carry no checksum, so a corrupted frame can't be detected. The revision and
+//! R0 commands don't exist on this module. [MicsVz89] shares the [Transport], [Config] and the
+//! [convert](crate::convert) functions with [MicsVz89Te](crate::MicsVz89Te), so both variants
+//! decode their raw bytes identically.
+//!
+//! # Example Usage
+//! ```ignore
+//! let mut device = MicsVz89::new(i2c);
+//! let (measurements, resistance) = device.read_measurements_with_resistance(&mut delay)?;
+//! ```
+
+use crate::{
+    config::Config, decode_raw, delay::Delay, error::PacketParseError, transport::Transport,
+    Clamped, Measurements,
+};
+
+/// Size of a command frame of the MICS-VZ-89 in bytes.
+pub const COMMAND_LEN: usize = 3;
+/// Size of a response frame of the MICS-VZ-89 in bytes.
+pub const RESPONSE_LEN: usize = 6;
+/// Frame requesting the status (measurements and resistance).
+pub const GET_STATUS_FRAME: [u8; COMMAND_LEN] = [0x09, 0x00, 0x00];
+
+/// Decode the raw sensor resistance in Ohms from a MICS-VZ-89 status response.
+pub fn decode_resistance(response: &[u8; RESPONSE_LEN]) -> u32 {
+    10 * u32::from_le_bytes([response[3], response[4], response[5], 0])
+}
+
+/// Driver for MICS-VZ-89 sensor.
+pub struct MicsVz89<I2C> {
+    i2c: I2C,
+    config: Config,
+    clamped: Clamped,
+}
+
+impl<I2C, E> MicsVz89<I2C>
+where
+    I2C: Transport<Error = E>,
+{
+    /// Create new driver on the supplied i2c bus.
+    pub fn new(i2c: I2C) -> Self {
+        Self::with_config(i2c, Config::default())
+    }
+
+    /// Create new driver on the supplied i2c bus with a custom configuration. The checksum
+    /// options of the configuration don't apply to this variant.
+    pub fn with_config(i2c: I2C, config: Config) -> Self {
+        Self {
+            i2c,
+            config,
+            clamped: Clamped::default(),
+        }
+    }
+
+    /// Read measurements from sensor.
+    ///
+    /// This function blocks a minimum time of [Config::wait_time_ms].
+    pub fn read_measurements(
+        &mut self,
+        delay: &mut impl Delay,
+    ) -> Result<Measurements, PacketParseError<E>> {
+        let response = self.request_status(delay)?;
+        Ok(self.decode_measurements(&response))
+    }
+
+    /// Read measurements together with the raw sensor resistance in Ohms.
+    ///
+    /// This function blocks a minimum time of [Config::wait_time_ms].
+    pub fn read_measurements_with_resistance(
+        &mut self,
+        delay: &mut impl Delay,
+    ) -> Result<(Measurements, u32), PacketParseError<E>> {
+        let response = self.request_status(delay)?;
+        Ok((
+            self.decode_measurements(&response),
+            decode_resistance(&response),
+        ))
+    }
+
+    fn request_status(
+        &mut self,
+        delay: &mut impl Delay,
+    ) -> Result<[u8; RESPONSE_LEN], PacketParseError<E>> {
+        let mut retries = self.config.retries;
+        loop {
+            match self.request_once(delay) {
+                Err(_) if retries > 0 => retries -= 1,
+                response => return response,
+            }
+        }
+    }
+
+    fn request_once(
+        &mut self,
+        delay: &mut impl Delay,
+    ) -> Result<[u8; RESPONSE_LEN], PacketParseError<E>> {
+        self.i2c
+            .write_frame(self.config.address, &GET_STATUS_FRAME)?;
+        delay.delay_ms(self.config.wait_time_ms);
+        let mut buffer = [0u8; RESPONSE_LEN];
+        self.i2c.read_frame(self.config.address, &mut buffer)?;
+        Ok(buffer)
+    }
+
+    fn decode_measurements(&mut self, response: &[u8; RESPONSE_LEN]) -> Measurements {
+        let (measurements, clamped) = decode_raw(&self.config, response[0], response[2]);
+        self.clamped = clamped;
+        measurements
+    }
+}
+
+impl<I2C> MicsVz89<I2C> {
+    /// Releases the underlying I2C bus and destroys the driver.
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+
+    /// Current configuration of the driver.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Channels clamped in the last read measurements. Always unclamped unless
+    /// [Config::strict_range] is set.
+    pub fn last_clamped(&self) -> Clamped {
+        self.clamped
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::MicsVz89;
+    use crate::{config::Config, convert};
+    use core::assert_eq;
+    use embedded_hal_mock::{
+        delay::MockNoop as DelayMock,
+        i2c::{Mock as I2cMock, Transaction as I2cTransaction},
+    };
+    use std::vec;
+
+    #[test]
+    fn test_read_measurements_with_resistance() {
+        let expectations = [
+            I2cTransaction::write(0x70, vec![0x09, 0, 0]),
+            I2cTransaction::read(0x70, vec![0x3C, 0x55, 0x27, 0xA0, 0x86, 0x01]),
+        ];
+        let mut device = MicsVz89::new(I2cMock::new(&expectations));
+
+        let (m, resistance) = device
+            .read_measurements_with_resistance(&mut DelayMock::new())
+            .unwrap();
+        // same raw bytes as the MICS-VZ-89TE status response 0x27 0x3C ...
+        assert_eq!(m.co2_ppm_u16(), 728);
+        assert_eq!(m.voc, convert::convert_voc(0x27));
+        assert_eq!(resistance, 1_000_000);
+        device.release().done();
+    }
+
+    #[test]
+    fn test_strict_range() {
+        let expectations = [
+            I2cTransaction::write(0x70, vec![0x09, 0, 0]),
+            I2cTransaction::read(0x70, vec![0xFA, 0, 0x05, 0, 0, 0]),
+        ];
+        let config = Config {
+            strict_range: true,
+            ..Config::default()
+        };
+        let mut device = MicsVz89::with_config(I2cMock::new(&expectations), config);
+
+        let m = device.read_measurements(&mut DelayMock::new()).unwrap();
+        assert_eq!((m.co2, m.voc), (2000.0, 0.0));
+        assert!(device.last_clamped().co2);
+        device.release().done();
+    }
+}
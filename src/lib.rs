@@ -11,10 +11,51 @@
 //!
 //! ## Feature flags
 //!
-//! - `std`: Enables error handling with `std::error::Error`.
+//! - `async`: Enables the `MicsVz89TeAsync` driver for embedded-hal-async buses and delays and
+//!   `MicsVz89Te::awaiting()` awaiting the response time on an embedded-hal-async delay.
+//! - `eh0` (default): Makes every embedded-hal 0.2 I2C bus a transport and every embedded-hal
+//!   0.2 `DelayMs<u16>` a delay of the driver. Without it, embedded-hal 0.2 isn't a dependency;
+//!   the embedded-hal 0.2 bus wrappers (`borrowed`, `uart_bridge`, the `session` buses,
+//!   `FaultyBus`, `SimulatedSensor`) and `write-read` need it.
+//! - `eh1`: Enables the `Eh1Bus` and `Eh1Delay` adapters for embedded-hal 1.0 buses and delays.
+//! - `minicbor`: Enables the compact CBOR encoding of measurements and diagnostics with
+//!   minicbor.
+//! - `embedded-storage`: Enables `FlashLog`, a ring buffer of aggregated records on any
+//!   embedded-storage `NorFlash`.
+//! - `heapless`: Makes a `heapless::spsc::Producer` a sink of published samples, for handing
+//!   them out of an interrupt.
+//! - `half`: Enables the IEEE half-precision encoding of measurements with the `half` crate.
+//! - `serde`: Enables `Serialize` and `Deserialize` of `Measurements`, `Config` and
+//!   `StateSnapshot`, e.g. to persist them with postcard across deep-sleep cycles.
+//! - `fugit`: Enables reads bounded by a `fugit` instant as deadline.
+//! - `embassy`: Enables ready-made tasks for the embassy executor, sharing the driver behind an
+//!   `embassy_sync` mutex. Implies `async`.
+//! - `std`: Enables error handling with `std::error::Error`. Implies `alloc`.
+//! - `alloc`: Enables heap-backed containers whose capacity is set at runtime: `VecHistory`,
+//!   `VecPeakHold` and `VecFleet`.
+//! - `tokio`: Enables `TokioSensor` for awaiting readings and periodic sampling on Linux
+//!   gateways with tokio. Implies `std`.
+//! - `host`: Enables constructors for USB-to-I2C bridges on a PC, an FTDI FT232H (links the
+//!   FTD2XX library) and, on Linux, a CH341. Implies `std` and `eh1`.
+//! - `graphics`: Enables the embedded-graphics `Readout` widget drawing a value with its unit
+//!   and a quality color bar.
 //! - `time`: Enables compatibility with `time::Date` on struct `RevisionDate`.
+//! - `fixed-point`: Enables conversions into Q16.16 fixed-point values and whole ppm/ppb
+//!   using `u16` arithmetic only, for targets without FPU or 8-bit targets.
+//! - `test-util`: Enables the `FaultyBus` wrapper injecting bus faults and the `SimulatedSensor`
+//!   playing scripted scenarios, for resilience and end-to-end tests. Both are always available
+//!   on `wasm32`, so browser dashboards can run the processing code against simulated data.
+//! - `portable-atomic`: Backs `DriverCell` and the heapless SPSC queue with
+//!   [portable-atomic](https://docs.rs/portable-atomic), so they also work on targets without
+//!   native compare-and-swap (thumbv6m, AVR). Enables its `critical-section` feature, the
+//!   application has to provide a `critical-section` implementation. The statistics counters
+//!   (`ChecksumStats`, the retries of the last read, the `DeviceHealth` of a `Fleet`) stay plain
+//!   integers: they are only updated through `&mut` of their owner, which an interrupt gets
+//!   through `DriverCell` or a critical-section mutex anyway, and as copyable snapshots they
+//!   can be compared and logged as a whole.
+//! - `write-read`: Enables reads using a combined write + repeated start + read transaction.
 //! - `unproven`: Enables ppm calibration and r0 value retrieving.
-//! (Correct functionality couldn't be verified.)
+//!   (Correct functionality couldn't be verified.)
 //!
 //! # Example Usage
 //! ```ignore
@@ -29,23 +70,206 @@
 //!
 //! let i2c = device.release(); // destruct driver to use bus with other drivers
 //! ```
+//!
+//! ## Shared bus
+//!
+//! The driver only needs the bus to implement the embedded-hal 0.2 blocking `Read` and `Write`
+//! traits with the same error type. Proxies of the
+//! [shared-bus](https://docs.rs/shared-bus) crate (including the cortex-m mutex variants)
+//! fulfill this, so the driver can be used on a shared bus without any adapter:
+//! ```ignore
+//! let bus = shared_bus::BusManagerSimple::new(i2c);
+//! let mut device = MicsVz89Te::new(bus.acquire_i2c());
+//! let mut other_device = OtherDriver::new(bus.acquire_i2c());
+//! ```
+//!
+//! ## Async on Linux
+//!
+//! With the `tokio` feature, `TokioSensor` wraps the driver for tokio: the bus transfers of
+//! [linux-embedded-hal](https://docs.rs/linux-embedded-hal) run on the blocking pool and the
+//! response time is awaited with the tokio timer, so the reactor isn't stalled:
+//! ```ignore
+//! let sensor = TokioSensor::new(MicsVz89Te::new(I2cdev::new("/dev/i2c-1")?));
+//! let measurements = sensor.read_measurements().await?;
+//! ```
+//! Other runtimes can split the read into [MicsVz89Te::start_measurement()] and
+//! [MicsVz89Te::get_measurement_result()] and await the response time with their own timer.
+//!
+//! ## USB-to-I2C bridges
+//!
+//! On a PC (e.g. for bench characterization) the sensor can be connected with a USB-to-I2C
+//! bridge. The `host` feature adds constructors for an FTDI FT232H and a CH341, see the
+//! `bridge` module. Other bridge crates implementing the embedded-hal 0.2 blocking I2C traits
+//! can be passed to [MicsVz89Te::new()], embedded-hal 1.0 bridges can be wrapped in `Eh1Bus`,
+//! and bridges with their own API can implement [transport::Transport].
+//! ```ignore
+//! let mut device = MicsVz89Te::ch341(0)?;
+//! let measurements = device.read_measurements(&mut Eh1Delay(linux_embedded_hal::Delay))?;
+//! ```
+
+#[cfg(any(feature = "alloc", doc, test))]
+extern crate alloc;
 
+pub mod aggregate;
+pub mod aqi;
+#[cfg(feature = "async")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+pub mod asynch;
+pub mod averaged;
+#[cfg(any(feature = "eh0", test))]
+#[cfg_attr(docsrs, doc(cfg(feature = "eh0")))]
+pub mod borrowed;
+#[cfg(feature = "host")]
+#[cfg_attr(docsrs, doc(cfg(feature = "host")))]
+pub mod bridge;
+pub mod builder;
+pub mod burn_in;
+#[cfg(any(feature = "unproven", doc, test))]
+#[cfg_attr(docsrs, doc(cfg(feature = "unproven")))]
+pub mod calibration;
+#[cfg(feature = "minicbor")]
+#[cfg_attr(docsrs, doc(cfg(feature = "minicbor")))]
+pub mod cbor;
+pub mod checksum;
+pub mod clock;
+pub mod compact_log;
+pub mod config;
+pub mod convert;
+pub mod custom_voc;
+pub mod deadline;
+pub mod delay;
+pub mod diagnostics;
+pub mod display;
+pub mod dose;
+#[cfg(feature = "eh1")]
+#[cfg_attr(docsrs, doc(cfg(feature = "eh1")))]
+pub mod eh1;
+#[cfg(feature = "embassy")]
+#[cfg_attr(docsrs, doc(cfg(feature = "embassy")))]
+pub mod embassy;
 pub mod error;
+pub mod event;
+pub mod extended;
+#[cfg(any(
+    all(any(feature = "test-util", target_arch = "wasm32"), feature = "eh0"),
+    test
+))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(any(feature = "test-util", target_arch = "wasm32"), feature = "eh0")))
+)]
+pub mod fault_injection;
+pub mod filter;
+#[cfg(any(feature = "fixed-point", doc, test))]
+#[cfg_attr(docsrs, doc(cfg(feature = "fixed-point")))]
+pub mod fixed;
+#[cfg(feature = "embedded-storage")]
+#[cfg_attr(docsrs, doc(cfg(feature = "embedded-storage")))]
+pub mod flash_log;
+pub mod fleet;
+pub mod forecast;
+pub mod format;
+pub mod forward;
+mod frontend;
+pub mod fusion;
+pub mod golden;
+#[cfg(feature = "half")]
+#[cfg_attr(docsrs, doc(cfg(feature = "half")))]
+pub mod half_precision;
+pub mod health;
+pub mod history;
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+pub mod host;
+pub mod humidity;
+pub mod iaq;
+pub mod identity;
+pub mod legacy;
+mod math;
+pub mod modbus;
+pub mod observer;
+pub mod peak;
+pub mod protocol;
+pub mod publish;
+pub mod pwm;
+pub mod r0_drift;
+#[cfg(any(feature = "unproven", doc, test))]
+#[cfg_attr(docsrs, doc(cfg(feature = "unproven")))]
+pub mod recalibration;
+pub mod resample;
+pub mod reset;
+pub mod sampler;
+pub mod self_test;
+pub mod session;
+#[cfg(any(
+    all(any(feature = "test-util", target_arch = "wasm32"), feature = "eh0"),
+    test
+))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(any(feature = "test-util", target_arch = "wasm32"), feature = "eh0")))
+)]
+pub mod simulator;
+// the cell needs compare-and-swap, which e.g. thumbv6m only has through portable-atomic
+#[cfg(any(target_has_atomic = "8", feature = "portable-atomic", doc))]
+pub mod singleton;
+pub mod spatial;
+#[cfg(feature = "heapless")]
+#[cfg_attr(docsrs, doc(cfg(feature = "heapless")))]
+pub mod spsc;
+pub mod status_word;
+pub mod sweep;
+pub mod temperature;
+pub mod threshold;
+pub mod transport;
+pub mod trend;
+#[cfg(any(feature = "eh0", test))]
+#[cfg_attr(docsrs, doc(cfg(feature = "eh0")))]
+pub mod uart_bridge;
+pub mod units;
+pub mod warmup;
+pub mod warnings;
+pub mod watchdog;
+#[cfg(feature = "graphics")]
+#[cfg_attr(docsrs, doc(cfg(feature = "graphics")))]
+pub mod widget;
+pub mod wire;
+#[cfg(any(feature = "write-read", test))]
+#[cfg_attr(docsrs, doc(cfg(feature = "write-read")))]
+pub mod write_read;
+pub mod zone;
 
-use embedded_hal::blocking::{
-    delay::DelayMs,
-    i2c::{Read, Write},
+use builder::MicsVz89TeBuilder;
+use checksum::ChecksumStats;
+use config::{CalibrationRecord, Config};
+use core::{
+    num::NonZeroU8,
+    ops::{Add, Div, Mul, Neg, RangeInclusive, Sub},
 };
+use delay::Delay;
 use error::PacketParseError;
+use protocol::{Command, RESPONSE_LEN};
+use transport::Transport;
 
 const MICS_VZ_89TE_ADDR: u8 = 0x70;
+const MICS_VZ_89TE_WAIT_TIME_MS: u16 = 100;
 
-const MICS_VZ_89TE_ADDR_CMD_GETSTATUS: u8 = 0x0C;
-const MICS_VZ_89TE_DATE_CODE: u8 = 0x0D;
-#[cfg(any(feature = "unproven", test))]
-const MICS_VZ_89TE_GET_CALIBR_VAL: u8 = 0x10;
-#[cfg(any(feature = "unproven", test))]
-const MICS_VZ_89TE_SET_CALIBR_PPM: u8 = 0x08;
+/// CO2 range in ppm covered by the sensor.
+pub const CO2_RANGE_PPM: RangeInclusive<u16> = 400..=2000;
+/// VOC range in ppb covered by the sensor.
+pub const VOC_RANGE_PPB: RangeInclusive<u16> = 0..=1000;
+/// Range of the raw CO2 and VOC bytes of a response, mapped onto [CO2_RANGE_PPM] and [VOC_RANGE_PPB].
+pub const RAW_RANGE: RangeInclusive<u8> = 13..=242;
+
+const RAW_MIN: u8 = *RAW_RANGE.start();
+const RAW_SPAN: NonZeroU8 = match NonZeroU8::new(*RAW_RANGE.end() - *RAW_RANGE.start()) {
+    Some(span) => span,
+    None => panic!("empty raw range"),
+};
+const CO2_MIN: f32 = *CO2_RANGE_PPM.start() as f32;
+const CO2_MAX: f32 = *CO2_RANGE_PPM.end() as f32;
+const VOC_MIN: f32 = *VOC_RANGE_PPB.start() as f32;
+const VOC_MAX: f32 = *VOC_RANGE_PPB.end() as f32;
 
 /// Represents the date of revision of the sensor.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -55,6 +279,29 @@ pub struct RevisionDate {
     pub day: u8,
 }
 
+impl RevisionDate {
+    /// Write the date as `YYYY-MM-DD` without using format strings.
+    pub fn write_to(&self, w: &mut impl core::fmt::Write) -> core::fmt::Result {
+        format::write_u16(w, self.year, 4)?;
+        w.write_char('-')?;
+        format::write_u16(w, u16::from(self.month), 2)?;
+        w.write_char('-')?;
+        format::write_u16(w, u16::from(self.day), 2)
+    }
+
+    /// The date as number `YYYYMMDD`, e.g. `20160317`.
+    pub fn to_yyyymmdd(&self) -> u32 {
+        u32::from(self.year) * 10_000 + u32::from(self.month) * 100 + u32::from(self.day)
+    }
+}
+
+impl core::fmt::Display for RevisionDate {
+    /// Formats the date as ISO 8601 (`YYYY-MM-DD`).
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
 #[cfg(feature = "time")]
 impl TryFrom<time::Date> for RevisionDate {
     type Error = time::Error;
@@ -80,47 +327,345 @@ impl TryFrom<RevisionDate> for time::Date {
 }
 
 /// Returned measurements by the sensor
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Measurements {
     pub co2: f32,
     pub voc: f32,
 }
 
+/// Channels which were clamped to the documented range, see [Config::strict_range].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Clamped {
+    /// CO2 was outside of [CO2_RANGE_PPM].
+    pub co2: bool,
+    /// VOC was outside of [VOC_RANGE_PPB].
+    pub voc: bool,
+}
+
+impl Clamped {
+    /// Returns `true` if any channel was clamped.
+    pub fn any(&self) -> bool {
+        self.co2 || self.voc
+    }
+}
+
+/// Selects one of the values reported by the sensor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    /// CO2 in ppm.
+    Co2,
+    /// VOC in ppb.
+    Voc,
+}
+
 impl Measurements {
-    fn from_response(response: &[u8; 7]) -> Self {
-        let co2 = f32::from(response[1].saturating_sub(13)) * (1600.0 / 229.0) + 400.0; // ppm: 400 .. 2000
-        let voc = f32::from(response[0].saturating_sub(13)) * (1000.0 / 229.0); // ppb: 0 .. 1000
-        Self { co2, voc }
+    /// Value of the selected channel.
+    pub fn get(&self, channel: Channel) -> f32 {
+        match channel {
+            Channel::Co2 => self.co2,
+            Channel::Voc => self.voc,
+        }
+    }
+
+    /// CO2 in ppm rounded to the nearest integer (halves round up).
+    ///
+    /// Negative values and NaN map to `0`, values above [u16::MAX] saturate.
+    pub fn co2_ppm_u16(&self) -> u16 {
+        units::round_to_u16(self.co2)
+    }
+
+    /// VOC in ppb rounded to the nearest integer (halves round up).
+    ///
+    /// Negative values and NaN map to `0`, values above [u16::MAX] saturate.
+    pub fn voc_ppb_u16(&self) -> u16 {
+        units::round_to_u16(self.voc)
+    }
+
+    /// Clamp the values to [CO2_RANGE_PPM] and [VOC_RANGE_PPB]. Returns the clamped values and
+    /// which channels were outside of their range. NaN is clamped to the lower bound.
+    pub fn clamp_to_range(&self) -> (Self, Clamped) {
+        let clamp = |value: f32, min: f32, max: f32| {
+            if value >= min && value <= max {
+                (value, false)
+            } else if value > max {
+                (max, true)
+            } else {
+                (min, true)
+            }
+        };
+        let (co2, co2_clamped) = clamp(self.co2, CO2_MIN, CO2_MAX);
+        let (voc, voc_clamped) = clamp(self.voc, VOC_MIN, VOC_MAX);
+        (
+            Self { co2, voc },
+            Clamped {
+                co2: co2_clamped,
+                voc: voc_clamped,
+            },
+        )
+    }
+
+    /// CO2 in percent.
+    pub fn co2_percent(&self) -> f32 {
+        units::ppm_to_percent(self.co2)
+    }
+
+    /// VOC in ppm.
+    pub fn voc_ppm(&self) -> f32 {
+        units::ppb_to_ppm(self.voc)
+    }
+
+    /// Write the measurements as `co2=728ppm voc=113ppb` without using format strings.
+    ///
+    /// Values are rounded like [Measurements::co2_ppm_u16()] and [Measurements::voc_ppb_u16()].
+    pub fn write_to(&self, w: &mut impl core::fmt::Write) -> core::fmt::Result {
+        w.write_str("co2=")?;
+        format::write_u16(w, self.co2_ppm_u16(), 1)?;
+        w.write_str("ppm voc=")?;
+        format::write_u16(w, self.voc_ppb_u16(), 1)?;
+        w.write_str("ppb")
+    }
+
+    /// Write the measurements as `co2=728.2ppm voc=113.0ppb` with `decimals` decimal places,
+    /// without using float formatting, see [format::write_fixed()].
+    pub fn write_fixed_to(&self, w: &mut impl core::fmt::Write, decimals: u8) -> core::fmt::Result {
+        w.write_str("co2=")?;
+        format::write_fixed(w, self.co2, decimals)?;
+        w.write_str("ppm voc=")?;
+        format::write_fixed(w, self.voc, decimals)?;
+        w.write_str("ppb")
+    }
+
+    /// Returns `true` if CO2 and VOC of both measurements differ by at most `eps`.
+    ///
+    /// Useful to compare converted values without failing on float rounding noise.
+    pub fn approx_eq(&self, other: &Self, eps: f32) -> bool {
+        (self.co2 - other.co2).abs() <= eps && (self.voc - other.voc).abs() <= eps
+    }
+
+    pub(crate) fn from_response(response: &[u8; 7]) -> Self {
+        Self::from_response_scaled(response, RAW_MIN, RAW_SPAN)
+    }
+
+    /// Convert a response with a custom raw offset and span, see [Config::raw_offset].
+    pub(crate) fn from_response_scaled(response: &[u8; 7], offset: u8, span: NonZeroU8) -> Self {
+        Self {
+            co2: convert::convert_co2_scaled(response[1], offset, span),
+            voc: convert::convert_voc_scaled(response[0], offset, span),
+        }
+    }
+}
+
+/// Decode the response to [Command::GetStatus] like every frontend of the driver does: apply
+/// [Config::raw_offset] and [Config::raw_span] and, with [Config::strict_range], clamp the values.
+pub(crate) fn decode_status(
+    config: &Config,
+    response: &[u8; RESPONSE_LEN],
+) -> (Measurements, Clamped) {
+    decode_raw(config, response[1], response[0])
+}
+
+/// Convert raw CO2 and VOC bytes with [Config::raw_offset] and [Config::raw_span] and, with
+/// [Config::strict_range], clamp the values.
+pub(crate) fn decode_raw(config: &Config, co2: u8, voc: u8) -> (Measurements, Clamped) {
+    let measurements = Measurements {
+        co2: convert::convert_co2_scaled(co2, config.raw_offset, config.raw_span),
+        voc: convert::convert_voc_scaled(voc, config.raw_offset, config.raw_span),
+    };
+    if config.strict_range {
+        measurements.clamp_to_range()
+    } else {
+        (measurements, Clamped::default())
+    }
+}
+
+/// Map a raw VOC value (see [RAW_RANGE]) to ppb: 0 .. 1000. Values below the range saturate.
+pub(crate) fn raw_to_voc(raw: f32) -> f32 {
+    convert::scale_raw(
+        (raw - f32::from(RAW_MIN)).max(0.0),
+        RAW_SPAN,
+        VOC_MIN,
+        VOC_MAX,
+    )
+}
+
+/// Difference between two [Measurements], e.g. used for trends and slopes.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MeasurementsDelta {
+    pub co2: f32,
+    pub voc: f32,
+}
+
+impl MeasurementsDelta {
+    /// Difference of the selected channel.
+    pub fn get(&self, channel: Channel) -> f32 {
+        match channel {
+            Channel::Co2 => self.co2,
+            Channel::Voc => self.voc,
+        }
+    }
+}
+
+/// Decode a raw response to the status command, validating its checksum like the driver does.
+///
+/// Useful for frames obtained outside the driver, e.g. from logs or remote nodes.
+impl TryFrom<&[u8; protocol::RESPONSE_LEN]> for Measurements {
+    type Error = PacketParseError<core::convert::Infallible>;
+
+    fn try_from(response: &[u8; protocol::RESPONSE_LEN]) -> Result<Self, Self::Error> {
+        protocol::check_response(response)?;
+        Ok(protocol::decode_measurements(response))
+    }
+}
+
+impl Sub for Measurements {
+    type Output = MeasurementsDelta;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        MeasurementsDelta {
+            co2: self.co2 - rhs.co2,
+            voc: self.voc - rhs.voc,
+        }
+    }
+}
+
+impl Add<MeasurementsDelta> for Measurements {
+    type Output = Measurements;
+
+    fn add(self, rhs: MeasurementsDelta) -> Self::Output {
+        Measurements {
+            co2: self.co2 + rhs.co2,
+            voc: self.voc + rhs.voc,
+        }
+    }
+}
+
+impl Add for MeasurementsDelta {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            co2: self.co2 + rhs.co2,
+            voc: self.voc + rhs.voc,
+        }
+    }
+}
+
+impl Sub for MeasurementsDelta {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self {
+            co2: self.co2 - rhs.co2,
+            voc: self.voc - rhs.voc,
+        }
+    }
+}
+
+impl Neg for MeasurementsDelta {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self {
+            co2: -self.co2,
+            voc: -self.voc,
+        }
+    }
+}
+
+impl Mul<f32> for MeasurementsDelta {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        Self {
+            co2: self.co2 * rhs,
+            voc: self.voc * rhs,
+        }
+    }
+}
+
+impl Div<f32> for MeasurementsDelta {
+    type Output = Self;
+
+    fn div(self, rhs: f32) -> Self::Output {
+        Self {
+            co2: self.co2 / rhs,
+            voc: self.voc / rhs,
+        }
     }
 }
 
 /// Driver for MICS-VZ-89TE sensor
+///
+/// Talks to the sensor through any [Transport], usually an I2C bus.
 pub struct MicsVz89Te<I2C> {
     i2c: I2C,
+    config: Config,
+    calibration: Option<CalibrationRecord>,
+    clamped: Clamped,
+    pending: Option<Command>,
+    retries_used: u8,
+    last_status: Option<u8>,
+    checksum: ChecksumStats,
 }
 
 impl<I2C, E> MicsVz89Te<I2C>
 where
-    I2C: Read<Error = E> + Write<Error = E>,
+    I2C: Transport<Error = E>,
 {
     /// Time (in millis) to wait until the sensor response should be valid.
-    pub const WAIT_ON_RESPONSE_TIME: u16 = 100;
+    ///
+    /// This is the default of [Config::wait_time_ms] used by the blocking read functions.
+    pub const WAIT_ON_RESPONSE_TIME: u16 = MICS_VZ_89TE_WAIT_TIME_MS;
 
     /// Create new driver on the supplied i2c bus.
     pub fn new(i2c: I2C) -> Self {
-        Self { i2c }
+        Self::with_config(i2c, Config::default())
     }
 
-    /// Read measurements from sensor.
+    /// Create new driver on the supplied i2c bus and check that the sensor responds by reading its
+    /// revision.
+    ///
+    /// If the sensor doesn't respond, the error is returned together with the bus, so it isn't
+    /// lost.
     ///
     /// This function blocks a minimum time of [MicsVz89Te::WAIT_ON_RESPONSE_TIME].
-    pub fn read_measurements(
-        &mut self,
-        delay: &mut impl DelayMs<u16>,
-    ) -> Result<Measurements, PacketParseError<E>> {
-        let response =
-            self.request_data(&[MICS_VZ_89TE_ADDR_CMD_GETSTATUS, 0, 0, 0, 0, 0xF3], delay)?;
-        Ok(Measurements::from_response(&response))
+    pub fn new_checked(
+        i2c: I2C,
+        delay: &mut impl Delay,
+    ) -> Result<Self, (PacketParseError<E>, I2C)> {
+        Self::new(i2c).checked(delay)
+    }
+
+    /// Check that the sensor responds by reading its revision. On failure the driver is
+    /// destroyed and the error is returned together with the bus.
+    pub(crate) fn checked(
+        mut self,
+        delay: &mut impl Delay,
+    ) -> Result<Self, (PacketParseError<E>, I2C)> {
+        match self.read_revision(delay) {
+            Ok(_) => Ok(self),
+            Err(e) => Err((e, self.release())),
+        }
+    }
+
+    /// Start building a driver with custom options. See [MicsVz89TeBuilder].
+    pub fn builder(i2c: I2C) -> MicsVz89TeBuilder<I2C> {
+        MicsVz89TeBuilder::new(i2c)
+    }
+
+    /// Create new driver on the supplied i2c bus with a custom configuration.
+    pub fn with_config(i2c: I2C, config: Config) -> Self {
+        Self {
+            i2c,
+            config,
+            calibration: None,
+            clamped: Clamped::default(),
+            pending: None,
+            retries_used: 0,
+            last_status: None,
+            checksum: ChecksumStats::default(),
+        }
     }
 
     /// This function starts a measurement request and can be used in context where the delay on a response
@@ -137,87 +682,42 @@ where
     /// let measurements = driver.get_measurement_result().unwrap();
     /// ```
     pub fn start_measurement(&mut self) -> Result<(), PacketParseError<E>> {
-        self.send_request(&[MICS_VZ_89TE_ADDR_CMD_GETSTATUS, 0, 0, 0, 0, 0xF3])
+        self.send_frame(Command::GetStatus)?;
+        self.pending = Some(Command::GetStatus);
+        Ok(())
     }
 
     /// Get the before requested measurements. To see an example, see [MicsVz89Te::start_measurement()].
     pub fn get_measurement_result(&mut self) -> Result<Measurements, PacketParseError<E>> {
-        let response = self.receive_response()?;
-        Ok(Measurements::from_response(&response))
+        let response = self.receive_frame();
+        self.pending = None;
+        let response = response?;
+        self.accept_response(&response, 0)?;
+        Ok(self.decode_measurements(&response))
     }
 
-    /// Read revision date of the sensor.
-    ///
-    /// This function blocks a minimum time of [MicsVz89Te::WAIT_ON_RESPONSE_TIME].
-    pub fn read_revision(
-        &mut self,
-        delay: &mut impl DelayMs<u16>,
-    ) -> Result<RevisionDate, PacketParseError<E>> {
-        let response = self.request_data(&[MICS_VZ_89TE_DATE_CODE, 0, 0, 0, 0, 0xF2], delay)?;
-        let date = RevisionDate {
-            year: u16::from(response[0]) + 2000,
-            month: response[1],
-            day: response[2],
-        };
-        Ok(date)
-    }
+    frontend::impl_requests!([] [] impl Delay);
 
-    #[cfg(any(feature = "unproven", doc, test))]
-    #[cfg_attr(docsrs, doc(cfg(feature = "unproven")))]
-    /// Read the calibration value R0 of the sensor in kOhms.
-    ///
-    /// This function blocks a minimum time of [MicsVz89Te::WAIT_ON_RESPONSE_TIME].
-    pub fn read_calibration_r0(
-        &mut self,
-        delay: &mut impl DelayMs<u16>,
-    ) -> Result<u16, PacketParseError<E>> {
-        let response =
-            self.request_data(&[MICS_VZ_89TE_GET_CALIBR_VAL, 0, 0, 0, 0, 0xEF], delay)?;
-        Ok(u16::from_le_bytes([response[0], response[1]]))
-    }
-
-    #[cfg(any(feature = "unproven", doc, test))]
-    #[cfg_attr(docsrs, doc(cfg(feature = "unproven")))]
-    /// Writes the calibration CO2 value in ppm in range from 400 to 2000 measured by another device.
-    pub fn write_calibration_ppm(&mut self, ppm: f32) -> Result<(), PacketParseError<E>> {
-        debug_assert!(
-            ppm > 400.0 && ppm < 2000.0,
-            "ppm must be in range from 400 to 2000"
-        );
-        let send_ppm = ((ppm - 400.0) / (1600.0 / 229.0) + 13.0) as u8;
-        let mut cmd_array = [MICS_VZ_89TE_SET_CALIBR_PPM, send_ppm, 0, 0, 0, 0];
-        cmd_array[5] = gen_checksum(&cmd_array[..5]);
+    fn send_frame(&mut self, command: Command) -> Result<(), PacketParseError<E>> {
         self.i2c
-            .write(MICS_VZ_89TE_ADDR, &cmd_array)
+            .write_frame(self.config.address, &command.frame())
             .map_err(PacketParseError::from)
     }
 
-    fn request_data(
-        &mut self,
-        cmd_buffer: &[u8; 6],
-        delay: &mut impl DelayMs<u16>,
-    ) -> Result<[u8; 7], PacketParseError<E>> {
-        self.send_request(cmd_buffer)?;
-        delay.delay_ms(Self::WAIT_ON_RESPONSE_TIME);
-        self.receive_response()
+    fn receive_frame(&mut self) -> Result<[u8; RESPONSE_LEN], PacketParseError<E>> {
+        let mut buffer = [0u8; RESPONSE_LEN];
+        self.i2c.read_frame(self.config.address, &mut buffer)?;
+        Ok(buffer)
     }
 
-    fn send_request(&mut self, cmd_buffer: &[u8; 6]) -> Result<(), PacketParseError<E>> {
-        self.i2c
-            .write(MICS_VZ_89TE_ADDR, cmd_buffer)
-            .map_err(PacketParseError::from)
+    fn wait(&mut self, delay: &mut impl Delay) {
+        delay.delay_ms(self.config.wait_time_ms);
     }
 
-    fn receive_response(&mut self) -> Result<[u8; 7], PacketParseError<E>> {
-        let mut buffer = [0u8; 7];
-        self.i2c.read(MICS_VZ_89TE_ADDR, &mut buffer)?;
-
-        let check = gen_checksum(&buffer[..5]);
-        if buffer[6].ne(&check) {
-            return Err(PacketParseError::WrongChecksum);
-        }
-
-        Ok(buffer)
+    /// A blocking request can't be abandoned halfway, a measurement started with
+    /// [MicsVz89Te::start_measurement()] is superseded by the next request.
+    fn settle_pending(&mut self) -> Result<(), PacketParseError<E>> {
+        Ok(())
     }
 }
 
@@ -234,24 +734,34 @@ impl<I2C> MicsVz89Te<I2C> {
     pub fn release(self) -> I2C {
         self.i2c
     }
-}
 
-fn gen_checksum(byte_array: &[u8]) -> u8 {
-    let sum = byte_array.iter().fold(0u16, |a, v| a + (*v as u16));
-    0xFF - (sum as u8).wrapping_add((sum / 0x0100) as u8)
+    /// Mutable access to the underlying I2C bus, e.g. to talk to other devices on it.
+    ///
+    /// This is meant for advanced use: don't issue transactions between
+    /// [MicsVz89Te::start_measurement()] and [MicsVz89Te::get_measurement_result()] and don't
+    /// send commands to the sensor behind the back of the driver.
+    pub fn bus_mut(&mut self) -> &mut I2C {
+        &mut self.i2c
+    }
+
+    frontend::impl_state!();
 }
 
 #[cfg(test)]
 mod test {
 
-    use crate::{error::PacketParseError, RevisionDate};
+    use crate::{
+        config::Config, error::PacketParseError, Clamped, Measurements, MeasurementsDelta,
+        RevisionDate,
+    };
 
     use super::MicsVz89Te;
     use assert_matches::assert_matches;
-    use core::assert_eq;
+    use core::{assert_eq, num::NonZeroU8};
     use embedded_hal_mock::{
         delay::MockNoop as DelayMock,
         i2c::{Mock as I2cMock, Transaction as I2cTransaction},
+        MockError,
     };
     use std::vec;
 
@@ -331,4 +841,286 @@ mod test {
 
         assert_matches!(value, Ok(v) if v == 507);
     }
+
+    #[test]
+    fn test_measurements_delta() {
+        let before = Measurements {
+            co2: 700.0,
+            voc: 100.0,
+        };
+        let after = Measurements {
+            co2: 760.0,
+            voc: 80.0,
+        };
+
+        let delta = after - before;
+        assert_eq!(
+            delta,
+            MeasurementsDelta {
+                co2: 60.0,
+                voc: -20.0
+            }
+        );
+
+        let slope = delta / 2.0;
+        assert_eq!((slope.co2, slope.voc), (30.0, -10.0));
+
+        let restored = after + -delta;
+        assert_eq!((restored.co2, restored.voc), (700.0, 100.0));
+    }
+
+    #[test]
+    fn test_measurements_approx_eq() {
+        let a = Measurements {
+            co2: 728.0,
+            voc: 113.0,
+        };
+        let b = Measurements {
+            co2: 728.0 + 1e-3,
+            voc: 113.0 - 1e-3,
+        };
+
+        assert_ne!(a, b);
+        assert!(a.approx_eq(&b, 0.01));
+        assert!(!a.approx_eq(&b, 1e-4));
+    }
+
+    #[test]
+    fn test_measurements_integer_getters() {
+        let min = Measurements::from_response(&[13, 13, 0, 0, 0, 0, 0]);
+        assert_eq!((min.co2_ppm_u16(), min.voc_ppb_u16()), (400, 0));
+
+        let max = Measurements::from_response(&[242, 242, 0, 0, 0, 0, 0]);
+        assert_eq!((max.co2_ppm_u16(), max.voc_ppb_u16()), (2000, 1000));
+
+        let m = Measurements::from_response(&[0x27, 0x3C, 0, 0, 0, 0, 0]);
+        assert_eq!((m.co2_ppm_u16(), m.voc_ppb_u16()), (728, 114));
+
+        let out_of_range = Measurements {
+            co2: 70_000.0,
+            voc: -3.0,
+        };
+        assert_eq!(out_of_range.co2_ppm_u16(), u16::MAX);
+        assert_eq!(out_of_range.voc_ppb_u16(), 0);
+        assert_eq!(
+            Measurements {
+                co2: f32::NAN,
+                voc: 0.5
+            }
+            .co2_ppm_u16(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_revision_date_display() {
+        let date = RevisionDate {
+            year: 2016,
+            month: 3,
+            day: 17,
+        };
+        assert_eq!(std::format!("{}", date), "2016-03-17");
+    }
+
+    #[test]
+    fn test_config_address_and_retries() {
+        let expectations = [
+            I2cTransaction::write(0x71, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x71, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x26]),
+            I2cTransaction::write(0x71, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x71, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+        ];
+        let i2c = I2cMock::new(&expectations);
+        let mut delay = DelayMock::new();
+
+        let config = Config {
+            address: 0x71,
+            retries: 1,
+            ..Config::default()
+        };
+        let mut device = MicsVz89Te::with_config(i2c, config);
+        let measurements = device.read_measurements(&mut delay);
+
+        assert_matches!(measurements, Ok(m) if m.co2_ppm_u16() == 728);
+    }
+
+    #[test]
+    fn test_raw_offset_override() {
+        let expectations = [
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+        ];
+        let config = Config {
+            raw_offset: 15,
+            raw_span: NonZeroU8::new(225).unwrap(),
+            ..Config::default()
+        };
+        let mut device = MicsVz89Te::with_config(I2cMock::new(&expectations), config);
+        let mut delay = DelayMock::new();
+
+        let measurements = device.read_measurements(&mut delay).unwrap();
+        assert_eq!(measurements.co2_ppm_u16(), 720);
+        assert_eq!(measurements.voc_ppb_u16(), 107);
+        device.release().done();
+    }
+
+    #[test]
+    fn test_strict_range() {
+        let expectations = [
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0xFA, 0, 0xBA, 0xBA, 0, 0x68]),
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+        ];
+        let config = Config {
+            strict_range: true,
+            ..Config::default()
+        };
+        let mut device = MicsVz89Te::with_config(I2cMock::new(&expectations), config);
+        let mut delay = DelayMock::new();
+
+        let measurements = device.read_measurements(&mut delay).unwrap();
+        assert_eq!(measurements.co2, 2000.0);
+        assert_eq!(
+            device.last_clamped(),
+            Clamped {
+                co2: true,
+                voc: false
+            }
+        );
+
+        device.read_measurements(&mut delay).unwrap();
+        assert!(!device.last_clamped().any());
+        device.release().done();
+
+        let (clamped, flags) = Measurements {
+            co2: f32::NAN,
+            voc: 1000.0,
+        }
+        .clamp_to_range();
+        assert_eq!(clamped.co2, 400.0);
+        assert_eq!(clamped.voc, 1000.0);
+        assert!(flags.co2 && !flags.voc);
+    }
+
+    #[test]
+    fn test_snapshot_apply() {
+        let expectations = [I2cTransaction::write(0x70, vec![0x08, 0x62, 0, 0, 0, 0x95])];
+        let i2c = I2cMock::new(&expectations);
+
+        let mut device = MicsVz89Te::new(i2c);
+        device.write_calibration_ppm(1000.0).unwrap();
+        let snapshot = device.snapshot();
+        assert_eq!(snapshot.calibration.map(|c| c.reference_ppm), Some(1000.0));
+
+        let mut restored = MicsVz89Te::new(I2cMock::new(&[]));
+        restored.apply(&snapshot);
+        assert_eq!(restored.snapshot(), snapshot);
+    }
+
+    #[test]
+    fn test_write_calibration_ppm_clamped() {
+        let expectations = [I2cTransaction::write(0x70, vec![0x08, 0xF2, 0, 0, 0, 0x05])];
+        let i2c = I2cMock::new(&expectations);
+
+        let mut device = MicsVz89Te::new(i2c);
+        let res = device.write_calibration_ppm(2500.0);
+
+        assert!(res.is_ok());
+        // the record holds the value sent, not the requested one
+        assert_eq!(device.last_calibration().unwrap().reference_ppm, 2000.0);
+    }
+
+    #[test]
+    fn test_write_calibration_out_of_range() {
+        let expectations = [I2cTransaction::write(0x70, vec![0x08, 0x0D, 0, 0, 0, 0xEA])];
+        let i2c = I2cMock::new(&expectations);
+
+        let mut device = MicsVz89Te::new(i2c);
+        device
+            .write_calibration(5.0, crate::config::CalibrationMethod::Manual, &|| 42)
+            .unwrap();
+
+        let record = device.last_calibration().unwrap();
+        assert_eq!(
+            (record.reference_ppm, record.timestamp_ms),
+            (400.0, Some(42))
+        );
+        device.release().done();
+    }
+
+    #[test]
+    fn test_write_to() {
+        let mut s = std::string::String::new();
+        Measurements {
+            co2: 728.4,
+            voc: 113.6,
+        }
+        .write_to(&mut s)
+        .unwrap();
+        s.push(' ');
+        RevisionDate {
+            year: 2016,
+            month: 3,
+            day: 17,
+        }
+        .write_to(&mut s)
+        .unwrap();
+        s.push(' ');
+        PacketParseError::<()>::WrongChecksum
+            .write_to(&mut s)
+            .unwrap();
+
+        assert_eq!(s, "co2=728ppm voc=114ppb 2016-03-17 wrong checksum");
+
+        s.clear();
+        Measurements {
+            co2: 728.4,
+            voc: 113.6,
+        }
+        .write_fixed_to(&mut s, 1)
+        .unwrap();
+        assert_eq!(s, "co2=728.4ppm voc=113.6ppb");
+    }
+
+    #[test]
+    fn test_new_checked_returns_bus() {
+        let expectations = [I2cTransaction::write(0x70, vec![0x0D, 0, 0, 0, 0, 0xF2])
+            .with_error(MockError::Io(std::io::ErrorKind::NotConnected))];
+        let i2c = I2cMock::new(&expectations);
+        let mut delay = DelayMock::new();
+
+        let res = MicsVz89Te::new_checked(i2c, &mut delay);
+
+        assert!(matches!(res, Err((PacketParseError::BusError(_), _))));
+    }
+
+    #[test]
+    fn test_shared_bus_proxies() {
+        let expectations = [
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+            I2cTransaction::write(0x71, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x71, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let bus = shared_bus::BusManagerSimple::new(i2c.clone());
+        let mut delay = DelayMock::new();
+
+        let mut first = MicsVz89Te::new(bus.acquire_i2c());
+        let mut second = MicsVz89Te::builder(bus.acquire_i2c()).address(0x71).build();
+
+        assert!(first.read_measurements(&mut delay).is_ok());
+        assert!(second.read_measurements(&mut delay).is_ok());
+        i2c.done();
+    }
+
+    #[test]
+    fn test_measurements_try_from_response() {
+        let m = Measurements::try_from(&[0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]).unwrap();
+        assert_eq!((m.co2_ppm_u16(), m.voc_ppb_u16()), (728, 114));
+
+        let res = Measurements::try_from(&[0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x26]);
+        assert_matches!(res, Err(PacketParseError::WrongChecksum));
+    }
 }
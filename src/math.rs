@@ -0,0 +1,99 @@
+//! Float helpers not available in `core`.
+
+/// Square root using Newton's method. Returns `0.0` for non-positive or NaN inputs.
+pub(crate) fn sqrt(value: f32) -> f32 {
+    if value.is_nan() || value <= 0.0 {
+        return 0.0;
+    }
+    if value.is_infinite() {
+        return value;
+    }
+    let mut x = if value > 1.0 { value / 2.0 } else { 1.0 };
+    for _ in 0..32 {
+        let next = 0.5 * (x + value / x);
+        if (next - x).abs() <= f32::EPSILON * next {
+            return next;
+        }
+        x = next;
+    }
+    x
+}
+
+/// Least-squares line through equidistant values.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LinearFit {
+    /// Change per step.
+    pub slope: f32,
+    /// Fitted value of the last step.
+    pub last: f32,
+    /// Standard error of the slope.
+    pub slope_std_err: f32,
+}
+
+/// Fits a line through `values` at steps `0, 1, 2, ...`. Needs at least 3 values.
+pub(crate) fn linear_fit(values: impl Iterator<Item = f32> + Clone) -> Option<LinearFit> {
+    linear_fit_xy(values.enumerate().map(|(i, v)| (i as f32, v)))
+}
+
+/// Fits a line through `(x, y)` points with ascending `x`. `slope` is the change per unit of
+/// `x`. Needs at least 3 points and distinct `x` values.
+pub(crate) fn linear_fit_xy(points: impl Iterator<Item = (f32, f32)> + Clone) -> Option<LinearFit> {
+    let (n, sum_x, sum_y, last_x) = points.clone().fold(
+        (0usize, 0.0f32, 0.0f32, 0.0f32),
+        |(n, sx, sy, _), (x, y)| (n + 1, sx + x, sy + y, x),
+    );
+    if n < 3 {
+        return None;
+    }
+    let count = n as f32;
+    let mean_x = sum_x / count;
+    let mean_y = sum_y / count;
+    let (sxx, sxy) = points.clone().fold((0.0f32, 0.0f32), |(sxx, sxy), (x, y)| {
+        let dx = x - mean_x;
+        (sxx + dx * dx, sxy + dx * (y - mean_y))
+    });
+    if sxx <= 0.0 {
+        return None;
+    }
+    let slope = sxy / sxx;
+    let intercept = mean_y - slope * mean_x;
+    let sse = points.fold(0.0f32, |sse, (x, y)| {
+        let r = y - (intercept + slope * x);
+        sse + r * r
+    });
+    Some(LinearFit {
+        slope,
+        last: intercept + slope * last_x,
+        slope_std_err: sqrt(sse / (count - 2.0) / sxx),
+    })
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{linear_fit, linear_fit_xy, sqrt};
+
+    #[test]
+    fn test_sqrt() {
+        for (value, expected) in [
+            (0.0, 0.0),
+            (-4.0, 0.0),
+            (0.25, 0.5),
+            (2.0, core::f32::consts::SQRT_2),
+            (1.0e6, 1.0e3),
+        ] {
+            assert!((sqrt(value) - expected).abs() < 1e-4, "sqrt({})", value);
+        }
+    }
+
+    #[test]
+    fn test_linear_fit() {
+        let fit = linear_fit([1.0, 3.0, 5.0, 7.0].into_iter()).unwrap();
+        assert_eq!((fit.slope, fit.last, fit.slope_std_err), (2.0, 7.0, 0.0));
+        assert!(linear_fit([1.0, 2.0].into_iter()).is_none());
+
+        let fit = linear_fit_xy([(0.0, 10.0), (1.0, 11.0), (4.0, 14.0)].into_iter()).unwrap();
+        assert_eq!((fit.slope, fit.last), (1.0, 14.0));
+        assert!(linear_fit_xy([(1.0, 1.0), (1.0, 2.0), (1.0, 3.0)].into_iter()).is_none());
+    }
+}
@@ -0,0 +1,272 @@
+//! Modbus holding-register map backed by the driver.
+//!
+//! [RegisterMap] keeps the values of a fixed register layout up to date, so a Modbus RTU or TCP
+//! slave only has to answer "read holding registers" (function `0x03`) requests with
+//! [RegisterMap::read()] or [RegisterMap::encode()].
+//!
+//! | address | content                                                          |
+//! |---------|------------------------------------------------------------------|
+//! | 0       | CO2 in ppm, see [wire] for the reserved values                   |
+//! | 1       | VOC in ppb, see [wire] for the reserved values                   |
+//! | 2       | status bits, see [STATUS_VALID] and following                    |
+//! | 3       | sensor revision year                                             |
+//! | 4       | sensor revision month (high byte) and day (low byte)             |
+//! | 5       | number of successful reads (wrapping)                            |
+//! | 6       | number of failed reads (wrapping)                                |
+//! | 7       | last calibration reference in ppm, `0` if none                   |
+//! | 8       | last calibration method, `0xFFFF` if none                        |
+//!
+//! # Example Usage
+//! ```ignore
+//! let mut registers = RegisterMap::new();
+//! registers.update_revision(&device.read_revision(&mut delay)?);
+//! loop {
+//!     registers.update_measurements(&device.read_measurements(&mut delay).map_err(|e| Fault::from(&e)));
+//!     registers.update_diagnostics(&device.diagnostics());
+//!     if let Some((start, count)) = slave.poll_read_holding_registers() {
+//!         match registers.read(start, count) {
+//!             Ok(values) => slave.respond(values),
+//!             Err(e) => slave.exception(e.code()),
+//!         }
+//!     }
+//! }
+//! ```
+
+use crate::{diagnostics::Diagnostics, event::Fault, units, wire, Measurements, RevisionDate};
+
+/// Number of registers in the map.
+pub const REGISTER_COUNT: usize = 9;
+
+/// Maximum number of registers of a "read holding registers" request, see the Modbus
+/// application protocol specification V1.1b3, section 6.3.
+pub const MAX_READ_COUNT: u16 = 125;
+
+/// Address of the CO2 register.
+pub const REG_CO2: u16 = 0;
+/// Address of the VOC register.
+pub const REG_VOC: u16 = 1;
+/// Address of the status register.
+pub const REG_STATUS: u16 = 2;
+/// Address of the revision year register.
+pub const REG_REVISION_YEAR: u16 = 3;
+/// Address of the revision month/day register.
+pub const REG_REVISION_MONTH_DAY: u16 = 4;
+/// Address of the successful reads counter.
+pub const REG_READS: u16 = 5;
+/// Address of the failed reads counter.
+pub const REG_FAULTS: u16 = 6;
+/// Address of the calibration reference register.
+pub const REG_CALIBRATION_PPM: u16 = 7;
+/// Address of the calibration method register.
+pub const REG_CALIBRATION_METHOD: u16 = 8;
+
+/// Status bit: CO2 and VOC hold values of the last successful read.
+pub const STATUS_VALID: u16 = 1 << 0;
+/// Status bit: the last read failed.
+pub const STATUS_FAULT: u16 = 1 << 1;
+/// Status bit: the last read failed with a bus error.
+pub const STATUS_BUS_ERROR: u16 = 1 << 2;
+/// Status bit: the last read failed with a wrong checksum.
+pub const STATUS_WRONG_CHECKSUM: u16 = 1 << 3;
+/// Status bit: the revision registers are set.
+pub const STATUS_REVISION: u16 = 1 << 4;
+
+/// Modbus exceptions of a register request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModbusError {
+    /// The requested range isn't part of the map (exception code `0x02`).
+    IllegalDataAddress,
+    /// The requested count is `0`, above [MAX_READ_COUNT] or the output buffer is too small
+    /// (exception code `0x03`).
+    IllegalDataValue,
+}
+
+impl ModbusError {
+    /// Modbus exception code.
+    pub fn code(&self) -> u8 {
+        match self {
+            Self::IllegalDataAddress => 0x02,
+            Self::IllegalDataValue => 0x03,
+        }
+    }
+}
+
+/// Holding registers of the layout described in the [module](self) documentation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisterMap {
+    registers: [u16; REGISTER_COUNT],
+}
+
+impl Default for RegisterMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RegisterMap {
+    /// Create a map without any values. CO2 and VOC are marked invalid.
+    pub const fn new() -> Self {
+        let mut registers = [0; REGISTER_COUNT];
+        registers[REG_CO2 as usize] = wire::INVALID;
+        registers[REG_VOC as usize] = wire::INVALID;
+        registers[REG_CALIBRATION_METHOD as usize] = 0xFFFF;
+        Self { registers }
+    }
+
+    /// All registers, starting at address `0`.
+    pub fn registers(&self) -> &[u16; REGISTER_COUNT] {
+        &self.registers
+    }
+
+    /// Update the registers with the result of a read. After a fault, the last successful
+    /// values are kept, but [STATUS_VALID] is cleared.
+    pub fn update_measurements(&mut self, result: &Result<Measurements, Fault>) {
+        let status = self.get(REG_STATUS) & STATUS_REVISION;
+        match result {
+            Ok(m) => {
+                let [co2_l, co2_h, voc_l, voc_h] = wire::encode(m);
+                self.set(REG_CO2, u16::from_le_bytes([co2_l, co2_h]));
+                self.set(REG_VOC, u16::from_le_bytes([voc_l, voc_h]));
+                self.set(REG_STATUS, status | STATUS_VALID);
+                self.set(REG_READS, self.get(REG_READS).wrapping_add(1));
+            }
+            Err(fault) => {
+                let cause = match fault {
+                    Fault::Bus => STATUS_BUS_ERROR,
+                    Fault::WrongChecksum => STATUS_WRONG_CHECKSUM,
+                };
+                self.set(REG_STATUS, status | STATUS_FAULT | cause);
+                self.set(REG_FAULTS, self.get(REG_FAULTS).wrapping_add(1));
+            }
+        }
+    }
+
+    /// Update the revision registers.
+    pub fn update_revision(&mut self, revision: &RevisionDate) {
+        self.set(REG_REVISION_YEAR, revision.year);
+        self.set(
+            REG_REVISION_MONTH_DAY,
+            u16::from_be_bytes([revision.month, revision.day]),
+        );
+        self.set(REG_STATUS, self.get(REG_STATUS) | STATUS_REVISION);
+    }
+
+    /// Update the calibration registers from a diagnostics report.
+    pub fn update_diagnostics(&mut self, diagnostics: &Diagnostics) {
+        let (ppm, method) = match &diagnostics.last_calibration {
+            Some(c) => (units::round_to_u16(c.reference_ppm), c.method as u16),
+            None => (0, 0xFFFF),
+        };
+        self.set(REG_CALIBRATION_PPM, ppm);
+        self.set(REG_CALIBRATION_METHOD, method);
+    }
+
+    /// Registers of a "read holding registers" request.
+    pub fn read(&self, start: u16, count: u16) -> Result<&[u16], ModbusError> {
+        if count == 0 || count > MAX_READ_COUNT {
+            return Err(ModbusError::IllegalDataValue);
+        }
+        let start = usize::from(start);
+        self.registers
+            .get(start..start + usize::from(count))
+            .ok_or(ModbusError::IllegalDataAddress)
+    }
+
+    /// Write the registers of a "read holding registers" request big-endian into `out`, as
+    /// they are sent on the wire. Returns the number of written bytes.
+    pub fn encode(&self, start: u16, count: u16, out: &mut [u8]) -> Result<usize, ModbusError> {
+        let registers = self.read(start, count)?;
+        let len = registers.len() * 2;
+        let out = out.get_mut(..len).ok_or(ModbusError::IllegalDataValue)?;
+        for (chunk, register) in out.chunks_exact_mut(2).zip(registers) {
+            chunk.copy_from_slice(&register.to_be_bytes());
+        }
+        Ok(len)
+    }
+
+    fn get(&self, address: u16) -> u16 {
+        self.registers[usize::from(address)]
+    }
+
+    fn set(&mut self, address: u16, value: u16) {
+        self.registers[usize::from(address)] = value;
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{
+        ModbusError, RegisterMap, MAX_READ_COUNT, STATUS_FAULT, STATUS_REVISION, STATUS_VALID,
+    };
+    use crate::{event::Fault, Measurements, RevisionDate};
+    use core::assert_eq;
+
+    #[test]
+    fn test_register_updates() {
+        let mut map = RegisterMap::new();
+        assert_eq!(map.read(0, 3), Ok(&[0xFFFF, 0xFFFF, 0][..]));
+
+        map.update_revision(&RevisionDate {
+            year: 2016,
+            month: 3,
+            day: 17,
+        });
+        map.update_measurements(&Ok(Measurements {
+            co2: 728.4,
+            voc: 113.5,
+        }));
+        assert_eq!(
+            map.registers(),
+            &[
+                728,
+                114,
+                STATUS_VALID | STATUS_REVISION,
+                2016,
+                0x0311,
+                1,
+                0,
+                0,
+                0xFFFF
+            ]
+        );
+
+        map.update_measurements(&Err(Fault::WrongChecksum));
+        let status = map.read(2, 1).unwrap()[0];
+        assert_eq!(status & (STATUS_VALID | STATUS_FAULT), STATUS_FAULT);
+        assert_eq!(map.read(0, 1), Ok(&[728][..]));
+        assert_eq!(map.read(6, 1), Ok(&[1][..]));
+    }
+
+    #[test]
+    fn test_encode() {
+        let mut map = RegisterMap::new();
+        map.update_measurements(&Ok(Measurements {
+            co2: 1000.0,
+            voc: 20.0,
+        }));
+        let mut out = [0u8; 4];
+        assert_eq!(map.encode(0, 2, &mut out), Ok(4));
+        assert_eq!(out, [0x03, 0xE8, 0x00, 0x14]);
+
+        assert_eq!(
+            map.encode(0, 3, &mut out),
+            Err(ModbusError::IllegalDataValue)
+        );
+        assert_eq!(map.read(8, 2), Err(ModbusError::IllegalDataAddress));
+        assert_eq!(map.read(0, 0), Err(ModbusError::IllegalDataValue));
+        assert_eq!(ModbusError::IllegalDataAddress.code(), 2);
+    }
+
+    #[test]
+    fn test_count_limit() {
+        let map = RegisterMap::new();
+        // checked before the address, like a Modbus server does
+        let error = map.read(0, MAX_READ_COUNT + 1).unwrap_err();
+        assert_eq!((error, error.code()), (ModbusError::IllegalDataValue, 0x03));
+        assert_eq!(
+            map.read(0, MAX_READ_COUNT),
+            Err(ModbusError::IllegalDataAddress)
+        );
+    }
+}
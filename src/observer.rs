@@ -0,0 +1,251 @@
+//! Push-style notification of measurements and alarm changes.
+//!
+//! As an alternative to polling an [EventQueue](crate::event::EventQueue), observers can be
+//! registered in [Observers] and get called for every new measurement and every event.
+//!
+//! # Example Usage
+//! ```ignore
+//! let mut log = |n: &Notification| log(n);
+//! let mut thresholds = [Threshold::new(Channel::Co2, 1000.0, 50.0)];
+//! let mut observers = Observers::<2>::new();
+//! observers.register(&mut log);
+//!
+//! let measurements = device.read_measurements(&mut delay).unwrap();
+//! observers.update(now_ms, &measurements, &mut thresholds);
+//! ```
+//!
+//! To notify observers about every measurement accepted by a sampler, wrap the samples with
+//! [Observers::notifying()]:
+//! ```ignore
+//! for sample in observers.notifying(device.samples(&mut delay, &clock).filtered(median)) {
+//!     // observers are already notified
+//! }
+//! ```
+
+use crate::{
+    event::{Event, EventKind, Fault},
+    threshold::Threshold,
+    Measurements,
+};
+
+/// What an [Observer] is notified about.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Notification<'a> {
+    /// New measurements read at `timestamp_ms`.
+    Measurements {
+        timestamp_ms: u64,
+        measurements: &'a Measurements,
+    },
+    /// An event, e.g. a raised or cleared alarm.
+    Event(Event),
+}
+
+/// Receiver of notifications. Implemented for closures taking a [Notification].
+pub trait Observer {
+    /// Called for every notification.
+    fn notify(&mut self, notification: &Notification<'_>);
+}
+
+impl<F> Observer for F
+where
+    F: FnMut(&Notification<'_>),
+{
+    fn notify(&mut self, notification: &Notification<'_>) {
+        self(notification)
+    }
+}
+
+/// Up to `N` registered observers.
+pub struct Observers<'a, const N: usize> {
+    observers: [Option<&'a mut dyn Observer>; N],
+}
+
+impl<const N: usize> Default for Observers<'_, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, const N: usize> Observers<'a, N> {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self {
+            observers: core::array::from_fn(|_| None),
+        }
+    }
+
+    /// Register an observer. Returns `false` if all `N` slots are taken.
+    pub fn register(&mut self, observer: &'a mut dyn Observer) -> bool {
+        match self.observers.iter_mut().find(|o| o.is_none()) {
+            Some(slot) => {
+                *slot = Some(observer);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove all observers.
+    pub fn clear(&mut self) {
+        self.observers.iter_mut().for_each(|o| *o = None);
+    }
+
+    /// Notify all observers.
+    pub fn notify(&mut self, notification: &Notification<'_>) {
+        self.observers
+            .iter_mut()
+            .flatten()
+            .for_each(|o| o.notify(notification));
+    }
+
+    /// Notify all observers about `event`.
+    pub fn notify_event(&mut self, event: Event) {
+        self.notify(&Notification::Event(event));
+    }
+
+    /// Notify all observers about new `measurements`.
+    pub fn notify_measurements(&mut self, timestamp_ms: u64, measurements: &Measurements) {
+        self.notify(&Notification::Measurements {
+            timestamp_ms,
+            measurements,
+        });
+    }
+
+    /// Notify all observers about new `measurements`, feed them to `thresholds` and notify
+    /// about every crossing.
+    pub fn update(
+        &mut self,
+        timestamp_ms: u64,
+        measurements: &Measurements,
+        thresholds: &mut [Threshold],
+    ) {
+        self.notify_measurements(timestamp_ms, measurements);
+        for threshold in thresholds {
+            if let Some(crossing) = threshold.update(measurements) {
+                self.notify_event(Event::threshold(
+                    timestamp_ms,
+                    threshold,
+                    crossing,
+                    measurements,
+                ));
+            }
+        }
+    }
+
+    /// Notify all observers about a failed read.
+    pub fn fault(&mut self, timestamp_ms: u64, fault: Fault) {
+        self.notify_event(Event::new(timestamp_ms, EventKind::SensorFault(fault)));
+    }
+
+    /// Wrap timestamped samples, e.g. of [Samples](crate::sampler::Samples), so all observers
+    /// are notified about every successful sample. See [Notifying].
+    pub fn notifying<'s, I>(&'s mut self, samples: I) -> Notifying<'s, 'a, I, N> {
+        Notifying {
+            samples,
+            observers: self,
+        }
+    }
+}
+
+/// Iterator passing samples on unchanged, after notifying [Observers] about the measurements
+/// of successful samples. Errors are passed on without notification. Created with
+/// [Observers::notifying()].
+pub struct Notifying<'s, 'a, I, const N: usize> {
+    samples: I,
+    observers: &'s mut Observers<'a, N>,
+}
+
+impl<I, E, const N: usize> Iterator for Notifying<'_, '_, I, N>
+where
+    I: Iterator<Item = Result<(u64, Measurements), E>>,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = self.samples.next()?;
+        if let Ok((timestamp_ms, measurements)) = &sample {
+            self.observers
+                .notify_measurements(*timestamp_ms, measurements);
+        }
+        Some(sample)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{Notification, Observers};
+    use crate::{
+        event::{EventKind, Fault},
+        threshold::Threshold,
+        Channel, Measurements,
+    };
+    use core::assert_eq;
+    use std::{vec, vec::Vec};
+
+    #[test]
+    fn test_notify() {
+        let mut measurements_seen = 0;
+        let mut events = Vec::new();
+        let mut on_measurements = |n: &Notification| {
+            if let Notification::Measurements { .. } = n {
+                measurements_seen += 1;
+            }
+        };
+        let mut on_event = |n: &Notification| {
+            if let Notification::Event(e) = n {
+                events.push(e.kind);
+            }
+        };
+        let mut thresholds = [Threshold::new(Channel::Co2, 1000.0, 0.0)];
+
+        let mut observers = Observers::<2>::new();
+        assert!(observers.register(&mut on_measurements));
+        assert!(observers.register(&mut on_event));
+        let mut unused = |_: &Notification| {};
+        assert!(!observers.register(&mut unused));
+
+        for co2 in [900.0, 1100.0, 800.0] {
+            observers.update(0, &Measurements { co2, voc: 0.0 }, &mut thresholds);
+        }
+        observers.fault(1, Fault::Bus);
+
+        assert_eq!(measurements_seen, 3);
+        assert_eq!(
+            events,
+            vec![
+                EventKind::ThresholdRaised {
+                    channel: Channel::Co2,
+                    value: 1100.0
+                },
+                EventKind::ThresholdCleared {
+                    channel: Channel::Co2,
+                    value: 800.0
+                },
+                EventKind::SensorFault(Fault::Bus),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_notifying() {
+        let mut seen = Vec::new();
+        let mut display = |n: &Notification| {
+            if let Notification::Measurements { timestamp_ms, .. } = n {
+                seen.push(*timestamp_ms);
+            }
+        };
+        let mut observers = Observers::<1>::new();
+        observers.register(&mut display);
+
+        let m = Measurements {
+            co2: 800.0,
+            voc: 10.0,
+        };
+        let samples = [Ok((1000, m)), Err(()), Ok((3000, m))];
+        let passed = observers.notifying(samples.into_iter()).count();
+
+        assert_eq!(passed, 3);
+        assert_eq!(seen, vec![1000, 3000]);
+    }
+}
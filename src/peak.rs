@@ -0,0 +1,284 @@
+//! Peak-hold readout over a sliding time window.
+//!
+//! [PeakHold] reports the maximum CO2 and VOC over the last `window_ms`, which is steadier to
+//! display than the noisy instantaneous value. The window is split into `N` buckets holding the
+//! maximum of their time span, so the memory is fixed and the window moves in steps of
+//! `window_ms / N`. Optionally the displayed peak decays at a limited rate instead of dropping
+//! at once when it leaves the window. With the `alloc` feature, [VecPeakHold] takes the number
+//! of buckets at runtime. [PeakHold::clocked()] turns it into a [MeasurementFilter] stage
+//! timestamping the measurements with a [Clock].
+//!
+//! # Example Usage
+//! ```ignore
+//! // last 10 minutes in 30 s steps, VOC falling by at most 50 ppb per minute
+//! let mut peak = PeakHold::<20>::new(600_000).with_decay(200.0, 50.0);
+//! loop {
+//!     let m = device.read_measurements(&mut delay)?;
+//!     display.show_voc(peak.push(clock.now_ms(), &m).voc);
+//! }
+//! ```
+
+#[cfg(any(feature = "alloc", doc, test))]
+use alloc::{vec, vec::Vec};
+
+use crate::{clock::Clock, filter::MeasurementFilter, Measurements};
+
+/// Maximum of a bucket and the bucket index it belongs to.
+type Bucket = Option<(u64, Measurements)>;
+
+/// Maximum of CO2 and VOC over a sliding window, with optional decay.
+#[derive(Debug, Clone)]
+pub struct PeakHold<const N: usize> {
+    window: Window,
+    buckets: [Bucket; N],
+}
+
+impl<const N: usize> PeakHold<N> {
+    /// Create a peak-hold over the last `window_ms` without decay.
+    pub fn new(window_ms: u64) -> Self {
+        Self {
+            window: Window::new(window_ms, N),
+            buckets: [None; N],
+        }
+    }
+
+    /// Let the reported peak fall by at most `co2_per_min` (ppm) and `voc_per_min` (ppb) per
+    /// minute.
+    pub fn with_decay(mut self, co2_per_min: f32, voc_per_min: f32) -> Self {
+        self.window.set_decay(co2_per_min, voc_per_min);
+        self
+    }
+
+    /// Add measurements read at `timestamp_ms` and return the peak to display.
+    pub fn push(&mut self, timestamp_ms: u64, measurements: &Measurements) -> Measurements {
+        self.window
+            .push(&mut self.buckets, timestamp_ms, measurements)
+    }
+
+    /// The peak returned by the last [PeakHold::push()].
+    pub fn peak(&self) -> Option<Measurements> {
+        self.window.peak()
+    }
+
+    /// Forget all measurements.
+    pub fn clear(&mut self) {
+        self.buckets = [None; N];
+        self.window.displayed = None;
+    }
+
+    /// Use as [MeasurementFilter] stage, pushing the measurements at the time of `clock`.
+    pub fn clocked<C: Clock>(self, clock: C) -> ClockedPeakHold<C, N> {
+        ClockedPeakHold { peak: self, clock }
+    }
+}
+
+/// [PeakHold] as filter stage, created with [PeakHold::clocked()]. Passes on the peak of every
+/// measurement.
+#[derive(Debug, Clone)]
+pub struct ClockedPeakHold<C, const N: usize> {
+    peak: PeakHold<N>,
+    clock: C,
+}
+
+impl<C, const N: usize> ClockedPeakHold<C, N> {
+    /// The wrapped peak-hold.
+    pub fn peak_hold(&self) -> &PeakHold<N> {
+        &self.peak
+    }
+}
+
+impl<C: Clock, const N: usize> MeasurementFilter for ClockedPeakHold<C, N> {
+    fn update(&mut self, measurements: Measurements) -> Option<Measurements> {
+        Some(self.peak.push(self.clock.now_ms(), &measurements))
+    }
+}
+
+#[cfg(any(feature = "alloc", doc, test))]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+/// Heap-backed peak-hold whose number of buckets is set at runtime.
+///
+/// Same as [PeakHold], e.g. for a window resolution taken from a runtime configuration.
+#[derive(Debug, Clone)]
+pub struct VecPeakHold {
+    window: Window,
+    buckets: Vec<Bucket>,
+}
+
+#[cfg(any(feature = "alloc", doc, test))]
+impl VecPeakHold {
+    /// Create a peak-hold over the last `window_ms` split into `buckets` buckets, without
+    /// decay.
+    pub fn new(window_ms: u64, buckets: usize) -> Self {
+        Self {
+            window: Window::new(window_ms, buckets),
+            buckets: vec![None; buckets],
+        }
+    }
+
+    /// Let the reported peak decay, see [PeakHold::with_decay()].
+    pub fn with_decay(mut self, co2_per_min: f32, voc_per_min: f32) -> Self {
+        self.window.set_decay(co2_per_min, voc_per_min);
+        self
+    }
+
+    /// Add measurements read at `timestamp_ms` and return the peak to display.
+    pub fn push(&mut self, timestamp_ms: u64, measurements: &Measurements) -> Measurements {
+        self.window
+            .push(&mut self.buckets, timestamp_ms, measurements)
+    }
+
+    /// The peak returned by the last [VecPeakHold::push()].
+    pub fn peak(&self) -> Option<Measurements> {
+        self.window.peak()
+    }
+
+    /// Forget all measurements.
+    pub fn clear(&mut self) {
+        self.buckets.fill(None);
+        self.window.displayed = None;
+    }
+}
+
+/// Window state shared by the fixed and heap-backed peak-holds, working on their buckets.
+#[derive(Debug, Clone)]
+struct Window {
+    bucket_ms: u64,
+    decay_per_min: Option<(f32, f32)>,
+    displayed: Option<(u64, Measurements)>,
+}
+
+impl Window {
+    fn new(window_ms: u64, buckets: usize) -> Self {
+        Self {
+            bucket_ms: (window_ms / buckets.max(1) as u64).max(1),
+            decay_per_min: None,
+            displayed: None,
+        }
+    }
+
+    fn set_decay(&mut self, co2_per_min: f32, voc_per_min: f32) {
+        self.decay_per_min = Some((co2_per_min.max(0.0), voc_per_min.max(0.0)));
+    }
+
+    fn peak(&self) -> Option<Measurements> {
+        self.displayed.map(|(_, m)| m)
+    }
+
+    fn push(
+        &mut self,
+        buckets: &mut [Bucket],
+        timestamp_ms: u64,
+        measurements: &Measurements,
+    ) -> Measurements {
+        let bucket = timestamp_ms / self.bucket_ms;
+        let slot = bucket
+            .checked_rem(buckets.len() as u64)
+            .and_then(|i| buckets.get_mut(i as usize));
+        if let Some(slot) = slot {
+            *slot = match *slot {
+                Some((b, max)) if b == bucket => Some((bucket, channel_max(&max, measurements))),
+                _ => Some((bucket, *measurements)),
+            };
+        }
+
+        let window_max = window_max(buckets, bucket).unwrap_or(*measurements);
+        let peak = match (self.displayed, self.decay_per_min) {
+            (Some((last_ms, last)), Some((co2_per_min, voc_per_min))) => {
+                let minutes = timestamp_ms.saturating_sub(last_ms) as f32 / 60_000.0;
+                channel_max(
+                    &window_max,
+                    &Measurements {
+                        co2: last.co2 - co2_per_min * minutes,
+                        voc: last.voc - voc_per_min * minutes,
+                    },
+                )
+            }
+            _ => window_max,
+        };
+        self.displayed = Some((timestamp_ms, peak));
+        peak
+    }
+}
+
+fn window_max(buckets: &[Bucket], current: u64) -> Option<Measurements> {
+    let oldest = current.saturating_sub((buckets.len() as u64).saturating_sub(1));
+    buckets
+        .iter()
+        .flatten()
+        .filter(|(b, _)| (oldest..=current).contains(b))
+        .map(|(_, m)| *m)
+        .reduce(|a, b| channel_max(&a, &b))
+}
+
+/// Per channel maximum of both measurements.
+fn channel_max(a: &Measurements, b: &Measurements) -> Measurements {
+    Measurements {
+        co2: a.co2.max(b.co2),
+        voc: a.voc.max(b.voc),
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{PeakHold, VecPeakHold};
+    use crate::{filter::MeasurementFilter, Measurements};
+    use core::{assert_eq, cell::Cell};
+
+    fn m(co2: f32, voc: f32) -> Measurements {
+        Measurements { co2, voc }
+    }
+
+    #[test]
+    fn test_sliding_window() {
+        let mut peak = PeakHold::<4>::new(4000);
+
+        assert_eq!(peak.push(0, &m(500.0, 300.0)), m(500.0, 300.0));
+        assert_eq!(peak.push(1000, &m(600.0, 100.0)), m(600.0, 300.0));
+        assert_eq!(peak.push(3999, &m(450.0, 50.0)), m(600.0, 300.0));
+        // first bucket left the window
+        assert_eq!(peak.push(4000, &m(450.0, 50.0)), m(600.0, 100.0));
+        // after a gap only the current value is in the window
+        assert_eq!(peak.push(20_000, &m(420.0, 20.0)), m(420.0, 20.0));
+
+        peak.clear();
+        assert_eq!(peak.peak(), None);
+    }
+
+    #[test]
+    fn test_decay() {
+        let mut peak = PeakHold::<2>::new(60_000).with_decay(100.0, 60.0);
+
+        peak.push(0, &m(1000.0, 400.0));
+        // peak left the window, decays instead of dropping
+        assert_eq!(peak.push(90_000, &m(500.0, 100.0)), m(850.0, 310.0));
+        assert_eq!(peak.push(150_000, &m(500.0, 100.0)), m(750.0, 250.0));
+        assert_eq!(peak.push(600_000, &m(500.0, 100.0)), m(500.0, 100.0));
+    }
+
+    #[test]
+    fn test_vec_peak_hold() {
+        let mut fixed = PeakHold::<4>::new(4000).with_decay(100.0, 60.0);
+        let mut heap = VecPeakHold::new(4000, 4).with_decay(100.0, 60.0);
+        for (t, co2, voc) in [(0, 500.0, 300.0), (1000, 600.0, 100.0), (4000, 450.0, 50.0)] {
+            assert_eq!(heap.push(t, &m(co2, voc)), fixed.push(t, &m(co2, voc)));
+        }
+
+        let mut empty = VecPeakHold::new(4000, 0);
+        assert_eq!(empty.push(0, &m(500.0, 300.0)), m(500.0, 300.0));
+        empty.clear();
+        assert_eq!(empty.peak(), None);
+    }
+
+    #[test]
+    fn test_clocked_filter() {
+        let time = Cell::new(0);
+        let clock = || time.get();
+        let mut stage = PeakHold::<4>::new(4000).clocked(clock);
+
+        assert_eq!(stage.update(m(600.0, 300.0)), Some(m(600.0, 300.0)));
+        time.set(5000);
+        assert_eq!(stage.update(m(450.0, 50.0)), Some(m(450.0, 50.0)));
+        assert_eq!(stage.peak_hold().peak(), Some(m(450.0, 50.0)));
+    }
+}
@@ -0,0 +1,156 @@
+//! Transport independent protocol of the sensor.
+//!
+//! Requests are 6 byte command frames, responses are 7 byte frames. A frontend (like the
+//! blocking [MicsVz89Te](crate::MicsVz89Te)) only moves these frames over the bus and uses this
+//! module to build commands and to validate and decode responses. This way all frontends
+//! share the same command and parsing logic.
+
+use crate::{convert, error::PacketParseError, Measurements, RevisionDate};
+
+/// Size of a command frame in bytes.
+pub const COMMAND_LEN: usize = 6;
+/// Size of a response frame in bytes.
+pub const RESPONSE_LEN: usize = 7;
+
+const MICS_VZ_89TE_ADDR_CMD_GETSTATUS: u8 = 0x0C;
+const MICS_VZ_89TE_DATE_CODE: u8 = 0x0D;
+const MICS_VZ_89TE_GET_CALIBR_VAL: u8 = 0x10;
+pub(crate) const MICS_VZ_89TE_SET_CALIBR_PPM: u8 = 0x08;
+
+/// Commands understood by the sensor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Request CO2 and VOC measurements.
+    GetStatus,
+    /// Request the revision date.
+    GetRevision,
+    /// Request the calibration value R0.
+    GetCalibrationR0,
+    /// Write the raw calibration CO2 value. See [Command::set_calibration_ppm()].
+    SetCalibrationPpm(u8),
+}
+
+impl Command {
+    /// Command writing the calibration CO2 value in ppm. The value is clamped to
+    /// [CO2_RANGE_PPM](crate::CO2_RANGE_PPM).
+    pub fn set_calibration_ppm(ppm: f32) -> Self {
+        Self::SetCalibrationPpm(convert::calibration_byte(ppm))
+    }
+
+    /// Complete frame of the command including the checksum.
+    ///
+    /// Frames of commands without data are precomputed at compile time.
+    pub const fn frame(&self) -> [u8; COMMAND_LEN] {
+        match *self {
+            Self::GetStatus => GET_STATUS_FRAME,
+            Self::GetRevision => GET_REVISION_FRAME,
+            Self::GetCalibrationR0 => GET_CALIBRATION_R0_FRAME,
+            Self::SetCalibrationPpm(raw) => build_frame(MICS_VZ_89TE_SET_CALIBR_PPM, raw),
+        }
+    }
+}
+
+/// Frame of [Command::GetStatus].
+pub const GET_STATUS_FRAME: [u8; COMMAND_LEN] = build_frame(MICS_VZ_89TE_ADDR_CMD_GETSTATUS, 0);
+/// Frame of [Command::GetRevision].
+pub const GET_REVISION_FRAME: [u8; COMMAND_LEN] = build_frame(MICS_VZ_89TE_DATE_CODE, 0);
+/// Frame of [Command::GetCalibrationR0].
+pub const GET_CALIBRATION_R0_FRAME: [u8; COMMAND_LEN] = build_frame(MICS_VZ_89TE_GET_CALIBR_VAL, 0);
+
+const fn build_frame(cmd: u8, data: u8) -> [u8; COMMAND_LEN] {
+    [cmd, data, 0, 0, 0, gen_checksum(&[cmd, data, 0, 0, 0])]
+}
+
+/// Check the checksum of a response.
+pub fn check_response<E>(response: &[u8; RESPONSE_LEN]) -> Result<(), PacketParseError<E>> {
+    let check = gen_checksum(&response[..5]);
+    if response[6].ne(&check) {
+        return Err(PacketParseError::WrongChecksum);
+    }
+    Ok(())
+}
+
+/// Decode the response to [Command::GetStatus].
+pub fn decode_measurements(response: &[u8; RESPONSE_LEN]) -> Measurements {
+    Measurements::from_response(response)
+}
+
+/// Decode the raw sensor resistance in Ohms from the response to [Command::GetStatus].
+///
+/// The MICS-VZ-89TE datasheet (I2C commands, "getStatus") gives the resistance as
+/// `Rs = 10 * (D5 + 256 * D4 + 65536 * D3)` with the data bytes `D3..=D5` at response bytes
+/// `2..=4`, i.e. most significant byte first. The older MICS-VZ-89 sends it least significant
+/// byte first, see [legacy::decode_resistance()](crate::legacy::decode_resistance()).
+pub fn decode_resistance(response: &[u8; RESPONSE_LEN]) -> u32 {
+    10 * u32::from_be_bytes([0, response[2], response[3], response[4]])
+}
+
+/// Decode the response to [Command::GetRevision].
+pub fn decode_revision(response: &[u8; RESPONSE_LEN]) -> RevisionDate {
+    RevisionDate {
+        year: u16::from(response[0]) + 2000,
+        month: response[1],
+        day: response[2],
+    }
+}
+
+/// Decode the response to [Command::GetCalibrationR0] in kOhms.
+pub fn decode_calibration_r0(response: &[u8; RESPONSE_LEN]) -> u16 {
+    u16::from_le_bytes([response[0], response[1]])
+}
+
+pub(crate) const fn gen_checksum(byte_array: &[u8]) -> u8 {
+    // low and high byte of the sum, kept in 8 bits each for 8-bit targets
+    let mut low = 0u8;
+    let mut high = 0u8;
+    let mut i = 0;
+    while i < byte_array.len() {
+        let (sum, carry) = low.overflowing_add(byte_array[i]);
+        low = sum;
+        high = high.wrapping_add(carry as u8);
+        i += 1;
+    }
+    0xFF - low.wrapping_add(high)
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{check_response, Command, GET_STATUS_FRAME};
+    use crate::error::PacketParseError;
+    use assert_matches::assert_matches;
+    use core::assert_eq;
+
+    #[test]
+    fn test_command_frames() {
+        assert_eq!(GET_STATUS_FRAME, [0x0C, 0, 0, 0, 0, 0xF3]);
+        assert_eq!(Command::GetStatus.frame(), [0x0C, 0, 0, 0, 0, 0xF3]);
+        assert_eq!(Command::GetRevision.frame(), [0x0D, 0, 0, 0, 0, 0xF2]);
+        assert_eq!(Command::GetCalibrationR0.frame(), [0x10, 0, 0, 0, 0, 0xEF]);
+        assert_eq!(
+            Command::set_calibration_ppm(1000.0).frame(),
+            [0x08, 0x62, 0, 0, 0, 0x95]
+        );
+    }
+
+    #[test]
+    fn test_decode_resistance() {
+        let response = [0x27, 0x3C, 0x01, 0x86, 0xA0, 0, 0];
+        assert_eq!(super::decode_resistance(&response), 1_000_000);
+        // D3 is the most significant byte
+        let response = [0x27, 0x3C, 0x01, 0x02, 0x03, 0, 0];
+        assert_eq!(super::decode_resistance(&response), 10 * 0x01_02_03);
+    }
+
+    #[test]
+    fn test_check_response() {
+        let response = [0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27];
+        assert_matches!(check_response::<()>(&response), Ok(()));
+
+        let response = [0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x26];
+        assert_matches!(
+            check_response::<()>(&response),
+            Err(PacketParseError::WrongChecksum)
+        );
+    }
+}
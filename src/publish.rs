@@ -0,0 +1,238 @@
+//! Decoupling of sampling from consumers.
+//!
+//! [Publisher] reads the sensor at a configured rate and hands each result to a [Sink]. A sink
+//! can be any `FnMut` (e.g. sending into an `embassy_sync` channel with `try_send`) or a
+//! [Watch], which keeps the latest result for any number of consumers. With the `embassy`
+//! feature, `embassy_sync` watches and pub/sub channels are sinks too, and
+//! `embassy::publish_task()` runs the sampling on the embassy timer.
+//!
+//! # Example Usage
+//! ```ignore
+//! let latest = Watch::new();
+//!
+//! // sampling
+//! let mut publisher = Publisher::new(5000);
+//! loop {
+//!     publisher.poll(clock.now_ms(), &mut device, &mut delay, &latest);
+//! }
+//!
+//! // consumer (display, MQTT, alarm, ...)
+//! let mut receiver = WatchReceiver::new();
+//! if let Some(sample) = receiver.changed(&latest) {
+//!     show(sample);
+//! }
+//! ```
+
+use core::cell::Cell;
+
+use crate::{
+    clock::{self, Clock, Timestamped},
+    delay::Delay,
+    event::Fault,
+    transport::Transport,
+    Measurements, MicsVz89Te,
+};
+
+/// Result of one read: the measurements or the fault, with the time (in millis) of the read.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    pub timestamp_ms: u64,
+    pub result: Result<Measurements, Fault>,
+}
+
+impl Sample {
+    /// Time (in millis) since the sample was read.
+    pub fn age(&self, clock: &impl Clock) -> u64 {
+        clock::age(self.timestamp_ms, clock)
+    }
+
+    /// Returns `true` if the sample is older than `max_age_ms`.
+    pub fn is_stale(&self, clock: &impl Clock, max_age_ms: u64) -> bool {
+        self.age(clock) > max_age_ms
+    }
+}
+
+/// Receiver of published samples.
+pub trait Sink {
+    /// Publish a sample.
+    fn publish(&mut self, sample: Sample);
+}
+
+impl<F> Sink for F
+where
+    F: FnMut(Sample),
+{
+    fn publish(&mut self, sample: Sample) {
+        self(sample)
+    }
+}
+
+impl Sink for &Watch {
+    fn publish(&mut self, sample: Sample) {
+        self.send(sample)
+    }
+}
+
+/// Reads the sensor at a fixed rate and publishes the results.
+#[derive(Debug, Clone)]
+pub struct Publisher {
+    interval_ms: u64,
+    next_ms: u64,
+}
+
+impl Publisher {
+    /// Create a publisher reading every `interval_ms`.
+    pub const fn new(interval_ms: u64) -> Self {
+        Self {
+            interval_ms,
+            next_ms: 0,
+        }
+    }
+
+    /// Interval of the reads in millis.
+    pub fn interval_ms(&self) -> u64 {
+        self.interval_ms
+    }
+
+    /// Read and publish a sample if it is due at `now_ms`. Returns `true` if a sample was
+    /// published.
+    pub fn poll<I2C, E>(
+        &mut self,
+        now_ms: u64,
+        driver: &mut MicsVz89Te<I2C>,
+        delay: &mut impl Delay,
+        mut sink: impl Sink,
+    ) -> bool
+    where
+        I2C: Transport<Error = E>,
+    {
+        if now_ms < self.next_ms {
+            return false;
+        }
+        self.next_ms = now_ms.saturating_add(self.interval_ms);
+        let result = driver.read_measurements(delay).map_err(|e| Fault::from(&e));
+        sink.publish(Sample {
+            timestamp_ms: now_ms,
+            result,
+        });
+        true
+    }
+}
+
+/// Single slot holding the latest published [Sample].
+///
+/// Additionally the latest successful measurements are kept, so consumers can show the last
+/// known value and its age during faults, see [Watch::last_measurements()].
+///
+/// The watch isn't `Sync`; to share it between interrupts or threads put it e.g. inside a
+/// critical section mutex.
+#[derive(Debug, Default)]
+pub struct Watch {
+    latest: Cell<Option<Sample>>,
+    last_measurements: Cell<Option<Timestamped<Measurements>>>,
+    version: Cell<u32>,
+}
+
+impl Watch {
+    /// Create an empty watch.
+    pub const fn new() -> Self {
+        Self {
+            latest: Cell::new(None),
+            last_measurements: Cell::new(None),
+            version: Cell::new(0),
+        }
+    }
+
+    /// Replace the latest sample.
+    pub fn send(&self, sample: Sample) {
+        self.latest.set(Some(sample));
+        if let Ok(m) = sample.result {
+            self.last_measurements
+                .set(Some(Timestamped::new(sample.timestamp_ms, m)));
+        }
+        self.version.set(self.version.get().wrapping_add(1));
+    }
+
+    /// The latest sample.
+    pub fn get(&self) -> Option<Sample> {
+        self.latest.get()
+    }
+
+    /// The latest successfully read measurements, which may be older than the latest sample.
+    pub fn last_measurements(&self) -> Option<Timestamped<Measurements>> {
+        self.last_measurements.get()
+    }
+}
+
+/// Consumer side of a [Watch], remembering which sample it has seen.
+#[derive(Debug, Clone, Default)]
+pub struct WatchReceiver {
+    seen: u32,
+}
+
+impl WatchReceiver {
+    /// Create a receiver which hasn't seen any sample.
+    pub const fn new() -> Self {
+        Self { seen: 0 }
+    }
+
+    /// Returns the latest sample of `watch` if it wasn't returned before.
+    pub fn changed(&mut self, watch: &Watch) -> Option<Sample> {
+        let version = watch.version.get();
+        if version == self.seen {
+            return None;
+        }
+        self.seen = version;
+        watch.get()
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{Publisher, Sample, Watch, WatchReceiver};
+    use crate::{event::Fault, MicsVz89Te};
+    use core::assert_eq;
+    use embedded_hal_mock::{
+        delay::MockNoop as DelayMock,
+        i2c::{Mock as I2cMock, Transaction as I2cTransaction},
+    };
+    use std::{vec, vec::Vec};
+
+    #[test]
+    fn test_publish() {
+        let expectations = [
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x26]),
+        ];
+        let mut device = MicsVz89Te::new(I2cMock::new(&expectations));
+        let mut delay = DelayMock::new();
+        let mut publisher = Publisher::new(1000);
+        let mut samples = Vec::new();
+
+        assert!(publisher.poll(0, &mut device, &mut delay, |s: Sample| samples.push(s)));
+        assert!(!publisher.poll(999, &mut device, &mut delay, |s: Sample| samples.push(s)));
+
+        let watch = Watch::new();
+        let mut receiver = WatchReceiver::new();
+        assert_eq!(receiver.changed(&watch), None);
+        assert!(publisher.poll(1000, &mut device, &mut delay, &watch));
+
+        assert_eq!(samples.len(), 1);
+        assert!(samples[0].result.is_ok());
+        let sample = receiver.changed(&watch).unwrap();
+        assert_eq!(sample.timestamp_ms, 1000);
+        assert_eq!(sample.result, Err(Fault::WrongChecksum));
+        assert_eq!(receiver.changed(&watch), None);
+
+        watch.send(samples[0]);
+        watch.send(sample);
+        let clock = || 61_000;
+        let last = watch.last_measurements().unwrap();
+        assert_eq!(last.age(&clock), 61_000);
+        assert!(last.is_stale(&clock, 60_000));
+        assert!(!sample.is_stale(&clock, 60_000));
+    }
+}
@@ -0,0 +1,123 @@
+//! Decoding of the VOC value from the PWM output of the sensor.
+//!
+//! The MICS-VZ-89TE datasheet (section "PWM output") specifies the tVOC output as a duty cycle
+//! rising linearly from [DUTY_MIN] (5 %, 0 ppb) to [DUTY_MAX] (95 %, 1000 ppb isobutylene
+//! equivalent). The duty cycle is mapped onto [RAW_RANGE] of the I2C status response, so the
+//! pulse durations measured by the user (e.g. with an input-capture timer) are converted and
+//! validated with the same code as [Measurements::voc](crate::Measurements).
+//!
+//! # Example Usage
+//! ```ignore
+//! let reader = PwmVocReader::new();
+//! let (high_us, low_us) = capture.measure_pulse();
+//! let voc_ppb = reader.voc_ppb(high_us, low_us)?;
+//! ```
+
+use crate::{raw_to_voc, RAW_RANGE};
+
+/// Duty cycle of the lower end of the VOC range (0 ppb).
+pub const DUTY_MIN: f32 = 0.05;
+/// Duty cycle of the upper end of the VOC range (1000 ppb).
+pub const DUTY_MAX: f32 = 0.95;
+
+/// Errors which can occur while decoding a PWM measurement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PwmError {
+    /// The period (high + low duration) is zero.
+    InvalidPeriod,
+    /// The duty cycle is outside of the range the sensor outputs (see [RAW_RANGE]).
+    OutOfRange,
+}
+
+/// Converts measured pulse durations of the PWM output into VOC values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PwmVocReader {
+    tolerance: f32,
+}
+
+impl Default for PwmVocReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PwmVocReader {
+    /// Create a reader accepting a deviation of up to one raw step from [RAW_RANGE].
+    pub const fn new() -> Self {
+        Self { tolerance: 1.0 }
+    }
+
+    /// Accept duty cycles deviating up to `raw_steps` (one step is `1 / 229` of the range from
+    /// [DUTY_MIN] to [DUTY_MAX]) from [RAW_RANGE], to allow for timer jitter. Values within the tolerance are clamped.
+    pub const fn with_tolerance(mut self, raw_steps: f32) -> Self {
+        self.tolerance = raw_steps;
+        self
+    }
+
+    /// Raw VOC value (see [RAW_RANGE]) for the measured high and low durations, given in any
+    /// but the same unit.
+    pub fn raw(&self, high: u32, low: u32) -> Result<f32, PwmError> {
+        let period = u64::from(high) + u64::from(low);
+        if period == 0 {
+            return Err(PwmError::InvalidPeriod);
+        }
+        let duty = high as f32 / period as f32;
+        let min = f32::from(*RAW_RANGE.start());
+        let max = f32::from(*RAW_RANGE.end());
+        let raw = min + (duty - DUTY_MIN) / (DUTY_MAX - DUTY_MIN) * (max - min);
+        if raw < min - self.tolerance || raw > max + self.tolerance {
+            return Err(PwmError::OutOfRange);
+        }
+        Ok(raw.clamp(min, max))
+    }
+
+    /// VOC value (in ppb) for the measured high and low durations, given in any but the same
+    /// unit.
+    pub fn voc_ppb(&self, high: u32, low: u32) -> Result<f32, PwmError> {
+        self.raw(high, low).map(raw_to_voc)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{PwmError, PwmVocReader};
+    use crate::Measurements;
+    use core::assert_eq;
+
+    #[test]
+    fn test_datasheet_points() {
+        let reader = PwmVocReader::new();
+        let close = |high, low, ppb: f32| (reader.voc_ppb(high, low).unwrap() - ppb).abs() < 1e-2;
+        // 5 %, 50 % and 95 % duty cycle
+        assert!(close(50, 950, 0.0));
+        assert!(close(500, 500, 500.0));
+        assert!(close(950, 50, 1000.0));
+    }
+
+    #[test]
+    fn test_matches_i2c_conversion() {
+        let reader = PwmVocReader::new();
+        // raw 0x27 (39) is 26 of 229 steps above 5 %: 5 % + 26 * 0.9 / 229
+        let voc = reader.voc_ppb(11_450 + 26 * 900, 229_000 - 11_450 - 26 * 900);
+        let response = [0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27];
+        let expected = Measurements::from_response(&response).voc;
+        assert!((voc.unwrap() - expected).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_invalid_pulses() {
+        let reader = PwmVocReader::new();
+        assert_eq!(reader.voc_ppb(0, 0), Err(PwmError::InvalidPeriod));
+        assert_eq!(reader.voc_ppb(0, 100), Err(PwmError::OutOfRange));
+        assert_eq!(reader.voc_ppb(100, 0), Err(PwmError::OutOfRange));
+        // 4.8 % is half a step below the range, clamped to the minimum
+        assert_eq!(reader.voc_ppb(48, 952), Ok(0.0));
+        assert_eq!(
+            reader.with_tolerance(0.0).voc_ppb(48, 952),
+            Err(PwmError::OutOfRange)
+        );
+        // 4.5 % is more than a step below
+        assert_eq!(reader.voc_ppb(45, 955), Err(PwmError::OutOfRange));
+    }
+}
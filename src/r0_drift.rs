@@ -0,0 +1,376 @@
+//! Estimation of the long-term drift of the calibration value R0.
+//!
+//! The clean-air resistance R0 of the sensor (see
+//! [MicsVz89Te::read_calibration_r0()](crate::MicsVz89Te::read_calibration_r0())) slowly drifts
+//! as the sensor ages. [R0History] keeps periodic R0 readings, which can be persisted as
+//! [R0Record]s, and fits a line through them. From the drift rate it projects when the deviation
+//! from the reference requires a recalibration and when R0 leaves the usable range, i.e. the
+//! sensor should be replaced. The estimate can be attached to the [Diagnostics] report.
+//!
+//! The reference is a fixed record, the first one after creating or clearing the history (e.g.
+//! at calibration). It isn't dropped with the oldest readings, so it has to be persisted
+//! together with them.
+//!
+//! # Example Usage
+//! ```ignore
+//! // after a calibration
+//! history.clear();
+//! let reference = history.record(&mut device, &mut delay, &clock)?;
+//! flash.store_reference(&reference.to_bytes());
+//!
+//! // once a day
+//! history.record(&mut device, &mut delay, &clock)?;
+//! flash.store(&history.latest().unwrap().to_bytes());
+//!
+//! // after a restart
+//! let mut history = R0History::with_reference(flash.reference(), flash.records());
+//!
+//! let drift = history.drift(&DriftLimits::default());
+//! report(device.diagnostics().with_r0_drift(drift));
+//! ```
+
+#[cfg(any(feature = "unproven", doc, test))]
+#[cfg(any(feature = "unproven", doc, test))]
+use crate::{
+    clock::Clock, delay::Delay, error::PacketParseError, transport::Transport, MicsVz89Te,
+};
+use crate::{diagnostics::Diagnostics, math};
+
+const MS_PER_DAY: f32 = 24.0 * 60.0 * 60.0 * 1000.0;
+
+/// An R0 reading with the time (in millis) it was taken at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct R0Record {
+    pub timestamp_ms: u64,
+    pub r0_kohm: u16,
+}
+
+impl R0Record {
+    /// Size of a serialized record in bytes.
+    pub const ENCODED_LEN: usize = 10;
+
+    /// Serialize the record: timestamp as `u64` LE, R0 as `u16` LE.
+    pub fn to_bytes(&self) -> [u8; Self::ENCODED_LEN] {
+        let mut bytes = [0; Self::ENCODED_LEN];
+        bytes[..8].copy_from_slice(&self.timestamp_ms.to_le_bytes());
+        bytes[8..].copy_from_slice(&self.r0_kohm.to_le_bytes());
+        bytes
+    }
+
+    /// Deserialize a record written by [R0Record::to_bytes()].
+    pub fn from_bytes(bytes: &[u8; Self::ENCODED_LEN]) -> Self {
+        let [t @ .., r0_l, r0_h] = *bytes;
+        Self {
+            timestamp_ms: u64::from_le_bytes(t),
+            r0_kohm: u16::from_le_bytes([r0_l, r0_h]),
+        }
+    }
+}
+
+/// Limits the drift projection is based on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DriftLimits {
+    /// Deviation from the reference R0 in permille which requires a recalibration.
+    pub recalibration_permille: u16,
+    /// Lowest usable R0 in kOhms.
+    pub min_r0_kohm: u16,
+    /// Highest usable R0 in kOhms.
+    pub max_r0_kohm: u16,
+}
+
+impl Default for DriftLimits {
+    fn default() -> Self {
+        Self {
+            recalibration_permille: 200,
+            min_r0_kohm: 50,
+            max_r0_kohm: 2000,
+        }
+    }
+}
+
+/// Estimated drift of R0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct R0Drift {
+    /// Drift rate in kOhms per day.
+    pub kohm_per_day: f32,
+    /// Drift rate relative to the reference R0 in permille per day.
+    pub permille_per_day: f32,
+    /// Days from the last record until a recalibration is needed. `0.0` if it is already due,
+    /// `None` if R0 doesn't drift away from the reference.
+    pub days_until_recalibration: Option<f32>,
+    /// Days from the last record until R0 leaves the usable range. `0.0` if it already left,
+    /// `None` if R0 doesn't drift towards a limit.
+    pub days_until_replacement: Option<f32>,
+}
+
+/// The last `N` R0 readings, oldest first, and the reference reading, e.g. taken after the last
+/// calibration.
+#[derive(Debug, Clone)]
+pub struct R0History<const N: usize> {
+    records: [Option<R0Record>; N],
+    start: usize,
+    len: usize,
+    reference: Option<R0Record>,
+}
+
+impl<const N: usize> Default for R0History<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> R0History<N> {
+    /// Create an empty history.
+    pub const fn new() -> Self {
+        Self {
+            records: [None; N],
+            start: 0,
+            len: 0,
+            reference: None,
+        }
+    }
+
+    /// Create a history from persisted records, keeping the last `N`. The first record becomes
+    /// the reference.
+    pub fn from_records(records: impl IntoIterator<Item = R0Record>) -> Self {
+        let mut history = Self::new();
+        for record in records {
+            history.push(record);
+        }
+        history
+    }
+
+    /// Create a history from a persisted reference and records, keeping the last `N` records.
+    pub fn with_reference(
+        reference: R0Record,
+        records: impl IntoIterator<Item = R0Record>,
+    ) -> Self {
+        let mut history = Self::new();
+        history.reference = Some(reference);
+        for record in records {
+            history.push(record);
+        }
+        history
+    }
+
+    /// Append a record. If the history is full, the oldest record is dropped. If no reference
+    /// is set, the record becomes the reference.
+    pub fn push(&mut self, record: R0Record) {
+        self.reference.get_or_insert(record);
+        if N == 0 {
+            return;
+        }
+        if self.len < N {
+            self.records[(self.start + self.len) % N] = Some(record);
+            self.len += 1;
+        } else {
+            self.records[self.start] = Some(record);
+            self.start = (self.start + 1) % N;
+        }
+    }
+
+    /// Remove all records and the reference, e.g. after a recalibration. The next record becomes
+    /// the new reference.
+    pub fn clear(&mut self) {
+        self.start = 0;
+        self.len = 0;
+        self.reference = None;
+    }
+
+    /// The reference record the drift is measured against.
+    pub fn reference(&self) -> Option<&R0Record> {
+        self.reference.as_ref()
+    }
+
+    /// Number of stored records.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no record is stored.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Iterate over the stored records from oldest to newest.
+    pub fn iter(&self) -> impl Iterator<Item = &R0Record> + Clone {
+        (0..self.len).filter_map(move |i| self.records[(self.start + i) % N].as_ref())
+    }
+
+    /// The most recent record.
+    pub fn latest(&self) -> Option<&R0Record> {
+        self.iter().last()
+    }
+
+    /// Estimate the drift of the stored records against the reference. Needs at least 3
+    /// records at different times.
+    pub fn drift(&self, limits: &DriftLimits) -> Option<R0Drift> {
+        let reference = self.reference.as_ref()?;
+        let points = self.iter().map(|r| {
+            let days = r.timestamp_ms.saturating_sub(reference.timestamp_ms) as f32 / MS_PER_DAY;
+            (days, f32::from(r.r0_kohm))
+        });
+        let fit = math::linear_fit_xy(points)?;
+        let reference_kohm = f32::from(reference.r0_kohm);
+
+        let allowed = reference_kohm * f32::from(limits.recalibration_permille) / 1000.0;
+        let deviation = fit.last - reference_kohm;
+        let days_until_recalibration = if deviation.abs() >= allowed {
+            Some(0.0)
+        } else if fit.slope > 0.0 {
+            Some((reference_kohm + allowed - fit.last) / fit.slope)
+        } else if fit.slope < 0.0 {
+            Some((reference_kohm - allowed - fit.last) / fit.slope)
+        } else {
+            None
+        };
+
+        let min = f32::from(limits.min_r0_kohm);
+        let max = f32::from(limits.max_r0_kohm);
+        let days_until_replacement = if fit.last <= min || fit.last >= max {
+            Some(0.0)
+        } else if fit.slope > 0.0 {
+            Some((max - fit.last) / fit.slope)
+        } else if fit.slope < 0.0 {
+            Some((min - fit.last) / fit.slope)
+        } else {
+            None
+        };
+
+        Some(R0Drift {
+            kohm_per_day: fit.slope,
+            permille_per_day: if reference_kohm > 0.0 {
+                fit.slope / reference_kohm * 1000.0
+            } else {
+                0.0
+            },
+            days_until_recalibration,
+            days_until_replacement,
+        })
+    }
+
+    #[cfg(any(feature = "unproven", doc, test))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "unproven")))]
+    /// Read R0 from the sensor and append it with the current time.
+    ///
+    /// This function blocks a minimum time of [MicsVz89Te::WAIT_ON_RESPONSE_TIME].
+    pub fn record<I2C, E>(
+        &mut self,
+        driver: &mut MicsVz89Te<I2C>,
+        delay: &mut impl Delay,
+        clock: &impl Clock,
+    ) -> Result<R0Record, PacketParseError<E>>
+    where
+        I2C: Transport<Error = E>,
+    {
+        let record = R0Record {
+            timestamp_ms: clock.now_ms(),
+            r0_kohm: driver.read_calibration_r0(delay)?,
+        };
+        self.push(record);
+        Ok(record)
+    }
+}
+
+impl Diagnostics {
+    /// Attach an R0 drift estimate, see [R0History::drift()].
+    pub fn with_r0_drift(mut self, r0_drift: Option<R0Drift>) -> Self {
+        self.r0_drift = r0_drift;
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{DriftLimits, R0History, R0Record};
+    use crate::MicsVz89Te;
+    use core::assert_eq;
+    use embedded_hal_mock::{
+        delay::MockNoop as DelayMock,
+        i2c::{Mock as I2cMock, Transaction as I2cTransaction},
+    };
+    use std::vec;
+
+    const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+    fn record(day: u64, r0_kohm: u16) -> R0Record {
+        R0Record {
+            timestamp_ms: day * DAY_MS,
+            r0_kohm,
+        }
+    }
+
+    #[test]
+    fn test_drift_projection() {
+        let history = R0History::<8>::from_records([
+            record(0, 500),
+            record(10, 490),
+            record(20, 480),
+            record(40, 460),
+        ]);
+        let drift = history.drift(&DriftLimits::default()).unwrap();
+
+        assert!((drift.kohm_per_day + 1.0).abs() < 1e-4);
+        assert!((drift.permille_per_day + 2.0).abs() < 1e-4);
+        // recalibration at 400 kOhm, replacement at 50 kOhm
+        assert!((drift.days_until_recalibration.unwrap() - 60.0).abs() < 1e-2);
+        assert!((drift.days_until_replacement.unwrap() - 410.0).abs() < 1e-1);
+
+        let stable = R0History::<4>::from_records([record(0, 500), record(1, 500), record(2, 500)]);
+        let drift = stable.drift(&DriftLimits::default()).unwrap();
+        assert_eq!(drift.days_until_recalibration, None);
+        assert_eq!(drift.days_until_replacement, None);
+
+        assert!(
+            R0History::<4>::from_records([record(0, 500), record(1, 400)])
+                .drift(&DriftLimits::default())
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_fixed_reference() {
+        let mut history = R0History::<3>::new();
+        for (day, r0_kohm) in [(0, 500), (10, 490), (20, 480), (30, 470), (40, 460)] {
+            history.push(record(day, r0_kohm));
+        }
+        // the reference stays after the ring wrapped
+        assert_eq!(history.iter().next(), Some(&record(20, 480)));
+        assert_eq!(history.reference(), Some(&record(0, 500)));
+        let drift = history.drift(&DriftLimits::default()).unwrap();
+        assert!((drift.permille_per_day + 2.0).abs() < 1e-4);
+        assert!((drift.days_until_recalibration.unwrap() - 60.0).abs() < 1e-2);
+
+        let restored =
+            R0History::<3>::with_reference(*history.reference().unwrap(), history.iter().copied());
+        assert_eq!(restored.drift(&DriftLimits::default()), Some(drift));
+
+        history.clear();
+        history.push(record(50, 450));
+        assert_eq!(history.reference(), Some(&record(50, 450)));
+    }
+
+    #[test]
+    fn test_record_and_diagnostics() {
+        let expectations = [
+            I2cTransaction::write(0x70, vec![0x10, 0, 0, 0, 0, 0xEF]),
+            I2cTransaction::read(0x70, vec![0xFB, 0x01, 0, 0, 0, 0, 0x03]),
+        ];
+        let mut device = MicsVz89Te::new(I2cMock::new(&expectations));
+        let mut history = R0History::<2>::from_records([record(0, 700), record(1, 600)]);
+
+        let new = history
+            .record(&mut device, &mut DelayMock::new(), &|| 2 * DAY_MS)
+            .unwrap();
+        assert_eq!(new, record(2, 507));
+        assert_eq!(history.len(), 2);
+        assert_eq!(R0Record::from_bytes(&new.to_bytes()), new);
+
+        let drift = history.drift(&DriftLimits::default());
+        assert!(drift.is_none());
+        let diagnostics = device.diagnostics().with_r0_drift(drift);
+        assert_eq!(diagnostics.r0_drift, None);
+        device.release().done();
+    }
+}
@@ -0,0 +1,216 @@
+//! Scheduled automatic fresh-air recalibration.
+//!
+//! The sensor is recalibrated by writing a reference CO2 value (e.g. the outdoor level) while it
+//! is exposed to fresh air. [RecalibrationScheduler] triggers this periodically or on request,
+//! but only if the recent measurements are stable and within sane bounds.
+//!
+//! # Example Usage
+//! ```ignore
+//! let mut scheduler = RecalibrationScheduler::new(RecalibrationConfig::default(), clock.now_ms());
+//! let mut history = History::<60>::new();
+//! loop {
+//!     history.push(device.read_measurements(&mut delay)?);
+//!     match scheduler.poll(clock.now_ms(), &history, &mut device)? {
+//!         RecalibrationOutcome::Calibrated { ppm } => log_calibration(ppm),
+//!         _ => {}
+//!     }
+//! }
+//! ```
+
+use crate::{
+    config::CalibrationMethod, error::PacketParseError, history::History, transport::Transport,
+    MicsVz89Te,
+};
+
+/// Configuration of [RecalibrationScheduler].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecalibrationConfig {
+    /// Time (in millis) between two automatic recalibrations.
+    pub interval_ms: u64,
+    /// CO2 value (in ppm) written to the sensor, i.e. the CO2 level of fresh air.
+    pub reference_ppm: f32,
+    /// Minimum number of recent measurements required.
+    pub min_samples: usize,
+    /// Maximum difference (in ppm) between the highest and lowest recent CO2 value.
+    pub max_spread_ppm: f32,
+    /// Mean of the recent CO2 values (in ppm) must be at least this value.
+    pub min_ppm: f32,
+    /// Mean of the recent CO2 values (in ppm) must be at most this value.
+    pub max_ppm: f32,
+}
+
+impl Default for RecalibrationConfig {
+    fn default() -> Self {
+        Self {
+            interval_ms: 7 * 24 * 60 * 60 * 1000,
+            reference_ppm: 420.0,
+            min_samples: 60,
+            max_spread_ppm: 50.0,
+            min_ppm: 400.0,
+            max_ppm: 600.0,
+        }
+    }
+}
+
+/// Reason why a due recalibration was skipped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// Less than [RecalibrationConfig::min_samples] recent measurements.
+    NotEnoughData,
+    /// The recent CO2 values spread more than [RecalibrationConfig::max_spread_ppm].
+    Unstable,
+    /// The mean of the recent CO2 values is outside the configured bounds.
+    OutOfBounds,
+}
+
+/// Result of [RecalibrationScheduler::poll()].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecalibrationOutcome {
+    /// No recalibration is due.
+    NotDue,
+    /// A recalibration is due but the safeguards prevented it. It is retried on the next poll.
+    Skipped(SkipReason),
+    /// The reference value was written to the sensor.
+    Calibrated { ppm: f32 },
+}
+
+/// Triggers fresh-air recalibrations periodically or on request.
+#[derive(Debug, Clone)]
+pub struct RecalibrationScheduler {
+    config: RecalibrationConfig,
+    last_ms: u64,
+    requested: bool,
+}
+
+impl RecalibrationScheduler {
+    /// Create a scheduler whose first automatic recalibration is due one interval after `now_ms`.
+    pub fn new(config: RecalibrationConfig, now_ms: u64) -> Self {
+        Self {
+            config,
+            last_ms: now_ms,
+            requested: false,
+        }
+    }
+
+    /// Configuration of the scheduler.
+    pub fn config(&self) -> &RecalibrationConfig {
+        &self.config
+    }
+
+    /// Make a recalibration due on the next poll, regardless of the interval.
+    pub fn request(&mut self) {
+        self.requested = true;
+    }
+
+    /// Returns `true` if a recalibration is due at `now_ms`.
+    pub fn is_due(&self, now_ms: u64) -> bool {
+        self.requested || now_ms.saturating_sub(self.last_ms) >= self.config.interval_ms
+    }
+
+    /// Check whether the recent measurements allow a recalibration.
+    pub fn check<const N: usize>(&self, recent: &History<N>) -> Result<(), SkipReason> {
+        let summary = match recent.summary() {
+            Some(s) if recent.len() >= self.config.min_samples => s,
+            _ => return Err(SkipReason::NotEnoughData),
+        };
+        if summary.max.co2 - summary.min.co2 > self.config.max_spread_ppm {
+            return Err(SkipReason::Unstable);
+        }
+        if summary.mean.co2 < self.config.min_ppm || summary.mean.co2 > self.config.max_ppm {
+            return Err(SkipReason::OutOfBounds);
+        }
+        Ok(())
+    }
+
+    /// Recalibrate the sensor if a recalibration is due and the recent measurements pass the
+    /// safeguards.
+    pub fn poll<I2C, E, const N: usize>(
+        &mut self,
+        now_ms: u64,
+        recent: &History<N>,
+        driver: &mut MicsVz89Te<I2C>,
+    ) -> Result<RecalibrationOutcome, PacketParseError<E>>
+    where
+        I2C: Transport<Error = E>,
+    {
+        if !self.is_due(now_ms) {
+            return Ok(RecalibrationOutcome::NotDue);
+        }
+        if let Err(reason) = self.check(recent) {
+            return Ok(RecalibrationOutcome::Skipped(reason));
+        }
+        driver.write_calibration(
+            self.config.reference_ppm,
+            CalibrationMethod::FreshAir,
+            &|| now_ms,
+        )?;
+        self.last_ms = now_ms;
+        self.requested = false;
+        Ok(RecalibrationOutcome::Calibrated {
+            ppm: self.config.reference_ppm,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{RecalibrationConfig, RecalibrationOutcome, RecalibrationScheduler, SkipReason};
+    use crate::{history::History, Measurements, MicsVz89Te};
+    use assert_matches::assert_matches;
+    use core::assert_eq;
+    use embedded_hal_mock::i2c::{Mock as I2cMock, Transaction as I2cTransaction};
+    use std::vec;
+
+    fn history(co2: &[f32]) -> History<4> {
+        let mut history = History::new();
+        co2.iter().for_each(|co2| {
+            history.push(Measurements {
+                co2: *co2,
+                voc: 0.0,
+            })
+        });
+        history
+    }
+
+    #[test]
+    fn test_recalibration_schedule() {
+        let expectations = [I2cTransaction::write(0x70, vec![0x08, 0x0F, 0, 0, 0, 0xE8])];
+        let mut device = MicsVz89Te::new(I2cMock::new(&expectations));
+        let config = RecalibrationConfig {
+            interval_ms: 1000,
+            min_samples: 3,
+            ..RecalibrationConfig::default()
+        };
+        let mut scheduler = RecalibrationScheduler::new(config, 0);
+        let stable = history(&[430.0, 440.0, 435.0]);
+
+        assert_matches!(
+            scheduler.poll(500, &stable, &mut device),
+            Ok(RecalibrationOutcome::NotDue)
+        );
+        assert_matches!(
+            scheduler.poll(1000, &history(&[430.0, 440.0]), &mut device),
+            Ok(RecalibrationOutcome::Skipped(SkipReason::NotEnoughData))
+        );
+        assert_matches!(
+            scheduler.poll(1000, &history(&[430.0, 500.0, 435.0]), &mut device),
+            Ok(RecalibrationOutcome::Skipped(SkipReason::Unstable))
+        );
+        assert_matches!(
+            scheduler.poll(1000, &history(&[900.0, 910.0, 905.0]), &mut device),
+            Ok(RecalibrationOutcome::Skipped(SkipReason::OutOfBounds))
+        );
+        assert_matches!(
+            scheduler.poll(1000, &stable, &mut device),
+            Ok(RecalibrationOutcome::Calibrated { ppm }) if ppm == 420.0
+        );
+        assert_eq!(
+            device.last_calibration().and_then(|c| c.timestamp_ms),
+            Some(1000)
+        );
+        assert!(!scheduler.is_due(1500));
+        scheduler.request();
+        assert!(scheduler.is_due(1500));
+    }
+}
@@ -0,0 +1,162 @@
+//! Resampling of irregularly timed measurements onto a fixed time grid.
+//!
+//! Retries and bus errors leave gaps and jitter in the sample times. [resample()] maps
+//! timestamped measurements onto the grid `start_ms + k * step_ms`, so aggregation and export
+//! see one value per grid point. Values between two samples are either held or interpolated,
+//! see [Interpolation]. Nothing is extrapolated: grid points before the first or after the
+//! last sample are skipped.
+//!
+//! # Example Usage
+//! ```ignore
+//! let samples = log.iter().map(|(t, m)| Timestamped::new(t, m));
+//! for point in resample(samples, 0, 60_000, Interpolation::Linear) {
+//!     export(point.timestamp_ms, &point.value);
+//! }
+//! ```
+
+use crate::{clock::Timestamped, Measurements};
+
+/// How values between two samples are derived.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Use the value of the preceding sample.
+    Hold,
+    /// Interpolate linearly between the preceding and the following sample.
+    Linear,
+}
+
+/// Value at `timestamp_ms` interpolated linearly between `before` and `after`.
+///
+/// Times outside of the two samples are clamped to the nearer sample.
+pub fn interpolate(
+    before: &Timestamped<Measurements>,
+    after: &Timestamped<Measurements>,
+    timestamp_ms: u64,
+) -> Measurements {
+    let span = after.timestamp_ms.saturating_sub(before.timestamp_ms);
+    if span == 0 {
+        return after.value;
+    }
+    let elapsed = timestamp_ms
+        .clamp(before.timestamp_ms, after.timestamp_ms)
+        .saturating_sub(before.timestamp_ms);
+    before.value + (after.value - before.value) * (elapsed as f32 / span as f32)
+}
+
+/// Resample `samples`, ordered by time, onto the grid `start_ms + k * step_ms`. See
+/// [Resample].
+pub fn resample<I>(
+    samples: I,
+    start_ms: u64,
+    step_ms: u64,
+    interpolation: Interpolation,
+) -> Resample<I::IntoIter>
+where
+    I: IntoIterator<Item = Timestamped<Measurements>>,
+{
+    Resample {
+        samples: samples.into_iter(),
+        start_ms,
+        step_ms: step_ms.max(1),
+        interpolation,
+        next_ms: start_ms,
+        before: None,
+        after: None,
+    }
+}
+
+/// Iterator over the resampled values, created with [resample()].
+#[derive(Debug, Clone)]
+pub struct Resample<I> {
+    samples: I,
+    start_ms: u64,
+    step_ms: u64,
+    interpolation: Interpolation,
+    next_ms: u64,
+    before: Option<Timestamped<Measurements>>,
+    after: Option<Timestamped<Measurements>>,
+}
+
+impl<I> Iterator for Resample<I>
+where
+    I: Iterator<Item = Timestamped<Measurements>>,
+{
+    type Item = Timestamped<Measurements>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // `after` becomes the first sample at or after the next grid point
+            while self
+                .after
+                .is_none_or(|after| after.timestamp_ms < self.next_ms)
+            {
+                if let Some(after) = self.after.take() {
+                    self.before = Some(after);
+                }
+                self.after = Some(self.samples.next()?);
+            }
+            let after = self.after?;
+            let timestamp_ms = self.next_ms;
+
+            let value = if after.timestamp_ms == timestamp_ms {
+                after.value
+            } else if let Some(before) = &self.before {
+                match self.interpolation {
+                    Interpolation::Hold => before.value,
+                    Interpolation::Linear => interpolate(before, &after, timestamp_ms),
+                }
+            } else {
+                // the grid starts before the first sample, skip to the first grid point
+                // covered by the samples
+                let steps = (after.timestamp_ms - self.start_ms).div_ceil(self.step_ms);
+                self.next_ms = self.start_ms + steps * self.step_ms;
+                continue;
+            };
+
+            self.next_ms = timestamp_ms.saturating_add(self.step_ms);
+            return Some(Timestamped::new(timestamp_ms, value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{resample, Interpolation};
+    use crate::{clock::Timestamped, Measurements};
+    use core::assert_eq;
+    use std::vec::Vec;
+
+    fn sample(timestamp_ms: u64, co2: f32) -> Timestamped<Measurements> {
+        Timestamped::new(timestamp_ms, Measurements { co2, voc: 0.0 })
+    }
+
+    #[test]
+    fn test_resample_linear() {
+        // the sample at 3000 was missed, the one at 2000 came late
+        let samples = [sample(500, 400.0), sample(2100, 560.0), sample(4000, 500.0)];
+
+        let resampled = resample(samples, 0, 1000, Interpolation::Linear).collect::<Vec<_>>();
+
+        let timestamps = resampled.iter().map(|p| p.timestamp_ms).collect::<Vec<_>>();
+        assert_eq!(timestamps, [1000, 2000, 3000, 4000]);
+        for (point, expected) in resampled.iter().zip([450.0, 550.0, 531.579, 500.0]) {
+            assert!((point.value.co2 - expected).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_resample_hold() {
+        let samples = [sample(0, 400.0), sample(1500, 500.0), sample(1800, 600.0)];
+
+        let resampled = resample(samples, 0, 500, Interpolation::Hold)
+            .map(|p| (p.timestamp_ms, p.value.co2))
+            .collect::<Vec<_>>();
+
+        // no value after the last sample
+        assert_eq!(
+            resampled,
+            [(0, 400.0), (500, 400.0), (1000, 400.0), (1500, 500.0)]
+        );
+    }
+}
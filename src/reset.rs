@@ -0,0 +1,143 @@
+//! Detection of unexpected sensor resets.
+//!
+//! After a power-cycle (e.g. a brown-out) the sensor starts its warm-up again: CO2 snaps back
+//! to the lower end of [CO2_RANGE_PPM](crate::CO2_RANGE_PPM) and the sensor resistance jumps.
+//! [ResetDetector] recognizes these patterns so the application can restart warm-up gating
+//! (see [WarmUpDetector::restart()](crate::warmup::WarmUpDetector::restart())) and alarms.
+//!
+//! # Example Usage
+//! ```ignore
+//! let mut reset = ResetDetector::new(ResetConfig::default());
+//! let (measurements, resistance) = device.read_measurements_with_resistance(&mut delay).unwrap();
+//! if let Some(cause) = reset.update(&measurements, Some(resistance)) {
+//!     warm_up.restart(now_ms);
+//!     events.push(Event::new(now_ms, EventKind::SensorReset(cause)));
+//! }
+//! ```
+
+use crate::{Measurements, CO2_MIN};
+
+/// Parameters of the reset detection.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResetConfig {
+    /// CO2 values up to this limit are considered the warm-up floor.
+    pub floor_ppm: f32,
+    /// Minimum CO2 value above the floor the previous sample must have had for a drop onto
+    /// the floor to count as reset.
+    pub min_drop_ppm: f32,
+    /// Minimum relative resistance change between two samples in permille to count as reset.
+    pub min_resistance_jump_permille: u16,
+}
+
+impl Default for ResetConfig {
+    fn default() -> Self {
+        Self {
+            floor_ppm: CO2_MIN + 5.0,
+            min_drop_ppm: 100.0,
+            min_resistance_jump_permille: 500,
+        }
+    }
+}
+
+/// Pattern which indicated a reset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResetCause {
+    /// CO2 dropped onto the warm-up floor within one sample.
+    Co2SnapToFloor,
+    /// The sensor resistance jumped between two samples.
+    ResistanceJump,
+}
+
+/// Watches consecutive readings for signs of a sensor reset.
+#[derive(Debug, Clone)]
+pub struct ResetDetector {
+    config: ResetConfig,
+    last: Option<(f32, Option<u32>)>,
+    resets: u32,
+}
+
+impl ResetDetector {
+    /// Create a detector.
+    pub const fn new(config: ResetConfig) -> Self {
+        Self {
+            config,
+            last: None,
+            resets: 0,
+        }
+    }
+
+    /// Number of detected resets.
+    pub fn resets(&self) -> u32 {
+        self.resets
+    }
+
+    /// Forget the last reading, e.g. after the sensor was power-cycled on purpose.
+    pub fn clear(&mut self) {
+        self.last = None;
+    }
+
+    /// Feed a new reading. The sensor resistance (see
+    /// [MicsVz89Te::read_measurements_with_resistance()](crate::MicsVz89Te::read_measurements_with_resistance()))
+    /// is optional. Returns the cause if the reading indicates a reset.
+    pub fn update(
+        &mut self,
+        measurements: &Measurements,
+        resistance: Option<u32>,
+    ) -> Option<ResetCause> {
+        let co2 = measurements.co2;
+        let cause = self.last.and_then(|(last_co2, last_resistance)| {
+            if last_co2 >= self.config.floor_ppm + self.config.min_drop_ppm
+                && co2 <= self.config.floor_ppm
+            {
+                return Some(ResetCause::Co2SnapToFloor);
+            }
+            match (last_resistance, resistance) {
+                (Some(last), Some(now))
+                    if u64::from(last.abs_diff(now)) * 1000
+                        >= u64::from(last)
+                            * u64::from(self.config.min_resistance_jump_permille) =>
+                {
+                    Some(ResetCause::ResistanceJump)
+                }
+                _ => None,
+            }
+        });
+        self.last = Some((co2, resistance));
+        if cause.is_some() {
+            self.resets = self.resets.saturating_add(1);
+        }
+        cause
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{ResetCause, ResetConfig, ResetDetector};
+    use crate::Measurements;
+    use core::assert_eq;
+
+    fn m(co2: f32) -> Measurements {
+        Measurements { co2, voc: 0.0 }
+    }
+
+    #[test]
+    fn test_detect_reset() {
+        let mut detector = ResetDetector::new(ResetConfig::default());
+
+        assert_eq!(detector.update(&m(400.0), Some(100_000)), None);
+        assert_eq!(detector.update(&m(650.0), Some(110_000)), None);
+        assert_eq!(detector.update(&m(640.0), Some(111_000)), None);
+        assert_eq!(
+            detector.update(&m(400.0), Some(111_000)),
+            Some(ResetCause::Co2SnapToFloor)
+        );
+        assert_eq!(detector.update(&m(402.0), Some(112_000)), None);
+        assert_eq!(
+            detector.update(&m(402.0), Some(40_000)),
+            Some(ResetCause::ResistanceJump)
+        );
+        assert_eq!(detector.update(&m(405.0), None), None);
+        assert_eq!(detector.resets(), 2);
+    }
+}
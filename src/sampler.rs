@@ -0,0 +1,266 @@
+//! Iterator over timestamped measurements.
+
+use crate::{
+    clock::Clock, delay::Delay, error::PacketParseError, filter::MeasurementFilter,
+    transport::Transport, Measurements, MicsVz89Te,
+};
+
+/// Interval (in millis) in which the sensor updates its measurements.
+pub const SENSOR_UPDATE_INTERVAL_MS: u16 = 1000;
+
+/// Bounds and thresholds of an adaptive sampling interval, see [Samples::adaptive()].
+///
+/// After a reading changed by at least one of the steps, the interval drops to
+/// `min_interval_ms`. Every stable reading doubles the interval up to `max_interval_ms`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdaptiveRate {
+    /// Interval (in millis) while the readings change quickly.
+    pub min_interval_ms: u16,
+    /// Interval (in millis) the sampler backs off to while the readings are stable.
+    pub max_interval_ms: u16,
+    /// Change of CO2 (in ppm) between two readings considered quick.
+    pub co2_step_ppm: f32,
+    /// Change of VOC (in ppb) between two readings considered quick.
+    pub voc_step_ppb: f32,
+}
+
+impl Default for AdaptiveRate {
+    fn default() -> Self {
+        Self {
+            min_interval_ms: SENSOR_UPDATE_INTERVAL_MS,
+            max_interval_ms: 60_000,
+            co2_step_ppm: 20.0,
+            voc_step_ppb: 20.0,
+        }
+    }
+}
+
+impl AdaptiveRate {
+    /// Interval following `interval_ms` after a reading changed from `last` to `current`.
+    fn next_interval(&self, interval_ms: u16, last: &Measurements, current: &Measurements) -> u16 {
+        let delta = *current - *last;
+        if delta.co2.abs() >= self.co2_step_ppm || delta.voc.abs() >= self.voc_step_ppb {
+            self.min_interval_ms
+        } else {
+            interval_ms.saturating_mul(2).clamp(
+                self.min_interval_ms,
+                self.max_interval_ms.max(self.min_interval_ms),
+            )
+        }
+    }
+}
+
+/// Iterator reading measurements paced to the update rate of the sensor.
+///
+/// Each item is the measurement (or error) with the time it was read at. Created with
+/// [MicsVz89Te::samples()]. The iterator never ends.
+///
+/// # Example Usage
+/// ```ignore
+/// let clock = || timer.millis();
+/// for sample in device.samples(&mut delay, &clock) {
+///     let (timestamp_ms, measurements) = sample.unwrap();
+/// }
+/// ```
+pub struct Samples<'a, I2C, D, C> {
+    driver: &'a mut MicsVz89Te<I2C>,
+    delay: &'a mut D,
+    clock: &'a C,
+    interval_ms: u16,
+    last_ms: Option<u64>,
+    adaptive: Option<AdaptiveRate>,
+    last: Option<Measurements>,
+}
+
+impl<'a, I2C, D, C> Samples<'a, I2C, D, C> {
+    /// Change the interval (in millis) between two reads.
+    ///
+    /// Intervals below [SENSOR_UPDATE_INTERVAL_MS] return repeated values.
+    pub fn with_interval(mut self, interval_ms: u16) -> Self {
+        self.interval_ms = interval_ms;
+        self
+    }
+
+    /// Adapt the interval to how quickly the readings change, see [AdaptiveRate]. Sampling
+    /// starts at the fast interval. Errors don't change the interval.
+    pub fn adaptive(mut self, rate: AdaptiveRate) -> Self {
+        self.interval_ms = rate.min_interval_ms;
+        self.adaptive = Some(rate);
+        self
+    }
+
+    /// Current interval (in millis) between two reads.
+    pub fn interval_ms(&self) -> u16 {
+        self.interval_ms
+    }
+
+    /// Pass the measurements through `filter`. Measurements held back by the filter are
+    /// skipped, errors are passed on unchanged.
+    pub fn filtered<F, E>(
+        self,
+        mut filter: F,
+    ) -> impl Iterator<Item = Result<(u64, Measurements), PacketParseError<E>>> + 'a
+    where
+        Self: Iterator<Item = Result<(u64, Measurements), PacketParseError<E>>> + 'a,
+        F: MeasurementFilter + 'a,
+    {
+        self.filter_map(move |sample| match sample {
+            Ok((timestamp_ms, m)) => filter.update(m).map(|m| Ok((timestamp_ms, m))),
+            Err(e) => Some(Err(e)),
+        })
+    }
+}
+
+impl<I2C, E, D, C> Iterator for Samples<'_, I2C, D, C>
+where
+    I2C: Transport<Error = E>,
+    D: Delay,
+    C: Clock,
+{
+    type Item = Result<(u64, Measurements), PacketParseError<E>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(last_ms) = self.last_ms {
+            let elapsed = self.clock.now_ms().saturating_sub(last_ms);
+            let remaining = u64::from(self.interval_ms).saturating_sub(elapsed);
+            // remaining never exceeds interval_ms
+            self.delay.delay_ms(remaining as u16);
+        }
+        let timestamp_ms = self.clock.now_ms();
+        self.last_ms = Some(timestamp_ms);
+        let result = self.driver.read_measurements(self.delay);
+        if let (Some(rate), Ok(m)) = (&self.adaptive, &result) {
+            if let Some(last) = &self.last {
+                self.interval_ms = rate.next_interval(self.interval_ms, last, m);
+            }
+            self.last = Some(*m);
+        }
+        Some(result.map(|m| (timestamp_ms, m)))
+    }
+}
+
+impl<I2C, E> MicsVz89Te<I2C>
+where
+    I2C: Transport<Error = E>,
+{
+    /// Iterate over timestamped measurements, paced to [SENSOR_UPDATE_INTERVAL_MS].
+    /// See [Samples].
+    pub fn samples<'a, D, C>(&'a mut self, delay: &'a mut D, clock: &'a C) -> Samples<'a, I2C, D, C>
+    where
+        D: Delay,
+        C: Clock,
+    {
+        Samples {
+            driver: self,
+            delay,
+            clock,
+            interval_ms: SENSOR_UPDATE_INTERVAL_MS,
+            last_ms: None,
+            adaptive: None,
+            last: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::AdaptiveRate;
+    use crate::delay::Delay;
+    use crate::MicsVz89Te;
+    use core::{assert_eq, cell::Cell};
+    use embedded_hal_mock::i2c::{Mock as I2cMock, Transaction as I2cTransaction};
+    use std::vec;
+
+    struct FakeTime<'a>(&'a Cell<u64>);
+
+    impl Delay for FakeTime<'_> {
+        fn delay_ms(&mut self, ms: u16) {
+            self.0.set(self.0.get() + u64::from(ms));
+        }
+    }
+
+    #[test]
+    fn test_samples_paced() {
+        let expectations = [
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+        ];
+        let i2c = I2cMock::new(&expectations);
+        let time = Cell::new(5000);
+        let clock = || time.get();
+        let mut delay = FakeTime(&time);
+
+        let mut device = MicsVz89Te::new(i2c);
+        let timestamps = device
+            .samples(&mut delay, &clock)
+            .take(2)
+            .map(|s| s.unwrap().0)
+            .collect::<vec::Vec<_>>();
+
+        // first read takes the 100ms response wait, then waits for the rest of the interval
+        assert_eq!(timestamps, [5000, 6000]);
+    }
+
+    #[test]
+    fn test_samples_filtered() {
+        let expectations = [
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+        ];
+        let time = Cell::new(0);
+        let clock = || time.get();
+        let mut delay = FakeTime(&time);
+
+        let mut device = MicsVz89Te::new(I2cMock::new(&expectations));
+        let mut first = true;
+        let skip_first = move |m| (!core::mem::take(&mut first)).then_some(m);
+        let sample = device
+            .samples(&mut delay, &clock)
+            .filtered(skip_first)
+            .next()
+            .unwrap();
+
+        assert_eq!(sample.map(|s| s.0).ok(), Some(1000));
+    }
+
+    #[test]
+    fn test_samples_adaptive() {
+        let status = || I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]);
+        let stable = || I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]);
+        let expectations = [
+            status(),
+            stable(),
+            status(),
+            stable(),
+            status(),
+            stable(),
+            status(),
+            // CO2 raw 0x3C -> 0x40, about +28 ppm
+            I2cTransaction::read(0x70, vec![0x27, 0x40, 0, 0xBA, 0xBA, 0, 0x23]),
+        ];
+        let time = Cell::new(0);
+        let clock = || time.get();
+        let mut delay = FakeTime(&time);
+
+        let mut device = MicsVz89Te::new(I2cMock::new(&expectations));
+        let mut samples = device.samples(&mut delay, &clock).adaptive(AdaptiveRate {
+            max_interval_ms: 3000,
+            ..AdaptiveRate::default()
+        });
+
+        let mut intervals = vec::Vec::new();
+        for _ in 0..4 {
+            samples.next().unwrap().unwrap();
+            intervals.push(samples.interval_ms());
+        }
+
+        assert_eq!(intervals, [1000, 2000, 3000, 1000]);
+        assert_eq!(time.get(), 1000 + 2000 + 3000 + 100);
+        device.release().done();
+    }
+}
@@ -0,0 +1,145 @@
+//! Built-in self-test of sensor and driver.
+
+use crate::{
+    delay::Delay, error::PacketParseError, event::Fault, protocol, transport::Transport,
+    Measurements, MicsVz89Te, RevisionDate,
+};
+
+/// Result of a single self-test step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepResult {
+    Passed,
+    Failed(Fault),
+    /// The step isn't available, e.g. because the `unproven` feature is disabled.
+    Skipped,
+}
+
+impl StepResult {
+    fn of<T, E>(result: &Result<T, PacketParseError<E>>) -> Self {
+        match result {
+            Ok(_) => Self::Passed,
+            Err(e) => Self::Failed(Fault::from(e)),
+        }
+    }
+
+    /// Returns `true` unless the step failed.
+    pub fn is_ok(&self) -> bool {
+        !matches!(self, Self::Failed(_))
+    }
+}
+
+/// Report of [MicsVz89Te::self_test()] with the result of each step and the read values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SelfTestReport {
+    /// Checksum validation of the driver accepts a valid and rejects a corrupted response.
+    pub checksum: StepResult,
+    /// Reading the revision date.
+    pub revision: StepResult,
+    /// Reading measurements.
+    pub measurement: StepResult,
+    /// Reading the calibration value R0 (only with feature `unproven`).
+    pub calibration_r0: StepResult,
+    pub revision_date: Option<RevisionDate>,
+    pub measurements: Option<Measurements>,
+    pub r0: Option<u16>,
+}
+
+impl SelfTestReport {
+    /// Returns `true` if no step failed.
+    pub fn passed(&self) -> bool {
+        [
+            self.checksum,
+            self.revision,
+            self.measurement,
+            self.calibration_r0,
+        ]
+        .iter()
+        .all(StepResult::is_ok)
+    }
+}
+
+impl<I2C, E> MicsVz89Te<I2C>
+where
+    I2C: Transport<Error = E>,
+{
+    /// Run a self-test: checksum validation, revision read, measurement read and, if the
+    /// `unproven` feature is enabled, R0 read. All steps are run even if one fails.
+    ///
+    /// This function blocks a minimum time of 2 (3 with `unproven`) times
+    /// [MicsVz89Te::WAIT_ON_RESPONSE_TIME].
+    pub fn self_test(&mut self, delay: &mut impl Delay) -> SelfTestReport {
+        let checksum = check_checksum_validation();
+        let revision = self.read_revision(delay);
+        let measurements = self.read_measurements(delay);
+
+        #[cfg(any(feature = "unproven", test))]
+        let (calibration_r0, r0) = {
+            let r0 = self.read_calibration_r0(delay);
+            (StepResult::of(&r0), r0.ok())
+        };
+        #[cfg(not(any(feature = "unproven", test)))]
+        let (calibration_r0, r0) = (StepResult::Skipped, None);
+
+        SelfTestReport {
+            checksum,
+            revision: StepResult::of(&revision),
+            measurement: StepResult::of(&measurements),
+            calibration_r0,
+            revision_date: revision.ok(),
+            measurements: measurements.ok(),
+            r0,
+        }
+    }
+}
+
+fn check_checksum_validation() -> StepResult {
+    let valid = [0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27];
+    let mut corrupted = valid;
+    corrupted[1] ^= 0x01;
+    match (
+        protocol::check_response::<()>(&valid),
+        protocol::check_response::<()>(&corrupted),
+    ) {
+        (Ok(()), Err(_)) => StepResult::Passed,
+        _ => StepResult::Failed(Fault::WrongChecksum),
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::StepResult;
+    use crate::{event::Fault, MicsVz89Te};
+    use core::assert_eq;
+    use embedded_hal_mock::{
+        delay::MockNoop as DelayMock,
+        i2c::{Mock as I2cMock, Transaction as I2cTransaction},
+    };
+    use std::vec;
+
+    #[test]
+    fn test_self_test() {
+        let expectations = [
+            I2cTransaction::write(0x70, vec![0x0D, 0, 0, 0, 0, 0xF2]),
+            I2cTransaction::read(0x70, vec![0x10, 0x03, 0x11, 0x48, 00, 0, 0x93]),
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x26]),
+            I2cTransaction::write(0x70, vec![0x10, 0, 0, 0, 0, 0xEF]),
+            I2cTransaction::read(0x70, vec![0xFB, 0x01, 0, 0, 0, 0, 0x03]),
+        ];
+        let i2c = I2cMock::new(&expectations);
+        let mut delay = DelayMock::new();
+
+        let mut device = MicsVz89Te::new(i2c);
+        let report = device.self_test(&mut delay);
+
+        assert_eq!(report.checksum, StepResult::Passed);
+        assert_eq!(report.revision, StepResult::Passed);
+        assert_eq!(report.measurement, StepResult::Failed(Fault::WrongChecksum));
+        assert_eq!(report.calibration_r0, StepResult::Passed);
+        assert_eq!(report.r0, Some(507));
+        assert_eq!(report.revision_date.map(|d| d.year), Some(2016));
+        assert!(report.measurements.is_none());
+        assert!(!report.passed());
+    }
+}
@@ -0,0 +1,347 @@
+//! Capture and playback of measurement sessions.
+//!
+//! `CaptureBus` wraps the bus of a deployed sensor and records every response frame with the
+//! command it answers and the time it was requested at. Stored as a session (see the format
+//! below), the recording can be replayed with `PlaybackBus`, which answers the driver with the
+//! recorded frames, so new firmware logic runs on real-world data through the normal API. Both
+//! are embedded-hal 0.2 buses, they need the `eh0` feature (enabled by default).
+//!
+//! Session format: the [HEADER] followed by records of [RECORD_LEN] bytes (little endian):
+//!
+//! | byte  | content                                    |
+//! |-------|--------------------------------------------|
+//! | 0..8  | time of the request in millis (`u64`)      |
+//! | 8     | command byte of the request                |
+//! | 9..16 | raw response frame, including the checksum |
+//!
+//! # Example Usage
+//! ```ignore
+//! // deployment
+//! file.write_all(&session::HEADER)?;
+//! let bus = CaptureBus::new(i2c, &clock, |record: &SessionRecord| file.write_all(&record.to_bytes()));
+//! let mut device = MicsVz89Te::new(bus);
+//!
+//! // development
+//! let mut device = MicsVz89Te::new(PlaybackBus::new(Session::parse(&recording)?));
+//! while let Ok(m) = device.read_measurements(&mut delay) {
+//!     app.step(device.bus_mut().timestamp_ms(), m);
+//! }
+//! ```
+
+#[cfg(any(feature = "eh0", test))]
+use embedded_hal::blocking::i2c::{Read, Write};
+
+use crate::protocol::RESPONSE_LEN;
+#[cfg(any(feature = "eh0", test))]
+use crate::{clock::Clock, protocol::MICS_VZ_89TE_SET_CALIBR_PPM};
+
+/// Magic bytes and format version at the start of a session.
+pub const HEADER: [u8; 5] = *b"MVZS\x01";
+/// Size of a record in bytes.
+pub const RECORD_LEN: usize = 16;
+
+/// A response frame with the request it answers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionRecord {
+    /// Time (in millis) the request was sent at.
+    pub timestamp_ms: u64,
+    /// Command byte of the request.
+    pub command: u8,
+    /// Raw response frame.
+    pub response: [u8; RESPONSE_LEN],
+}
+
+impl SessionRecord {
+    /// Serialize the record, see the [module](self) documentation.
+    pub fn to_bytes(&self) -> [u8; RECORD_LEN] {
+        let mut bytes = [0; RECORD_LEN];
+        bytes[..8].copy_from_slice(&self.timestamp_ms.to_le_bytes());
+        bytes[8] = self.command;
+        bytes[9..].copy_from_slice(&self.response);
+        bytes
+    }
+
+    /// Deserialize a record written by [SessionRecord::to_bytes()].
+    pub fn from_bytes(bytes: &[u8; RECORD_LEN]) -> Self {
+        let [t0, t1, t2, t3, t4, t5, t6, t7, command, response @ ..] = *bytes;
+        Self {
+            timestamp_ms: u64::from_le_bytes([t0, t1, t2, t3, t4, t5, t6, t7]),
+            command,
+            response,
+        }
+    }
+}
+
+/// Errors of parsing a session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionError {
+    /// The data doesn't start with the [HEADER] of a supported version.
+    InvalidHeader,
+    /// The data ends in the middle of a record.
+    Truncated,
+}
+
+/// Records of a stored session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Session<'a> {
+    records: &'a [u8],
+}
+
+impl<'a> Session<'a> {
+    /// Check the header and the length of a stored session.
+    pub fn parse(data: &'a [u8]) -> Result<Self, SessionError> {
+        let records = data
+            .strip_prefix(&HEADER[..])
+            .ok_or(SessionError::InvalidHeader)?;
+        if !records.len().is_multiple_of(RECORD_LEN) {
+            return Err(SessionError::Truncated);
+        }
+        Ok(Self { records })
+    }
+
+    /// Number of records.
+    pub fn len(&self) -> usize {
+        self.records.len() / RECORD_LEN
+    }
+
+    /// Returns `true` if the session has no records.
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Iterate over the records in the order they were captured.
+    pub fn records(&self) -> impl Iterator<Item = SessionRecord> + 'a {
+        self.records
+            .chunks_exact(RECORD_LEN)
+            .filter_map(|chunk| chunk.try_into().ok().map(SessionRecord::from_bytes))
+    }
+}
+
+#[cfg(any(feature = "eh0", test))]
+#[cfg_attr(docsrs, doc(cfg(feature = "eh0")))]
+/// Bus wrapper passing every response frame as [SessionRecord] to a callback.
+pub struct CaptureBus<I2C, C, F> {
+    i2c: I2C,
+    clock: C,
+    on_record: F,
+    request: Option<(u64, u8)>,
+}
+
+#[cfg(any(feature = "eh0", test))]
+impl<I2C, C, F> CaptureBus<I2C, C, F>
+where
+    C: Clock,
+    F: FnMut(&SessionRecord),
+{
+    /// Wrap `i2c`, timestamping requests with `clock`.
+    pub fn new(i2c: I2C, clock: C, on_record: F) -> Self {
+        Self {
+            i2c,
+            clock,
+            on_record,
+            request: None,
+        }
+    }
+
+    /// Destroy the wrapper and return the wrapped bus.
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+}
+
+#[cfg(any(feature = "eh0", test))]
+impl<I2C, C, F, E> Write for CaptureBus<I2C, C, F>
+where
+    I2C: Write<Error = E>,
+    C: Clock,
+{
+    type Error = E;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.i2c.write(address, bytes)?;
+        self.request = bytes.first().map(|c| (self.clock.now_ms(), *c));
+        Ok(())
+    }
+}
+
+#[cfg(any(feature = "eh0", test))]
+impl<I2C, C, F, E> Read for CaptureBus<I2C, C, F>
+where
+    I2C: Read<Error = E>,
+    F: FnMut(&SessionRecord),
+{
+    type Error = E;
+
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.i2c.read(address, buffer)?;
+        // frames with wrong checksums are kept, they are part of the real-world data. A request
+        // is recorded once, a repeated read without a request isn't an answer to it.
+        let request = self.request.take();
+        if let (Some((timestamp_ms, command)), Ok(response)) = (request, buffer.try_into()) {
+            (self.on_record)(&SessionRecord {
+                timestamp_ms,
+                command,
+                response,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(any(feature = "eh0", test))]
+#[cfg_attr(docsrs, doc(cfg(feature = "eh0")))]
+/// Error of a [PlaybackBus].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaybackError {
+    /// No recorded response is left for the request.
+    EndOfSession,
+}
+
+#[cfg(any(feature = "eh0", test))]
+#[cfg_attr(docsrs, doc(cfg(feature = "eh0")))]
+/// Bus answering requests with the recorded responses of a [Session].
+///
+/// A request is answered with the next record of the same command; records of other commands
+/// in between are skipped. Calibration writes have no response, they are accepted without
+/// consuming a record.
+pub struct PlaybackBus<'a> {
+    session: Session<'a>,
+    position: usize,
+    current: Option<SessionRecord>,
+}
+
+#[cfg(any(feature = "eh0", test))]
+impl<'a> PlaybackBus<'a> {
+    /// Replay `session` from the start.
+    pub fn new(session: Session<'a>) -> Self {
+        Self {
+            session,
+            position: 0,
+            current: None,
+        }
+    }
+
+    /// Capture time (in millis) of the record answering the last request.
+    pub fn timestamp_ms(&self) -> Option<u64> {
+        self.current.map(|r| r.timestamp_ms)
+    }
+
+    /// Returns `true` if all records are replayed or skipped.
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.session.len()
+    }
+}
+
+#[cfg(any(feature = "eh0", test))]
+impl Write for PlaybackBus<'_> {
+    type Error = PlaybackError;
+
+    fn write(&mut self, _address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        let command = bytes.first().copied();
+        if command == Some(MICS_VZ_89TE_SET_CALIBR_PPM) {
+            return Ok(());
+        }
+        let (skipped, record) = self
+            .session
+            .records()
+            .skip(self.position)
+            .enumerate()
+            .find(|(_, r)| Some(r.command) == command)
+            .ok_or(PlaybackError::EndOfSession)?;
+        self.position += skipped + 1;
+        self.current = Some(record);
+        Ok(())
+    }
+}
+
+#[cfg(any(feature = "eh0", test))]
+impl Read for PlaybackBus<'_> {
+    type Error = PlaybackError;
+
+    fn read(&mut self, _address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        let record = self.current.take().ok_or(PlaybackError::EndOfSession)?;
+        let len = buffer.len().min(RESPONSE_LEN);
+        buffer[..len].copy_from_slice(&record.response[..len]);
+        // keep the time for timestamp_ms()
+        self.current = Some(record);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{CaptureBus, PlaybackBus, Session, SessionError, SessionRecord, HEADER};
+    use crate::{error::PacketParseError, MicsVz89Te};
+    use assert_matches::assert_matches;
+    use core::{assert_eq, cell::Cell};
+    use embedded_hal::blocking::i2c::Read;
+    use embedded_hal_mock::{
+        delay::MockNoop as DelayMock,
+        i2c::{Mock as I2cMock, Transaction as I2cTransaction},
+    };
+    use std::{vec, vec::Vec};
+
+    #[test]
+    fn test_capture_and_playback() {
+        let expectations = [
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+            I2cTransaction::write(0x70, vec![0x0D, 0, 0, 0, 0, 0xF2]),
+            I2cTransaction::read(0x70, vec![0x10, 0x03, 0x11, 0x48, 0, 0, 0x93]),
+            I2cTransaction::read(0x70, vec![0x10, 0x03, 0x11, 0x48, 0, 0, 0x93]),
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x26]),
+        ];
+        let time = Cell::new(1000);
+        let clock = || time.get();
+        let mut recording = HEADER.to_vec();
+        let bus = CaptureBus::new(I2cMock::new(&expectations), &clock, |r: &SessionRecord| {
+            recording.extend_from_slice(&r.to_bytes())
+        });
+        let mut device = MicsVz89Te::new(bus);
+        let mut delay = DelayMock::new();
+
+        device.read_measurements(&mut delay).unwrap();
+        time.set(2000);
+        device.read_revision(&mut delay).unwrap();
+        // a read without a request isn't recorded
+        device.bus_mut().read(0x70, &mut [0; 7]).unwrap();
+        time.set(3000);
+        assert!(device.read_measurements(&mut delay).is_err());
+        device.release().release().done();
+
+        let session = Session::parse(&recording).unwrap();
+        assert_eq!(session.len(), 3);
+
+        // the new firmware doesn't read the revision
+        let mut device = MicsVz89Te::new(PlaybackBus::new(session));
+        let m = device.read_measurements(&mut delay).unwrap();
+        assert_eq!(m.co2_ppm_u16(), 728);
+        assert_eq!(device.bus_mut().timestamp_ms(), Some(1000));
+        // the new firmware calibrates, which has no recorded response
+        device.write_calibration_ppm(450.0).unwrap();
+        assert_matches!(
+            device.read_measurements(&mut delay),
+            Err(PacketParseError::WrongChecksum)
+        );
+        assert_eq!(device.bus_mut().timestamp_ms(), Some(3000));
+        assert!(device.bus_mut().is_finished());
+        assert_matches!(
+            device.read_measurements(&mut delay),
+            Err(PacketParseError::BusError(_))
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Session::parse(b"MVZS\x02"),
+            Err(SessionError::InvalidHeader)
+        );
+        let mut data: Vec<u8> = HEADER.to_vec();
+        data.extend_from_slice(&[0; 10]);
+        assert_eq!(Session::parse(&data), Err(SessionError::Truncated));
+        assert!(Session::parse(&HEADER).unwrap().is_empty());
+    }
+}
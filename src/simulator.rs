@@ -0,0 +1,305 @@
+//! Simulated sensor playing scripted scenarios.
+//!
+//! [SimulatedSensor] is a bus answering the commands of the driver like a real sensor, with
+//! values taken from a [Scenario]. Scenarios are plain data: [Keyframe]s between which the
+//! values are interpolated linearly, and [FaultWindow]s during which the sensor fails. Together
+//! with a fake clock this gives reproducible end-to-end tests of alarms and control logic.
+//!
+//! # Example Usage
+//! ```ignore
+//! // sensor fault in the 30th minute
+//! let faults = [FaultWindow {
+//!     from_ms: 30 * MINUTE_MS,
+//!     until_ms: 31 * MINUTE_MS,
+//!     fault: Fault::Bus,
+//! }];
+//! let scenario = Scenario::COOKING.with_faults(&faults);
+//! let mut device = MicsVz89Te::new(SimulatedSensor::new(scenario, &clock));
+//! while clock.now_ms() < scenario.duration_ms() {
+//!     app.step(device.read_measurements(&mut delay));
+//!     clock.advance(1000);
+//! }
+//! ```
+
+use core::convert::Infallible;
+
+use embedded_hal::blocking::i2c::{Read, Write};
+
+use crate::{
+    clock::{Clock, Timestamped},
+    event::Fault,
+    fault_injection::InjectedError,
+    protocol::{gen_checksum, Command, GET_CALIBRATION_R0_FRAME, GET_REVISION_FRAME, RESPONSE_LEN},
+    resample, units, Measurements, CO2_MAX, CO2_MIN, RAW_MIN, RAW_SPAN, VOC_MAX, VOC_MIN,
+};
+
+/// One minute in millis.
+pub const MINUTE_MS: u64 = 60 * 1000;
+/// One hour in millis.
+pub const HOUR_MS: u64 = 60 * MINUTE_MS;
+
+/// Values of a scenario at a point in time (in millis since the start of the scenario).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Keyframe {
+    pub at_ms: u64,
+    pub measurements: Measurements,
+}
+
+impl Keyframe {
+    /// Keyframe with CO2 in ppm and VOC in ppb.
+    pub const fn new(at_ms: u64, co2: f32, voc: f32) -> Self {
+        Self {
+            at_ms,
+            measurements: Measurements { co2, voc },
+        }
+    }
+}
+
+/// Time span (in millis since the start of the scenario) in which the sensor fails.
+///
+/// [Fault::Bus] NACKs every transaction, [Fault::WrongChecksum] corrupts every response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FaultWindow {
+    pub from_ms: u64,
+    pub until_ms: u64,
+    pub fault: Fault,
+}
+
+/// Scripted course of the sensor values and faults.
+///
+/// Before the first keyframe the values of the first keyframe apply, after the last keyframe
+/// the values of the last one. Keyframes must be ordered by time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scenario<'a> {
+    pub keyframes: &'a [Keyframe],
+    pub faults: &'a [FaultWindow],
+}
+
+impl<'a> Scenario<'a> {
+    /// Office day over 10 hours: occupancy in the morning and afternoon, empty at lunch.
+    pub const OFFICE_DAY: Scenario<'static> = Scenario::new(&[
+        Keyframe::new(0, 420.0, 20.0),
+        Keyframe::new(HOUR_MS, 600.0, 80.0),
+        Keyframe::new(3 * HOUR_MS, 1100.0, 250.0),
+        Keyframe::new(4 * HOUR_MS, 1200.0, 300.0),
+        Keyframe::new(5 * HOUR_MS, 700.0, 120.0),
+        Keyframe::new(7 * HOUR_MS, 1300.0, 280.0),
+        Keyframe::new(9 * HOUR_MS, 900.0, 150.0),
+        Keyframe::new(10 * HOUR_MS, 450.0, 30.0),
+    ]);
+
+    /// Cooking: a VOC spike within 5 minutes, which slowly clears over the following hour.
+    pub const COOKING: Scenario<'static> = Scenario::new(&[
+        Keyframe::new(0, 500.0, 50.0),
+        Keyframe::new(5 * MINUTE_MS, 550.0, 900.0),
+        Keyframe::new(20 * MINUTE_MS, 520.0, 400.0),
+        Keyframe::new(60 * MINUTE_MS, 500.0, 60.0),
+    ]);
+
+    /// Window opened in a stuffy room: roughly exponential decay towards outdoor air.
+    pub const WINDOW_OPEN: Scenario<'static> = Scenario::new(&[
+        Keyframe::new(0, 1600.0, 600.0),
+        Keyframe::new(2 * MINUTE_MS, 1100.0, 350.0),
+        Keyframe::new(5 * MINUTE_MS, 750.0, 150.0),
+        Keyframe::new(10 * MINUTE_MS, 520.0, 60.0),
+        Keyframe::new(20 * MINUTE_MS, 430.0, 25.0),
+    ]);
+
+    /// Scenario without faults.
+    pub const fn new(keyframes: &'a [Keyframe]) -> Self {
+        Self {
+            keyframes,
+            faults: &[],
+        }
+    }
+
+    /// Replace the faults of the scenario.
+    pub const fn with_faults(self, faults: &'a [FaultWindow]) -> Self {
+        Self {
+            keyframes: self.keyframes,
+            faults,
+        }
+    }
+
+    /// Time (in millis) of the last keyframe.
+    pub fn duration_ms(&self) -> u64 {
+        self.keyframes.last().map_or(0, |k| k.at_ms)
+    }
+
+    /// Values at `at_ms`. Clean air (400 ppm, 0 ppb) if there are no keyframes.
+    pub fn measurements_at(&self, at_ms: u64) -> Measurements {
+        let clean_air = Measurements {
+            co2: CO2_MIN,
+            voc: VOC_MIN,
+        };
+        let timestamped = |k: &Keyframe| Timestamped::new(k.at_ms, k.measurements);
+        match self.keyframes.iter().position(|k| k.at_ms > at_ms) {
+            Some(0) => self.keyframes[0].measurements,
+            Some(i) => resample::interpolate(
+                &timestamped(&self.keyframes[i - 1]),
+                &timestamped(&self.keyframes[i]),
+                at_ms,
+            ),
+            None => self.keyframes.last().map_or(clean_air, |k| k.measurements),
+        }
+    }
+
+    /// Fault active at `at_ms`, the first matching window wins.
+    pub fn fault_at(&self, at_ms: u64) -> Option<Fault> {
+        self.faults
+            .iter()
+            .find(|w| (w.from_ms..w.until_ms).contains(&at_ms))
+            .map(|w| w.fault)
+    }
+}
+
+/// Bus simulating a sensor which plays a [Scenario] in the time of a [Clock].
+///
+/// The scenario starts when the bus is created. Commands are answered like by a real sensor,
+/// the revision is 2016-03-17 and R0 507 kOhm.
+pub struct SimulatedSensor<'a, C> {
+    scenario: Scenario<'a>,
+    clock: C,
+    start_ms: u64,
+    command: u8,
+}
+
+impl<'a, C: Clock> SimulatedSensor<'a, C> {
+    /// Start playing `scenario`.
+    pub fn new(scenario: Scenario<'a>, clock: C) -> Self {
+        Self {
+            scenario,
+            start_ms: clock.now_ms(),
+            clock,
+            command: Command::GetStatus.frame()[0],
+        }
+    }
+
+    /// Time (in millis) since the start of the scenario.
+    pub fn elapsed_ms(&self) -> u64 {
+        self.clock.now_ms().saturating_sub(self.start_ms)
+    }
+
+    fn response(&self) -> [u8; RESPONSE_LEN] {
+        let data = if self.command == GET_REVISION_FRAME[0] {
+            [16, 3, 17, 0, 0, 0]
+        } else if self.command == GET_CALIBRATION_R0_FRAME[0] {
+            [0xFB, 0x01, 0, 0, 0, 0]
+        } else {
+            let m = self.scenario.measurements_at(self.elapsed_ms());
+            [
+                to_raw(m.voc, VOC_MIN, VOC_MAX),
+                to_raw(m.co2, CO2_MIN, CO2_MAX),
+                0,
+                0xBA,
+                0xBA,
+                0,
+            ]
+        };
+        let [d0, d1, d2, d3, d4, d5] = data;
+        [d0, d1, d2, d3, d4, d5, gen_checksum(&data[..5])]
+    }
+}
+
+/// Raw byte of a value in `min..=max`, rounded to nearest.
+fn to_raw(value: f32, min: f32, max: f32) -> u8 {
+    let raw = (value.clamp(min, max) - min) * (f32::from(RAW_SPAN.get()) / (max - min))
+        + f32::from(RAW_MIN);
+    u8::try_from(units::round_to_u16(raw)).unwrap_or(u8::MAX)
+}
+
+impl<C: Clock> Write for SimulatedSensor<'_, C> {
+    type Error = InjectedError<Infallible>;
+
+    fn write(&mut self, _address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        if self.scenario.fault_at(self.elapsed_ms()) == Some(Fault::Bus) {
+            return Err(InjectedError::Nack);
+        }
+        if let Some(command) = bytes.first() {
+            self.command = *command;
+        }
+        Ok(())
+    }
+}
+
+impl<C: Clock> Read for SimulatedSensor<'_, C> {
+    type Error = InjectedError<Infallible>;
+
+    fn read(&mut self, _address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        let fault = self.scenario.fault_at(self.elapsed_ms());
+        if fault == Some(Fault::Bus) {
+            return Err(InjectedError::Nack);
+        }
+        let mut response = self.response();
+        if fault == Some(Fault::WrongChecksum) {
+            response[RESPONSE_LEN - 1] ^= 0x01;
+        }
+        let len = buffer.len().min(RESPONSE_LEN);
+        buffer[..len].copy_from_slice(&response[..len]);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{FaultWindow, Keyframe, Scenario, SimulatedSensor, MINUTE_MS};
+    use crate::{error::PacketParseError, event::Fault, threshold::Threshold, Channel, MicsVz89Te};
+    use assert_matches::assert_matches;
+    use core::{assert_eq, cell::Cell};
+    use embedded_hal_mock::delay::MockNoop as DelayMock;
+
+    #[test]
+    fn test_scenario_values() {
+        let keyframes = [
+            Keyframe::new(1000, 500.0, 0.0),
+            Keyframe::new(3000, 700.0, 100.0),
+        ];
+        let scenario = Scenario::new(&keyframes);
+
+        assert_eq!(scenario.measurements_at(0).co2, 500.0);
+        assert_eq!(scenario.measurements_at(2000).co2, 600.0);
+        assert_eq!(scenario.measurements_at(2000).voc, 50.0);
+        assert_eq!(scenario.measurements_at(5000).co2, 700.0);
+        assert_eq!(scenario.duration_ms(), 3000);
+        assert_eq!(Scenario::new(&[]).measurements_at(0).co2, 400.0);
+    }
+
+    #[test]
+    fn test_cooking_alarm_with_fault() {
+        let time = Cell::new(0);
+        let clock = || time.get();
+        let faults = [FaultWindow {
+            from_ms: 10 * MINUTE_MS,
+            until_ms: 11 * MINUTE_MS,
+            fault: Fault::Bus,
+        }];
+        let scenario = Scenario::COOKING.with_faults(&faults);
+        let mut device = MicsVz89Te::new(SimulatedSensor::new(scenario, &clock));
+        let mut delay = DelayMock::new();
+        let mut alarm = Threshold::new(Channel::Voc, 660.0, 50.0);
+
+        let mut raised_at = None;
+        let mut faults_seen = 0;
+        while time.get() <= scenario.duration_ms() {
+            match device.read_measurements(&mut delay) {
+                Ok(m) => {
+                    if alarm.update(&m).is_some() && raised_at.is_none() {
+                        raised_at = Some(time.get());
+                    }
+                }
+                Err(e) => {
+                    assert_matches!(e, PacketParseError::BusError(_));
+                    faults_seen += 1;
+                }
+            }
+            time.set(time.get() + MINUTE_MS);
+        }
+
+        // VOC passes 660 ppb between minute 3 and 4
+        assert_eq!(raised_at, Some(4 * MINUTE_MS));
+        assert_eq!(faults_seen, 1);
+        let revision = device.read_revision(&mut delay).unwrap();
+        assert_eq!(revision.year, 2016);
+    }
+}
@@ -0,0 +1,143 @@
+//! Static storage for the driver.
+//!
+//! [DriverCell] holds the driver in a `static`, so it can be reached from interrupt handlers
+//! and multiple modules without passing references around. Access is exclusive: while one
+//! context uses the driver, other attempts return `None` instead of blocking, which makes
+//! the cell safe to use from interrupts.
+//!
+//! The cell needs atomic compare-and-swap. On targets without it (thumbv6m, AVR) it is only
+//! available with the `portable-atomic` feature.
+//!
+//! # Example Usage
+//! ```ignore
+//! static SENSOR: DriverCell<I2c> = DriverCell::new();
+//!
+//! fn main() {
+//!     SENSOR.init(MicsVz89Te::new(i2c)).ok().unwrap();
+//! }
+//!
+//! #[interrupt]
+//! fn TIMER0() {
+//!     if let Some(Ok(measurements)) = SENSOR.with(|device| device.read_measurements(&mut delay)) {
+//!         ...
+//!     }
+//! }
+//! ```
+
+use core::{cell::UnsafeCell, mem::MaybeUninit};
+
+#[cfg(not(feature = "portable-atomic"))]
+use core::sync::atomic::{AtomicU8, Ordering};
+#[cfg(feature = "portable-atomic")]
+use portable_atomic::{AtomicU8, Ordering};
+
+use crate::MicsVz89Te;
+
+const EMPTY: u8 = 0;
+const INITIALIZING: u8 = 1;
+const READY: u8 = 2;
+const IN_USE: u8 = 3;
+
+/// Cell which can be initialized once with a driver and gives exclusive access to it.
+pub struct DriverCell<I2C> {
+    state: AtomicU8,
+    driver: UnsafeCell<MaybeUninit<MicsVz89Te<I2C>>>,
+}
+
+// SAFETY: the driver is only accessed by the context which moved the state to `INITIALIZING`
+// or `IN_USE`, so there is never more than one reference to it.
+unsafe impl<I2C: Send> Sync for DriverCell<I2C> {}
+
+impl<I2C> Default for DriverCell<I2C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I2C> DriverCell<I2C> {
+    /// Create an empty cell.
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU8::new(EMPTY),
+            driver: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Store the driver. If the cell was already initialized, the driver is returned.
+    pub fn init(&self, driver: MicsVz89Te<I2C>) -> Result<(), MicsVz89Te<I2C>> {
+        if self
+            .state
+            .compare_exchange(EMPTY, INITIALIZING, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            return Err(driver);
+        }
+        // SAFETY: only this context moved the state from `EMPTY`.
+        unsafe { (*self.driver.get()).write(driver) };
+        self.state.store(READY, Ordering::Release);
+        Ok(())
+    }
+
+    /// Returns `true` if the cell holds a driver.
+    pub fn is_initialized(&self) -> bool {
+        matches!(self.state.load(Ordering::Acquire), READY | IN_USE)
+    }
+
+    /// Run `f` with exclusive access to the driver.
+    ///
+    /// Returns `None` if the cell isn't initialized or the driver is used by another context.
+    pub fn with<R>(&self, f: impl FnOnce(&mut MicsVz89Te<I2C>) -> R) -> Option<R> {
+        self.state
+            .compare_exchange(READY, IN_USE, Ordering::Acquire, Ordering::Relaxed)
+            .ok()?;
+        // SAFETY: the cell is initialized and only this context moved the state to `IN_USE`.
+        let result = f(unsafe { (*self.driver.get()).assume_init_mut() });
+        self.state.store(READY, Ordering::Release);
+        Some(result)
+    }
+}
+
+impl<I2C> Drop for DriverCell<I2C> {
+    fn drop(&mut self) {
+        if *self.state.get_mut() == READY {
+            // SAFETY: the cell is initialized and not used anymore.
+            unsafe { self.driver.get_mut().assume_init_drop() };
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::DriverCell;
+    use crate::MicsVz89Te;
+    use core::assert_eq;
+    use embedded_hal_mock::{
+        delay::MockNoop as DelayMock,
+        i2c::{Mock as I2cMock, Transaction as I2cTransaction},
+    };
+    use std::vec;
+
+    #[test]
+    fn test_driver_cell() {
+        let expectations = [
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+        ];
+        let cell = DriverCell::new();
+        assert!(cell.with(|_| ()).is_none());
+
+        assert!(cell
+            .init(MicsVz89Te::new(I2cMock::new(&expectations)))
+            .is_ok());
+        assert!(cell.init(MicsVz89Te::new(I2cMock::new(&[]))).is_err());
+        assert!(cell.is_initialized());
+
+        let co2 = cell.with(|device| {
+            // nested access is refused
+            assert!(cell.with(|_| ()).is_none());
+            device.read_measurements(&mut DelayMock::new()).unwrap().co2
+        });
+        assert_eq!(co2.map(|co2| co2 as u32), Some(728));
+    }
+}
@@ -0,0 +1,130 @@
+//! Weighted aggregation of multiple sensors into one zone estimate.
+//!
+//! A building management system consumes one value per zone rather than the raw streams of all
+//! sensors in it. [estimate()] combines the results of a [sweep](crate::sweep) into a
+//! [ZoneEstimate]: the weighted mean of the sensors which delivered a reading, with the spread
+//! between them and the share of the weight that contributed as uncertainty indicators.
+//!
+//! # Example Usage
+//! ```ignore
+//! // sensor 0 at the desks counts double, sensor 3 is next to the door
+//! let weights = [2.0, 1.0, 1.0, 0.5];
+//! let results = sweep(&mut drivers, &mut delay);
+//! if let Some(zone) = estimate(&results, &weights).filter(|z| z.coverage >= 0.5) {
+//!     bms.report(zone.measurements.co2, zone.spread.co2);
+//! }
+//! ```
+
+use crate::{math, Measurements};
+
+/// Combined measurements of the sensors of a zone.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ZoneEstimate {
+    /// Weighted mean of the contributing sensors.
+    pub measurements: Measurements,
+    /// Weighted standard deviation of the contributing sensors per channel. `0` if only one
+    /// sensor contributed.
+    pub spread: Measurements,
+    /// Weight of the contributing sensors relative to the weight of all sensors, `0..=1`.
+    pub coverage: f32,
+    /// Number of contributing sensors.
+    pub sensors: usize,
+}
+
+/// Combine the `results` of the sensors of a zone with the `weights` at the same index.
+///
+/// Sensors with a failed read, a weight which isn't positive or non-finite values are excluded.
+/// Results without weight are ignored. Returns `None` if no sensor contributes.
+pub fn estimate<E>(results: &[Result<Measurements, E>], weights: &[f32]) -> Option<ZoneEstimate> {
+    let valid_weight = |w: f32| w.is_finite() && w > 0.0;
+    let total_weight: f32 = weights
+        .iter()
+        .take(results.len())
+        .copied()
+        .filter(|w| valid_weight(*w))
+        .sum();
+    let contributing = || {
+        results
+            .iter()
+            .zip(weights.iter().copied())
+            .filter_map(|(result, weight)| match result {
+                Ok(m) if valid_weight(weight) && m.co2.is_finite() && m.voc.is_finite() => {
+                    Some((m, weight))
+                }
+                _ => None,
+            })
+    };
+
+    let (mut weight, mut co2, mut voc, mut sensors) = (0.0, 0.0, 0.0, 0);
+    for (m, w) in contributing() {
+        weight += w;
+        co2 += m.co2 * w;
+        voc += m.voc * w;
+        sensors += 1;
+    }
+    if sensors == 0 {
+        return None;
+    }
+    let mean = Measurements {
+        co2: co2 / weight,
+        voc: voc / weight,
+    };
+
+    let (mut co2_var, mut voc_var) = (0.0, 0.0);
+    for (m, w) in contributing() {
+        co2_var += w * (m.co2 - mean.co2) * (m.co2 - mean.co2);
+        voc_var += w * (m.voc - mean.voc) * (m.voc - mean.voc);
+    }
+
+    Some(ZoneEstimate {
+        measurements: mean,
+        spread: Measurements {
+            co2: math::sqrt(co2_var / weight),
+            voc: math::sqrt(voc_var / weight),
+        },
+        coverage: weight / total_weight,
+        sensors,
+    })
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::estimate;
+    use crate::Measurements;
+    use core::assert_eq;
+
+    fn m(co2: f32, voc: f32) -> Measurements {
+        Measurements { co2, voc }
+    }
+
+    #[test]
+    fn test_weighted_estimate() {
+        let results = [
+            Ok(m(600.0, 100.0)),
+            Ok(m(900.0, 400.0)),
+            Err(()),
+            Ok(m(2000.0, 1000.0)),
+        ];
+        // the last sensor is disabled
+        let weights = [2.0, 1.0, 1.0, 0.0];
+
+        let zone = estimate(&results, &weights).unwrap();
+
+        assert_eq!(zone.sensors, 2);
+        assert_eq!(zone.measurements, m(700.0, 200.0));
+        assert!((zone.spread.co2 - 141.421).abs() < 1e-2);
+        assert!((zone.spread.voc - 141.421).abs() < 1e-2);
+        assert_eq!(zone.coverage, 0.75);
+    }
+
+    #[test]
+    fn test_estimate_without_sensors() {
+        let results: [Result<Measurements, ()>; 2] = [Err(()), Ok(m(f32::NAN, 0.0))];
+        assert_eq!(estimate(&results, &[1.0, 1.0]), None);
+
+        let single = estimate::<()>(&[Ok(m(500.0, 0.0))], &[1.0]).unwrap();
+        assert_eq!(single.spread, m(0.0, 0.0));
+        assert_eq!(single.coverage, 1.0);
+    }
+}
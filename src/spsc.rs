@@ -0,0 +1,134 @@
+//! Handing samples out of an interrupt through a [heapless](https://docs.rs/heapless) SPSC queue.
+//!
+//! The sampler (e.g. a timer interrupt or a high priority task) owns the
+//! `heapless::spsc::Producer` and the main loop owns the `Consumer`. The producer is a [Sink],
+//! so it can be passed directly to [Publisher::poll()](crate::publish::Publisher::poll()). If
+//! the queue is full, the new sample is dropped: the sampler never blocks and the consumer
+//! always sees the oldest unread samples in order. [CountingProducer] additionally counts the
+//! dropped samples.
+//!
+//! A heapless `Queue<T, N>` holds `N - 1` items. The `heapless` feature enables the module; with
+//! the `portable-atomic` feature the queue also works on targets without native atomics, e.g.
+//! AVR.
+//!
+//! # Example Usage
+//! ```ignore
+//! let queue = cortex_m::singleton!(: Queue<Sample, 9> = Queue::new()).unwrap();
+//! let (producer, mut consumer) = queue.split();
+//! let mut producer = CountingProducer::new(producer);
+//!
+//! // interrupt
+//! publisher.poll(clock.now_ms(), &mut device, &mut delay, &mut producer);
+//!
+//! // main loop
+//! while let Some(sample) = consumer.dequeue() {
+//!     handle(sample);
+//! }
+//! ```
+
+use heapless::spsc::Producer;
+
+use crate::publish::{Sample, Sink};
+
+impl<const N: usize> Sink for &mut Producer<'_, Sample, N> {
+    fn publish(&mut self, sample: Sample) {
+        // a full queue drops the new sample
+        let _ = self.enqueue(sample);
+    }
+}
+
+/// Producer counting the samples dropped because the queue was full.
+pub struct CountingProducer<'a, const N: usize> {
+    producer: Producer<'a, Sample, N>,
+    dropped: u32,
+}
+
+impl<'a, const N: usize> CountingProducer<'a, N> {
+    /// Count the dropped samples of `producer`.
+    pub fn new(producer: Producer<'a, Sample, N>) -> Self {
+        Self {
+            producer,
+            dropped: 0,
+        }
+    }
+
+    /// Number of samples dropped because the queue was full.
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+
+    /// Destroy the counter and return the producer.
+    pub fn release(self) -> Producer<'a, Sample, N> {
+        self.producer
+    }
+}
+
+impl<const N: usize> Sink for &mut CountingProducer<'_, N> {
+    fn publish(&mut self, sample: Sample) {
+        if self.producer.enqueue(sample).is_err() {
+            self.dropped = self.dropped.saturating_add(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::CountingProducer;
+    use crate::{
+        event::Fault,
+        publish::{Publisher, Sample, Sink},
+        MicsVz89Te,
+    };
+    use core::assert_eq;
+    use embedded_hal_mock::{
+        delay::MockNoop as DelayMock,
+        i2c::{Mock as I2cMock, Transaction as I2cTransaction},
+    };
+    use heapless::spsc::Queue;
+    use std::vec;
+
+    fn sample(timestamp_ms: u64) -> Sample {
+        Sample {
+            timestamp_ms,
+            result: Err(Fault::Bus),
+        }
+    }
+
+    #[test]
+    fn test_drops_newest_when_full() {
+        let mut queue = Queue::<Sample, 3>::new();
+        let (producer, mut consumer) = queue.split();
+        let mut producer = CountingProducer::new(producer);
+
+        for t in 1..=3 {
+            (&mut producer).publish(sample(t));
+        }
+        assert_eq!(producer.dropped(), 1);
+
+        assert_eq!(consumer.dequeue(), Some(sample(1)));
+        (&mut producer).publish(sample(4));
+        assert_eq!(consumer.dequeue(), Some(sample(2)));
+        assert_eq!(consumer.dequeue(), Some(sample(4)));
+        assert_eq!(consumer.dequeue(), None);
+        assert_eq!(producer.dropped(), 1);
+    }
+
+    #[test]
+    fn test_publisher_sink() {
+        let expectations = [
+            I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x26]),
+        ];
+        let mut device = MicsVz89Te::new(I2cMock::new(&expectations));
+        let mut queue = Queue::<Sample, 4>::new();
+        let (mut producer, mut consumer) = queue.split();
+
+        let mut publisher = Publisher::new(1000);
+        publisher.poll(0, &mut device, &mut DelayMock::new(), &mut producer);
+
+        let sample = consumer.dequeue().unwrap();
+        assert_eq!(sample.result, Err(Fault::WrongChecksum));
+        device.release().done();
+    }
+}
@@ -0,0 +1,141 @@
+//! Compact status word for watchdog and supervisor telemetry.
+//!
+//! [StatusWord] summarizes the sensor health in 16 bits, small enough for a heartbeat or
+//! watchdog message to a supervisor MCU.
+//!
+//! | bit   | content                                                             |
+//! |-------|---------------------------------------------------------------------|
+//! | 0     | connected: the sensor answered the last request                     |
+//! | 1     | warm-up done, see [WarmUpDetector](crate::warmup::WarmUpDetector)   |
+//! | 2..=3 | last fault: `0` none, `1` bus error, `2` wrong checksum             |
+//! | 4     | CO2 alarm active                                                    |
+//! | 5     | VOC alarm active                                                    |
+//! | 6     | readings stuck, see [StuckWatchdog](crate::watchdog::StuckWatchdog) |
+//! | 7     | unhealthy, see [HealthMonitor](crate::health::HealthMonitor)        |
+//! | 8..   | reserved, written as `0` and ignored by the decoder                 |
+//!
+//! # Example Usage
+//! ```ignore
+//! let mut status = StatusWord::default();
+//! status.record(&device.read_measurements(&mut delay).map_err(|e| Fault::from(&e)));
+//! status.warmed_up = warm_up.is_complete();
+//! status.co2_alarm = co2_threshold.is_active();
+//! supervisor.heartbeat(status.to_bits());
+//! ```
+
+use crate::{event::Fault, Measurements};
+
+/// Bit: the sensor answered the last request.
+pub const CONNECTED: u16 = 1 << 0;
+/// Bit: the warm-up is done.
+pub const WARMED_UP: u16 = 1 << 1;
+/// Position of the 2 bit fault code.
+pub const FAULT_SHIFT: u16 = 2;
+/// Mask of the fault code.
+pub const FAULT_MASK: u16 = 0b11 << FAULT_SHIFT;
+/// Bit: the CO2 alarm is active.
+pub const CO2_ALARM: u16 = 1 << 4;
+/// Bit: the VOC alarm is active.
+pub const VOC_ALARM: u16 = 1 << 5;
+/// Bit: the readings are stuck.
+pub const STUCK: u16 = 1 << 6;
+/// Bit: the sensor is unhealthy.
+pub const UNHEALTHY: u16 = 1 << 7;
+
+/// Summary of the sensor health. See the [module](self) documentation for the bit layout.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StatusWord {
+    pub connected: bool,
+    pub warmed_up: bool,
+    /// Fault of the last request, `None` if it succeeded.
+    pub last_fault: Option<Fault>,
+    pub co2_alarm: bool,
+    pub voc_alarm: bool,
+    pub stuck: bool,
+    pub unhealthy: bool,
+}
+
+impl StatusWord {
+    /// Update the connection state and the last fault with the result of a read.
+    pub fn record(&mut self, result: &Result<Measurements, Fault>) {
+        self.last_fault = result.err();
+        // a wrong checksum still means the sensor answered
+        self.connected = self.last_fault != Some(Fault::Bus);
+    }
+
+    /// Pack the status into its 16 bit representation.
+    pub fn to_bits(&self) -> u16 {
+        let fault = match self.last_fault {
+            None => 0,
+            Some(Fault::Bus) => 1,
+            Some(Fault::WrongChecksum) => 2,
+        };
+        [
+            (self.connected, CONNECTED),
+            (self.warmed_up, WARMED_UP),
+            (self.co2_alarm, CO2_ALARM),
+            (self.voc_alarm, VOC_ALARM),
+            (self.stuck, STUCK),
+            (self.unhealthy, UNHEALTHY),
+        ]
+        .into_iter()
+        .filter(|(set, _)| *set)
+        .fold(fault << FAULT_SHIFT, |bits, (_, bit)| bits | bit)
+    }
+
+    /// Unpack a status written by [StatusWord::to_bits()]. Reserved bits are ignored.
+    ///
+    /// Returns `None` if the fault code is unknown.
+    pub fn from_bits(bits: u16) -> Option<Self> {
+        let last_fault = match (bits & FAULT_MASK) >> FAULT_SHIFT {
+            0 => None,
+            1 => Some(Fault::Bus),
+            2 => Some(Fault::WrongChecksum),
+            _ => return None,
+        };
+        let set = |bit: u16| bits & bit != 0;
+        Some(Self {
+            connected: set(CONNECTED),
+            warmed_up: set(WARMED_UP),
+            last_fault,
+            co2_alarm: set(CO2_ALARM),
+            voc_alarm: set(VOC_ALARM),
+            stuck: set(STUCK),
+            unhealthy: set(UNHEALTHY),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{StatusWord, CO2_ALARM, CONNECTED, WARMED_UP};
+    use crate::{event::Fault, Measurements};
+    use core::assert_eq;
+
+    #[test]
+    fn test_roundtrip() {
+        let mut status = StatusWord::default();
+        status.record(&Ok(Measurements {
+            co2: 800.0,
+            voc: 50.0,
+        }));
+        status.warmed_up = true;
+        status.co2_alarm = true;
+        assert_eq!(status.to_bits(), CONNECTED | WARMED_UP | CO2_ALARM);
+        assert_eq!(StatusWord::from_bits(status.to_bits()), Some(status));
+
+        status.record(&Err(Fault::Bus));
+        assert!(!status.connected);
+        assert_eq!(status.to_bits(), 0b0001_0110);
+        assert_eq!(StatusWord::from_bits(status.to_bits()), Some(status));
+
+        status.record(&Err(Fault::WrongChecksum));
+        assert!(status.connected);
+        assert_eq!(
+            StatusWord::from_bits(status.to_bits() | 0xFF00),
+            Some(status)
+        );
+        assert_eq!(StatusWord::from_bits(0b1100), None);
+    }
+}
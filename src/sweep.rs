@@ -0,0 +1,159 @@
+//! Pipelined reads of multiple sensors.
+//!
+//! Reading `N` sensors one after another costs `N` times the wait between request and response.
+//! A sweep sends the requests to all sensors first, waits once and then collects the responses
+//! in the same order, so the waits overlap: 8 sensors take about 100 ms instead of 800 ms.
+//!
+//! [sweep()] reads sensors with their own drivers, e.g. at different addresses or on different
+//! buses. [sweep_mux()] reads sensors with the same address behind an I2C multiplexer through
+//! one driver, selecting the channel of each sensor before it is accessed.
+//!
+//! Failed requests aren't repeated, [Config::retries](crate::config::Config::retries) doesn't
+//! apply to sweeps. A sensor whose request failed is skipped when collecting.
+//!
+//! # Example Usage
+//! with a TCA9548A multiplexer at address `0x74`
+//! ```ignore
+//! let results: [_; 8] = sweep_mux(&mut device, &mut delay, |i2c, channel| {
+//!     i2c.write(0x74, &[1u8 << channel])
+//! });
+//! ```
+
+use crate::{
+    delay::Delay, error::PacketParseError, transport::Transport, Measurements, MicsVz89Te,
+};
+
+/// Read all `drivers` with overlapping waits. Returns the results in the order of the drivers.
+///
+/// Waits for the longest [Config::wait_time_ms](crate::config::Config::wait_time_ms) of the
+/// drivers.
+pub fn sweep<I2C, E, const N: usize>(
+    drivers: &mut [MicsVz89Te<I2C>; N],
+    delay: &mut impl Delay,
+) -> [Result<Measurements, PacketParseError<E>>; N]
+where
+    I2C: Transport<Error = E>,
+{
+    let mut failed: [Option<PacketParseError<E>>; N] =
+        core::array::from_fn(|i| drivers[i].start_measurement().err());
+
+    if failed.iter().any(Option::is_none) {
+        let wait_ms = drivers
+            .iter()
+            .map(|driver| driver.config().wait_time_ms)
+            .max()
+            .unwrap_or(0);
+        delay.delay_ms(wait_ms);
+    }
+
+    core::array::from_fn(|i| match failed[i].take() {
+        Some(e) => Err(e),
+        None => drivers[i].get_measurement_result(),
+    })
+}
+
+/// Read `N` sensors behind a multiplexer with overlapping waits. `select` switches the
+/// multiplexer to the given channel `0..N`. Returns the results in the order of the channels.
+///
+/// If selecting a channel fails, its bus error is the result of the channel.
+pub fn sweep_mux<I2C, E, const N: usize>(
+    driver: &mut MicsVz89Te<I2C>,
+    delay: &mut impl Delay,
+    mut select: impl FnMut(&mut I2C, usize) -> Result<(), E>,
+) -> [Result<Measurements, PacketParseError<E>>; N]
+where
+    I2C: Transport<Error = E>,
+{
+    let mut failed: [Option<PacketParseError<E>>; N] = core::array::from_fn(|channel| {
+        select(driver.bus_mut(), channel)
+            .map_err(PacketParseError::from)
+            .and_then(|()| driver.start_measurement())
+            .err()
+    });
+
+    if failed.iter().any(Option::is_none) {
+        delay.delay_ms(driver.config().wait_time_ms);
+    }
+
+    core::array::from_fn(|channel| match failed[channel].take() {
+        Some(e) => Err(e),
+        None => select(driver.bus_mut(), channel)
+            .map_err(PacketParseError::from)
+            .and_then(|()| driver.get_measurement_result()),
+    })
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{sweep, sweep_mux};
+    use crate::delay::Delay;
+    use crate::{error::PacketParseError, MicsVz89Te};
+    use assert_matches::assert_matches;
+    use core::assert_eq;
+    use embedded_hal::blocking::i2c::Write;
+    use embedded_hal_mock::{
+        i2c::{Mock as I2cMock, Transaction as I2cTransaction},
+        MockError,
+    };
+    use std::{io::ErrorKind, vec};
+
+    struct CountingDelay(u32);
+
+    impl Delay for CountingDelay {
+        fn delay_ms(&mut self, ms: u16) {
+            self.0 += u32::from(ms);
+        }
+    }
+
+    #[test]
+    fn test_sweep() {
+        let status = || I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]);
+        let mut drivers = [
+            MicsVz89Te::new(I2cMock::new(&[
+                status(),
+                I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+            ])),
+            MicsVz89Te::new(I2cMock::new(&[
+                status().with_error(MockError::Io(ErrorKind::Other))
+            ])),
+        ];
+        let mut delay = CountingDelay(0);
+
+        let [first, second] = sweep(&mut drivers, &mut delay);
+
+        assert_matches!(first, Ok(m) if m.co2_ppm_u16() == 728);
+        assert_matches!(second, Err(PacketParseError::BusError(_)));
+        assert_eq!(delay.0, 100);
+        for driver in drivers {
+            driver.release().done();
+        }
+    }
+
+    #[test]
+    fn test_sweep_mux() {
+        let select = |channel: u8| I2cTransaction::write(0x74, vec![1 << channel]);
+        let status = I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]);
+        let response = I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]);
+        let expectations = [
+            select(0),
+            status.clone(),
+            select(1),
+            status,
+            select(0),
+            response.clone(),
+            select(1),
+            response,
+        ];
+        let mut device = MicsVz89Te::new(I2cMock::new(&expectations));
+        let mut delay = CountingDelay(0);
+
+        let results: [_; 2] = sweep_mux(&mut device, &mut delay, |i2c, channel| {
+            i2c.write(0x74, &[1u8 << channel])
+        });
+
+        assert!(results.iter().all(|r| r.is_ok()));
+        assert_eq!(delay.0, 100);
+        device.release().done();
+    }
+}
@@ -0,0 +1,188 @@
+//! Flagging of readings taken outside the specified operating temperature.
+//!
+//! The sensor is specified for ambient temperatures from 0 to 50 °C. Outside of this range the
+//! readings are still reported, but their accuracy is unknown. [TemperatureMonitor] rates each
+//! reading with the ambient temperature measured by another sensor, derates its confidence
+//! and reports changes of the operating condition as [Event]s.
+//!
+//! # Example Usage
+//! ```ignore
+//! let mut monitor = TemperatureMonitor::new(TemperatureLimits::default());
+//! let m = device.read_measurements(&mut delay)?;
+//! let (rated, event) = monitor.update(now_ms, &m, sht.temperature()?);
+//! if let Some(event) = event {
+//!     events.push(event);
+//! }
+//! display.show(&rated.measurements, rated.confidence);
+//! ```
+
+use core::ops::RangeInclusive;
+
+use crate::{
+    event::{Event, EventKind},
+    Measurements,
+};
+
+/// Specified operating temperature range of the sensor in °C.
+pub const OPERATING_RANGE_CELSIUS: RangeInclusive<f32> = 0.0..=50.0;
+
+/// Operating condition of a reading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemperatureStatus {
+    /// Within the operating range.
+    InRange,
+    /// Colder than the operating range.
+    BelowRange,
+    /// Hotter than the operating range.
+    AboveRange,
+}
+
+/// Limits and derating of [TemperatureMonitor].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TemperatureLimits {
+    /// Lowest temperature (in °C) in spec.
+    pub min_celsius: f32,
+    /// Highest temperature (in °C) in spec.
+    pub max_celsius: f32,
+    /// Confidence lost per °C outside of the limits. `0.0` disables derating.
+    pub derating_per_celsius: f32,
+}
+
+impl Default for TemperatureLimits {
+    fn default() -> Self {
+        Self {
+            min_celsius: *OPERATING_RANGE_CELSIUS.start(),
+            max_celsius: *OPERATING_RANGE_CELSIUS.end(),
+            derating_per_celsius: 0.05,
+        }
+    }
+}
+
+impl TemperatureLimits {
+    /// Operating condition at `celsius`.
+    pub fn status(&self, celsius: f32) -> TemperatureStatus {
+        if celsius < self.min_celsius {
+            TemperatureStatus::BelowRange
+        } else if celsius > self.max_celsius {
+            TemperatureStatus::AboveRange
+        } else {
+            TemperatureStatus::InRange
+        }
+    }
+
+    /// Confidence (`0.0..=1.0`) of a reading taken at `celsius`.
+    pub fn confidence(&self, celsius: f32) -> f32 {
+        let outside = (self.min_celsius - celsius).max(celsius - self.max_celsius);
+        (1.0 - outside.max(0.0) * self.derating_per_celsius).clamp(0.0, 1.0)
+    }
+}
+
+/// A reading with the ambient temperature it was taken at.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RatedMeasurements {
+    pub measurements: Measurements,
+    pub temperature_celsius: f32,
+    pub status: TemperatureStatus,
+    /// `1.0` within the limits, lower outside. See [TemperatureLimits::derating_per_celsius].
+    pub confidence: f32,
+}
+
+/// Rates readings by ambient temperature and tracks changes of the operating condition.
+#[derive(Debug, Clone)]
+pub struct TemperatureMonitor {
+    limits: TemperatureLimits,
+    status: Option<TemperatureStatus>,
+}
+
+impl TemperatureMonitor {
+    /// Create a monitor with the given limits.
+    pub const fn new(limits: TemperatureLimits) -> Self {
+        Self {
+            limits,
+            status: None,
+        }
+    }
+
+    /// Operating condition of the last reading.
+    pub fn status(&self) -> Option<TemperatureStatus> {
+        self.status
+    }
+
+    /// Rate `measurements` taken at `celsius`.
+    ///
+    /// Returns an [EventKind::OperatingTemperature] event if the operating condition changed.
+    /// The first reading only reports an event if it is out of range.
+    pub fn update(
+        &mut self,
+        timestamp_ms: u64,
+        measurements: &Measurements,
+        celsius: f32,
+    ) -> (RatedMeasurements, Option<Event>) {
+        let status = self.limits.status(celsius);
+        let changed = match self.status {
+            Some(last) => last != status,
+            None => status != TemperatureStatus::InRange,
+        };
+        self.status = Some(status);
+
+        let rated = RatedMeasurements {
+            measurements: *measurements,
+            temperature_celsius: celsius,
+            status,
+            confidence: self.limits.confidence(celsius),
+        };
+        let event = changed.then(|| {
+            Event::new(
+                timestamp_ms,
+                EventKind::OperatingTemperature { status, celsius },
+            )
+        });
+        (rated, event)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{TemperatureLimits, TemperatureMonitor, TemperatureStatus};
+    use crate::{event::EventKind, Measurements};
+    use core::assert_eq;
+
+    #[test]
+    fn test_confidence() {
+        let limits = TemperatureLimits::default();
+        assert_eq!(limits.confidence(25.0), 1.0);
+        assert_eq!(limits.confidence(50.0), 1.0);
+        assert!((limits.confidence(54.0) - 0.8).abs() < 1e-6);
+        assert!((limits.confidence(-2.0) - 0.9).abs() < 1e-6);
+        assert_eq!(limits.confidence(-40.0), 0.0);
+    }
+
+    #[test]
+    fn test_status_events() {
+        let m = Measurements {
+            co2: 600.0,
+            voc: 50.0,
+        };
+        let mut monitor = TemperatureMonitor::new(TemperatureLimits::default());
+
+        let (rated, event) = monitor.update(0, &m, 22.0);
+        assert_eq!(rated.status, TemperatureStatus::InRange);
+        assert!(event.is_none());
+
+        let (rated, event) = monitor.update(1000, &m, 52.0);
+        assert_eq!(rated.status, TemperatureStatus::AboveRange);
+        assert_eq!(
+            event.map(|e| e.kind),
+            Some(EventKind::OperatingTemperature {
+                status: TemperatureStatus::AboveRange,
+                celsius: 52.0
+            })
+        );
+        assert!(monitor.update(2000, &m, 53.0).1.is_none());
+        assert!(monitor.update(3000, &m, 45.0).1.is_some());
+
+        let mut monitor = TemperatureMonitor::new(TemperatureLimits::default());
+        assert!(monitor.update(0, &m, -5.0).1.is_some());
+    }
+}
@@ -0,0 +1,132 @@
+//! Threshold monitoring with hysteresis.
+
+use crate::{Channel, Measurements};
+
+/// Change of the state of a [Threshold].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Crossing {
+    /// The value rose above the limit.
+    Raised,
+    /// The value fell below the limit minus the hysteresis.
+    Cleared,
+}
+
+/// Monitors one channel against an upper limit.
+///
+/// The threshold is raised when the value exceeds `limit` and cleared when it falls below
+/// `limit - hysteresis`, so values oscillating around the limit don't toggle the state.
+/// Additionally the state can be debounced, see [Threshold::with_debounce()].
+#[derive(Debug, Clone, Copy)]
+pub struct Threshold {
+    channel: Channel,
+    limit: f32,
+    hysteresis: f32,
+    raise_samples: u8,
+    clear_samples: u8,
+    pending: u8,
+    active: bool,
+}
+
+impl Threshold {
+    /// Create a threshold on `channel`. A negative `hysteresis` is treated as `0`.
+    pub const fn new(channel: Channel, limit: f32, hysteresis: f32) -> Self {
+        Self {
+            channel,
+            limit,
+            hysteresis: if hysteresis > 0.0 { hysteresis } else { 0.0 },
+            raise_samples: 1,
+            clear_samples: 1,
+            pending: 0,
+            active: false,
+        }
+    }
+
+    /// Require `raise_samples` consecutive samples above the limit before raising and
+    /// `clear_samples` consecutive samples below the cleared level before clearing.
+    /// A count of `0` is treated as `1`, which is the default.
+    pub const fn with_debounce(mut self, raise_samples: u8, clear_samples: u8) -> Self {
+        self.raise_samples = if raise_samples == 0 { 1 } else { raise_samples };
+        self.clear_samples = if clear_samples == 0 { 1 } else { clear_samples };
+        self
+    }
+
+    /// Monitored channel.
+    pub fn channel(&self) -> Channel {
+        self.channel
+    }
+
+    /// Upper limit of the threshold.
+    pub fn limit(&self) -> f32 {
+        self.limit
+    }
+
+    /// Returns `true` while the threshold is raised.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Feed a new measurement. Returns the crossing if the state changed.
+    pub fn update(&mut self, measurements: &Measurements) -> Option<Crossing> {
+        let value = measurements.get(self.channel);
+        let (beyond, required) = if self.active {
+            (value < self.limit - self.hysteresis, self.clear_samples)
+        } else {
+            (value > self.limit, self.raise_samples)
+        };
+        if !beyond {
+            self.pending = 0;
+            return None;
+        }
+        self.pending = self.pending.saturating_add(1);
+        if self.pending < required {
+            return None;
+        }
+        self.pending = 0;
+        self.active = !self.active;
+        Some(if self.active {
+            Crossing::Raised
+        } else {
+            Crossing::Cleared
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{Crossing, Threshold};
+    use crate::{Channel, Measurements};
+    use core::assert_eq;
+
+    #[test]
+    fn test_threshold_hysteresis() {
+        let mut threshold = Threshold::new(Channel::Co2, 1000.0, 50.0);
+        let mut update = |co2| threshold.update(&Measurements { co2, voc: 0.0 });
+
+        assert_eq!(update(900.0), None);
+        assert_eq!(update(1010.0), Some(Crossing::Raised));
+        assert_eq!(update(1020.0), None);
+        assert_eq!(update(980.0), None);
+        assert_eq!(update(940.0), Some(Crossing::Cleared));
+        assert_eq!(update(990.0), None);
+    }
+
+    #[test]
+    fn test_threshold_debounce() {
+        let mut threshold = Threshold::new(Channel::Voc, 500.0, 0.0).with_debounce(3, 2);
+        let mut update = |voc| threshold.update(&Measurements { co2: 400.0, voc });
+
+        // short spike
+        assert_eq!(update(800.0), None);
+        assert_eq!(update(900.0), None);
+        assert_eq!(update(300.0), None);
+
+        assert_eq!(update(600.0), None);
+        assert_eq!(update(600.0), None);
+        assert_eq!(update(600.0), Some(Crossing::Raised));
+        assert_eq!(update(400.0), None);
+        assert_eq!(update(600.0), None);
+        assert_eq!(update(400.0), None);
+        assert_eq!(update(400.0), Some(Crossing::Cleared));
+    }
+}
@@ -0,0 +1,113 @@
+//! Carrier of the request and response frames.
+//!
+//! The driver doesn't depend on I2C itself, it only writes request frames and reads response
+//! frames through a [Transport]. With the `eh0` feature (enabled by default) every bus
+//! implementing the embedded-hal 0.2 blocking I2C `Read` and `Write` traits with the same error
+//! type is a transport, so nothing changes for I2C users. embedded-hal 1.0 buses are wrapped in
+//! `Eh1Bus` (`eh1` feature). To run the same command and parse logic over another carrier, e.g.
+//! RPC to a co-processor which owns the sensor or a remote probe, implement [Transport] for it
+//! and pass it to [MicsVz89Te::new()](crate::MicsVz89Te::new()).
+//!
+//! # Example Usage
+//! ```ignore
+//! struct Coprocessor<'a>(&'a mut Rpc);
+//!
+//! impl Transport for Coprocessor<'_> {
+//!     type Error = RpcError;
+//!
+//!     fn write_frame(&mut self, address: u8, frame: &[u8]) -> Result<(), RpcError> {
+//!         self.0.call(Method::SensorWrite, address, frame)
+//!     }
+//!
+//!     fn read_frame(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), RpcError> {
+//!         self.0.call_into(Method::SensorRead, address, buffer)
+//!     }
+//! }
+//!
+//! let mut device = MicsVz89Te::new(Coprocessor(&mut rpc));
+//! let measurements = device.read_measurements(&mut delay)?;
+//! ```
+
+#[cfg(any(feature = "eh0", test))]
+use embedded_hal::blocking::i2c::{Read, Write};
+
+/// Minimal interface the driver needs to talk to a sensor.
+pub trait Transport {
+    type Error;
+
+    /// Send the request `frame` to the sensor at `address`.
+    fn write_frame(&mut self, address: u8, frame: &[u8]) -> Result<(), Self::Error>;
+
+    /// Receive a response frame from the sensor at `address`, filling `buffer`.
+    fn read_frame(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Self::Error>;
+}
+
+#[cfg(any(feature = "eh0", test))]
+impl<I2C, E> Transport for I2C
+where
+    I2C: Read<Error = E> + Write<Error = E>,
+{
+    type Error = E;
+
+    fn write_frame(&mut self, address: u8, frame: &[u8]) -> Result<(), E> {
+        self.write(address, frame)
+    }
+
+    fn read_frame(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), E> {
+        self.read(address, buffer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::Transport;
+    use crate::{error::PacketParseError, MicsVz89Te};
+    use assert_matches::assert_matches;
+    use core::assert_eq;
+    use embedded_hal_mock::delay::MockNoop as DelayMock;
+
+    /// Loopback to a sensor simulated in the same process, answering every request with a
+    /// status frame.
+    struct Loopback {
+        requests: usize,
+        broken: bool,
+    }
+
+    impl Transport for Loopback {
+        type Error = &'static str;
+
+        fn write_frame(&mut self, address: u8, frame: &[u8]) -> Result<(), Self::Error> {
+            assert_eq!((address, frame), (0x70, &[0x0C, 0, 0, 0, 0, 0xF3][..]));
+            self.requests += 1;
+            Ok(())
+        }
+
+        fn read_frame(&mut self, _address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+            if self.broken {
+                return Err("link down");
+            }
+            buffer.copy_from_slice(&[0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_custom_transport() {
+        let mut device = MicsVz89Te::new(Loopback {
+            requests: 0,
+            broken: false,
+        });
+        let mut delay = DelayMock::new();
+
+        let m = device.read_measurements(&mut delay).unwrap();
+        assert_eq!(m.co2_ppm_u16(), 728);
+
+        device.bus_mut().broken = true;
+        assert_matches!(
+            device.read_measurements(&mut delay),
+            Err(PacketParseError::BusError("link down"))
+        );
+        assert_eq!(device.release().requests, 2);
+    }
+}
@@ -0,0 +1,143 @@
+//! Classification of the recent trajectory of CO2 and VOC.
+//!
+//! A line is fitted through the recent samples. The trend is only reported as rising or
+//! falling if the slope is steep enough and clearly exceeds the noise of the samples, so
+//! noisy but flat readings stay [Direction::Stable].
+
+#[cfg(any(feature = "alloc", doc, test))]
+use crate::history::VecHistory;
+use crate::{history::History, math, Channel, Measurements};
+
+/// Direction of a trend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Rising,
+    Falling,
+    Stable,
+}
+
+/// Trend of one channel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Trend {
+    pub direction: Direction,
+    /// Fitted slope per minute (ppm/min for CO2, ppb/min for VOC).
+    pub slope_per_min: f32,
+    /// Standard error of the slope per minute, an estimate of the noise.
+    pub slope_std_err_per_min: f32,
+}
+
+/// Parameters of the classification.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrendConfig {
+    /// Interval between two samples in millis.
+    pub sample_interval_ms: u32,
+    /// Minimum absolute slope per minute for a rising or falling trend.
+    pub min_slope_per_min: f32,
+    /// The slope must exceed its standard error by this factor.
+    pub noise_factor: f32,
+}
+
+impl Default for TrendConfig {
+    fn default() -> Self {
+        Self {
+            sample_interval_ms: 1000,
+            min_slope_per_min: 5.0,
+            noise_factor: 2.0,
+        }
+    }
+}
+
+impl TrendConfig {
+    /// Classify the trend of `channel` over `samples`, oldest first, taken every
+    /// [TrendConfig::sample_interval_ms]. Returns `None` for less than 3 samples.
+    pub fn classify<'a>(
+        &self,
+        samples: impl Iterator<Item = &'a Measurements> + Clone,
+        channel: Channel,
+    ) -> Option<Trend> {
+        let fit = math::linear_fit(samples.map(|m| m.get(channel)))?;
+        let steps_per_min = 60_000.0 / self.sample_interval_ms.max(1) as f32;
+        let slope = fit.slope * steps_per_min;
+        let std_err = fit.slope_std_err * steps_per_min;
+
+        let direction =
+            if slope.abs() < self.min_slope_per_min || slope.abs() <= self.noise_factor * std_err {
+                Direction::Stable
+            } else if slope > 0.0 {
+                Direction::Rising
+            } else {
+                Direction::Falling
+            };
+        Some(Trend {
+            direction,
+            slope_per_min: slope,
+            slope_std_err_per_min: std_err,
+        })
+    }
+}
+
+impl<const N: usize> History<N> {
+    /// Trend of `channel` over the stored measurements. See [TrendConfig::classify()].
+    pub fn trend(&self, channel: Channel, config: &TrendConfig) -> Option<Trend> {
+        config.classify(self.iter(), channel)
+    }
+}
+
+#[cfg(any(feature = "alloc", doc, test))]
+impl VecHistory {
+    /// Trend of `channel` over the stored measurements. See [TrendConfig::classify()].
+    pub fn trend(&self, channel: Channel, config: &TrendConfig) -> Option<Trend> {
+        config.classify(self.iter(), channel)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{Direction, TrendConfig};
+    use crate::{history::History, Channel, Measurements};
+    use core::assert_eq;
+
+    fn history(co2: &[f32]) -> History<10> {
+        let mut history = History::new();
+        for &co2 in co2 {
+            history.push(Measurements { co2, voc: 0.0 });
+        }
+        history
+    }
+
+    #[test]
+    fn test_trend() {
+        let config = TrendConfig {
+            sample_interval_ms: 60_000,
+            ..Default::default()
+        };
+
+        let rising = history(&[500.0, 520.0, 535.0, 560.0, 580.0])
+            .trend(Channel::Co2, &config)
+            .unwrap();
+        assert_eq!(rising.direction, Direction::Rising);
+        assert!((rising.slope_per_min - 20.0).abs() < 1e-3);
+
+        let falling = history(&[600.0, 580.0, 560.0, 540.0])
+            .trend(Channel::Co2, &config)
+            .unwrap();
+        assert_eq!(falling.direction, Direction::Falling);
+
+        // noisy without clear slope
+        let noisy = history(&[500.0, 560.0, 490.0, 570.0, 510.0, 580.0])
+            .trend(Channel::Co2, &config)
+            .unwrap();
+        assert_eq!(noisy.direction, Direction::Stable);
+        assert!(noisy.slope_per_min > config.min_slope_per_min);
+
+        let flat = history(&[500.0, 501.0, 500.0, 502.0])
+            .trend(Channel::Co2, &config)
+            .unwrap();
+        assert_eq!(flat.direction, Direction::Stable);
+
+        assert!(history(&[500.0, 510.0])
+            .trend(Channel::Co2, &config)
+            .is_none());
+    }
+}
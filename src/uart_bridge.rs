@@ -0,0 +1,351 @@
+//! Transport tunneling the sensor protocol over a UART bridge.
+//!
+//! For installations where the sensor sits meters away from the MCU, a small bridge next to the
+//! sensor forwards I2C transfers received over a serial link. [UartBridge] is the MCU side of
+//! that link and implements [Transport], so the driver runs over it unchanged.
+//!
+//! Every transfer is a request answered by a response (checksum as in the sensor protocol,
+//! over all bytes between sync and checksum):
+//!
+//! | request            | response           |
+//! |--------------------|--------------------|
+//! | sync `0xA5`        | sync `0xA5`        |
+//! | sequence number    | sequence number    |
+//! | op                 | status             |
+//! | I2C address        | payload length `n` |
+//! | payload length `n` | payload (`n`)      |
+//! | payload (`n`)      | checksum           |
+//! | checksum           |                    |
+//!
+//! The op is [OP_WRITE] with the bytes to write as payload (answered without payload) or
+//! [OP_READ] with the number of bytes to read as payload. The status is [STATUS_OK] or
+//! [STATUS_NACK] if the sensor didn't acknowledge the transfer.
+//!
+//! A request whose response doesn't arrive within the timeout or is corrupted is sent again
+//! with a new sequence number, responses with an old sequence number are dropped.
+//!
+//! # Example Usage
+//! ```ignore
+//! let serial = Serial::new(dp.USART2, (tx, rx), 115_200.bps(), &clocks);
+//! let bridge = UartBridge::new(serial, &clock);
+//! let mut device = MicsVz89Te::new(bridge);
+//! let measurements = device.read_measurements(&mut delay)?;
+//! ```
+
+use embedded_hal::serial;
+
+use crate::{clock::Clock, protocol::gen_checksum, transport::Transport};
+
+/// First byte of every request and response.
+pub const SYNC: u8 = 0xA5;
+/// Op of a write request.
+pub const OP_WRITE: u8 = 0x01;
+/// Op of a read request.
+pub const OP_READ: u8 = 0x02;
+/// Status of a successful transfer.
+pub const STATUS_OK: u8 = 0x00;
+/// Status of a transfer the sensor didn't acknowledge.
+pub const STATUS_NACK: u8 = 0x01;
+/// Maximum payload length of a request or response.
+pub const MAX_PAYLOAD: usize = 16;
+
+/// Timing of the bridge link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BridgeConfig {
+    /// Time (in millis) to wait for a response after sending a request.
+    pub timeout_ms: u32,
+    /// How often a request is sent again after a timeout or a corrupted response.
+    pub retransmits: u8,
+}
+
+impl Default for BridgeConfig {
+    fn default() -> Self {
+        Self {
+            timeout_ms: 50,
+            retransmits: 2,
+        }
+    }
+}
+
+/// Errors of a [UartBridge].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BridgeError<E> {
+    /// Error of the serial interface.
+    Serial(E),
+    /// The sensor didn't acknowledge the transfer.
+    Nack,
+    /// No response arrived in time, including retransmits.
+    Timeout,
+    /// The last response was corrupted, e.g. by a wrong checksum.
+    Corrupted,
+}
+
+/// MCU side of a UART bridge to a remote sensor.
+///
+/// Takes a serial interface implementing both halves with the same error type.
+pub struct UartBridge<S, C> {
+    serial: S,
+    clock: C,
+    config: BridgeConfig,
+    sequence: u8,
+}
+
+impl<S, C, E> UartBridge<S, C>
+where
+    S: serial::Read<u8, Error = E> + serial::Write<u8, Error = E>,
+    C: Clock,
+{
+    /// Create a bridge with the default [BridgeConfig], timing out on `clock`.
+    pub fn new(serial: S, clock: C) -> Self {
+        Self::with_config(serial, clock, BridgeConfig::default())
+    }
+
+    /// Create a bridge with a custom configuration.
+    pub fn with_config(serial: S, clock: C, config: BridgeConfig) -> Self {
+        Self {
+            serial,
+            clock,
+            config,
+            sequence: 0,
+        }
+    }
+
+    /// Destroy the bridge and return the serial interface.
+    pub fn release(self) -> S {
+        self.serial
+    }
+
+    fn transfer(
+        &mut self,
+        op: u8,
+        address: u8,
+        payload: &[u8],
+        response: &mut [u8],
+    ) -> Result<(), BridgeError<E>> {
+        if payload.len() > MAX_PAYLOAD || response.len() > MAX_PAYLOAD {
+            return Err(BridgeError::Corrupted);
+        }
+        let mut failure = BridgeError::Timeout;
+        for _ in 0..=self.config.retransmits {
+            self.sequence = self.sequence.wrapping_add(1);
+            self.send(op, address, payload)?;
+            match self.receive(response) {
+                Err(e @ (BridgeError::Timeout | BridgeError::Corrupted)) => failure = e,
+                result => return result,
+            }
+        }
+        Err(failure)
+    }
+
+    fn send(&mut self, op: u8, address: u8, payload: &[u8]) -> Result<(), BridgeError<E>> {
+        let mut frame = [0; MAX_PAYLOAD + 6];
+        frame[..5].copy_from_slice(&[SYNC, self.sequence, op, address, payload.len() as u8]);
+        frame[5..5 + payload.len()].copy_from_slice(payload);
+        let end = 5 + payload.len();
+        frame[end] = gen_checksum(&frame[1..end]);
+        for byte in &frame[..=end] {
+            nb::block!(self.serial.write(*byte)).map_err(BridgeError::Serial)?;
+        }
+        nb::block!(self.serial.flush()).map_err(BridgeError::Serial)
+    }
+
+    fn receive(&mut self, response: &mut [u8]) -> Result<(), BridgeError<E>> {
+        let deadline_ms = self
+            .clock
+            .now_ms()
+            .saturating_add(u64::from(self.config.timeout_ms));
+        loop {
+            if self.read_byte(deadline_ms)? != SYNC {
+                continue;
+            }
+            // sequence number, status, length, payload
+            let mut frame = [0; MAX_PAYLOAD + 3];
+            for byte in &mut frame[..3] {
+                *byte = self.read_byte(deadline_ms)?;
+            }
+            let len = usize::from(frame[2]);
+            if len > MAX_PAYLOAD {
+                return Err(BridgeError::Corrupted);
+            }
+            for byte in &mut frame[3..3 + len] {
+                *byte = self.read_byte(deadline_ms)?;
+            }
+            if self.read_byte(deadline_ms)? != gen_checksum(&frame[..3 + len]) {
+                return Err(BridgeError::Corrupted);
+            }
+            let [sequence, status, ..] = frame;
+            if sequence != self.sequence {
+                // late response to an earlier attempt
+                continue;
+            }
+            return match status {
+                STATUS_OK if len == response.len() => {
+                    response.copy_from_slice(&frame[3..3 + len]);
+                    Ok(())
+                }
+                STATUS_NACK => Err(BridgeError::Nack),
+                _ => Err(BridgeError::Corrupted),
+            };
+        }
+    }
+
+    fn read_byte(&mut self, deadline_ms: u64) -> Result<u8, BridgeError<E>> {
+        loop {
+            match self.serial.read() {
+                Ok(byte) => return Ok(byte),
+                Err(nb::Error::Other(e)) => return Err(BridgeError::Serial(e)),
+                Err(nb::Error::WouldBlock) if self.clock.now_ms() >= deadline_ms => {
+                    return Err(BridgeError::Timeout)
+                }
+                Err(nb::Error::WouldBlock) => {}
+            }
+        }
+    }
+}
+
+impl<S, C, E> Transport for UartBridge<S, C>
+where
+    S: serial::Read<u8, Error = E> + serial::Write<u8, Error = E>,
+    C: Clock,
+{
+    type Error = BridgeError<E>;
+
+    fn write_frame(&mut self, address: u8, frame: &[u8]) -> Result<(), Self::Error> {
+        self.transfer(OP_WRITE, address, frame, &mut [])
+    }
+
+    fn read_frame(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        let len = buffer.len() as u8;
+        self.transfer(OP_READ, address, &[len], buffer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{BridgeError, UartBridge, OP_READ, OP_WRITE, STATUS_NACK, STATUS_OK, SYNC};
+    use crate::{error::PacketParseError, protocol::gen_checksum, MicsVz89Te};
+    use assert_matches::assert_matches;
+    use core::{assert_eq, cell::Cell, convert::Infallible};
+    use embedded_hal::serial;
+    use embedded_hal_mock::delay::MockNoop as DelayMock;
+    use std::{collections::VecDeque, vec, vec::Vec};
+
+    /// Remote end of the bridge with a sensor answering every read with a status frame.
+    #[derive(Default)]
+    struct RemoteBridge {
+        request: Vec<u8>,
+        responses: VecDeque<u8>,
+        requests: usize,
+        lost_responses: usize,
+        nack: bool,
+    }
+
+    impl RemoteBridge {
+        fn handle(&mut self) {
+            let [SYNC, sequence, op, 0x70, _, ref payload @ .., _] = self.request[..] else {
+                panic!("invalid request {:?}", self.request);
+            };
+            let status_frame = [0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27];
+            let payload = match (op, payload) {
+                (OP_WRITE, _) => vec![],
+                (OP_READ, [len]) => status_frame[..usize::from(*len)].to_vec(),
+                _ => panic!("invalid op {op}"),
+            };
+            self.requests += 1;
+            if self.lost_responses > 0 {
+                self.lost_responses -= 1;
+                return;
+            }
+            let status = if self.nack { STATUS_NACK } else { STATUS_OK };
+            let mut frame = vec![sequence, status, payload.len() as u8];
+            frame.extend(payload);
+            frame.push(gen_checksum(&frame));
+            // noise before the response
+            self.responses.extend([0x00, SYNC]);
+            self.responses.extend(frame);
+        }
+    }
+
+    impl serial::Write<u8> for RemoteBridge {
+        type Error = Infallible;
+
+        fn write(&mut self, byte: u8) -> nb::Result<(), Infallible> {
+            self.request.push(byte);
+            let complete =
+                self.request.len() > 5 && self.request.len() == 6 + usize::from(self.request[4]);
+            if complete {
+                let checksum = gen_checksum(&self.request[1..self.request.len() - 1]);
+                assert_eq!(self.request.last(), Some(&checksum));
+                self.handle();
+                self.request.clear();
+            }
+            Ok(())
+        }
+
+        fn flush(&mut self) -> nb::Result<(), Infallible> {
+            Ok(())
+        }
+    }
+
+    impl serial::Read<u8> for RemoteBridge {
+        type Error = Infallible;
+
+        fn read(&mut self) -> nb::Result<u8, Infallible> {
+            self.responses.pop_front().ok_or(nb::Error::WouldBlock)
+        }
+    }
+
+    #[test]
+    fn test_read_over_bridge() {
+        // every poll of the clock takes a millisecond
+        let time = Cell::new(0);
+        let clock = || {
+            time.set(time.get() + 1);
+            time.get()
+        };
+        let remote = RemoteBridge {
+            lost_responses: 1,
+            ..RemoteBridge::default()
+        };
+        let mut device = MicsVz89Te::new(UartBridge::new(remote, &clock));
+        let mut delay = DelayMock::new();
+
+        let m = device.read_measurements(&mut delay).unwrap();
+
+        assert_eq!(m.co2_ppm_u16(), 728);
+        // the first write was sent again
+        assert_eq!(device.release().release().requests, 3);
+        assert!(time.get() >= 50);
+    }
+
+    #[test]
+    fn test_bridge_errors() {
+        let time = Cell::new(0);
+        let clock = || {
+            time.set(time.get() + 1);
+            time.get()
+        };
+        let remote = RemoteBridge {
+            nack: true,
+            ..RemoteBridge::default()
+        };
+        let mut device = MicsVz89Te::new(UartBridge::new(remote, &clock));
+        let mut delay = DelayMock::new();
+        assert_matches!(
+            device.read_measurements(&mut delay),
+            Err(PacketParseError::BusError(BridgeError::Nack))
+        );
+
+        let remote = RemoteBridge {
+            lost_responses: 3,
+            ..RemoteBridge::default()
+        };
+        let mut device = MicsVz89Te::new(UartBridge::new(remote, &clock));
+        assert_matches!(
+            device.read_measurements(&mut delay),
+            Err(PacketParseError::BusError(BridgeError::Timeout))
+        );
+        assert_eq!(device.release().release().requests, 3);
+    }
+}
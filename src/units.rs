@@ -0,0 +1,90 @@
+//! Unit conversions and rounding shared by the formatting helpers.
+//!
+//! The sensor reports CO2 in ppm and VOC in ppb. Use these helpers instead of ad hoc factors
+//! to avoid mixing up the scales.
+
+/// ppm per percent.
+pub const PPM_PER_PERCENT: f32 = 10_000.0;
+/// ppb per ppm.
+pub const PPB_PER_PPM: f32 = 1000.0;
+
+/// Convert a concentration in ppm to percent (`1000 ppm` is `0.1 %`).
+pub fn ppm_to_percent(ppm: f32) -> f32 {
+    ppm / PPM_PER_PERCENT
+}
+
+/// Convert a concentration in percent to ppm.
+pub fn percent_to_ppm(percent: f32) -> f32 {
+    percent * PPM_PER_PERCENT
+}
+
+/// Convert a concentration in ppb to ppm (`1000 ppb` is `1 ppm`).
+pub fn ppb_to_ppm(ppb: f32) -> f32 {
+    ppb / PPB_PER_PPM
+}
+
+/// Convert a concentration in ppm to ppb.
+pub fn ppm_to_ppb(ppm: f32) -> f32 {
+    ppm * PPB_PER_PPM
+}
+
+/// Round to the nearest integer (halves round up).
+///
+/// Negative values and NaN map to `0`, values above [u16::MAX] saturate.
+pub fn round_to_u16(value: f32) -> u16 {
+    // float to int casts saturate and map NaN to 0
+    (value + 0.5) as u16
+}
+
+/// Round to the nearest integer (halves round up).
+///
+/// Negative values and NaN map to `0`, values above [u32::MAX] saturate.
+pub fn round_to_u32(value: f32) -> u32 {
+    // float to int casts saturate and map NaN to 0
+    (value + 0.5) as u32
+}
+
+/// Round `value` to `decimals` decimal places and return it scaled by `10^decimals`, e.g.
+/// `scaled(0.0728, 3)` is `73`. Halves round away from zero.
+///
+/// Printing the integer part and the remainder separately avoids float formatting. NaN maps
+/// to `0`, out of range values saturate.
+pub fn scaled(value: f32, decimals: u8) -> i32 {
+    let scaled = (0..decimals).fold(value, |v, _| v * 10.0);
+    // float to int casts saturate and map NaN to 0
+    if scaled < 0.0 {
+        (scaled - 0.5) as i32
+    } else {
+        (scaled + 0.5) as i32
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{
+        percent_to_ppm, ppb_to_ppm, ppm_to_percent, ppm_to_ppb, round_to_u16, round_to_u32, scaled,
+    };
+    use core::assert_eq;
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(ppm_to_percent(1000.0), 0.1);
+        assert_eq!(percent_to_ppm(0.04), 400.0);
+        assert_eq!(ppb_to_ppm(250.0), 0.25);
+        assert_eq!(ppm_to_ppb(1.0), 1000.0);
+    }
+
+    #[test]
+    fn test_rounding() {
+        assert_eq!(round_to_u16(113.5), 114);
+        assert_eq!(round_to_u16(-3.0), 0);
+        assert_eq!(round_to_u16(f32::NAN), 0);
+        assert_eq!(round_to_u32(70_000.5), 70_001);
+        assert_eq!(round_to_u32(-0.4), 0);
+        assert_eq!(scaled(0.0728, 3), 73);
+        assert_eq!(scaled(-1.25, 1), -13);
+        assert_eq!(scaled(f32::NAN, 2), 0);
+        assert_eq!(scaled(1e12, 2), i32::MAX);
+    }
+}
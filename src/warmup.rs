@@ -0,0 +1,319 @@
+//! Detection of the end of the sensor warm-up.
+//!
+//! The datasheet specifies a warm-up of around 15 minutes, but sensors are often ready
+//! earlier. During warm-up the CO2 value sits at the lower end of [CO2_RANGE_PPM](crate::CO2_RANGE_PPM)
+//! and the sensor resistance drifts. [WarmUpDetector] considers the warm-up complete once CO2 has
+//! left this floor and both values have settled, or at the latest after the fixed duration.
+//!
+//! Applications which must never act on an invalid CO2 value can opt into
+//! [MicsVz89Te::read_measurements_ready()], which withholds CO2 until the warm-up is complete.
+
+use crate::{
+    clock::Clock, delay::Delay, error::PacketParseError, transport::Transport, Measurements,
+    MicsVz89Te, CO2_MIN,
+};
+
+/// Warm-up time specified in the datasheet in millis.
+pub const DATASHEET_WARM_UP_MS: u64 = 15 * 60 * 1000;
+
+/// Parameters of the warm-up heuristic.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WarmUpConfig {
+    /// Minimum time before the heuristic may report completion.
+    pub min_duration_ms: u64,
+    /// Time after which the warm-up is complete regardless of the readings.
+    pub max_duration_ms: u64,
+    /// CO2 values up to this limit are considered the warm-up floor.
+    pub floor_ppm: f32,
+    /// Number of consecutive settled samples needed for completion.
+    pub stable_samples: u8,
+    /// Maximum CO2 change between two samples to count as settled.
+    pub max_co2_step_ppm: f32,
+    /// Maximum relative resistance change between two samples in permille to count as settled.
+    pub max_resistance_step_permille: u16,
+}
+
+impl Default for WarmUpConfig {
+    fn default() -> Self {
+        Self {
+            min_duration_ms: 2 * 60 * 1000,
+            max_duration_ms: DATASHEET_WARM_UP_MS,
+            floor_ppm: CO2_MIN + 5.0,
+            stable_samples: 30,
+            max_co2_step_ppm: 20.0,
+            max_resistance_step_permille: 10,
+        }
+    }
+}
+
+/// Warm-up state reported by [WarmUpDetector::update()].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarmUpState {
+    /// The sensor is still warming up. `progress_percent` is an estimate in `0..100`.
+    WarmingUp { progress_percent: u8 },
+    /// The warm-up is complete.
+    Complete,
+}
+
+/// Tracks the warm-up of the sensor from the readings.
+///
+/// # Example Usage
+/// ```ignore
+/// let mut warm_up = WarmUpDetector::new(WarmUpConfig::default(), clock.now_ms());
+/// loop {
+///     let (measurements, resistance) = device.read_measurements_with_resistance(&mut delay).unwrap();
+///     if warm_up.update(clock.now_ms(), &measurements, Some(resistance)) == WarmUpState::Complete {
+///         publish(measurements);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct WarmUpDetector {
+    config: WarmUpConfig,
+    start_ms: u64,
+    left_floor: bool,
+    stable: u8,
+    last: Option<(f32, Option<u32>)>,
+    complete: bool,
+}
+
+impl WarmUpDetector {
+    /// Create a detector for a sensor powered up at `start_ms`.
+    pub fn new(config: WarmUpConfig, start_ms: u64) -> Self {
+        Self {
+            config,
+            start_ms,
+            left_floor: false,
+            stable: 0,
+            last: None,
+            complete: false,
+        }
+    }
+
+    /// Restart the warm-up, e.g. after the sensor was power-cycled.
+    pub fn restart(&mut self, now_ms: u64) {
+        *self = Self::new(self.config, now_ms);
+    }
+
+    /// Returns `true` once the warm-up is complete.
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// Feed a new reading taken at `now_ms`. The sensor resistance (see
+    /// [MicsVz89Te::read_measurements_with_resistance()](crate::MicsVz89Te::read_measurements_with_resistance()))
+    /// is optional and only tightens the settling check.
+    pub fn update(
+        &mut self,
+        now_ms: u64,
+        measurements: &Measurements,
+        resistance: Option<u32>,
+    ) -> WarmUpState {
+        if self.complete {
+            return WarmUpState::Complete;
+        }
+
+        let co2 = measurements.co2;
+        self.left_floor |= co2 > self.config.floor_ppm;
+
+        let settled = match self.last {
+            Some((last_co2, last_resistance)) => {
+                (co2 - last_co2).abs() <= self.config.max_co2_step_ppm
+                    && match (last_resistance, resistance) {
+                        (Some(last), Some(now)) => {
+                            resistance_settled(last, now, self.config.max_resistance_step_permille)
+                        }
+                        _ => true,
+                    }
+            }
+            None => false,
+        };
+        self.stable = if settled && self.left_floor {
+            self.stable.saturating_add(1)
+        } else {
+            0
+        };
+        self.last = Some((co2, resistance));
+
+        let elapsed = now_ms.saturating_sub(self.start_ms);
+        self.complete = elapsed >= self.config.max_duration_ms
+            || (elapsed >= self.config.min_duration_ms
+                && self.stable >= self.config.stable_samples);
+
+        if self.complete {
+            WarmUpState::Complete
+        } else {
+            WarmUpState::WarmingUp {
+                progress_percent: self.progress(elapsed),
+            }
+        }
+    }
+
+    fn progress(&self, elapsed: u64) -> u8 {
+        let by_time = percent(elapsed, self.config.max_duration_ms);
+        let by_stability = percent(
+            u64::from(self.stable),
+            u64::from(self.config.stable_samples),
+        )
+        .min(percent(elapsed, self.config.min_duration_ms));
+        by_time.max(by_stability).min(99)
+    }
+}
+
+/// Measurements with the CO2 value withheld during warm-up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReadyMeasurements {
+    /// CO2 in ppm, `None` while the sensor is warming up.
+    pub co2: Option<f32>,
+    /// VOC in ppb.
+    pub voc: f32,
+}
+
+impl ReadyMeasurements {
+    /// Both values, `None` while the sensor is warming up.
+    pub fn measurements(&self) -> Option<Measurements> {
+        self.co2.map(|co2| Measurements { co2, voc: self.voc })
+    }
+}
+
+impl<I2C, E> MicsVz89Te<I2C>
+where
+    I2C: Transport<Error = E>,
+{
+    /// Read measurements and feed them to `warm_up`. CO2 is only returned once the warm-up is
+    /// complete, see [ReadyMeasurements].
+    ///
+    /// For a fixed warm-up window without the heuristic, set
+    /// [WarmUpConfig::min_duration_ms] and [WarmUpConfig::max_duration_ms] to the same value.
+    ///
+    /// This function blocks a minimum time of [MicsVz89Te::WAIT_ON_RESPONSE_TIME].
+    pub fn read_measurements_ready(
+        &mut self,
+        delay: &mut impl Delay,
+        clock: &impl Clock,
+        warm_up: &mut WarmUpDetector,
+    ) -> Result<ReadyMeasurements, PacketParseError<E>> {
+        let (measurements, resistance) = self.read_measurements_with_resistance(delay)?;
+        let state = warm_up.update(clock.now_ms(), &measurements, Some(resistance));
+        Ok(ReadyMeasurements {
+            co2: (state == WarmUpState::Complete).then_some(measurements.co2),
+            voc: measurements.voc,
+        })
+    }
+}
+
+/// Returns `true` if the resistance changed by at most `max_step_permille` of `last`.
+pub(crate) fn resistance_settled(last: u32, now: u32, max_step_permille: u16) -> bool {
+    u64::from(last.abs_diff(now)) * 1000 <= u64::from(last) * u64::from(max_step_permille)
+}
+
+fn percent(value: u64, total: u64) -> u8 {
+    if total == 0 {
+        return 100;
+    }
+    (value.min(total) * 100 / total) as u8
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{WarmUpConfig, WarmUpDetector, WarmUpState};
+    use crate::{Measurements, MicsVz89Te};
+    use core::{assert_eq, cell::Cell};
+    use embedded_hal_mock::{
+        delay::MockNoop as DelayMock,
+        i2c::{Mock as I2cMock, Transaction as I2cTransaction},
+    };
+    use std::vec;
+
+    fn m(co2: f32) -> Measurements {
+        Measurements { co2, voc: 0.0 }
+    }
+
+    fn config() -> WarmUpConfig {
+        WarmUpConfig {
+            min_duration_ms: 5000,
+            max_duration_ms: 100_000,
+            stable_samples: 3,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_completes_when_settled() {
+        let mut detector = WarmUpDetector::new(config(), 0);
+
+        // at the floor, only time based progress
+        assert_eq!(
+            detector.update(1000, &m(400.0), Some(100_000)),
+            WarmUpState::WarmingUp {
+                progress_percent: 1
+            }
+        );
+        detector.update(2000, &m(520.0), Some(120_000));
+        // resistance still drifting
+        detector.update(3000, &m(525.0), Some(110_000));
+        detector.update(4000, &m(530.0), Some(110_500));
+        detector.update(5000, &m(528.0), Some(110_400));
+        assert!(!detector.is_complete());
+        assert_eq!(
+            detector.update(6000, &m(531.0), Some(110_300)),
+            WarmUpState::Complete
+        );
+        assert_eq!(
+            detector.update(7000, &m(900.0), None),
+            WarmUpState::Complete
+        );
+
+        detector.restart(10_000);
+        assert!(!detector.is_complete());
+    }
+
+    #[test]
+    fn test_completes_after_max_duration() {
+        let mut detector = WarmUpDetector::new(config(), 0);
+        for t in 1..100 {
+            assert_ne!(
+                detector.update(t * 1000, &m(400.0), None),
+                WarmUpState::Complete
+            );
+        }
+        assert_eq!(
+            detector.update(100_000, &m(400.0), None),
+            WarmUpState::Complete
+        );
+    }
+
+    #[test]
+    fn test_read_measurements_ready() {
+        let status = || I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]);
+        let response = || I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]);
+        let expectations = [status(), response(), status(), response()];
+        let mut device = MicsVz89Te::new(I2cMock::new(&expectations));
+        let mut delay = DelayMock::new();
+        let time = Cell::new(0);
+        let clock = || time.get();
+        // fixed window of one minute
+        let config = WarmUpConfig {
+            min_duration_ms: 60_000,
+            max_duration_ms: 60_000,
+            ..Default::default()
+        };
+        let mut warm_up = WarmUpDetector::new(config, 0);
+
+        time.set(59_999);
+        let ready = device
+            .read_measurements_ready(&mut delay, &clock, &mut warm_up)
+            .unwrap();
+        assert_eq!(ready.co2, None);
+        assert!((ready.voc - 113.6).abs() < 0.1);
+        assert_eq!(ready.measurements(), None);
+
+        time.set(60_000);
+        let ready = device
+            .read_measurements_ready(&mut delay, &clock, &mut warm_up)
+            .unwrap();
+        assert_eq!(ready.measurements().map(|m| m.co2_ppm_u16()), Some(728));
+        device.release().done();
+    }
+}
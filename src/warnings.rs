@@ -0,0 +1,235 @@
+//! Caveats reported alongside the measurements.
+//!
+//! Some readings are valid but deserve a note in the log: the value was clamped to the
+//! documented range, the read only succeeded after a retry or the sensor is close to the end of
+//! its range.
+//! [MicsVz89Te::read_measurements_with_warnings()](crate::MicsVz89Te::read_measurements_with_warnings())
+//! returns these as [Warnings] instead of refusing the reading, the async frontends do the same.
+//! Layers above the driver add their own caveats, e.g. a cached value served again or a
+//! compensation applied to it.
+//!
+//! | bit | warning                                                              |
+//! |-----|----------------------------------------------------------------------|
+//! | 0   | [CLAMPED](Warnings::CLAMPED): a channel was clamped to its range      |
+//! | 1   | [RETRIED](Warnings::RETRIED): the read needed retries                 |
+//! | 2   | [STALE](Warnings::STALE): the value was served from a cache           |
+//! | 3   | [COMPENSATED](Warnings::COMPENSATED): e.g. humidity correction applied |
+//! | 4   | [NEAR_SATURATION](Warnings::NEAR_SATURATION): close to the range end  |
+//! | 5   | [UNVERIFIED](Warnings::UNVERIFIED): accepted with a wrong checksum    |
+//! | 6.. | reserved, written as `0` and ignored by [Warnings::from_bits()]      |
+//!
+//! # Example Usage
+//! ```ignore
+//! let reading = device
+//!     .read_measurements_with_warnings(&mut delay)?
+//!     .compensate(|m| correction.correct(m, sht.humidity()?));
+//! if !reading.warnings.is_empty() {
+//!     log::warn!("caveats {:#04x}", reading.warnings.bits());
+//! }
+//! publish(reading.measurements);
+//! ```
+
+use core::ops::{BitOr, BitOrAssign};
+
+use crate::{checksum::ChecksumStats, Clamped, Measurements, CO2_MAX, CO2_MIN, VOC_MAX, VOC_MIN};
+
+/// Fraction of the documented range above which a channel is near saturation.
+pub const NEAR_SATURATION_FRACTION: f32 = 0.95;
+
+/// Set of caveats of a reading. See the [module](self) documentation for the bit layout.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Warnings(u8);
+
+impl Warnings {
+    /// A channel was clamped to the documented range.
+    pub const CLAMPED: Self = Self(1 << 0);
+    /// The read succeeded only after retries.
+    pub const RETRIED: Self = Self(1 << 1);
+    /// The value was served from a cache instead of a fresh read.
+    pub const STALE: Self = Self(1 << 2);
+    /// A compensation was applied to the value.
+    pub const COMPENSATED: Self = Self(1 << 3);
+    /// A channel is above [NEAR_SATURATION_FRACTION] of its documented range.
+    pub const NEAR_SATURATION: Self = Self(1 << 4);
+    /// The response had a wrong checksum and was accepted in the lenient mode, see
+    /// [checksum](crate::checksum).
+    pub const UNVERIFIED: Self = Self(1 << 5);
+
+    const ALL: u8 = 0b11_1111;
+
+    /// No warnings.
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// The warnings as bits for logs and telemetry.
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// Warnings from bits, reserved bits are ignored.
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits & Self::ALL)
+    }
+
+    /// Returns `true` if there are no warnings.
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns `true` if all warnings of `other` are set.
+    pub const fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Set the warnings of `other`.
+    pub fn insert(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+
+    /// Warnings for values close to the end of the documented range.
+    pub fn near_saturation(measurements: &Measurements) -> Self {
+        let near =
+            |value: f32, min: f32, max: f32| value >= min + (max - min) * NEAR_SATURATION_FRACTION;
+        if near(measurements.co2, CO2_MIN, CO2_MAX) || near(measurements.voc, VOC_MIN, VOC_MAX) {
+            Self::NEAR_SATURATION
+        } else {
+            Self::empty()
+        }
+    }
+}
+
+impl BitOr for Warnings {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Warnings {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.insert(rhs);
+    }
+}
+
+/// Measurements with their caveats.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WarnedMeasurements {
+    pub measurements: Measurements,
+    pub warnings: Warnings,
+}
+
+impl WarnedMeasurements {
+    /// Add `warnings`, e.g. [Warnings::STALE] when serving the reading again from a cache.
+    pub fn with(mut self, warnings: Warnings) -> Self {
+        self.warnings |= warnings;
+        self
+    }
+
+    /// Collect the caveats of a read from the driver state after it: [Warnings::CLAMPED],
+    /// [Warnings::RETRIED], [Warnings::NEAR_SATURATION] and [Warnings::UNVERIFIED].
+    pub(crate) fn from_read(
+        measurements: Measurements,
+        clamped: Clamped,
+        retries_used: u8,
+        checksum: &ChecksumStats,
+    ) -> Self {
+        let mut warnings = Warnings::near_saturation(&measurements);
+        if clamped.any() {
+            warnings |= Warnings::CLAMPED;
+        }
+        if retries_used > 0 {
+            warnings |= Warnings::RETRIED;
+        }
+        if checksum.last_unverified {
+            warnings |= Warnings::UNVERIFIED;
+        }
+        Self {
+            measurements,
+            warnings,
+        }
+    }
+
+    /// Apply a compensation to the measurements and add [Warnings::COMPENSATED].
+    pub fn compensate(self, f: impl FnOnce(&Measurements) -> Measurements) -> Self {
+        Self {
+            measurements: f(&self.measurements),
+            warnings: self.warnings | Warnings::COMPENSATED,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::Warnings;
+    use crate::{config::Config, Measurements, MicsVz89Te};
+    use core::assert_eq;
+    use embedded_hal_mock::{
+        delay::MockNoop as DelayMock,
+        i2c::{Mock as I2cMock, Transaction as I2cTransaction},
+        MockError,
+    };
+    use std::{io::ErrorKind, vec};
+
+    #[test]
+    fn test_read_measurements_with_warnings() {
+        let status = || I2cTransaction::write(0x70, vec![0x0C, 0, 0, 0, 0, 0xF3]);
+        let expectations = [
+            status(),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27])
+                .with_error(MockError::Io(ErrorKind::Other)),
+            status(),
+            I2cTransaction::read(0x70, vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27]),
+            status(),
+            // CO2 raw 0xFA is above the range
+            I2cTransaction::read(0x70, vec![0x27, 0xFA, 0, 0xBA, 0xBA, 0, 0x68]),
+        ];
+        let config = Config {
+            retries: 1,
+            strict_range: true,
+            ..Config::default()
+        };
+        let mut device = MicsVz89Te::with_config(I2cMock::new(&expectations), config);
+        let mut delay = DelayMock::new();
+
+        let reading = device.read_measurements_with_warnings(&mut delay).unwrap();
+        assert_eq!(reading.measurements.co2_ppm_u16(), 728);
+        assert_eq!(reading.warnings, Warnings::RETRIED);
+
+        let reading = device.read_measurements_with_warnings(&mut delay).unwrap();
+        assert_eq!(reading.measurements.co2, 2000.0);
+        assert_eq!(
+            reading.warnings,
+            Warnings::CLAMPED | Warnings::NEAR_SATURATION
+        );
+
+        let reading = reading
+            .compensate(|m| Measurements {
+                co2: m.co2 * 0.9,
+                ..*m
+            })
+            .with(Warnings::STALE);
+        assert_eq!(reading.measurements.co2, 1800.0);
+        assert!(reading
+            .warnings
+            .contains(Warnings::STALE | Warnings::COMPENSATED));
+        assert_eq!(reading.warnings.bits(), 0b1_1101);
+
+        device.release().done();
+    }
+
+    #[test]
+    fn test_bits() {
+        assert_eq!(Warnings::from_bits(0xFF).bits(), 0b11_1111);
+        assert!(Warnings::from_bits(0b100_0000).is_empty());
+        assert_eq!(
+            Warnings::near_saturation(&Measurements {
+                co2: 800.0,
+                voc: 960.0
+            }),
+            Warnings::NEAR_SATURATION
+        );
+    }
+}
@@ -0,0 +1,129 @@
+//! Watchdog for a stuck sensor.
+//!
+//! The sensor updates its values every [SENSOR_UPDATE_INTERVAL_MS]. Readings that stay
+//! bit-identical over many update intervals indicate a hung sensor or a wedged bus.
+
+use crate::{sampler::SENSOR_UPDATE_INTERVAL_MS, Measurements};
+
+/// Flags readings that didn't change for a number of consecutive update intervals.
+///
+/// Readings taken less than [SENSOR_UPDATE_INTERVAL_MS] after the last counted reading are
+/// ignored, since the sensor legitimately repeats its values within this interval.
+///
+/// # Example Usage
+/// ```ignore
+/// let mut watchdog = StuckWatchdog::new(60);
+/// let measurements = device.read_measurements(&mut delay).unwrap();
+/// watchdog.update_or_recover(now_ms, &measurements, || power_cycle_sensor());
+/// ```
+#[derive(Debug, Clone)]
+pub struct StuckWatchdog {
+    limit: u16,
+    repeats: u16,
+    last: Option<(u64, [u32; 2])>,
+}
+
+impl StuckWatchdog {
+    /// Create a watchdog flagging `limit` consecutive identical readings. A limit of `0` is
+    /// treated as `1`.
+    pub const fn new(limit: u16) -> Self {
+        Self {
+            limit: if limit == 0 { 1 } else { limit },
+            repeats: 0,
+            last: None,
+        }
+    }
+
+    /// Returns `true` while the readings are considered stuck.
+    pub fn is_stuck(&self) -> bool {
+        self.repeats >= self.limit
+    }
+
+    /// Number of consecutive identical readings after the first one.
+    pub fn repeats(&self) -> u16 {
+        self.repeats
+    }
+
+    /// Forget the previous readings, e.g. after the sensor was recovered.
+    pub fn reset(&mut self) {
+        self.repeats = 0;
+        self.last = None;
+    }
+
+    /// Feed a reading taken at `now_ms`. Returns `true` once, when the readings become stuck.
+    pub fn update(&mut self, now_ms: u64, measurements: &Measurements) -> bool {
+        let bits = [measurements.co2.to_bits(), measurements.voc.to_bits()];
+        match self.last {
+            Some((last_ms, _))
+                if now_ms.saturating_sub(last_ms) < u64::from(SENSOR_UPDATE_INTERVAL_MS) =>
+            {
+                return false;
+            }
+            Some((_, last_bits)) if last_bits == bits => {
+                self.repeats = self.repeats.saturating_add(1);
+            }
+            _ => self.repeats = 0,
+        }
+        self.last = Some((now_ms, bits));
+        self.repeats == self.limit
+    }
+
+    /// Like [StuckWatchdog::update()], but calls `recover` when the readings become stuck and
+    /// starts over afterwards.
+    pub fn update_or_recover(
+        &mut self,
+        now_ms: u64,
+        measurements: &Measurements,
+        recover: impl FnOnce(),
+    ) -> bool {
+        let stuck = self.update(now_ms, measurements);
+        if stuck {
+            recover();
+            self.reset();
+        }
+        stuck
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::StuckWatchdog;
+    use crate::Measurements;
+    use core::assert_eq;
+
+    const M: Measurements = Measurements {
+        co2: 612.0,
+        voc: 40.0,
+    };
+
+    #[test]
+    fn test_stuck() {
+        let mut watchdog = StuckWatchdog::new(3);
+
+        assert!(!watchdog.update(0, &M));
+        // faster than the update interval, ignored
+        assert!(!watchdog.update(500, &M));
+        assert!(!watchdog.update(1000, &M));
+        assert!(!watchdog.update(2000, &M));
+        assert_eq!(watchdog.repeats(), 2);
+        assert!(watchdog.update(3000, &M));
+        assert!(watchdog.is_stuck());
+        assert!(!watchdog.update(4000, &M));
+
+        let changed = Measurements { co2: 613.0, ..M };
+        assert!(!watchdog.update(5000, &changed));
+        assert!(!watchdog.is_stuck());
+    }
+
+    #[test]
+    fn test_recover() {
+        let mut watchdog = StuckWatchdog::new(1);
+        let mut recovered = 0;
+
+        watchdog.update_or_recover(0, &M, || recovered += 1);
+        assert!(watchdog.update_or_recover(1000, &M, || recovered += 1));
+        assert_eq!(recovered, 1);
+        assert!(!watchdog.is_stuck());
+    }
+}
@@ -0,0 +1,178 @@
+//! embedded-graphics readout of a channel.
+//!
+//! [Readout] draws the value of one channel with its unit and, below it, a bar colored by the
+//! [AqiBand] of the value. The text is rendered like [display](crate::display) does, without
+//! float formatting, in [FONT_6X10]:
+//!
+//! ```text
+//! 728 ppm
+//! ██████████  <- green (Good) .. maroon (Hazardous)
+//! ```
+//!
+//! # Example Usage
+//! ```ignore
+//! let readout = Readout::new(&measurements, Channel::Co2, Point::new(0, 0));
+//! readout.draw(&mut display)?;
+//! ```
+
+use embedded_graphics::{
+    mono_font::{ascii::FONT_6X10, MonoTextStyle},
+    pixelcolor::{Rgb565, RgbColor},
+    prelude::{DrawTarget, Drawable, Point, Primitive, Size},
+    primitives::{PrimitiveStyle, Rectangle},
+    text::{Baseline, Text},
+};
+
+use crate::{
+    aqi::{AqiBand, AqiScale, DEFAULT_CO2_BREAKPOINTS, DEFAULT_VOC_BREAKPOINTS},
+    display::TextBuffer,
+    format, Channel, Measurements,
+};
+
+/// Width of the quality bar in pixels.
+pub const BAR_WIDTH: u32 = 60;
+/// Height of the quality bar in pixels.
+pub const BAR_HEIGHT: u32 = 4;
+/// Gap between the text and the quality bar in pixels.
+pub const BAR_GAP: i32 = 2;
+
+/// Color of the quality bar for `band`.
+pub fn band_color(band: AqiBand) -> Rgb565 {
+    match band {
+        AqiBand::Good => Rgb565::GREEN,
+        AqiBand::Moderate => Rgb565::YELLOW,
+        AqiBand::UnhealthyForSensitiveGroups => Rgb565::new(31, 41, 0),
+        AqiBand::Unhealthy => Rgb565::RED,
+        AqiBand::VeryUnhealthy => Rgb565::new(18, 0, 18),
+        AqiBand::Hazardous => Rgb565::new(16, 0, 4),
+    }
+}
+
+/// Value, unit and quality bar of one channel, see the [module](self) documentation.
+#[derive(Debug, Clone, Copy)]
+pub struct Readout<'a> {
+    measurements: &'a Measurements,
+    channel: Channel,
+    top_left: Point,
+    scale: AqiScale<'a>,
+    text_color: Rgb565,
+}
+
+impl<'a> Readout<'a> {
+    /// Readout of `channel` with its top left corner at `top_left`, white text and the default
+    /// breakpoints.
+    pub fn new(measurements: &'a Measurements, channel: Channel, top_left: Point) -> Self {
+        Self {
+            measurements,
+            channel,
+            top_left,
+            scale: AqiScale {
+                co2: &DEFAULT_CO2_BREAKPOINTS,
+                voc: &DEFAULT_VOC_BREAKPOINTS,
+            },
+            text_color: Rgb565::WHITE,
+        }
+    }
+
+    /// Use custom breakpoints for the bar color.
+    pub fn scale(mut self, scale: AqiScale<'a>) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Use a custom text color.
+    pub fn text_color(mut self, color: Rgb565) -> Self {
+        self.text_color = color;
+        self
+    }
+
+    /// Band of the shown channel, which colors the bar.
+    pub fn band(&self) -> AqiBand {
+        let score = self.scale.score(self.measurements);
+        AqiBand::from_score(match self.channel {
+            Channel::Co2 => score.co2,
+            Channel::Voc => score.voc,
+        })
+    }
+}
+
+impl Drawable for Readout<'_> {
+    type Color = Rgb565;
+    type Output = ();
+
+    fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Rgb565>,
+    {
+        let (value, unit) = match self.channel {
+            Channel::Co2 => (self.measurements.co2_ppm_u16(), " ppm"),
+            Channel::Voc => (self.measurements.voc_ppb_u16(), " ppb"),
+        };
+        // a u16 always fits
+        let mut text = TextBuffer::<5>::new();
+        let _ = format::write_u16(&mut text, value, 1);
+
+        let style = MonoTextStyle::new(&FONT_6X10, self.text_color);
+        let next =
+            Text::with_baseline(text.as_str(), self.top_left, style, Baseline::Top).draw(target)?;
+        Text::with_baseline(unit, next, style, Baseline::Top).draw(target)?;
+
+        let bar_top =
+            self.top_left + Point::new(0, FONT_6X10.character_size.height as i32 + BAR_GAP);
+        Rectangle::new(bar_top, Size::new(BAR_WIDTH, BAR_HEIGHT))
+            .into_styled(PrimitiveStyle::with_fill(band_color(self.band())))
+            .draw(target)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{band_color, Readout, BAR_HEIGHT, BAR_WIDTH};
+    use crate::{aqi::AqiBand, Channel, Measurements};
+    use core::assert_eq;
+    use embedded_graphics::{
+        mock_display::MockDisplay,
+        pixelcolor::{Rgb565, RgbColor},
+        prelude::{Drawable, Point},
+    };
+
+    #[test]
+    fn test_draw_readout() {
+        let measurements = Measurements {
+            co2: 1200.0,
+            voc: 113.0,
+        };
+        let mut display = MockDisplay::<Rgb565>::new();
+        let readout = Readout::new(&measurements, Channel::Co2, Point::new(1, 1));
+        readout.draw(&mut display).unwrap();
+
+        assert_eq!(readout.band(), AqiBand::UnhealthyForSensitiveGroups);
+        // bar below the 10 px high text and the gap
+        let bar = band_color(AqiBand::UnhealthyForSensitiveGroups);
+        assert_eq!(display.get_pixel(Point::new(1, 13)), Some(bar));
+        assert_eq!(
+            display.get_pixel(Point::new(BAR_WIDTH as i32, 12 + BAR_HEIGHT as i32)),
+            Some(bar)
+        );
+        assert_eq!(display.get_pixel(Point::new(1, 12)), None);
+        // "1200 ppm" in 6 px wide glyphs
+        assert_eq!(display.affected_area().top_left.x, 1);
+        assert!((1..11)
+            .any(|y| (43..49).any(|x| display.get_pixel(Point::new(x, y)) == Some(Rgb565::WHITE))));
+        assert!((1..11).all(|y| display.get_pixel(Point::new(49, y)).is_none()));
+    }
+
+    #[test]
+    fn test_band_colors() {
+        let measurements = Measurements {
+            co2: 450.0,
+            voc: 3000.0,
+        };
+        let voc = Readout::new(&measurements, Channel::Voc, Point::zero());
+        assert_eq!(voc.band(), AqiBand::VeryUnhealthy);
+        let co2 = Readout::new(&measurements, Channel::Co2, Point::zero());
+        assert_eq!(co2.band(), AqiBand::Good);
+        assert_eq!(band_color(co2.band()), Rgb565::GREEN);
+    }
+}
@@ -0,0 +1,192 @@
+//! Canonical 4-byte encoding of measurements for exchange between nodes.
+//!
+//! | byte | content                |
+//! |------|------------------------|
+//! | 0..2 | CO2 in ppm, `u16` LE   |
+//! | 2..4 | VOC in ppb, `u16` LE   |
+//!
+//! Values are rounded to integers. Two values are reserved:
+//!
+//! - [SATURATED] (`0xFFFE`): the value is above the range of the sensor
+//!   ([CO2_RANGE_PPM], [VOC_RANGE_PPB]).
+//! - [INVALID] (`0xFFFF`): no valid value, e.g. NaN, a negative value or a failed read.
+//!
+//! Records leaving the device should carry the version of their schema, so both ends can be
+//! upgraded independently. [encode_versioned()] prepends [SCHEMA_VERSION] to the record
+//! ([VERSIONED_LEN] bytes). [decode_versioned()] also takes records of version `1`, the plain
+//! 4-byte record without a version byte.
+//!
+//! Records don't carry the [identity](crate::identity) of the sensor, see there.
+
+use crate::{units, Measurements, CO2_RANGE_PPM, VOC_RANGE_PPB};
+
+/// Size of an encoded record in bytes.
+pub const ENCODED_LEN: usize = 4;
+/// Reserved value for a value above the sensor range.
+pub const SATURATED: u16 = 0xFFFE;
+/// Reserved value for an invalid or missing value.
+pub const INVALID: u16 = 0xFFFF;
+/// Version of the schema written by [encode_versioned()].
+pub const SCHEMA_VERSION: u8 = 2;
+/// Size of a versioned record in bytes.
+pub const VERSIONED_LEN: usize = ENCODED_LEN + 1;
+
+/// Errors of [decode_versioned()].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WireError {
+    /// The data is neither a record of version `1` nor a versioned record.
+    InvalidLength(usize),
+    /// The record was written with a newer or unknown schema version.
+    UnsupportedVersion(u8),
+}
+
+/// A decoded value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WireValue {
+    Valid(u16),
+    Saturated,
+    Invalid,
+}
+
+impl WireValue {
+    /// The value if it is valid.
+    pub fn valid(&self) -> Option<u16> {
+        match self {
+            Self::Valid(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    fn encode(value: f32, max: u16) -> u16 {
+        if value.is_nan() || value < 0.0 {
+            INVALID
+        } else if value > f32::from(max) {
+            SATURATED
+        } else {
+            units::round_to_u16(value)
+        }
+    }
+
+    fn decode(raw: u16) -> Self {
+        match raw {
+            INVALID => Self::Invalid,
+            SATURATED => Self::Saturated,
+            v => Self::Valid(v),
+        }
+    }
+}
+
+/// Decoded record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WireMeasurements {
+    pub co2: WireValue,
+    pub voc: WireValue,
+}
+
+/// Encode measurements.
+pub fn encode(measurements: &Measurements) -> [u8; ENCODED_LEN] {
+    let co2 = WireValue::encode(measurements.co2, *CO2_RANGE_PPM.end());
+    let voc = WireValue::encode(measurements.voc, *VOC_RANGE_PPB.end());
+    pack(co2, voc)
+}
+
+/// Encode a record marking both values as invalid, e.g. after a failed read.
+pub fn encode_invalid() -> [u8; ENCODED_LEN] {
+    pack(INVALID, INVALID)
+}
+
+fn pack(co2: u16, voc: u16) -> [u8; ENCODED_LEN] {
+    let [co2_l, co2_h] = co2.to_le_bytes();
+    let [voc_l, voc_h] = voc.to_le_bytes();
+    [co2_l, co2_h, voc_l, voc_h]
+}
+
+/// Encode measurements with a leading schema version.
+pub fn encode_versioned(measurements: &Measurements) -> [u8; VERSIONED_LEN] {
+    let [co2_l, co2_h, voc_l, voc_h] = encode(measurements);
+    [SCHEMA_VERSION, co2_l, co2_h, voc_l, voc_h]
+}
+
+/// Decode a record written by [encode_versioned()] or a plain record of version `1`.
+pub fn decode_versioned(bytes: &[u8]) -> Result<WireMeasurements, WireError> {
+    match *bytes {
+        [co2_l, co2_h, voc_l, voc_h] => Ok(decode(&[co2_l, co2_h, voc_l, voc_h])),
+        [SCHEMA_VERSION, co2_l, co2_h, voc_l, voc_h] => Ok(decode(&[co2_l, co2_h, voc_l, voc_h])),
+        [version, ..] if bytes.len() == VERSIONED_LEN => {
+            Err(WireError::UnsupportedVersion(version))
+        }
+        _ => Err(WireError::InvalidLength(bytes.len())),
+    }
+}
+
+/// Decode a record.
+pub fn decode(bytes: &[u8; ENCODED_LEN]) -> WireMeasurements {
+    WireMeasurements {
+        co2: WireValue::decode(u16::from_le_bytes([bytes[0], bytes[1]])),
+        voc: WireValue::decode(u16::from_le_bytes([bytes[2], bytes[3]])),
+    }
+}
+
+impl WireMeasurements {
+    /// The measurements if both values are valid.
+    pub fn measurements(&self) -> Option<Measurements> {
+        Some(Measurements {
+            co2: f32::from(self.co2.valid()?),
+            voc: f32::from(self.voc.valid()?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{
+        decode, decode_versioned, encode, encode_invalid, encode_versioned, WireError, WireValue,
+    };
+    use crate::Measurements;
+    use core::assert_eq;
+
+    #[test]
+    fn test_encode_decode() {
+        let bytes = encode(&Measurements {
+            co2: 728.4,
+            voc: 113.5,
+        });
+        assert_eq!(bytes, [0xD8, 0x02, 0x72, 0x00]);
+        let decoded = decode(&bytes);
+        assert_eq!(decoded.co2, WireValue::Valid(728));
+        assert_eq!(decoded.measurements().map(|m| m.voc), Some(114.0));
+
+        let decoded = decode(&encode(&Measurements {
+            co2: 2100.0,
+            voc: f32::NAN,
+        }));
+        assert_eq!(decoded.co2, WireValue::Saturated);
+        assert_eq!(decoded.voc, WireValue::Invalid);
+        assert!(decoded.measurements().is_none());
+
+        assert_eq!(encode_invalid(), [0xFF; 4]);
+    }
+
+    #[test]
+    fn test_versioned() {
+        let m = Measurements {
+            co2: 728.4,
+            voc: 113.5,
+        };
+        let bytes = encode_versioned(&m);
+        assert_eq!(bytes, [0x02, 0xD8, 0x02, 0x72, 0x00]);
+        assert_eq!(decode_versioned(&bytes), Ok(decode(&encode(&m))));
+        // version 1 without version byte
+        assert_eq!(decode_versioned(&bytes[1..]), Ok(decode(&encode(&m))));
+
+        assert_eq!(
+            decode_versioned(&[0x03, 0xD8, 0x02, 0x72, 0x00]),
+            Err(WireError::UnsupportedVersion(3))
+        );
+        assert_eq!(
+            decode_versioned(&bytes[2..]),
+            Err(WireError::InvalidLength(3))
+        );
+    }
+}
@@ -0,0 +1,68 @@
+//! Reads using a combined write + repeated start + read transaction.
+//!
+//! Some I2C controllers and multiplexers behave better if the command and the response are
+//! transferred in one transaction. In this mode there is no wait between the command and the
+//! response, so the sensor answers with the data of its last internal update. Only use it if
+//! your sensor tolerates this.
+
+use embedded_hal::blocking::i2c::WriteRead;
+
+use crate::{
+    error::PacketParseError,
+    frontend,
+    protocol::{self, Command, RESPONSE_LEN},
+    transport::Transport,
+    Measurements, MicsVz89Te, RevisionDate,
+};
+
+impl<I2C, E> MicsVz89Te<I2C>
+where
+    I2C: Transport<Error = E> + WriteRead<Error = E>,
+{
+    /// Read measurements from sensor in a single write-read transaction.
+    pub fn read_measurements_write_read(&mut self) -> Result<Measurements, PacketParseError<E>> {
+        let response = self.transact(Command::GetStatus)?;
+        Ok(self.decode_measurements(&response))
+    }
+
+    /// Read revision date of the sensor in a single write-read transaction.
+    pub fn read_revision_write_read(&mut self) -> Result<RevisionDate, PacketParseError<E>> {
+        let response = self.transact(Command::GetRevision)?;
+        Ok(protocol::decode_revision(&response))
+    }
+
+    fn transact(&mut self, command: Command) -> Result<[u8; RESPONSE_LEN], PacketParseError<E>> {
+        frontend::with_retries!(self, {
+            let mut buffer = [0u8; RESPONSE_LEN];
+            self.i2c
+                .write_read(self.config.address, &command.frame(), &mut buffer)
+                .map_err(PacketParseError::from)
+                .and_then(|()| self.accept_response(&buffer, 0))
+                .map(|()| buffer)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::MicsVz89Te;
+    use assert_matches::assert_matches;
+    use embedded_hal_mock::i2c::{Mock as I2cMock, Transaction as I2cTransaction};
+    use std::vec;
+
+    #[test]
+    fn test_read_measurements_write_read() {
+        let expectations = [I2cTransaction::write_read(
+            0x70,
+            vec![0x0C, 0, 0, 0, 0, 0xF3],
+            vec![0x27, 0x3C, 0, 0xBA, 0xBA, 0, 0x27],
+        )];
+        let i2c = I2cMock::new(&expectations);
+
+        let mut device = MicsVz89Te::new(i2c);
+        let measurements = device.read_measurements_write_read();
+
+        assert_matches!(measurements, Ok(m) if m.co2_ppm_u16() == 728);
+    }
+}
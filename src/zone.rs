@@ -0,0 +1,199 @@
+//! Classification of readings into an ordered table of named zones.
+//!
+//! Where a [Threshold](crate::threshold::Threshold) only knows raised and cleared, a
+//! [ZoneClassifier] maps a channel onto any number of levels, e.g. for a five-level UI. Each
+//! zone reaches up to its `upper` boundary. Moving to a higher zone happens as soon as the
+//! boundary is exceeded, moving down only once the value falls below the boundary minus the
+//! hysteresis.
+//!
+//! # Example Usage
+//! ```ignore
+//! let mut zones = ZoneClassifier::new(Channel::Co2, &DEFAULT_CO2_ZONES, 50.0);
+//! let m = device.read_measurements(&mut delay)?;
+//! if let Some(change) = zones.update(&m) {
+//!     events.push(change.event(now_ms));
+//! }
+//! led.show(zones.zone().map(|z| z.name));
+//! ```
+
+use crate::{
+    event::{Event, EventKind},
+    Channel, Measurements,
+};
+
+/// A named zone reaching up to (excluding) `upper`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Zone<'a> {
+    pub name: &'a str,
+    pub upper: f32,
+}
+
+const fn zone(name: &str, upper: f32) -> Zone<'_> {
+    Zone { name, upper }
+}
+
+/// Default zones for CO2 in ppm.
+pub const DEFAULT_CO2_ZONES: [Zone<'static>; 5] = [
+    zone("Excellent", 600.0),
+    zone("Good", 800.0),
+    zone("Moderate", 1000.0),
+    zone("Poor", 1500.0),
+    zone("Very poor", f32::INFINITY),
+];
+
+/// Default zones for VOC in ppb.
+pub const DEFAULT_VOC_ZONES: [Zone<'static>; 5] = [
+    zone("Excellent", 65.0),
+    zone("Good", 220.0),
+    zone("Moderate", 660.0),
+    zone("Poor", 2200.0),
+    zone("Very poor", f32::INFINITY),
+];
+
+/// Change of the zone of a [ZoneClassifier].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ZoneChange {
+    pub channel: Channel,
+    /// Index of the previous zone, `None` for the first classification.
+    pub from: Option<usize>,
+    /// Index of the new zone.
+    pub to: usize,
+}
+
+impl ZoneChange {
+    /// The change as [EventKind::ZoneChanged] event occurred at `timestamp_ms`.
+    pub fn event(&self, timestamp_ms: u64) -> Event {
+        Event::new(
+            timestamp_ms,
+            EventKind::ZoneChanged {
+                channel: self.channel,
+                from: self.from,
+                to: self.to,
+            },
+        )
+    }
+}
+
+/// Classifies one channel into a table of zones sorted by `upper`.
+#[derive(Debug, Clone, Copy)]
+pub struct ZoneClassifier<'a> {
+    channel: Channel,
+    zones: &'a [Zone<'a>],
+    hysteresis: f32,
+    current: Option<usize>,
+}
+
+impl<'a> ZoneClassifier<'a> {
+    /// Create a classifier on `channel`. A negative `hysteresis` is treated as `0`.
+    pub const fn new(channel: Channel, zones: &'a [Zone<'a>], hysteresis: f32) -> Self {
+        Self {
+            channel,
+            zones,
+            hysteresis: if hysteresis > 0.0 { hysteresis } else { 0.0 },
+            current: None,
+        }
+    }
+
+    /// Monitored channel.
+    pub fn channel(&self) -> Channel {
+        self.channel
+    }
+
+    /// Index of the current zone.
+    pub fn index(&self) -> Option<usize> {
+        self.current
+    }
+
+    /// The current zone.
+    pub fn zone(&self) -> Option<&'a Zone<'a>> {
+        self.current.and_then(|i| self.zones.get(i))
+    }
+
+    /// Feed a new measurement. Returns the change if the zone changed.
+    pub fn update(&mut self, measurements: &Measurements) -> Option<ZoneChange> {
+        let value = measurements.get(self.channel);
+        let last = self.zones.len().checked_sub(1)?;
+        let zone_of = |offset: f32| {
+            self.zones
+                .iter()
+                .position(|z| value < z.upper - offset)
+                .unwrap_or(last)
+        };
+
+        let next = match self.current {
+            Some(current) => {
+                let lower = zone_of(self.hysteresis);
+                let upper = zone_of(0.0);
+                if lower < current {
+                    lower
+                } else if upper > current {
+                    upper
+                } else {
+                    return None;
+                }
+            }
+            None => zone_of(0.0),
+        };
+        let change = ZoneChange {
+            channel: self.channel,
+            from: self.current,
+            to: next,
+        };
+        self.current = Some(next);
+        Some(change)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{ZoneChange, ZoneClassifier, DEFAULT_CO2_ZONES};
+    use crate::{event::EventKind, Channel, Measurements};
+    use core::assert_eq;
+
+    #[test]
+    fn test_zone_hysteresis() {
+        let mut zones = ZoneClassifier::new(Channel::Co2, &DEFAULT_CO2_ZONES, 50.0);
+        let mut update = |co2| zones.update(&Measurements { co2, voc: 0.0 }).map(|c| c.to);
+
+        assert_eq!(update(700.0), Some(1));
+        assert_eq!(update(810.0), Some(2));
+        assert_eq!(update(780.0), None);
+        assert_eq!(update(749.0), Some(1));
+        assert_eq!(update(2500.0), Some(4));
+        assert_eq!(update(500.0), Some(0));
+        assert_eq!(zones.zone().map(|z| z.name), Some("Excellent"));
+    }
+
+    #[test]
+    fn test_zone_event() {
+        let mut zones = ZoneClassifier::new(Channel::Voc, &super::DEFAULT_VOC_ZONES, 0.0);
+        let change = zones.update(&Measurements {
+            co2: 400.0,
+            voc: 700.0,
+        });
+        assert_eq!(
+            change,
+            Some(ZoneChange {
+                channel: Channel::Voc,
+                from: None,
+                to: 3
+            })
+        );
+        assert_eq!(
+            change.unwrap().event(10).kind,
+            EventKind::ZoneChanged {
+                channel: Channel::Voc,
+                from: None,
+                to: 3
+            }
+        );
+        let m = Measurements {
+            co2: 400.0,
+            voc: 0.0,
+        };
+        assert!(ZoneClassifier::new(Channel::Co2, &[], 0.0)
+            .update(&m)
+            .is_none());
+    }
+}